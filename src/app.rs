@@ -8,18 +8,94 @@
 //! coordinating between different UI components and the data model.
 
 use crate::models::{
-    annotation::{Annotation, AnnotationType},
-    project::ProjectData,
+    activity::ActivityLog,
+    annotation::{self, Annotation, AnnotationType, Point, Vertices},
+    layers::Layers,
+    project::{self, ProjectData},
+    session::{DialogPurpose, LastSession, RecentDirectories},
+    vertex_selection::VertexSelection,
+    settings::{AfterFinish, AnnotationFormat, AutosaveLocation, CoordinateUnit, SessionRestore, Settings},
 };
-use crate::ui::{canvas, properties, toolbar};
+use crate::io::media::{LoadedImage, MediaInfo};
+use crate::ui::{
+    canvas,
+    keymap::{Action, Keymap},
+    layers as layers_panel,
+    notifications::{NotificationLevel, Notifications},
+    preferences, properties,
+    render_cache::{self, RenderCache},
+    statistics::{self, StatisticsView},
+    templates::{self as templates_panel, TemplatesAction},
+    toolbar,
+    view::ViewTransform,
+};
+use crate::util::{geometry, progress::BatchJob, raster};
+use anyhow::Context as _;
+use crate::io::autosave;
+use crate::io::serialization::BatchImportReport;
+use crate::io::validation::{self, ExportIssue, ImportPreview};
 use std::sync::mpsc::{channel, Receiver};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::time::{Duration, Instant};
+
+/// Storage key for persisted settings.
+const SETTINGS_KEY: &str = "settings";
+
+/// Storage key for the last session's files.
+const SESSION_KEY: &str = "last_session";
+
+/// Storage key for the directories file dialogs were last used in.
+const RECENT_DIRECTORIES_KEY: &str = "recent_directories";
+
+/// Number of vertices in the polygon created for an enclosing circle.
+const CIRCLE_SEGMENTS: usize = 64;
+
+/// Traced vertices closer than this (normalized units) to their predecessor
+/// are merged when tracing stops.
+const TRACE_DEDUP_DISTANCE: f64 = 1e-6;
+
+/// Storage key for persisted keyboard shortcuts.
+const KEYMAP_KEY: &str = "keymap";
+
+/// Window title while no project is open.
+pub const DEFAULT_TITLE: &str = "ROIDS - Region Of Interest Designation System";
+
+/// How often the media file is looked up again to notice it being deleted.
+const MEDIA_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Straightening a line that discards more vertices than this asks first.
+const STRAIGHTEN_CONFIRM_VERTICES: usize = 3;
+
+/// The undoable part of a project's state.
+#[derive(Debug, Clone, PartialEq)]
+struct Snapshot {
+    annotations: Vec<Annotation>,
+    north_offset_deg: Option<f64>,
+}
+
+impl Snapshot {
+    fn of(project: &ProjectData) -> Self {
+        Self {
+            annotations: project.annotations.clone(),
+            north_offset_deg: project.north_offset_deg,
+        }
+    }
+
+    /// Put this state back into the project.
+    fn restore(self, project: &mut ProjectData) {
+        project.annotations = self.annotations;
+        project.north_offset_deg = self.north_offset_deg;
+    }
+}
 
 /// History system for undo/redo functionality.
 struct History {
     /// Undo stack (past states)
-    undo_stack: Vec<Vec<Annotation>>,
+    undo_stack: Vec<Snapshot>,
     /// Redo stack (future states after undo)
-    redo_stack: Vec<Vec<Annotation>>,
+    redo_stack: Vec<Snapshot>,
     /// Maximum history size
     max_size: usize,
 }
@@ -34,8 +110,8 @@ impl History {
     }
 
     /// Save current state before making a change
-    fn push(&mut self, annotations: Vec<Annotation>) {
-        self.undo_stack.push(annotations);
+    fn push(&mut self, snapshot: Snapshot) {
+        self.undo_stack.push(snapshot);
         // Limit history size
         if self.undo_stack.len() > self.max_size {
             self.undo_stack.remove(0);
@@ -45,7 +121,7 @@ impl History {
     }
 
     /// Undo: restore previous state
-    fn undo(&mut self, current: Vec<Annotation>) -> Option<Vec<Annotation>> {
+    fn undo(&mut self, current: Snapshot) -> Option<Snapshot> {
         if let Some(previous) = self.undo_stack.pop() {
             self.redo_stack.push(current);
             Some(previous)
@@ -55,7 +131,7 @@ impl History {
     }
 
     /// Redo: restore next state
-    fn redo(&mut self, current: Vec<Annotation>) -> Option<Vec<Annotation>> {
+    fn redo(&mut self, current: Snapshot) -> Option<Snapshot> {
         if let Some(next) = self.redo_stack.pop() {
             self.undo_stack.push(current);
             Some(next)
@@ -79,6 +155,13 @@ impl History {
         self.undo_stack.clear();
         self.redo_stack.clear();
     }
+
+    /// Apply a change to every saved state
+    fn remap(&mut self, f: impl Fn(&mut Snapshot)) {
+        for snapshot in self.undo_stack.iter_mut().chain(self.redo_stack.iter_mut()) {
+            f(snapshot);
+        }
+    }
 }
 
 /// Current drawing tool selection.
@@ -87,14 +170,147 @@ pub enum Tool {
     Select,
     Polygon,
     Line,
+    Point,
+    Measure,
+    Split,
+    Weld,
+    OrientedBox,
+    Stamp,
+}
+
+/// An export waiting on the user's decision about invalid polygons.
+#[derive(Debug, Clone)]
+enum PendingExport {
+    Annotations(std::path::PathBuf),
+    Coco(std::path::PathBuf),
+    GeoJson(std::path::PathBuf),
+    /// Outlines as SVG, over the embedded image if the flag is set
+    Svg(std::path::PathBuf, bool),
+    AnchoredYaml(std::path::PathBuf),
+    Mesh(std::path::PathBuf),
+    YoloObb(std::path::PathBuf),
+    Overlay(std::path::PathBuf),
+    Individual(std::path::PathBuf),
+    /// One annotation file per annotated video frame, into a folder
+    Frames(std::path::PathBuf, AnnotationFormat),
+    /// Annotations re-normalized to a crop rectangle (min, max)
+    Cropped(std::path::PathBuf, (Point, Point)),
+}
+
+impl PendingExport {
+    /// File the export writes, or None for exports into a folder.
+    fn target_file(&self) -> Option<&std::path::Path> {
+        match self {
+            PendingExport::Annotations(path)
+            | PendingExport::Coco(path)
+            | PendingExport::GeoJson(path)
+            | PendingExport::Svg(path, _)
+            | PendingExport::AnchoredYaml(path)
+            | PendingExport::Mesh(path)
+            | PendingExport::YoloObb(path)
+            | PendingExport::Overlay(path)
+            | PendingExport::Cropped(path, _) => Some(path),
+            PendingExport::Individual(_) | PendingExport::Frames(..) => None,
+        }
+    }
+}
+
+/// User's answer to the import preview.
+enum ImportChoice {
+    Replace,
+    Merge,
+    Cancel,
+}
+
+/// User's answer to the invalid polygons prompt.
+enum ExportChoice {
+    Fix,
+    Skip,
+    ExportAnyway,
+    Cancel,
 }
 
 /// Result of background image loading operation.
 struct LoadedImageData {
+    image: LoadedImage,
+    project: Option<ProjectData>,
+    /// Messages to show once loading finishes
+    notices: Vec<(NotificationLevel, String)>,
+}
+
+/// State remembered for an image while switching between images.
+#[derive(Debug, Clone, Default)]
+struct ImageState {
+    /// Zoom and pan the image was last viewed at
+    view: ViewTransform,
+}
+
+/// Inputs of the rename dialog.
+#[derive(Debug, Clone, Default)]
+struct RenameDialog {
+    find: String,
+    replace: String,
+    use_regex: bool,
+}
+
+/// Inputs of the new-annotation-from-coordinates dialog.
+#[derive(Debug, Clone)]
+struct CoordinateDialog {
+    name: String,
+    annotation_type: AnnotationType,
+    normalized: bool,
+    text: String,
+}
+
+/// Crop rectangle, in pixels, for exporting annotations relative to it.
+#[derive(Debug, Clone, Copy, Default)]
+struct CropExportDialog {
+    x: u32,
+    y: u32,
     width: u32,
     height: u32,
-    pixels: Vec<u8>,
-    project: Option<ProjectData>,
+}
+
+impl CropExportDialog {
+    /// Fill the inputs from a normalized region, rounded to whole pixels.
+    fn set_region(&mut self, (min, max): (Point, Point), image_size: (u32, u32)) {
+        let (x0, y0) = geometry::denormalize_coordinates(&min, image_size.0, image_size.1);
+        let (x1, y1) = geometry::denormalize_coordinates(&max, image_size.0, image_size.1);
+        self.x = x0.round().max(0.0) as u32;
+        self.y = y0.round().max(0.0) as u32;
+        self.width = (x1.round() as u32).min(image_size.0).saturating_sub(self.x);
+        self.height = (y1.round() as u32).min(image_size.1).saturating_sub(self.y);
+    }
+
+    /// The crop as a normalized rectangle (min, max), or why it is unusable.
+    fn crop(&self, image_size: (u32, u32)) -> anyhow::Result<(Point, Point)> {
+        let (width, height) = image_size;
+        if self.width == 0 || self.height == 0 {
+            anyhow::bail!("The crop needs a non-zero width and height");
+        }
+        if self.x + self.width > width || self.y + self.height > height {
+            anyhow::bail!("The crop extends past the {} × {} image", width, height);
+        }
+        let (width, height) = (width as f64, height as f64);
+        Ok((
+            Point::new(self.x as f64 / width, self.y as f64 / height),
+            Point::new((self.x + self.width) as f64 / width, (self.y + self.height) as f64 / height),
+        ))
+    }
+}
+
+/// Result of a folder operation run on a worker thread.
+enum BatchOutcome {
+    FolderImport(std::path::PathBuf, anyhow::Result<BatchImportReport>),
+    Crops(std::path::PathBuf, anyhow::Result<usize>),
+}
+
+/// Options chosen before importing a CSV coordinate list.
+#[derive(Debug, Clone)]
+struct CsvImport {
+    path: std::path::PathBuf,
+    annotation_type: AnnotationType,
+    normalized: bool,
 }
 
 /// Main application state.
@@ -108,9 +324,16 @@ pub struct RoidsApp {
     /// Index of currently selected annotation
     selected_annotation: Option<usize>,
 
+    /// Annotation the properties panel stays on regardless of selection
+    pinned_annotation: Option<usize>,
+
     /// Loaded image texture for display
     image_texture: Option<egui::TextureHandle>,
 
+    /// Bumped each time the texture is regenerated from image pixels.
+    /// Pan, zoom, and opacity only change how it is drawn, never the texture.
+    texture_version: u64,
+
     /// Image dimensions (width, height)
     image_size: Option<(u32, u32)>,
 
@@ -123,6 +346,9 @@ pub struct RoidsApp {
     /// Currently dragged vertex (annotation_index, vertex_index)
     dragging_vertex: Option<(usize, usize)>,
 
+    /// Oriented box being turned by its rotation handle
+    rotating_box: Option<usize>,
+
     /// History for undo/redo
     history: History,
 
@@ -131,6 +357,151 @@ pub struct RoidsApp {
 
     /// Loading state message
     loading_message: Option<String>,
+
+    /// Toast notifications
+    notifications: Notifications,
+
+    /// User preferences
+    settings: Settings,
+
+    /// Keyboard shortcuts
+    keymap: Keymap,
+
+    /// Full-resolution source image pixels (for exports)
+    source_image: Option<LoadedImage>,
+
+    /// Whether the preferences window is open
+    show_preferences: bool,
+    /// Whether the statistics window is open
+    show_statistics: bool,
+    /// Statistics window display options
+    statistics_view: StatisticsView,
+    /// Whether the out-of-bounds annotations window is open
+    show_out_of_bounds: bool,
+
+    /// Find-and-replace dialog for annotation names, while open
+    rename_dialog: Option<RenameDialog>,
+
+    /// Dialog for typing in a new annotation's coordinates, while open
+    coordinate_dialog: Option<CoordinateDialog>,
+
+    /// Dialog for exporting annotations relative to a crop, while open
+    crop_export_dialog: Option<CropExportDialog>,
+
+    /// Canvas zoom and pan
+    view: ViewTransform,
+
+    /// Files to reopen on the next startup
+    last_session: LastSession,
+
+    /// Where each kind of file dialog was last used
+    recent_directories: RecentDirectories,
+
+    /// Whether to ask about reopening the last session
+    restore_prompt_open: bool,
+
+    /// Reopen the last session on the next frame
+    pending_restore: bool,
+
+    /// File given on the command line, opened on the first frame
+    pending_open: Option<std::path::PathBuf>,
+
+    /// Annotation group visibility and active group
+    layers: Layers,
+
+    /// Name being typed for a new group in the layers panel
+    new_group_name: String,
+
+    /// Vertices picked in the properties table for bulk deletion
+    vertex_selection: VertexSelection,
+
+    /// Annotation templates loaded from a templates file
+    templates: Vec<Annotation>,
+
+    /// Template the Stamp tool places
+    active_template: Option<usize>,
+
+    /// Cached shapes for committed annotations
+    render_cache: RenderCache,
+
+    /// Annotation density heatmap, keyed on the inputs it was built from
+    heatmap: Option<(u64, egui::TextureHandle)>,
+
+    /// Index of a finished annotation that exceeds the complexity threshold
+    pending_simplify: Option<usize>,
+
+    /// Index of a line awaiting confirmation to be straightened
+    pending_straighten: Option<usize>,
+
+    /// Neighbor vertex the last drawn vertex was snapped to (annotation_index, vertex_index)
+    borrow_anchor: Option<(usize, usize)>,
+
+    /// Sorted images of the folder being annotated (empty if none)
+    folder_images: Vec<std::path::PathBuf>,
+
+    /// Index of the current image in `folder_images`
+    folder_index: usize,
+
+    /// Per-image state, keyed by media path, restored when an image is
+    /// opened again this session
+    image_states: HashMap<std::path::PathBuf, ImageState>,
+
+    /// Annotations as last loaded from or saved to the sidecar file
+    sidecar_annotations: Option<Vec<Annotation>>,
+
+    /// Show only the selected annotation
+    isolate: bool,
+
+    /// Class every new annotation is named after, until unlocked
+    locked_class: Option<String>,
+
+    /// Annotations are hidden while the hide-overlays key is held
+    overlays_hidden: bool,
+
+    /// Annotations chosen together by lasso; the first is also `selected_annotation`
+    lasso_selection: Vec<usize>,
+
+    /// Second annotation set (e.g. ground truth) overlaid for comparison
+    reference_project: Option<ProjectData>,
+
+    /// Signed offset in pixels previewed on the selected polygon
+    offset_distance: f64,
+
+    /// Export held back until the user decides about invalid polygons
+    pending_export: Option<(PendingExport, Vec<ExportIssue>)>,
+
+    /// Export held back until the user confirms overwriting its file
+    pending_overwrite: Option<PendingExport>,
+
+    /// Parsed annotation file waiting for the user to confirm loading it
+    pending_import: Option<(std::path::PathBuf, ImportPreview)>,
+
+    /// CSV file waiting for the user to choose how to interpret it
+    pending_csv_import: Option<CsvImport>,
+
+    /// Folder operation running on a worker thread, with its progress
+    batch_job: Option<BatchJob<BatchOutcome>>,
+
+    /// Log of annotation changes made this session
+    activity: ActivityLog,
+
+    /// Annotations before the latest change, until it is logged
+    activity_before: Option<Vec<Annotation>>,
+
+    /// Segment dragged by the Measure or Split tool (start, end)
+    drag_segment: Option<(Point, Point)>,
+
+    /// Time of the last autosave check
+    last_autosave: Instant,
+
+    /// Project state as of the last autosave, load, or export
+    autosaved: Option<Snapshot>,
+
+    /// On-disk details of the project's media file
+    media_info: Option<MediaInfo>,
+
+    /// Time the media file was last looked up
+    media_checked: Instant,
 }
 
 impl Default for RoidsApp {
@@ -146,199 +517,2661 @@ impl RoidsApp {
             current_tool: Tool::Select,
             project: None,
             selected_annotation: None,
+            pinned_annotation: None,
             image_texture: None,
+            texture_version: 0,
             image_size: None,
             in_progress_annotation: None,
             annotation_counter: 0,
             dragging_vertex: None,
+            rotating_box: None,
             history: History::new(),
             image_loader: None,
             loading_message: None,
+            notifications: Notifications::new(),
+            settings: Settings::default(),
+            keymap: Keymap::default(),
+            source_image: None,
+            show_preferences: false,
+            show_statistics: false,
+            statistics_view: StatisticsView::default(),
+            show_out_of_bounds: false,
+            rename_dialog: None,
+            coordinate_dialog: None,
+            crop_export_dialog: None,
+            view: ViewTransform::new(),
+            last_session: LastSession::default(),
+            recent_directories: RecentDirectories::default(),
+            restore_prompt_open: false,
+            pending_restore: false,
+            pending_open: None,
+            layers: Layers::new(),
+            new_group_name: String::new(),
+            vertex_selection: VertexSelection::default(),
+            templates: Vec::new(),
+            active_template: None,
+            render_cache: RenderCache::new(),
+            heatmap: None,
+            pending_simplify: None,
+            pending_straighten: None,
+            borrow_anchor: None,
+            folder_images: Vec::new(),
+            image_states: HashMap::new(),
+            folder_index: 0,
+            sidecar_annotations: None,
+            isolate: false,
+            locked_class: None,
+            overlays_hidden: false,
+            lasso_selection: Vec::new(),
+            pending_export: None,
+            pending_overwrite: None,
+            pending_import: None,
+            pending_csv_import: None,
+            batch_job: None,
+            activity: ActivityLog::default(),
+            activity_before: None,
+            reference_project: None,
+            offset_distance: 0.0,
+            drag_segment: None,
+            last_autosave: Instant::now(),
+            media_info: None,
+            media_checked: Instant::now(),
+            autosaved: None,
         }
     }
 
-    /// Save annotations to history before making a change
-    fn save_to_history(&mut self, annotations: &[Annotation]) {
-        self.history.push(annotations.to_vec());
-    }
-
-    /// Start a new annotation based on the current tool.
-    fn start_annotation(&mut self) {
-        let annotation_type = match self.current_tool {
-            Tool::Polygon => AnnotationType::Polygon,
-            Tool::Line => AnnotationType::Line,
-            Tool::Select => return, // Don't create annotations in select mode
+    /// Create an application instance, restoring settings and the last
+    /// session from persistent storage if available.
+    pub fn from_storage(storage: Option<&dyn eframe::Storage>) -> Self {
+        let mut app = Self::new();
+        let Some(storage) = storage else {
+            return app;
         };
 
-        let name = match annotation_type {
-            AnnotationType::Polygon => format!("region {}", self.annotation_counter + 1),
-            AnnotationType::Line => format!("line {}", self.annotation_counter + 1),
-        };
+        if let Some(settings) = eframe::get_value(storage, SETTINGS_KEY) {
+            app.settings = settings;
+        }
+        if let Some(session) = eframe::get_value(storage, SESSION_KEY) {
+            app.last_session = session;
+        }
+        if let Some(directories) = eframe::get_value(storage, RECENT_DIRECTORIES_KEY) {
+            app.recent_directories = directories;
+        }
+        if let Some(mut keymap) = eframe::get_value::<Keymap>(storage, KEYMAP_KEY) {
+            keymap.fill_missing();
+            let conflicts = keymap.conflicts();
+            if conflicts.is_empty() {
+                app.keymap = keymap;
+            } else {
+                for (binding, first, second) in conflicts {
+                    log::warn!("Shortcut {} is bound to both {:?} and {:?}", binding.label(), first, second);
+                }
+                app.notifications.warning("Saved keyboard shortcuts conflict, using defaults");
+            }
+        }
 
-        self.in_progress_annotation = Some(Annotation::new(name, annotation_type));
-    }
+        // Files may have moved since the last run
+        for path in app.last_session.clear_missing() {
+            log::warn!("Last session file no longer exists: {}", path.display());
+            app.notifications.warning(format!("Last session file not found: {}", path.display()));
+        }
 
-    /// Finish the current in-progress annotation and add it to the project.
-    fn finish_annotation(&mut self) {
-        if let Some(annotation) = self.in_progress_annotation.take() {
-            if annotation.vertex_count() >= 2 {
-                // Clone annotations for history
-                let annotations_clone = self.project.as_ref()
-                    .map(|p| p.annotations.clone());
+        if !app.last_session.is_empty() {
+            match app.settings.restore_session {
+                SessionRestore::Off => {}
+                SessionRestore::Ask => app.restore_prompt_open = true,
+                SessionRestore::Auto => app.pending_restore = true,
+            }
+        }
 
-                // Save to history before making changes
-                if let Some(annotations) = annotations_clone {
-                    self.save_to_history(&annotations);
-                }
+        app
+    }
 
-                // Now mutably borrow and make changes
-                if let Some(ref mut project) = self.project {
-                    project.annotations.push(annotation);
-                    self.annotation_counter += 1;
-                    log::info!("Added annotation, total: {}", project.annotations.len());
-                }
-            }
+    /// Open a file given on the command line once the app starts, instead
+    /// of restoring the last session.
+    pub fn with_launch_file(mut self, path: Option<std::path::PathBuf>) -> Self {
+        if path.is_some() {
+            self.pending_open = path;
+            self.pending_restore = false;
+            self.restore_prompt_open = false;
         }
+        self
     }
 
-    /// Cancel the current in-progress annotation.
-    fn cancel_annotation(&mut self) {
-        self.in_progress_annotation = None;
+    /// Open an image or annotation file named on the command line, choosing
+    /// by extension.
+    ///
+    /// Missing or unsupported files are reported and otherwise ignored.
+    fn open_path(&mut self, path: std::path::PathBuf, ctx: &egui::Context) {
+        if !path.is_file() {
+            log::error!("File not found: {}", path.display());
+            self.notifications.error(format!("File not found: {}", path.display()));
+        } else if crate::io::media::is_supported_image(&path) {
+            self.load_image_file(path, ctx);
+        } else if crate::io::serialization::is_annotation_file(&path) {
+            self.load_annotations(path);
+        } else {
+            log::error!("Unsupported file type: {}", path.display());
+            self.notifications.error(format!("Unsupported file type: {}", path.display()));
+        }
     }
 
-    /// Export annotations to a file.
-    fn export_annotations(&self, path: std::path::PathBuf) {
-        if let Some(ref project) = self.project {
-            let extension = path.extension().and_then(|s| s.to_str());
-            let result = match extension {
-                Some("yaml") | Some("yml") => crate::io::serialization::export_yaml(project, &path),
-                Some("json") => crate::io::serialization::export_json(project, &path),
-                _ => {
-                    log::error!("Unsupported file extension: {:?}", extension);
-                    return;
-                }
-            };
+    /// Reopen the image and annotations from the last session.
+    fn restore_session(&mut self) {
+        for path in self.last_session.clear_missing() {
+            self.notifications.warning(format!("Last session file not found: {}", path.display()));
+        }
 
-            match result {
-                Ok(_) => log::info!("Exported annotations to {}", path.display()),
-                Err(e) => log::error!("Failed to export annotations: {}", e),
-            }
+        let session = self.last_session.clone();
+        match (session.media_path, session.annotation_path) {
+            (Some(media), annotations) => self.load_image_with_annotations(media, annotations),
+            (None, Some(annotations)) => self.load_annotations(annotations),
+            (None, None) => {}
         }
     }
 
-    /// Import annotations from a file and load the associated image (asynchronously).
-    fn import_annotations(&mut self, path: std::path::PathBuf, _ctx: &egui::Context) {
-        let (sender, receiver) = channel();
-        self.image_loader = Some(receiver);
-        self.loading_message = Some("Loading annotations and image...".to_string());
+    /// Show the prompt offering to reopen the last session.
+    fn show_restore_prompt(&mut self, ctx: &egui::Context) {
+        if !self.restore_prompt_open {
+            return;
+        }
 
-        // Spawn background thread for loading
-        std::thread::spawn(move || {
-            let result = (|| -> Result<LoadedImageData, String> {
-                // Parse annotation file
-                let extension = path.extension().and_then(|s| s.to_str());
-                let project_data = match extension {
-                    Some("yaml") | Some("yml") => crate::io::serialization::import_yaml(&path)
-                        .map_err(|e| format!("Failed to import YAML: {}", e))?,
-                    Some("json") => crate::io::serialization::import_json(&path)
-                        .map_err(|e| format!("Failed to import JSON: {}", e))?,
-                    _ => return Err(format!("Unsupported file extension: {:?}", extension)),
-                };
+        let name = self.last_session.media_path.as_ref()
+            .or(self.last_session.annotation_path.as_ref())
+            .map(|p| p.display().to_string())
+            .unwrap_or_default();
 
-                log::info!("Imported {} annotations from {}",
-                    project_data.annotations.len(), path.display());
+        let mut choice = None;
+        egui::Window::new("Reopen Last Session")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+            .show(ctx, |ui| {
+                ui.label(format!("Reopen {}?", name));
+                ui.horizontal(|ui| {
+                    if ui.button("Reopen").clicked() {
+                        choice = Some(true);
+                    }
+                    if ui.button("Start Fresh").clicked() {
+                        choice = Some(false);
+                    }
+                });
+            });
 
-                // Load the referenced image file
-                let image_path = std::path::PathBuf::from(&project_data.media_file);
-                if !image_path.exists() {
-                    return Err(format!("Referenced image not found: {}", image_path.display()));
-                }
+        if let Some(reopen) = choice {
+            self.restore_prompt_open = false;
+            if reopen {
+                self.restore_session();
+            }
+        }
+    }
 
-                let loaded_img = crate::io::media::load_image(&image_path)
-                    .map_err(|e| format!("Failed to load image: {}", e))?;
+    /// Save annotations to history before making a change
+    fn save_to_history(&mut self, annotations: &[Annotation]) {
+        self.history.push(Snapshot {
+            annotations: annotations.to_vec(),
+            north_offset_deg: self.project.as_ref().and_then(|p| p.north_offset_deg),
+        });
+        // Vertex indices may not survive the change about to be made, nor
+        // may the index an open prompt refers to
+        self.vertex_selection.clear();
+        self.pending_simplify = None;
+        self.pending_straighten = None;
 
-                log::info!("Loaded image: {}", image_path.display());
+        // The change about to be made is logged once it is complete
+        self.flush_activity();
+        self.activity_before = Some(annotations.to_vec());
+    }
 
-                Ok(LoadedImageData {
-                    width: loaded_img.width,
-                    height: loaded_img.height,
-                    pixels: loaded_img.pixels,
-                    project: Some(project_data),
-                })
-            })();
+    /// Index and name of the pinned annotation, to find it again after
+    /// annotations are removed or reordered (see [`Self::repin`]).
+    fn pin_key(&self) -> Option<(usize, String)> {
+        let idx = self.pinned_annotation?;
+        let annotation = self.project.as_ref()?.annotations.get(idx)?;
+        Some((idx, annotation.name.clone()))
+    }
 
-            let _ = sender.send(result);
+    /// Move the pin to wherever its annotation ended up, matching by name
+    /// and preferring the closest index; unpin if it is gone.
+    fn repin(&mut self, key: Option<(usize, String)>) {
+        self.pinned_annotation = key.and_then(|(old, name)| {
+            self.project
+                .as_ref()?
+                .annotations
+                .iter()
+                .enumerate()
+                .filter(|(_, annotation)| annotation.name == name)
+                .min_by_key(|(idx, _)| idx.abs_diff(old))
+                .map(|(idx, _)| idx)
         });
     }
 
-    /// Load an image file and create a texture for display (asynchronously).
-    pub fn load_image_file(&mut self, path: std::path::PathBuf, _ctx: &egui::Context) {
-        let (sender, receiver) = channel();
-        self.image_loader = Some(receiver);
-        self.loading_message = Some("Loading image...".to_string());
+    /// Restore the previous state from history.
+    fn undo(&mut self) {
+        let pin = self.pin_key();
+        let Some(ref mut project) = self.project else {
+            return;
+        };
+        if let Some(previous) = self.history.undo(Snapshot::of(project)) {
+            previous.restore(project);
+            self.selected_annotation = None;
+            self.vertex_selection.clear();
+            self.pending_simplify = None;
+            self.pending_straighten = None;
+            self.repin(pin);
+            log::info!("Undo");
+        }
+    }
 
-        let path_string = path.to_string_lossy().to_string();
+    /// Reapply the last undone state from history.
+    fn redo(&mut self) {
+        let pin = self.pin_key();
+        let Some(ref mut project) = self.project else {
+            return;
+        };
+        if let Some(next) = self.history.redo(Snapshot::of(project)) {
+            next.restore(project);
+            self.selected_annotation = None;
+            self.vertex_selection.clear();
+            self.pending_simplify = None;
+            self.pending_straighten = None;
+            self.repin(pin);
+            log::info!("Redo");
+        }
+    }
 
-        // Spawn background thread for loading
-        std::thread::spawn(move || {
-            let result = (|| -> Result<LoadedImageData, String> {
-                let loaded_img = crate::io::media::load_image(&path)
-                    .map_err(|e| format!("Failed to load image: {}", e))?;
+    /// Log the changes made since the last history save.
+    fn flush_activity(&mut self) {
+        if let (Some(before), Some(project)) = (self.activity_before.take(), &self.project) {
+            self.activity.record_changes(&before, &project.annotations, &crate::util::time::now_timestamp());
+        }
+    }
 
-                log::info!("Loaded image: {} ({}x{})", path.display(), loaded_img.width, loaded_img.height);
+    /// Delete an annotation, saving history first.
+    fn delete_annotation(&mut self, idx: usize) {
+        // Clone annotations for history
+        let annotations_clone = self.project.as_ref()
+            .filter(|p| idx < p.annotations.len())
+            .map(|p| p.annotations.clone());
 
-                // Create project data
-                let project = ProjectData::new(
-                    path_string,
-                    loaded_img.width,
-                    loaded_img.height,
-                );
+        // Save to history before making changes
+        let pin = self.pin_key();
+        if let Some(annotations) = annotations_clone {
+            self.save_to_history(&annotations);
+        }
 
-                Ok(LoadedImageData {
-                    width: loaded_img.width,
-                    height: loaded_img.height,
-                    pixels: loaded_img.pixels,
-                    project: Some(project),
-                })
-            })();
+        // Now mutably borrow and make changes
+        if let Some(ref mut project) = self.project {
+            if idx < project.annotations.len() {
+                project.annotations.remove(idx);
+                self.selected_annotation = None;
+                log::info!("Deleted annotation, total: {}", project.annotations.len());
+            }
+        }
+        self.repin(pin);
+    }
 
-            let _ = sender.send(result);
-        });
+    /// Delete the vertices of an annotation picked in the properties table,
+    /// saving history first.
+    fn delete_selected_vertices(&mut self, idx: usize) {
+        let indices = self.vertex_selection.vertices_of(idx);
+        let Some(mut annotation) = self.project.as_ref().and_then(|p| p.annotations.get(idx)).cloned() else {
+            return;
+        };
+        let removed = annotation.remove_vertices(&indices);
+        if removed == 0 {
+            self.notifications.warning(format!(
+                "Can't delete {} vertices from '{}', it needs at least {}",
+                indices.len(),
+                annotation.name,
+                annotation.annotation_type.min_vertices()
+            ));
+            return;
+        }
+
+        if let Some(annotations) = self.project.as_ref().map(|p| p.annotations.clone()) {
+            self.save_to_history(&annotations);
+        }
+        if let Some(ref mut project) = self.project {
+            log::info!("Deleted {} vertices from '{}'", removed, annotation.name);
+            project.annotations[idx] = annotation;
+        }
+        self.vertex_selection.clear();
     }
-}
 
-impl eframe::App for RoidsApp {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        // Check for completed image loading
-        if let Some(ref receiver) = self.image_loader {
-            if let Ok(result) = receiver.try_recv() {
-                self.image_loader = None;
-                self.loading_message = None;
+    /// Export the activity log as CSV.
+    fn export_activity_log(&mut self, path: std::path::PathBuf) {
+        self.flush_activity();
+        match crate::io::atomic::write_bytes(&path, self.activity.to_csv()) {
+            Ok(_) => {
+                log::info!("Exported {} activity events to {}", self.activity.len(), path.display());
+                self.notifications.info(format!("Exported activity log to {}", path.display()));
+            }
+            Err(e) => {
+                log::error!("Failed to export activity log: {:#}", e);
+                self.notifications.error(format!("Failed to export activity log: {:#}", e));
+            }
+        }
+    }
 
-                match result {
-                    Ok(loaded_data) => {
-                        // Create egui texture from the loaded image data
-                        let size = [loaded_data.width as usize, loaded_data.height as usize];
-                        let color_image = egui::ColorImage::from_rgba_unmultiplied(size, &loaded_data.pixels);
-                        let texture = ctx.load_texture(
-                            "loaded_image",
-                            color_image,
-                            egui::TextureOptions::LINEAR,
-                        );
+    /// Rebuild the density heatmap texture when it is shown and the
+    /// annotations or heatmap settings changed.
+    fn update_heatmap(&mut self, ctx: &egui::Context) {
+        let (true, Some(project)) = (self.settings.show_heatmap, &self.project) else {
+            self.heatmap = None;
+            return;
+        };
 
-                        self.image_texture = Some(texture);
-                        self.image_size = Some((loaded_data.width, loaded_data.height));
+        let columns = self.settings.heatmap_resolution.max(1);
+        let aspect = project.frame_height as f64 / project.frame_width.max(1) as f64;
+        let rows = ((columns as f64 * aspect).round() as usize).max(1);
+        let mut hasher = DefaultHasher::new();
+        for annotation in &project.annotations {
+            render_cache::hash_geometry(annotation, &mut hasher);
+        }
+        (project.annotations.len(), columns, rows, self.settings.heatmap_ramp, self.settings.fill_rule).hash(&mut hasher);
+        let key = hasher.finish();
+        if self.heatmap.as_ref().is_some_and(|(built, _)| *built == key) {
+            return;
+        }
 
-                        if let Some(project) = loaded_data.project {
+        let grid = crate::util::stats::density_grid(&project.annotations, columns, rows, self.settings.fill_rule);
+        let image = canvas::heatmap_image(&grid, self.settings.heatmap_ramp);
+        self.heatmap = Some((key, ctx.load_texture("heatmap", image, egui::TextureOptions::LINEAR)));
+        log::debug!("Built {}x{} density heatmap", columns, rows);
+    }
+
+    /// Upload image pixels as the display texture.
+    ///
+    /// This is the only place the texture is (re)built; call it only when
+    /// the pixels change (loading or rotating), never for view changes.
+    ///
+    /// Images larger than the GPU's maximum texture side (as reported by the
+    /// renderer, or egui's conservative default) are downscaled for display
+    /// only; `image_size` and exports keep the full resolution. Returns the
+    /// texture limit when the image was downscaled, so a newly loaded image
+    /// can warn about it once.
+    fn upload_texture(&mut self, ctx: &egui::Context, image: &LoadedImage) -> Option<u32> {
+        let max_side = ctx.input(|i| i.max_texture_side) as u32;
+        let downscaled = crate::io::media::display_size(image.width, image.height, max_side).map(|(width, height)| {
+            log::warn!("{}x{} image exceeds the {} px texture limit, displaying at {}x{}", image.width, image.height, max_side, width, height);
+            crate::io::media::resize_image(image, width, height)
+        });
+        let display = downscaled.as_ref().unwrap_or(image);
+
+        let size = [display.width as usize, display.height as usize];
+        let color_image = egui::ColorImage::from_rgba_unmultiplied(size, &display.pixels);
+        self.image_texture = Some(ctx.load_texture("loaded_image", color_image, egui::TextureOptions::LINEAR));
+        self.image_size = Some((image.width, image.height));
+        self.texture_version += 1;
+        log::debug!("Uploaded {}x{} image texture (version {})", image.width, image.height, self.texture_version);
+        downscaled.is_some().then_some(max_side)
+    }
+
+    /// Rotate the image and every annotation by 90 degrees.
+    fn rotate_image(&mut self, cw: bool, ctx: &egui::Context) {
+        let (Some(project), Some(source)) = (&mut self.project, &self.source_image) else {
+            return;
+        };
+
+        project.rotate_90(cw);
+        let rotated = crate::io::media::rotate_image(source, if cw { 90 } else { 270 });
+        if let Some(ref mut annotation) = self.in_progress_annotation {
+            annotation.rotate_90(cw);
+        }
+        if let Some(ref mut reference) = self.reference_project {
+            reference.rotate_90(cw);
+        }
+        self.history.remap(|snapshot| {
+            snapshot.annotations.iter_mut().for_each(|annotation| annotation.rotate_90(cw));
+            snapshot.north_offset_deg = snapshot.north_offset_deg.map(|north| project::rotate_north_90(north, cw));
+        });
+
+        let rotation = project.rotation;
+        self.upload_texture(ctx, &rotated);
+        self.source_image = Some(rotated);
+        self.drag_segment = None;
+        self.view.reset();
+        log::info!("Rotated image {}, now at {} degrees", if cw { "clockwise" } else { "counter-clockwise" }, rotation);
+    }
+
+    /// Mirror the image and every annotation, left-right when `horizontal`.
+    fn flip_image(&mut self, horizontal: bool, ctx: &egui::Context) {
+        let (Some(project), Some(source)) = (&mut self.project, &self.source_image) else {
+            return;
+        };
+
+        project.flip(horizontal);
+        let flipped = crate::io::media::flip_image(source, horizontal);
+        if let Some(ref mut annotation) = self.in_progress_annotation {
+            annotation.flip(horizontal);
+        }
+        if let Some(ref mut reference) = self.reference_project {
+            reference.flip(horizontal);
+        }
+        self.history.remap(|snapshot| {
+            snapshot.annotations.iter_mut().for_each(|annotation| annotation.flip(horizontal));
+            snapshot.north_offset_deg = snapshot.north_offset_deg.map(|north| project::flip_north(north, horizontal));
+        });
+
+        self.upload_texture(ctx, &flipped);
+        self.source_image = Some(flipped);
+        self.drag_segment = None;
+        log::info!("Flipped image {}", if horizontal { "horizontally" } else { "vertically" });
+    }
+
+    /// Toggle showing only the selected annotation.
+    fn toggle_isolate(&mut self) {
+        if self.isolate {
+            self.isolate = false;
+        } else if self.selected_annotation.is_some() {
+            self.isolate = true;
+        } else {
+            self.notifications.warning("Select an annotation to isolate");
+        }
+        log::info!("Isolate mode {}", if self.isolate { "on" } else { "off" });
+    }
+
+    /// Select the next (or previous) visible annotation in list order,
+    /// panning the canvas to keep it in view.
+    fn cycle_selection(&mut self, forward: bool) {
+        let Some(ref project) = self.project else {
+            return;
+        };
+        let layers = &self.layers;
+        let Some(idx) = cycle_index(self.selected_annotation, project.annotations.len(), forward, |i| layers.is_visible(&project.annotations[i])) else {
+            return;
+        };
+        if let Some((min, max)) = project.annotations[idx].bounding_box() {
+            self.view.reveal(min, max, (project.frame_width, project.frame_height));
+        }
+        self.selected_annotation = Some(idx);
+        self.lasso_selection.clear();
+        self.vertex_selection.clear();
+        log::info!("Selected annotation {}", idx);
+    }
+
+    /// Lock new annotations to the selected annotation's class, or unlock.
+    fn toggle_class_lock(&mut self) {
+        if let Some(class) = self.locked_class.take() {
+            self.notifications.info(format!("Unlocked class '{}'", class));
+            return;
+        }
+        let class = self
+            .selected_annotation
+            .and_then(|idx| self.project.as_ref()?.annotations.get(idx))
+            .map(|a| a.category().to_string());
+        match class {
+            Some(class) => {
+                self.notifications.info(format!("New annotations will be named '{}'", class));
+                self.locked_class = Some(class);
+            }
+            None => self.notifications.warning("Select an annotation to lock its class"),
+        }
+    }
+
+    /// Split the selected polygon in two along a cut line.
+    ///
+    /// The halves keep the original's group, color, and role and are named
+    /// after it with "a" and "b" suffixes. Of a multi-part polygon, the
+    /// first part the cut crosses twice is split, and every other part
+    /// joins the half on its side of the cut.
+    fn split_selected(&mut self, start: Point, end: Point) {
+        let Some(idx) = self.selected_annotation else {
+            self.notifications.warning("Select a polygon to split");
+            return;
+        };
+        let Some(original) = self.project.as_ref().and_then(|p| p.annotations.get(idx)).cloned() else {
+            return;
+        };
+        if original.annotation_type != AnnotationType::Polygon {
+            self.notifications.warning("Only polygons can be split");
+            return;
+        }
+        let Some((cut_ring, (first, second))) = original
+            .rings()
+            .enumerate()
+            .find_map(|(i, ring)| geometry::split_polygon(ring, (start, end)).map(|halves| (i, halves)))
+        else {
+            self.notifications.warning("The cut line must cross the polygon exactly twice");
+            return;
+        };
+        // Which side of the cut line a ring's centroid is on
+        let side = |ring: &[Point]| {
+            geometry::polygon_centroid(ring)
+                .is_some_and(|c| (end.x - start.x) * (c.y - start.y) - (end.y - start.y) * (c.x - start.x) >= 0.0)
+        };
+        let first_side = side(&first);
+        let (first_parts, second_parts): (Vec<_>, Vec<_>) = original
+            .rings()
+            .enumerate()
+            .filter(|&(i, _)| i != cut_ring)
+            .map(|(_, ring)| Vertices(ring.clone()))
+            .partition(|ring| side(&ring.0) == first_side);
+
+        // Save to history before making changes
+        let pin = self.pin_key();
+        if let Some(annotations) = self.project.as_ref().map(|p| p.annotations.clone()) {
+            self.save_to_history(&annotations);
+        }
+
+        let half = |suffix: &str, vertices: Vec<Point>, extra_rings: Vec<Vertices>| {
+            let mut annotation = original.clone();
+            annotation.name = format!("{} {}", original.name, suffix);
+            annotation.vertices.0 = vertices;
+            annotation.extra_rings = extra_rings;
+            annotation
+        };
+        if let Some(ref mut project) = self.project {
+            project.annotations[idx] = half("a", first, first_parts);
+            project.annotations.insert(idx + 1, half("b", second, second_parts));
+            log::info!("Split '{}' into two polygons", original.name);
+        }
+        self.repin(pin);
+    }
+
+    /// Add an axis-aligned box dragged out from its center; it can then be
+    /// turned with its rotation handle.
+    fn create_box(&mut self, center: Point, corner: Point) {
+        let Some(ref project) = self.project else {
+            return;
+        };
+        let (dx, dy) = ((corner.x - center.x).abs(), (corner.y - center.y).abs());
+        if dx * (project.frame_width as f64) < 1.0 || dy * (project.frame_height as f64) < 1.0 {
+            return;
+        }
+
+        self.start_annotation();
+        if let Some(ref mut annotation) = self.in_progress_annotation {
+            for (x, y) in [(-dx, -dy), (dx, -dy), (dx, dy), (-dx, dy)] {
+                annotation.add_vertex(Point::new((center.x + x).clamp(0.0, 1.0), (center.y + y).clamp(0.0, 1.0)));
+            }
+        }
+        self.finish_annotation();
+
+        // Select the box so its rotation handle shows
+        if self.current_tool == Tool::OrientedBox {
+            self.in_progress_annotation = None;
+        }
+        self.selected_annotation = self.project.as_ref().map(|p| p.annotations.len() - 1);
+    }
+
+    /// Add a polygon tracing the smallest circle around an annotation's
+    /// vertices, round in pixels (an ellipse in normalized coordinates on
+    /// non-square frames).
+    fn create_enclosing_circle(&mut self, idx: usize) {
+        let Some(project) = self.project.as_ref() else {
+            return;
+        };
+        let Some(source) = project.annotations.get(idx) else {
+            return;
+        };
+        let image_size = (project.frame_width, project.frame_height);
+        let vertices: Vec<Point> = source.rings().flatten().copied().collect();
+        let (center, radius) = geometry::min_enclosing_circle(&geometry::to_pixels(&vertices, image_size));
+        if radius <= 0.0 {
+            return;
+        }
+
+        let mut circle = Annotation::new(format!("{} circle", source.name), AnnotationType::Polygon);
+        circle.group = source.group.clone();
+        circle.vertices.0 = geometry::from_pixels(&geometry::circle_polygon(&center, radius, CIRCLE_SEGMENTS), image_size);
+
+        let annotations = project.annotations.clone();
+        self.save_to_history(&annotations);
+        if let Some(ref mut project) = self.project {
+            log::info!("Created '{}' with radius {:.1} px", circle.name, radius);
+            project.annotations.push(circle);
+            self.selected_annotation = Some(project.annotations.len() - 1);
+        }
+    }
+
+    /// Turn the box being rotated so its rotation handle points at `toward`.
+    fn rotate_box(&mut self, toward: Point) {
+        let integer_pixels = self.integer_pixel_size().is_some();
+        let (Some(idx), Some(ref mut project)) = (self.rotating_box, &mut self.project) else {
+            return;
+        };
+        let image_size = (project.frame_width, project.frame_height);
+        let Some(annotation) = project.annotations.get_mut(idx) else {
+            return;
+        };
+        let Some(obb) = geometry::obb_from_polygon(&geometry::to_pixels(&annotation.vertices.0, image_size)) else {
+            return;
+        };
+
+        let (dx, dy) = (toward.x * image_size.0 as f64 - obb.cx, toward.y * image_size.1 as f64 - obb.cy);
+        if dx == 0.0 && dy == 0.0 {
+            return;
+        }
+        let rotated = geometry::OrientedBox { angle: dx.atan2(-dy).to_degrees(), ..obb };
+        annotation.vertices.0 = geometry::from_pixels(&rotated.corners(), image_size);
+        // The turned corners fall between pixels, so snap them back
+        if integer_pixels {
+            for vertex in &mut annotation.vertices.0 {
+                *vertex = geometry::round_to_pixel(vertex, image_size);
+            }
+        }
+    }
+
+    /// Frame size to round new vertices to when "integer pixels" is on.
+    fn integer_pixel_size(&self) -> Option<(u32, u32)> {
+        let project = self.project.as_ref().filter(|_| self.settings.integer_pixels)?;
+        Some((project.frame_width, project.frame_height))
+    }
+
+    /// Merge two adjacent vertices of an annotation at their midpoint.
+    fn weld_vertices(&mut self, ann_idx: usize, first: usize, second: usize) {
+        let Some(mut annotation) = self.project.as_ref().and_then(|p| p.annotations.get(ann_idx)).cloned() else {
+            return;
+        };
+        if !annotation.weld_vertices(first, second) {
+            self.notifications.warning(format!("Only adjacent vertices can be welded, keeping at least {} vertices", annotation.annotation_type.min_vertices()));
+            return;
+        }
+
+        // Save to history before making changes
+        if let Some(annotations) = self.project.as_ref().map(|p| p.annotations.clone()) {
+            self.save_to_history(&annotations);
+        }
+        if let Some(ref mut project) = self.project {
+            log::info!("Welded vertices {} and {} of '{}'", first, second, annotation.name);
+            project.annotations[ann_idx] = annotation;
+        }
+    }
+
+    /// File dialog starting in the directory last used for `purpose`.
+    fn file_dialog(&self, purpose: DialogPurpose) -> rfd::FileDialog {
+        let dialog = rfd::FileDialog::new();
+        match self.recent_directories.start_directory(purpose) {
+            Some(directory) => dialog.set_directory(directory),
+            None => dialog,
+        }
+    }
+
+    /// Carry out a request from the templates panel.
+    fn handle_templates_action(&mut self, action: TemplatesAction) {
+        match action {
+            TemplatesAction::None => {}
+            TemplatesAction::Load => {
+                let Some(path) = self.file_dialog(DialogPurpose::OpenAnnotations).add_filter("Templates", &["json"]).pick_file() else {
+                    return;
+                };
+                self.recent_directories.remember(DialogPurpose::OpenAnnotations, &path);
+                match crate::io::templates::load_templates(&path) {
+                    Ok(templates) => {
+                        log::info!("Loaded {} templates from {}", templates.len(), path.display());
+                        self.active_template = (!templates.is_empty()).then_some(0);
+                        self.templates = templates;
+                    }
+                    Err(e) => self.notifications.error(format!("{:#}", e)),
+                }
+            }
+            TemplatesAction::Save => {
+                let Some(path) = self.file_dialog(DialogPurpose::Export).add_filter("Templates", &["json"]).set_file_name("templates.json").save_file() else {
+                    return;
+                };
+                self.recent_directories.remember(DialogPurpose::Export, &path);
+                match crate::io::templates::save_templates(&path, &self.templates) {
+                    Ok(()) => self.notifications.info(format!("Saved {} templates to {}", self.templates.len(), path.display())),
+                    Err(e) => self.notifications.error(format!("{:#}", e)),
+                }
+            }
+            TemplatesAction::AddSelected => {
+                let selected = self.selected_annotation.and_then(|idx| self.project.as_ref()?.annotations.get(idx));
+                if let Some(annotation) = selected.cloned() {
+                    self.templates.push(annotation);
+                    self.active_template = Some(self.templates.len() - 1);
+                }
+            }
+            TemplatesAction::Remove(i) => {
+                if i < self.templates.len() {
+                    self.templates.remove(i);
+                    self.active_template = match self.active_template {
+                        Some(active) if active == i => None,
+                        Some(active) if active > i => Some(active - 1),
+                        active => active,
+                    };
+                }
+            }
+        }
+    }
+
+    /// Place a copy of the active template centered on `at`.
+    fn stamp_template(&mut self, at: Point) {
+        let Some(template) = self.active_template.and_then(|i| self.templates.get(i)).cloned() else {
+            self.notifications.warning("Choose a template in the Templates panel first");
+            return;
+        };
+        let Some(annotations) = self.project.as_ref().map(|p| p.annotations.clone()) else {
+            return;
+        };
+
+        let mut stamp = crate::io::templates::stamp_template(&template, at, &annotations);
+        if let Some(image_size) = self.integer_pixel_size() {
+            for vertex in stamp.rings_mut().flatten() {
+                *vertex = geometry::round_to_pixel(vertex, image_size);
+            }
+        }
+        if stamp.group.is_none() {
+            stamp.group = self.layers.active.clone();
+        }
+        self.save_to_history(&annotations);
+        if let Some(ref mut project) = self.project {
+            log::info!("Stamped template '{}' as '{}'", template.name, stamp.name);
+            project.annotations.push(stamp);
+            self.selected_annotation = Some(project.annotations.len() - 1);
+        }
+    }
+
+    /// Straighten the selected line to its endpoints, first asking the
+    /// user to confirm if that would discard many vertices.
+    fn request_straighten_selected(&mut self) {
+        let Some(idx) = self.selected_annotation else {
+            return;
+        };
+        let Some(annotation) = self.project.as_ref().and_then(|p| p.annotations.get(idx)) else {
+            return;
+        };
+        if annotation.annotation_type != AnnotationType::Line {
+            self.notifications.warning("Only lines can be straightened");
+        } else if annotation.vertex_count().saturating_sub(2) > STRAIGHTEN_CONFIRM_VERTICES {
+            self.pending_straighten = Some(idx);
+        } else {
+            self.straighten_line(idx);
+        }
+    }
+
+    /// Collapse a line to its first and last vertices as one undo step.
+    fn straighten_line(&mut self, idx: usize) {
+        let Some(mut annotation) = self.project.as_ref().and_then(|p| p.annotations.get(idx)).cloned() else {
+            return;
+        };
+        let discarded = annotation.straighten();
+        if discarded == 0 {
+            return;
+        }
+
+        // Save to history before making changes
+        if let Some(annotations) = self.project.as_ref().map(|p| p.annotations.clone()) {
+            self.save_to_history(&annotations);
+        }
+        if let Some(ref mut project) = self.project {
+            log::info!("Straightened '{}', discarding {} vertices", annotation.name, discarded);
+            project.annotations[idx] = annotation;
+        }
+    }
+
+    /// Show the prompt confirming a straighten that discards many vertices.
+    fn show_straighten_prompt(&mut self, ctx: &egui::Context) {
+        let Some(idx) = self.pending_straighten else {
+            return;
+        };
+        let Some(annotation) = self.project.as_ref().and_then(|p| p.annotations.get(idx)) else {
+            self.pending_straighten = None;
+            return;
+        };
+
+        let message = format!(
+            "Straightening \"{}\" discards {} of its {} vertices. Continue?",
+            annotation.name,
+            annotation.vertex_count() - 2,
+            annotation.vertex_count()
+        );
+
+        let mut choice = None;
+        egui::Window::new("Straighten Line")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+            .show(ctx, |ui| {
+                ui.label(message);
+                ui.horizontal(|ui| {
+                    if ui.button("Straighten").clicked() {
+                        choice = Some(true);
+                    }
+                    if ui.button("Cancel").clicked() {
+                        choice = Some(false);
+                    }
+                });
+            });
+
+        if let Some(straighten) = choice {
+            if straighten {
+                self.straighten_line(idx);
+            }
+            self.pending_straighten = None;
+        }
+    }
+
+    /// Selected polygon expanded or contracted by the offset distance, if
+    /// an offset is being previewed.
+    fn offset_preview(&self) -> Option<Vec<Point>> {
+        if self.offset_distance == 0.0 {
+            return None;
+        }
+        let project = self.project.as_ref()?;
+        let annotation = project.annotations.get(self.selected_annotation?)?;
+        (annotation.annotation_type == AnnotationType::Polygon).then(|| {
+            geometry::offset_polygon(&annotation.vertices.0, self.offset_distance, (project.frame_width, project.frame_height))
+        })
+    }
+
+    /// Replace the selected polygon with its offset preview.
+    fn offset_selected(&mut self) {
+        let Some(vertices) = self.offset_preview() else {
+            return;
+        };
+        if vertices.len() < 3 || geometry::has_self_intersection(&vertices, true) {
+            self.notifications.warning("The offset is too large for this polygon");
+            return;
+        }
+
+        // Save to history before making changes
+        if let Some(annotations) = self.project.as_ref().map(|p| p.annotations.clone()) {
+            self.save_to_history(&annotations);
+        }
+
+        if let (Some(project), Some(idx)) = (self.project.as_mut(), self.selected_annotation) {
+            project.annotations[idx].vertices.0 = vertices;
+            log::info!("Offset '{}' by {} px", project.annotations[idx].name, self.offset_distance);
+        }
+        self.offset_distance = 0.0;
+    }
+
+    /// Start a new annotation based on the current tool.
+    fn start_annotation(&mut self) {
+        let annotation_type = match self.current_tool {
+            Tool::Polygon | Tool::OrientedBox => AnnotationType::Polygon,
+            Tool::Line => AnnotationType::Line,
+            Tool::Point => AnnotationType::Point,
+            Tool::Select | Tool::Measure | Tool::Split | Tool::Weld | Tool::Stamp => return, // These tools don't create annotations
+        };
+
+        let existing = self.project.as_ref().map_or(&[][..], |p| p.annotations.as_slice());
+        let class = match self.locked_class {
+            Some(ref class) => Some(class.as_str()),
+            None if self.settings.inherit_class => annotation::inherited_class(existing, annotation_type),
+            None => None,
+        };
+        let name = annotation::new_annotation_name(annotation_type, class, self.annotation_counter, existing);
+
+        let mut annotation = Annotation::new(name, annotation_type);
+        annotation.group = self.layers.active.clone();
+
+        self.in_progress_annotation = Some(annotation);
+        self.borrow_anchor = None;
+    }
+
+    /// Get the points to add for a click while drawing.
+    ///
+    /// With edge borrowing enabled, a click near a neighbor's vertex snaps to it,
+    /// and a second click on the same neighbor follows its vertices in between.
+    fn borrowed_points(&mut self, point: Point) -> Vec<Point> {
+        if !self.settings.borrow_edges {
+            return vec![point];
+        }
+        let Some(ref project) = self.project else {
+            return vec![point];
+        };
+
+        let threshold = self.view.normalized_distance(self.settings.snap_radius, (project.frame_width, project.frame_height));
+        let hit = project.annotations.iter().enumerate().find_map(|(ann_idx, annotation)| {
+            annotation
+                .find_vertex_within_threshold(&point, threshold)
+                .map(|vertex_idx| (ann_idx, vertex_idx))
+        });
+        let Some((ann_idx, vertex_idx)) = hit else {
+            self.borrow_anchor = None;
+            return vec![point];
+        };
+
+        let neighbor = &project.annotations[ann_idx].vertices.0;
+        let points = match self.borrow_anchor {
+            Some((prev_ann, prev_vertex)) if prev_ann == ann_idx && prev_vertex != vertex_idx => {
+                let path = if project.annotations[ann_idx].is_closed() {
+                    geometry::shortest_ring_sub_path(neighbor, prev_vertex, vertex_idx)
+                } else {
+                    geometry::ring_sub_path(neighbor, prev_vertex, vertex_idx, vertex_idx > prev_vertex)
+                };
+                // The first point of the path was added by the previous click
+                path.into_iter().skip(1).collect()
+            }
+            _ => vec![neighbor[vertex_idx]],
+        };
+
+        self.borrow_anchor = Some((ann_idx, vertex_idx));
+        points
+    }
+
+    /// Finish the current in-progress annotation and add it to the project.
+    fn finish_annotation(&mut self) {
+        if let Some(annotation) = self.in_progress_annotation.take() {
+            if annotation.can_finish() {
+                // Clone annotations for history
+                let annotations_clone = self.project.as_ref()
+                    .map(|p| p.annotations.clone());
+
+                // Save to history before making changes
+                if let Some(annotations) = annotations_clone {
+                    self.save_to_history(&annotations);
+                }
+
+                // Now mutably borrow and make changes
+                if let Some(ref mut project) = self.project {
+                    let too_complex = annotation.exceeds_vertex_limit(self.settings.complexity_threshold);
+                    project.annotations.push(annotation);
+                    self.annotation_counter += 1;
+                    log::info!("Added annotation, total: {}", project.annotations.len());
+
+                    // Offer to simplify overly complex annotations
+                    if too_complex {
+                        self.pending_simplify = Some(project.annotations.len() - 1);
+                    }
+                }
+
+                match self.settings.after_finish {
+                    AfterFinish::Keep => {}
+                    AfterFinish::Continue => self.start_annotation(),
+                    AfterFinish::Select => self.current_tool = Tool::Select,
+                }
+            }
+        }
+    }
+
+    /// Simplify an annotation with the configured tolerance.
+    fn simplify_annotation(&mut self, idx: usize) {
+        let annotations_clone = self.project.as_ref()
+            .filter(|p| idx < p.annotations.len())
+            .map(|p| p.annotations.clone());
+
+        if let Some(annotations) = annotations_clone {
+            self.save_to_history(&annotations);
+        }
+
+        if let Some(ref mut project) = self.project {
+            if let Some(annotation) = project.annotations.get_mut(idx) {
+                let before = annotation.vertex_count();
+                annotation.vertices.0 = geometry::simplify_polygon(
+                    &annotation.vertices.0,
+                    self.settings.simplify_tolerance,
+                    annotation.is_closed(),
+                );
+                log::info!("Simplified annotation {} from {} to {} vertices",
+                    idx, before, annotation.vertex_count());
+            }
+        }
+    }
+
+    /// Show the prompt offering to simplify a complex annotation.
+    fn show_simplify_prompt(&mut self, ctx: &egui::Context) {
+        let Some(idx) = self.pending_simplify else {
+            return;
+        };
+        let Some(annotation) = self.project.as_ref().and_then(|p| p.annotations.get(idx)) else {
+            self.pending_simplify = None;
+            return;
+        };
+
+        let message = format!(
+            "\"{}\" has {} vertices (limit {}). Simplify it?",
+            annotation.name,
+            annotation.vertex_count(),
+            self.settings.complexity_threshold
+        );
+
+        let mut choice = None;
+        egui::Window::new("Complex Annotation")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+            .show(ctx, |ui| {
+                ui.label(message);
+                ui.horizontal(|ui| {
+                    if ui.button("Simplify").clicked() {
+                        choice = Some(true);
+                    }
+                    if ui.button("Keep").clicked() {
+                        choice = Some(false);
+                    }
+                });
+            });
+
+        if let Some(simplify) = choice {
+            if simplify {
+                self.simplify_annotation(idx);
+            }
+            self.pending_simplify = None;
+        }
+    }
+
+    /// Move every vertex outside the image onto its edge, as one undo step.
+    fn clamp_out_of_bounds(&mut self) {
+        // Nothing to clamp would leave an empty undo step
+        let Some(annotations) = self.project.as_ref()
+            .filter(|p| !validation::find_out_of_bounds(p).is_empty())
+            .map(|p| p.annotations.clone()) else {
+            return;
+        };
+        self.save_to_history(&annotations);
+
+        if let Some(ref mut project) = self.project {
+            let indices = validation::find_out_of_bounds(project);
+            let clamped: usize = indices.iter().map(|&idx| validation::clamp_annotation_to_bounds(&mut project.annotations[idx])).sum();
+            log::info!("Clamped {} vertices in {} annotations", clamped, indices.len());
+            self.notifications.info(format!("Clamped {} vertices in {} annotations", clamped, indices.len()));
+        }
+    }
+
+    /// Rename annotations as one undo step.
+    fn apply_renames(&mut self, renames: &[(usize, String)]) {
+        let Some(annotations) = self.project.as_ref().map(|p| p.annotations.clone()) else {
+            return;
+        };
+        self.save_to_history(&annotations);
+
+        if let Some(ref mut project) = self.project {
+            for (idx, name) in renames {
+                if let Some(annotation) = project.annotations.get_mut(*idx) {
+                    annotation.name = name.clone();
+                }
+            }
+        }
+        log::info!("Renamed {} annotations", renames.len());
+        self.notifications.info(format!("Renamed {} annotations", renames.len()));
+    }
+
+    /// Show the find-and-replace dialog with a preview of the new names.
+    fn show_rename_dialog(&mut self, ctx: &egui::Context) {
+        let (Some(dialog), Some(project)) = (&mut self.rename_dialog, &self.project) else {
+            self.rename_dialog = None;
+            return;
+        };
+
+        let renames = crate::util::rename::rename_annotations(&project.annotations, &dialog.find, &dialog.replace, dialog.use_regex);
+        let mut open = true;
+        let (mut apply, mut cancel) = (false, false);
+        egui::Window::new("Rename Annotations")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                egui::Grid::new("rename_inputs").num_columns(2).show(ui, |ui| {
+                    ui.label("Find:");
+                    ui.text_edit_singleline(&mut dialog.find);
+                    ui.end_row();
+                    ui.label("Replace with:");
+                    ui.text_edit_singleline(&mut dialog.replace);
+                    ui.end_row();
+                });
+                ui.checkbox(&mut dialog.use_regex, "Regular expression")
+                    .on_hover_text("Use $1 or ${name} in the replacement for captured groups");
+                ui.separator();
+
+                match renames {
+                    Ok(ref renames) if renames.is_empty() => {
+                        ui.label(egui::RichText::new("No names match").weak().italics());
+                    }
+                    Ok(ref renames) => {
+                        ui.label(format!("{} annotations will be renamed:", renames.len()));
+                        egui::ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+                            for (idx, name) in renames {
+                                ui.label(format!("{} → {}", project.annotations[*idx].name, name));
+                            }
+                        });
+                    }
+                    Err(ref e) => {
+                        ui.colored_label(egui::Color32::RED, format!("{:#}", e));
+                    }
+                }
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    let ready = renames.as_ref().is_ok_and(|r| !r.is_empty());
+                    if ui.add_enabled(ready, egui::Button::new("Apply")).clicked() {
+                        apply = true;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        cancel = true;
+                    }
+                });
+            });
+
+        if let (true, Ok(renames)) = (apply, renames) {
+            self.apply_renames(&renames);
+            cancel = true;
+        }
+        if !open || cancel {
+            self.rename_dialog = None;
+        }
+    }
+
+    /// Add an annotation with the given vertices to the project and select it.
+    fn add_annotation_from_points(&mut self, name: String, annotation_type: AnnotationType, points: Vec<Point>) {
+        let annotations_clone = self.project.as_ref().map(|p| p.annotations.clone());
+        if let Some(annotations) = annotations_clone {
+            self.save_to_history(&annotations);
+        }
+
+        if let Some(ref mut project) = self.project {
+            let mut annotation = Annotation::new(name, annotation_type);
+            for point in points {
+                annotation.add_vertex(point);
+            }
+            log::info!("Created \"{}\" from {} typed coordinates", annotation.name, annotation.vertex_count());
+            project.annotations.push(annotation);
+            self.annotation_counter = project.annotations.len();
+            self.selected_annotation = Some(project.annotations.len() - 1);
+        }
+    }
+
+    /// Show the dialog for creating an annotation from typed coordinates,
+    /// validating the input as it is edited.
+    fn show_coordinate_dialog(&mut self, ctx: &egui::Context) {
+        let (Some(dialog), Some(image_size)) = (&mut self.coordinate_dialog, self.image_size.filter(|_| self.project.is_some())) else {
+            self.coordinate_dialog = None;
+            return;
+        };
+
+        let parsed = crate::io::serialization::parse_coordinates(&dialog.text, dialog.annotation_type, dialog.normalized, image_size);
+        let mut open = true;
+        let (mut create, mut cancel) = (false, false);
+        egui::Window::new("New From Coordinates")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                egui::Grid::new("coordinate_inputs").num_columns(2).show(ui, |ui| {
+                    ui.label("Name:");
+                    ui.text_edit_singleline(&mut dialog.name);
+                    ui.end_row();
+                    ui.label("Type:");
+                    ui.horizontal(|ui| {
+                        ui.radio_value(&mut dialog.annotation_type, AnnotationType::Polygon, "Polygon");
+                        ui.radio_value(&mut dialog.annotation_type, AnnotationType::Line, "Line");
+                        ui.radio_value(&mut dialog.annotation_type, AnnotationType::Point, "Point");
+                    });
+                    ui.end_row();
+                    ui.label("Units:");
+                    ui.horizontal(|ui| {
+                        ui.radio_value(&mut dialog.normalized, false, format!("Pixels ({} × {})", image_size.0, image_size.1));
+                        ui.radio_value(&mut dialog.normalized, true, "Normalized (0-1)");
+                    });
+                    ui.end_row();
+                });
+                ui.label("Coordinates, one x,y pair per line:");
+                ui.add(egui::TextEdit::multiline(&mut dialog.text).code_editor().desired_rows(8).hint_text("120, 45\n300, 45\n300, 210"));
+
+                match parsed {
+                    Ok(ref points) => ui.label(format!("{} vertices", points.len())),
+                    Err(ref e) => ui.colored_label(egui::Color32::RED, format!("{:#}", e)),
+                };
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    let ready = parsed.is_ok() && !dialog.name.trim().is_empty();
+                    if ui.add_enabled(ready, egui::Button::new("Create")).clicked() {
+                        create = true;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        cancel = true;
+                    }
+                });
+            });
+
+        if let (true, Ok(points)) = (create, parsed) {
+            let (name, annotation_type) = (dialog.name.trim().to_string(), dialog.annotation_type);
+            self.add_annotation_from_points(name, annotation_type, points);
+            cancel = true;
+        }
+        if !open || cancel {
+            self.coordinate_dialog = None;
+        }
+    }
+
+    /// Show the dialog for choosing the crop rectangle of a cropped export.
+    fn show_crop_export_dialog(&mut self, ctx: &egui::Context) {
+        let (Some(dialog), Some(image_size)) = (&mut self.crop_export_dialog, self.image_size.filter(|_| self.project.is_some())) else {
+            self.crop_export_dialog = None;
+            return;
+        };
+
+        let visible = self.view.visible;
+        let selection_box = self
+            .selected_annotation
+            .and_then(|idx| self.project.as_ref()?.annotations.get(idx)?.bounding_box());
+        let mut open = true;
+        let (mut export, mut cancel) = (false, false);
+        egui::Window::new("Export Cropped")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label("Crop rectangle in pixels:");
+                egui::Grid::new("crop_inputs").num_columns(4).show(ui, |ui| {
+                    ui.label("X:");
+                    ui.add(egui::DragValue::new(&mut dialog.x).range(0..=image_size.0));
+                    ui.label("Y:");
+                    ui.add(egui::DragValue::new(&mut dialog.y).range(0..=image_size.1));
+                    ui.end_row();
+                    ui.label("Width:");
+                    ui.add(egui::DragValue::new(&mut dialog.width).range(0..=image_size.0));
+                    ui.label("Height:");
+                    ui.add(egui::DragValue::new(&mut dialog.height).range(0..=image_size.1));
+                    ui.end_row();
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Use:");
+                    if let (true, Some(region)) = (ui.add_enabled(visible.is_some(), egui::Button::new("Current View")).clicked(), visible) {
+                        dialog.set_region(region, image_size);
+                    }
+                    if let (true, Some(region)) = (ui.add_enabled(selection_box.is_some(), egui::Button::new("Selection")).on_hover_text("Bounding box of the selected annotation").clicked(), selection_box) {
+                        dialog.set_region(region, image_size);
+                    }
+                    if ui.button("Whole Image").clicked() {
+                        dialog.set_region((Point::new(0.0, 0.0), Point::new(1.0, 1.0)), image_size);
+                    }
+                });
+
+                let crop = dialog.crop(image_size);
+                if let Err(ref e) = crop {
+                    ui.colored_label(egui::Color32::RED, format!("{:#}", e));
+                } else {
+                    ui.label("Annotations outside the crop are dropped and the rest clipped to it.");
+                }
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui.add_enabled(crop.is_ok(), egui::Button::new("Export...")).clicked() {
+                        export = true;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        cancel = true;
+                    }
+                });
+            });
+
+        if let (true, Ok(crop)) = (export, dialog.crop(image_size)) {
+            if let Some(path) = self.file_dialog(DialogPurpose::Export)
+                .add_filter("JSON", &["json"])
+                .add_filter("YAML", &["yaml", "yml"])
+                .set_file_name("annotations_cropped.json")
+                .save_file()
+            {
+                self.recent_directories.remember(DialogPurpose::Export, &path);
+                self.request_export(PendingExport::Cropped(path, crop));
+                cancel = true;
+            }
+        }
+        if !open || cancel {
+            self.crop_export_dialog = None;
+        }
+    }
+
+    /// Show the window listing annotations with vertices outside the image.
+    fn show_out_of_bounds_window(&mut self, ctx: &egui::Context) {
+        if !self.show_out_of_bounds {
+            return;
+        }
+        let Some(ref project) = self.project else {
+            self.show_out_of_bounds = false;
+            return;
+        };
+
+        let indices = validation::find_out_of_bounds(project);
+        let mut open = true;
+        let (mut select, mut clamp) = (None, false);
+        egui::Window::new("Out-of-Bounds Annotations")
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                if indices.is_empty() {
+                    ui.label("All vertices are inside the image.");
+                    return;
+                }
+                ui.label(format!("{} annotations have vertices outside the image:", indices.len()));
+                egui::ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+                    for &idx in &indices {
+                        let annotation = &project.annotations[idx];
+                        let outside = annotation.vertices.0.iter().filter(|v| !(0.0..=1.0).contains(&v.x) || !(0.0..=1.0).contains(&v.y)).count();
+                        let text = format!("{} ({} of {} vertices)", annotation.name, outside, annotation.vertex_count());
+                        if ui.selectable_label(self.selected_annotation == Some(idx), text).clicked() {
+                            select = Some(idx);
+                        }
+                    }
+                });
+                ui.separator();
+                if ui.button("Clamp All").on_hover_text("Move outside vertices onto the nearest image edge").clicked() {
+                    clamp = true;
+                }
+            });
+
+        if let Some(idx) = select {
+            self.selected_annotation = Some(idx);
+            self.current_tool = Tool::Select;
+        }
+        if clamp {
+            self.clamp_out_of_bounds();
+        }
+        self.show_out_of_bounds = open;
+    }
+
+    /// Select every shown annotation whose centroid is inside a lasso loop.
+    fn lasso_select(&mut self, lasso: &[Point]) {
+        let Some(ref project) = self.project else {
+            return;
+        };
+        let (layers, selected, isolate) = (&self.layers, self.selected_annotation, self.isolate);
+        self.lasso_selection = canvas::lasso_selection(&project.annotations, lasso, |idx, annotation| {
+            canvas::is_shown(idx, annotation, layers, selected, isolate)
+        });
+        self.selected_annotation = self.lasso_selection.first().copied();
+        log::info!("Lasso selected {} annotations", self.lasso_selection.len());
+    }
+
+    /// Delete every lasso-selected annotation as a single undo step.
+    fn delete_lasso_selection(&mut self) {
+        let Some(annotations) = self.project.as_ref().map(|p| p.annotations.clone()) else {
+            return;
+        };
+        let pin = self.pin_key();
+        self.save_to_history(&annotations);
+
+        // Indices are ascending, so remove from the end
+        let indices = std::mem::take(&mut self.lasso_selection);
+        if let Some(ref mut project) = self.project {
+            for idx in indices.into_iter().rev() {
+                if idx < project.annotations.len() {
+                    project.annotations.remove(idx);
+                }
+            }
+            log::info!("Deleted selected annotations, total: {}", project.annotations.len());
+        }
+        self.selected_annotation = None;
+        self.repin(pin);
+    }
+
+    /// Flatten the lasso-selected polygons of one group into a single
+    /// multi-part polygon as a single undo step.
+    fn flatten_lasso_selection(&mut self) {
+        let Some(ref mut project) = self.project else {
+            return;
+        };
+        // Flattening checks the selection before changing anything
+        let annotations = project.annotations.clone();
+        let pin = self.pinned_annotation.and_then(|idx| Some((idx, annotations.get(idx)?.name.clone())));
+        match project.flatten(&self.lasso_selection) {
+            Ok(idx) => {
+                log::info!("Flattened {} polygons into {}", self.lasso_selection.len(), project.annotations[idx].name);
+                self.save_to_history(&annotations);
+                self.lasso_selection.clear();
+                self.selected_annotation = Some(idx);
+                self.repin(pin);
+            }
+            Err(e) => self.notifications.warning(format!("{:#}", e)),
+        }
+    }
+
+    /// Cancel the current in-progress annotation.
+    fn cancel_annotation(&mut self) {
+        self.in_progress_annotation = None;
+    }
+
+    /// Remove the most recent vertex of the in-progress annotation,
+    /// canceling it once no vertices remain. Does not touch the undo stack.
+    ///
+    /// Returns false if nothing is being drawn.
+    fn undo_last_vertex(&mut self) -> bool {
+        let Some(ref mut annotation) = self.in_progress_annotation else {
+            return false;
+        };
+        let count = annotation.vertex_count();
+        annotation.remove_vertex(count.saturating_sub(1));
+        log::info!("Removed last vertex, {} remaining", annotation.vertex_count());
+        if annotation.vertex_count() == 0 {
+            self.cancel_annotation();
+        }
+        // A borrowed vertex may have been removed
+        self.borrow_anchor = None;
+        true
+    }
+
+    /// Start an export, first asking the user to confirm if it would
+    /// overwrite an existing file.
+    fn request_export(&mut self, export: PendingExport) {
+        if export.target_file().is_some_and(|path| path.exists()) {
+            self.pending_overwrite = Some(export);
+        } else {
+            self.validate_and_export(export);
+        }
+    }
+
+    /// Validate the project and run the export, or hold it back and ask
+    /// the user what to do if any polygons are degenerate or any
+    /// coordinates are non-finite.
+    fn validate_and_export(&mut self, export: PendingExport) {
+        // YOLO-OBB writes one box per annotation, so it can't hold several parts
+        let multi_part = !matches!(export, PendingExport::YoloObb(_));
+        let issues = self.project.as_ref().map(|p| validation::validate_for_export(p, multi_part)).unwrap_or_default();
+        if issues.is_empty() {
+            self.run_export(export);
+        } else {
+            log::warn!("{} invalid annotations found before export", issues.len());
+            self.pending_export = Some((export, issues));
+        }
+    }
+
+    /// Run an export against the current project.
+    ///
+    /// Dataset exports merge straight edges first when enabled; project
+    /// saves, whole or per frame, always keep every vertex.
+    fn run_export(&mut self, export: PendingExport) {
+        if self.settings.merge_collinear_on_export && !matches!(export, PendingExport::Annotations(_) | PendingExport::Frames(..)) {
+            let merged = self.project.as_ref().map(|p| {
+                let mut merged = p.clone();
+                merged.remove_collinear(self.settings.collinear_tolerance.to_radians());
+                merged
+            });
+            let original = std::mem::replace(&mut self.project, merged);
+            self.dispatch_export(export);
+            self.project = original;
+        } else {
+            self.dispatch_export(export);
+        }
+    }
+
+    /// Call the exporter for a pending export.
+    fn dispatch_export(&mut self, export: PendingExport) {
+        match export {
+            PendingExport::Annotations(path) => self.export_annotations(path),
+            PendingExport::Coco(path) => self.export_coco(path),
+            PendingExport::GeoJson(path) => self.export_geojson(path),
+            PendingExport::Svg(path, embed_image) => self.export_svg(path, embed_image),
+            PendingExport::AnchoredYaml(path) => self.export_anchored_yaml(path),
+            PendingExport::Mesh(path) => self.export_mesh(path),
+            PendingExport::YoloObb(path) => self.export_yolo_obb(path),
+            PendingExport::Overlay(path) => self.export_overlay(path),
+            PendingExport::Individual(dir) => self.export_individual_annotations(dir),
+            PendingExport::Frames(dir, format) => self.export_frames(dir, format),
+            PendingExport::Cropped(path, crop) => self.export_cropped(path, crop),
+        }
+    }
+
+    /// Show the prompt asking whether an export may overwrite its file.
+    fn show_overwrite_prompt(&mut self, ctx: &egui::Context) {
+        let Some(path) = self.pending_overwrite.as_ref().and_then(PendingExport::target_file) else {
+            return;
+        };
+
+        let mut overwrite = None;
+        egui::Window::new("Overwrite File?")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+            .show(ctx, |ui| {
+                ui.label(format!("{} already exists. Replace it?", path.display()));
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui.button("Overwrite").clicked() {
+                        overwrite = Some(true);
+                    }
+                    if ui.button("Cancel").clicked() {
+                        overwrite = Some(false);
+                    }
+                });
+            });
+
+        match overwrite {
+            Some(true) => {
+                if let Some(export) = self.pending_overwrite.take() {
+                    self.validate_and_export(export);
+                }
+            }
+            Some(false) => self.pending_overwrite = None,
+            None => {}
+        }
+    }
+
+    /// Show the prompt listing invalid polygons found before an export.
+    ///
+    /// The user can select the first one to fix it, export without them,
+    /// export anyway, or cancel.
+    fn show_export_issues_prompt(&mut self, ctx: &egui::Context) {
+        let Some((_, ref issues)) = self.pending_export else {
+            return;
+        };
+
+        let mut choice = None;
+        egui::Window::new("Invalid Annotations")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+            .show(ctx, |ui| {
+                ui.label(format!("{} annotations may corrupt the exported data:", issues.len()));
+                egui::ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+                    for issue in issues {
+                        let name = self.project.as_ref()
+                            .and_then(|p| p.annotations.get(issue.index))
+                            .map_or("?", |a| a.name.as_str());
+                        ui.label(format!("• {}: {}", name, issue.kind.description()));
+                    }
+                });
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui.button("Fix").on_hover_text("Cancel the export and select the first invalid annotation").clicked() {
+                        choice = Some(ExportChoice::Fix);
+                    }
+                    if ui.button("Skip Invalid").clicked() {
+                        choice = Some(ExportChoice::Skip);
+                    }
+                    if ui.button("Export Anyway").clicked() {
+                        choice = Some(ExportChoice::ExportAnyway);
+                    }
+                    if ui.button("Cancel").clicked() {
+                        choice = Some(ExportChoice::Cancel);
+                    }
+                });
+            });
+
+        let Some(choice) = choice else {
+            return;
+        };
+        let Some((export, issues)) = self.pending_export.take() else {
+            return;
+        };
+        match choice {
+            ExportChoice::Fix => {
+                self.selected_annotation = issues.first().map(|issue| issue.index);
+                self.current_tool = Tool::Select;
+            }
+            ExportChoice::Skip => {
+                // Export a copy without the invalid annotations, then put the
+                // full project back
+                let filtered = self.project.as_ref().map(|p| validation::without_issues(p, &issues));
+                let original = std::mem::replace(&mut self.project, filtered);
+                self.run_export(export);
+                self.project = original;
+            }
+            ExportChoice::ExportAnyway => self.run_export(export),
+            ExportChoice::Cancel => {}
+        }
+    }
+
+    /// Export annotations to a file.
+    fn export_annotations(&mut self, path: std::path::PathBuf) {
+        if let Some(ref project) = self.project {
+            let extension = path.extension().and_then(|s| s.to_str());
+            let result = match extension {
+                Some("yaml") | Some("yml") => crate::io::serialization::export_yaml(project, &path),
+                Some("json") => crate::io::serialization::export_json(project, &path, self.settings.export_vertex_indices),
+                Some(crate::io::serialization::BINARY_EXTENSION) => crate::io::serialization::save_project_bin(project, &path),
+                _ => {
+                    log::error!("Unsupported file extension: {:?}", extension);
+                    return;
+                }
+            };
+
+            match result {
+                Ok(_) => {
+                    log::info!("Exported annotations to {}", path.display());
+                    self.last_session.annotation_path = Some(path);
+                    self.autosaved = Some(Snapshot::of(project));
+
+                    // The export supersedes any autosaves
+                    if let Some(ref media_path) = self.last_session.media_path {
+                        for removed in autosave::remove_autosaves(media_path, &self.settings.autosave_folder) {
+                            log::info!("Removed stale autosave {}", removed.display());
+                        }
+                    }
+                }
+                Err(e) => log::error!("Failed to export annotations: {}", e),
+            }
+        }
+    }
+
+    /// Export annotations re-normalized to a crop rectangle as JSON or YAML.
+    ///
+    /// The image is cropped the same way and saved as a PNG beside the
+    /// annotations, which refer to it, so the export reopens at the right
+    /// scale. Unlike a project save, this leaves the session's annotation
+    /// file and autosaves alone.
+    fn export_cropped(&mut self, path: std::path::PathBuf, crop: (Point, Point)) {
+        let Some(ref project) = self.project else {
+            return;
+        };
+        let mut cropped = project.cropped(crop);
+        let result = (|| -> anyhow::Result<()> {
+            let extension = path.extension().and_then(|s| s.to_str());
+            if !matches!(extension, Some("yaml") | Some("yml") | Some("json")) {
+                anyhow::bail!("Unsupported file extension: {:?}", extension);
+            }
+            if let Some(ref source) = self.source_image {
+                let (min, max) = crop;
+                let to_px = |p: &Point| ((p.x * source.width as f64).round() as u32, (p.y * source.height as f64).round() as u32);
+                let image_path = path.with_extension("png");
+                crate::io::media::save_image(&crate::io::media::crop_image(source, to_px(&min), to_px(&max)), &image_path)?;
+                cropped.media_file = image_path.to_string_lossy().into_owned();
+            }
+            match extension {
+                Some("json") => crate::io::serialization::export_json(&cropped, &path, self.settings.export_vertex_indices),
+                _ => crate::io::serialization::export_yaml(&cropped, &path),
+            }
+        })();
+        match result {
+            Ok(_) => log::info!(
+                "Exported {} of {} annotations cropped to {} × {} px to {}",
+                cropped.annotations.len(),
+                project.annotations.len(),
+                cropped.frame_width,
+                cropped.frame_height,
+                path.display()
+            ),
+            Err(e) => {
+                log::error!("Failed to export cropped annotations: {:#}", e);
+                self.notifications.error(format!("Failed to export cropped annotations: {:#}", e));
+            }
+        }
+    }
+
+    /// Look up the media file again when the project changes or every few
+    /// seconds, so a deleted file is noticed, and keep the window title
+    /// naming it.
+    fn refresh_media_info(&mut self, ctx: &egui::Context) {
+        let stale = match (&self.project, &self.media_info) {
+            (Some(project), Some(info)) => {
+                info.path.as_os_str() != project.media_file.as_str()
+                    || (info.width, info.height) != (project.frame_width, project.frame_height)
+                    || self.media_checked.elapsed() >= MEDIA_CHECK_INTERVAL
+            }
+            (Some(_), None) => true,
+            (None, info) => info.is_some(),
+        };
+        if !stale {
+            return;
+        }
+
+        let info = self.project.as_ref().map(MediaInfo::read);
+        self.media_checked = Instant::now();
+        let title = |info: Option<&MediaInfo>| info.map_or_else(|| DEFAULT_TITLE.to_string(), MediaInfo::window_title);
+        if title(info.as_ref()) != title(self.media_info.as_ref()) {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Title(title(info.as_ref())));
+        }
+        if let Some(ref info) = info {
+            if info.missing && !self.media_info.as_ref().is_some_and(|old| old.missing) {
+                log::warn!("Media file {} is missing", info.path.display());
+            }
+        }
+        self.media_info = info;
+        ctx.request_repaint_after(MEDIA_CHECK_INTERVAL);
+    }
+
+    /// Autosave the project if the interval has elapsed and annotations changed.
+    ///
+    /// If the configured location can't be written, the autosave goes to the
+    /// temp dir instead.
+    fn autosave_if_due(&mut self, ctx: &egui::Context) {
+        if self.settings.autosave_interval == 0 {
+            return;
+        }
+        let interval = Duration::from_secs(self.settings.autosave_interval);
+        let elapsed = self.last_autosave.elapsed();
+        if elapsed < interval {
+            ctx.request_repaint_after(interval - elapsed);
+            return;
+        }
+        self.last_autosave = Instant::now();
+        ctx.request_repaint_after(interval);
+
+        let Some(ref project) = self.project else {
+            return;
+        };
+        if self.autosaved.as_ref() == Some(&Snapshot::of(project)) {
+            return;
+        }
+
+        let media_path = self.last_session.media_path.clone()
+            .unwrap_or_else(|| std::path::PathBuf::from(&project.media_file));
+        let format = self.settings.autosave_format;
+        let path = autosave::autosave_path(
+            &media_path,
+            self.settings.autosave_location,
+            &self.settings.autosave_folder,
+            format,
+        );
+
+        let result = autosave::write_autosave(project, &path, format).map(|_| path.clone()).or_else(|e| {
+            let fallback = autosave::autosave_path(
+                &media_path,
+                AutosaveLocation::TempDir,
+                &self.settings.autosave_folder,
+                format,
+            );
+            log::warn!("Failed to autosave to {}: {:#}", path.display(), e);
+            self.notifications.warning(format!(
+                "Can't autosave to {}, using {} instead",
+                path.display(),
+                fallback.display()
+            ));
+            autosave::write_autosave(project, &fallback, format).map(|_| fallback)
+        });
+
+        match result {
+            Ok(path) => {
+                log::info!("Autosaved annotations to {}", path.display());
+                self.autosaved = Some(Snapshot::of(project));
+            }
+            Err(e) => {
+                log::error!("Failed to autosave: {:#}", e);
+                self.notifications.error(format!("Failed to autosave: {:#}", e));
+            }
+        }
+    }
+
+    /// Export the source image with annotation outlines drawn into it.
+    fn export_overlay(&mut self, path: std::path::PathBuf) {
+        let (Some(project), Some(source)) = (&self.project, &self.source_image) else {
+            return;
+        };
+
+        let mut overlay = source.clone();
+        let width = (source.width.max(source.height) as f64 / 500.0).max(2.0);
+        for annotation in project.exported_annotations() {
+            let stroke = raster::RasterStroke {
+                width,
+                color: annotation.display_color(),
+                antialias: self.settings.antialiasing,
+                cap: self.settings.line_cap,
+            };
+            raster::draw_annotation(&mut overlay, annotation, &stroke);
+        }
+
+        match crate::io::media::save_image(&overlay, &path) {
+            Ok(_) => {
+                log::info!("Exported overlay to {}", path.display());
+                self.notifications.info(format!("Exported overlay to {}", path.display()));
+            }
+            Err(e) => {
+                log::error!("Failed to export overlay: {:#}", e);
+                self.notifications.error(format!("Failed to export overlay: {:#}", e));
+            }
+        }
+    }
+
+    /// Export each annotation as a cropped image plus a JSON description
+    /// (on a worker thread).
+    fn export_individual_annotations(&mut self, dir: std::path::PathBuf) {
+        let (Some(project), Some(source)) = (&self.project, &self.source_image) else {
+            return;
+        };
+        if self.batch_job.is_some() {
+            self.notifications.warning("Wait for the current folder operation to finish");
+            return;
+        }
+
+        let (project, source) = (project.clone(), source.clone());
+        self.batch_job = Some(BatchJob::spawn("Exporting annotations", move |progress| {
+            let result = crate::io::media::export_annotation_crops(&project, &source, &dir, &progress);
+            BatchOutcome::Crops(dir, result)
+        }));
+    }
+
+    /// Report a finished annotation crop export.
+    fn finish_crop_export(&mut self, dir: std::path::PathBuf, result: anyhow::Result<usize>, cancelled: bool) {
+        match result {
+            Ok(count) if cancelled => {
+                log::info!("Annotation crop export cancelled after {} to {}", count, dir.display());
+                self.notifications.warning(format!("Export cancelled; {} annotations written to {}", count, dir.display()));
+            }
+            Ok(count) => {
+                log::info!("Exported {} annotation crops to {}", count, dir.display());
+                self.notifications.info(format!("Exported {} annotations to {}", count, dir.display()));
+            }
+            Err(e) => {
+                log::error!("Failed to export annotation crops: {:#}", e);
+                self.notifications.error(format!("Failed to export annotation crops: {:#}", e));
+            }
+        }
+    }
+
+    /// Parse an annotation file and show a preview to confirm before it
+    /// replaces the current project.
+    fn import_annotations(&mut self, path: std::path::PathBuf, _ctx: &egui::Context) {
+        match validation::preview_import(&path) {
+            Ok(preview) => self.pending_import = Some((path, preview)),
+            Err(e) => {
+                log::error!("Failed to import {}: {:#}", path.display(), e);
+                self.notifications.error(format!("Failed to import annotations: {:#}", e));
+            }
+        }
+    }
+
+    /// Load an annotation file and its image without the import preview,
+    /// for files the user didn't just pick (the last session or a file
+    /// named on the command line).
+    fn load_annotations(&mut self, path: std::path::PathBuf) {
+        match crate::io::serialization::import_file(&path) {
+            Ok(data) => self.load_imported_project(path, data),
+            Err(e) => {
+                log::error!("Failed to import {}: {:#}", path.display(), e);
+                self.notifications.error(format!("Failed to import annotations: {:#}", e));
+            }
+        }
+    }
+
+    /// Collect the result of a finished folder operation.
+    fn poll_batch_job(&mut self, ctx: &egui::Context) {
+        let Some(ref mut job) = self.batch_job else {
+            return;
+        };
+        let Some(result) = job.poll() else {
+            // Keep repainting so the progress bar advances
+            ctx.request_repaint();
+            return;
+        };
+        let cancelled = job.is_cancelled();
+        self.batch_job = None;
+
+        match result {
+            Ok(BatchOutcome::FolderImport(dir, result)) => self.finish_folder_import(dir, result),
+            Ok(BatchOutcome::Crops(dir, result)) => self.finish_crop_export(dir, result, cancelled),
+            Err(e) => {
+                log::error!("{:#}", e);
+                self.notifications.error(format!("{:#}", e));
+            }
+        }
+    }
+
+    /// Show the running folder operation's progress with a cancel button.
+    fn show_batch_progress(&mut self, ctx: &egui::Context) {
+        let Some(ref job) = self.batch_job else {
+            return;
+        };
+
+        egui::Window::new(&job.progress.label)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_BOTTOM, egui::vec2(0.0, -40.0))
+            .show(ctx, |ui| {
+                let bar = match job.progress.fraction() {
+                    Some(fraction) => egui::ProgressBar::new(fraction),
+                    None => egui::ProgressBar::new(0.0).animate(true),
+                };
+                ui.add(bar.text(job.progress.text()).desired_width(320.0));
+                ui.horizontal(|ui| {
+                    if job.is_cancelled() {
+                        ui.spinner();
+                        ui.label("Cancelling...");
+                    } else if ui.button("Cancel").on_hover_text("Stop after the current file").clicked() {
+                        job.cancel();
+                    }
+                });
+            });
+    }
+
+    /// Show the import preview with the file's contents and any warnings.
+    fn show_import_preview(&mut self, ctx: &egui::Context) {
+        let Some((ref path, ref preview)) = self.pending_import else {
+            return;
+        };
+
+        let mut choice = None;
+        let mut open = None;
+        egui::Window::new("Import Preview")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+            .show(ctx, |ui| {
+                ui.label(egui::RichText::new(path.display().to_string()).strong());
+                egui::Grid::new("import_preview").num_columns(2).show(ui, |ui| {
+                    ui.label("Image:");
+                    ui.label(&preview.data.media_file);
+                    ui.end_row();
+                    ui.label("Frame size:");
+                    ui.label(format!("{} × {}", preview.data.frame_width, preview.data.frame_height));
+                    ui.end_row();
+                    ui.label("Polygons:");
+                    ui.label(preview.polygons.to_string());
+                    ui.end_row();
+                    ui.label("Lines:");
+                    ui.label(preview.lines.to_string());
+                    ui.end_row();
+                    ui.label("Points:");
+                    ui.label(preview.points.to_string());
+                    ui.end_row();
+                });
+
+                if !preview.warnings.is_empty() || !preview.unclosed.is_empty() {
+                    ui.separator();
+                    egui::ScrollArea::vertical().max_height(150.0).show(ui, |ui| {
+                        for warning in &preview.warnings {
+                            ui.colored_label(egui::Color32::YELLOW, format!("⚠ {}", warning));
+                        }
+                        for &index in &preview.unclosed {
+                            let annotation = &preview.data.annotations[index];
+                            ui.horizontal(|ui| {
+                                ui.colored_label(egui::Color32::YELLOW, format!(
+                                    "⚠ {}: outline looks unclosed, with a long gap from the last vertex back to the first",
+                                    annotation.name
+                                ));
+                                if ui.add_enabled(!annotation.is_multi_part(), egui::Button::new("Convert to line").small())
+                                    .on_hover_text("Keep the outline open instead of closing it across the gap")
+                                    .clicked()
+                                {
+                                    open = Some(index);
+                                }
+                            });
+                        }
+                    });
+                }
+                let sizes_match = self.project.as_ref()
+                    .map(|p| (p.frame_width, p.frame_height) == (preview.data.frame_width, preview.data.frame_height));
+                if let Some(ref project) = self.project {
+                    ui.separator();
+                    ui.label(format!(
+                        "Replace the current {} annotations, or merge these in alongside them?",
+                        project.annotations.len()
+                    ));
+                    if sizes_match == Some(false) {
+                        ui.colored_label(egui::Color32::YELLOW, format!(
+                            "⚠ Frame size differs from the current {} × {}; merging would need the annotations reprojected",
+                            project.frame_width, project.frame_height
+                        ));
+                    }
+                }
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    let replace = if self.project.is_some() { "Replace" } else { "Confirm" };
+                    if ui.button(replace).clicked() {
+                        choice = Some(ImportChoice::Replace);
+                    }
+                    if sizes_match.is_some()
+                        && ui.add_enabled(sizes_match == Some(true), egui::Button::new("Merge"))
+                            .on_hover_text("Append to the current annotations, renaming duplicates")
+                            .clicked()
+                    {
+                        choice = Some(ImportChoice::Merge);
+                    }
+                    if ui.button("Cancel").clicked() {
+                        choice = Some(ImportChoice::Cancel);
+                    }
+                });
+            });
+
+        if let (Some(index), Some((_, ref mut preview))) = (open, self.pending_import.as_mut()) {
+            let image_size = validation::pixel_size(&preview.data);
+            if validation::open_unclosed(&mut preview.data.annotations[index], image_size) {
+                log::info!("Converted '{}' to a line", preview.data.annotations[index].name);
+                preview.unclosed.retain(|&i| i != index);
+                preview.polygons -= 1;
+                preview.lines += 1;
+            }
+        }
+
+        match (choice, self.pending_import.take()) {
+            (Some(ImportChoice::Replace), Some((path, preview))) => self.load_imported_project(path, preview.data),
+            (Some(ImportChoice::Merge), Some((path, preview))) => self.merge_imported_project(&path, preview.data),
+            (None, pending) => self.pending_import = pending,
+            _ => log::info!("Import cancelled"),
+        }
+    }
+
+    /// Merge a confirmed import into the current project.
+    ///
+    /// The incoming annotations are turned to match the image as it's shown
+    /// now, and an undo step is only recorded if the merge succeeds.
+    fn merge_imported_project(&mut self, path: &std::path::Path, mut data: ProjectData) {
+        let Some(ref project) = self.project else {
+            return;
+        };
+        data.orient_like(project);
+        let count = data.annotations.len();
+        let mut merged = project.clone();
+        match crate::io::serialization::merge_projects(&mut merged, data) {
+            Ok(renamed) => {
+                let annotations = project.annotations.clone();
+                self.save_to_history(&annotations);
+                self.annotation_counter = merged.annotations.len();
+                self.project = Some(merged);
+                log::info!("Merged {} annotations from {} ({} renamed)", count, path.display(), renamed);
+                let renamed = if renamed > 0 { format!(", {} renamed to avoid duplicates", renamed) } else { String::new() };
+                self.notifications.info(format!("Merged {} annotations from {}{}", count, path.display(), renamed));
+            }
+            Err(e) => {
+                log::error!("Failed to merge {}: {:#}", path.display(), e);
+                self.notifications.error(format!("Failed to merge annotations: {:#}", e));
+            }
+        }
+    }
+
+    /// Load the image for a confirmed import (asynchronously).
+    fn load_imported_project(&mut self, path: std::path::PathBuf, project_data: ProjectData) {
+        self.last_session.annotation_path = Some(path.clone());
+
+        let (sender, receiver) = channel();
+        self.image_loader = Some(receiver);
+        self.loading_message = Some("Loading annotations and image...".to_string());
+
+        // Spawn background thread for loading
+        std::thread::spawn(move || {
+            let result = (|| -> Result<LoadedImageData, String> {
+                log::info!("Imported {} annotations from {}",
+                    project_data.annotations.len(), path.display());
+
+                // Load the referenced image file
+                let image_path = std::path::PathBuf::from(&project_data.media_file);
+                if !image_path.exists() {
+                    return Err(format!("Referenced image not found: {}", image_path.display()));
+                }
+
+                let loaded_img = crate::io::media::load_image(&image_path)
+                    .map_err(|e| format!("Failed to load image: {}", e))?;
+                let loaded_img = crate::io::media::orient_image(&loaded_img, &project_data);
+
+                log::info!("Loaded image: {}", image_path.display());
+
+                Ok(LoadedImageData {
+                    image: loaded_img,
+                    project: Some(project_data),
+                    notices: Vec::new(),
+                })
+            })();
+
+            let _ = sender.send(result);
+        });
+    }
+
+    /// Import GeoJSON polygons and lines into the current project.
+    fn import_geojson(&mut self, path: std::path::PathBuf) {
+        let Some(image_size) = self.image_size.filter(|_| self.project.is_some()) else {
+            self.notifications.warning("Open an image before importing GeoJSON");
+            return;
+        };
+
+        let data = match crate::io::serialization::import_geojson(&path, image_size) {
+            Ok(data) => data,
+            Err(e) => {
+                log::error!("Failed to import GeoJSON: {:#}", e);
+                self.notifications.error(format!("Failed to import GeoJSON: {:#}", e));
+                return;
+            }
+        };
+
+        let annotations_clone = self.project.as_ref().map(|p| p.annotations.clone());
+        if let Some(annotations) = annotations_clone {
+            self.save_to_history(&annotations);
+        }
+
+        if let Some(ref mut project) = self.project {
+            let count = data.annotations.len();
+            project.annotations.extend(data.annotations);
+            self.annotation_counter = project.annotations.len();
+            log::info!("Imported {} features from {}", count, path.display());
+            self.notifications.info(format!("Imported {} features from {}", count, path.display()));
+        }
+    }
+
+    /// Ask how to interpret a CSV coordinate list before importing it.
+    fn show_csv_import_prompt(&mut self, ctx: &egui::Context) {
+        let Some(ref mut options) = self.pending_csv_import else {
+            return;
+        };
+
+        let mut choice = None;
+        egui::Window::new("Import CSV")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+            .show(ctx, |ui| {
+                ui.label(egui::RichText::new(options.path.display().to_string()).strong());
+                ui.label("Rows are grouped by name into one annotation each.");
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.label("Shapes:");
+                    ui.radio_value(&mut options.annotation_type, AnnotationType::Polygon, "Polygons");
+                    ui.radio_value(&mut options.annotation_type, AnnotationType::Line, "Polylines");
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Coordinates:");
+                    ui.radio_value(&mut options.normalized, false, "Pixels");
+                    ui.radio_value(&mut options.normalized, true, "Normalized (0-1)");
+                });
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui.button("Import").clicked() {
+                        choice = Some(true);
+                    }
+                    if ui.button("Cancel").clicked() {
+                        choice = Some(false);
+                    }
+                });
+            });
+
+        match (choice, self.pending_csv_import.take()) {
+            (Some(true), Some(options)) => self.import_csv(options),
+            (None, pending) => self.pending_csv_import = pending,
+            _ => log::info!("CSV import cancelled"),
+        }
+    }
+
+    /// Import polygons or polylines from a CSV coordinate list into the current project.
+    fn import_csv(&mut self, options: CsvImport) {
+        let Some(image_size) = self.image_size.filter(|_| self.project.is_some()) else {
+            self.notifications.warning("Open an image before importing CSV");
+            return;
+        };
+
+        let imported = match crate::io::serialization::import_csv(&options.path, options.normalized, image_size, options.annotation_type) {
+            Ok(imported) => imported,
+            Err(e) => {
+                log::error!("Failed to import CSV: {:#}", e);
+                self.notifications.error(format!("Failed to import CSV: {:#}", e));
+                return;
+            }
+        };
+        if imported.is_empty() {
+            self.notifications.warning(format!("No annotations found in {}", options.path.display()));
+            return;
+        }
+
+        let annotations_clone = self.project.as_ref().map(|p| p.annotations.clone());
+        if let Some(annotations) = annotations_clone {
+            self.save_to_history(&annotations);
+        }
+
+        if let Some(ref mut project) = self.project {
+            let count = imported.len();
+            project.annotations.extend(imported);
+            self.annotation_counter = project.annotations.len();
+            log::info!("Imported {} annotations from {}", count, options.path.display());
+            self.notifications.info(format!("Imported {} annotations from {}", count, options.path.display()));
+        }
+    }
+
+    /// Load a second annotation set to overlay for comparison.
+    fn load_comparison(&mut self, path: std::path::PathBuf) {
+        match crate::io::serialization::import_file(&path) {
+            Ok(mut reference) => {
+                if let Some(ref project) = self.project {
+                    // Turn the reference to match the image as it's shown now
+                    reference.orient_like(project);
+                    if (reference.frame_width, reference.frame_height) != (project.frame_width, project.frame_height) {
+                        self.notifications.warning(format!(
+                            "Comparison was made on a {}x{} frame, this image is {}x{}",
+                            reference.frame_width, reference.frame_height, project.frame_width, project.frame_height
+                        ));
+                    }
+                }
+                log::info!("Loaded {} comparison annotations from {}", reference.annotations.len(), path.display());
+                self.reference_project = Some(reference);
+            }
+            Err(e) => {
+                log::error!("Failed to load comparison: {:#}", e);
+                self.notifications.error(format!("Failed to load comparison: {:#}", e));
+            }
+        }
+    }
+
+    /// Export annotations as a COCO dataset.
+    fn export_coco(&mut self, path: std::path::PathBuf) {
+        let Some(ref project) = self.project else {
+            return;
+        };
+
+        match crate::io::coco::export_coco(project, &path) {
+            Ok(_) => log::info!("Exported COCO to {}", path.display()),
+            Err(e) => {
+                log::error!("Failed to export COCO: {:#}", e);
+                self.notifications.error(format!("Failed to export COCO: {:#}", e));
+            }
+        }
+    }
+
+    /// Export one annotation file per annotated video frame.
+    fn export_frames(&mut self, dir: std::path::PathBuf, format: AnnotationFormat) {
+        let Some(ref project) = self.project else {
+            return;
+        };
+
+        match crate::io::serialization::export_frames(project, &dir, format) {
+            Ok(written) => {
+                log::info!("Exported {} frame files to {}", written.len(), dir.display());
+                self.notifications.info(format!("Exported {} frame files to {}", written.len(), dir.display()));
+            }
+            Err(e) => {
+                log::error!("Failed to export frames: {:#}", e);
+                self.notifications.error(format!("Failed to export frames: {:#}", e));
+            }
+        }
+    }
+
+    /// Export annotations as GeoJSON.
+    fn export_geojson(&mut self, path: std::path::PathBuf) {
+        let Some(ref project) = self.project else {
+            return;
+        };
+
+        match crate::io::serialization::export_geojson(project, &path) {
+            Ok(_) => log::info!("Exported GeoJSON to {}", path.display()),
+            Err(e) => {
+                log::error!("Failed to export GeoJSON: {:#}", e);
+                self.notifications.error(format!("Failed to export GeoJSON: {:#}", e));
+            }
+        }
+    }
+
+    /// Export annotation outlines as SVG, optionally over the current image.
+    fn export_svg(&mut self, path: std::path::PathBuf, embed_image: bool) {
+        let Some(ref project) = self.project else {
+            return;
+        };
+
+        let vertex_indices = self.settings.export_vertex_indices;
+        // The image in memory is already rotated and flipped to match
+        let result = match (embed_image, &self.source_image) {
+            (false, _) => crate::io::serialization::export_svg(project, &path, vertex_indices),
+            (true, Some(image)) => crate::io::serialization::export_svg_with_image(project, &path, image, vertex_indices),
+            (true, None) => Err(anyhow::anyhow!("No image is open to embed")),
+        };
+        match result {
+            Ok(_) => log::info!("Exported SVG to {}", path.display()),
+            Err(e) => {
+                log::error!("Failed to export SVG: {:#}", e);
+                self.notifications.error(format!("Failed to export SVG: {:#}", e));
+            }
+        }
+    }
+
+    /// Export annotations as YAML with shared vertices anchored.
+    fn export_anchored_yaml(&mut self, path: std::path::PathBuf) {
+        let Some(ref project) = self.project else {
+            return;
+        };
+
+        match crate::io::serialization::export_yaml_anchored(project, &path) {
+            Ok(_) => log::info!("Exported anchored YAML to {}", path.display()),
+            Err(e) => {
+                log::error!("Failed to export anchored YAML: {:#}", e);
+                self.notifications.error(format!("Failed to export anchored YAML: {:#}", e));
+            }
+        }
+    }
+
+    /// Export polygons as triangle meshes for rendering.
+    fn export_mesh(&mut self, path: std::path::PathBuf) {
+        let Some(ref project) = self.project else {
+            return;
+        };
+
+        match crate::io::mesh::export_mesh(project, &path) {
+            Ok(_) => log::info!("Exported mesh to {}", path.display()),
+            Err(e) => {
+                log::error!("Failed to export mesh: {:#}", e);
+                self.notifications.error(format!("Failed to export mesh: {:#}", e));
+            }
+        }
+    }
+
+    /// Export oriented boxes as a YOLO-OBB label file.
+    fn export_yolo_obb(&mut self, path: std::path::PathBuf) {
+        let Some(ref project) = self.project else {
+            return;
+        };
+
+        match crate::io::yolo::export_yolo_obb(project, &path) {
+            Ok(_) => log::info!("Exported YOLO OBB labels to {}", path.display()),
+            Err(e) => {
+                log::error!("Failed to export YOLO OBB labels: {:#}", e);
+                self.notifications.error(format!("Failed to export YOLO OBB labels: {:#}", e));
+            }
+        }
+    }
+
+    /// Import every annotation file in a folder (on a worker thread) and
+    /// append the annotations to the current project.
+    fn import_annotation_folder(&mut self, dir: std::path::PathBuf) {
+        if self.project.is_none() {
+            self.notifications.warning("Open an image before importing a folder of annotations");
+            return;
+        }
+        if self.batch_job.is_some() {
+            self.notifications.warning("Wait for the current folder operation to finish");
+            return;
+        }
+
+        self.batch_job = Some(BatchJob::spawn("Importing annotations", move |progress| {
+            let result = crate::io::serialization::import_folder(&dir, &progress);
+            BatchOutcome::FolderImport(dir, result)
+        }));
+    }
+
+    /// Append the annotations of a finished folder import, reporting
+    /// per-file failures. Cancelled imports change nothing.
+    fn finish_folder_import(&mut self, dir: std::path::PathBuf, result: anyhow::Result<BatchImportReport>) {
+        let report = match result {
+            Ok(report) => report,
+            Err(e) => {
+                log::error!("Failed to import folder: {:#}", e);
+                self.notifications.error(format!("Failed to import folder: {:#}", e));
+                return;
+            }
+        };
+        if report.cancelled {
+            log::info!("Folder import from {} cancelled", dir.display());
+            self.notifications.info("Folder import cancelled");
+            return;
+        }
+        if self.project.is_none() {
+            self.notifications.warning("Open an image before importing a folder of annotations");
+            return;
+        }
+
+        let mut renamed = 0;
+        if !report.imported.is_empty() {
+            let annotations_clone = self.project.as_ref().map(|p| p.annotations.clone());
+            if let Some(annotations) = annotations_clone {
+                self.save_to_history(&annotations);
+            }
+
+            if let Some(ref mut project) = self.project {
+                let before = validation::find_aspect_mismatches(project).len();
+                // Files may come from differently sized frames; each
+                // annotation remembers its own so mismatched aspect ratios
+                // can be flagged. Duplicate names are renamed as in a merge.
+                for (_, data) in report.imported.iter() {
+                    let mut data = data.clone();
+                    data.tag_source_frame();
+                    renamed += crate::io::serialization::append_annotations(project, data);
+                }
+                self.annotation_counter = project.annotations.len();
+                log::info!("Imported folder {}, total: {}", dir.display(), project.annotations.len());
+
+                let mismatched = validation::find_aspect_mismatches(project).len() - before;
+                if mismatched > 0 {
+                    self.notifications.warning(format!("{} imported annotations were drawn on a frame with a different aspect ratio", mismatched));
+                }
+            }
+        }
+
+        for (path, error) in &report.failed {
+            log::warn!("Failed to import {}: {}", path.display(), error);
+        }
+        let mut summary = report.summary();
+        if renamed > 0 {
+            summary.push_str(&format!(", {} renamed to avoid duplicates", renamed));
+        }
+        if report.failed.is_empty() {
+            self.notifications.info(summary);
+        } else {
+            self.notifications.warning(summary);
+        }
+    }
+
+    /// Load an image file and create a texture for display (asynchronously).
+    pub fn load_image_file(&mut self, path: std::path::PathBuf, _ctx: &egui::Context) {
+        self.load_image_with_annotations(path, None);
+    }
+
+    /// Remember the current image's view so it can be restored if the
+    /// image is opened again.
+    fn remember_image_state(&mut self) {
+        let (Some(_), Some(media_path)) = (&self.project, &self.last_session.media_path) else {
+            return;
+        };
+        let view = ViewTransform { pending_frame: None, ..self.view };
+        self.image_states.entry(media_path.clone()).or_default().view = view;
+    }
+
+    /// Restore the view remembered for an image, keeping the current
+    /// (fit-to-window or default) view for images not seen before.
+    fn restore_image_state(&mut self, media_path: &std::path::Path) {
+        if let Some(state) = self.image_states.get(media_path) {
+            self.view = state.view;
+            log::debug!("Restored view of {} at zoom {:.2}", media_path.display(), self.view.zoom);
+        }
+    }
+
+    /// Open every supported image in a folder for sequential annotation,
+    /// starting with the first.
+    fn open_folder(&mut self, dir: std::path::PathBuf, ctx: &egui::Context) {
+        let images = match crate::io::media::list_images(&dir) {
+            Ok(images) => images,
+            Err(e) => {
+                log::error!("Failed to open folder: {:#}", e);
+                self.notifications.error(format!("Failed to open folder: {:#}", e));
+                return;
+            }
+        };
+        if images.is_empty() {
+            self.notifications.warning(format!("No images found in {}", dir.display()));
+            return;
+        }
+
+        log::info!("Opened folder {} with {} images", dir.display(), images.len());
+        self.folder_images = images;
+        self.folder_index = 0;
+        self.load_image_file(self.folder_images[0].clone(), ctx);
+    }
+
+    /// Move to another image of the open folder, saving the current
+    /// annotations to their sidecar file first. Stays on the current image
+    /// if they can't be saved, so they aren't lost.
+    fn step_folder_image(&mut self, delta: isize, ctx: &egui::Context) {
+        if self.folder_images.is_empty() || self.image_loader.is_some() {
+            return;
+        }
+        let index = self.folder_index.saturating_add_signed(delta).min(self.folder_images.len() - 1);
+        if index == self.folder_index {
+            return;
+        }
+
+        let path = self.folder_images[self.folder_index].clone();
+        if let Err(e) = self.save_sidecar_if_changed() {
+            log::error!("Not leaving {}: {:#}", path.display(), e);
+            self.notifications.error(format!("{:#}; staying on this image so the annotations aren't lost", e));
+            return;
+        }
+        self.folder_index = index;
+        self.load_image_file(self.folder_images[index].clone(), ctx);
+    }
+
+    /// Write the annotations to the image's sidecar file if they changed
+    /// since it was loaded or last saved.
+    fn save_sidecar_if_changed(&mut self) -> anyhow::Result<()> {
+        let (Some(project), Some(media_path)) = (&self.project, &self.last_session.media_path) else {
+            return Ok(());
+        };
+        if self.sidecar_annotations.as_ref() == Some(&project.annotations) {
+            return Ok(());
+        }
+
+        let path = crate::io::serialization::find_sidecar(media_path)
+            .unwrap_or_else(|| media_path.with_extension("json"));
+        let result = match path.extension().and_then(|s| s.to_str()) {
+            Some("yaml") | Some("yml") => crate::io::serialization::export_yaml(project, &path),
+            _ => crate::io::serialization::export_json(project, &path, false),
+        };
+
+        result.with_context(|| format!("Failed to save {}", path.display()))?;
+        log::info!("Saved annotations to {}", path.display());
+        self.sidecar_annotations = Some(project.annotations.clone());
+        Ok(())
+    }
+
+    /// Load an image file, merging annotations from `annotation_path` (or a
+    /// sidecar file if none is given) into the new project.
+    fn load_image_with_annotations(
+        &mut self,
+        path: std::path::PathBuf,
+        annotation_path: Option<std::path::PathBuf>,
+    ) {
+        self.remember_image_state();
+        self.last_session.media_path = Some(path.clone());
+        self.last_session.annotation_path = annotation_path.clone();
+
+        let (sender, receiver) = channel();
+        self.image_loader = Some(receiver);
+        self.loading_message = Some("Loading image...".to_string());
+
+        let path_string = path.to_string_lossy().to_string();
+        let load_sidecar = self.settings.load_sidecar;
+
+        // Spawn background thread for loading
+        std::thread::spawn(move || {
+            let result = (|| -> Result<LoadedImageData, String> {
+                let loaded_img = crate::io::media::load_image(&path)
+                    .map_err(|e| format!("Failed to load image: {}", e))?;
+
+                log::info!("Loaded image: {} ({}x{}, {:?})", path.display(), loaded_img.width, loaded_img.height, loaded_img.color_type);
+
+                // Create project data
+                let mut project = ProjectData::new(
+                    path_string,
+                    loaded_img.width,
+                    loaded_img.height,
+                );
+
+                // Merge annotations from an explicit file, or else a sidecar next to the image
+                let mut notices = Vec::new();
+                let merge_path = annotation_path.or_else(|| {
+                    load_sidecar
+                        .then(|| crate::io::serialization::find_sidecar(&path))
+                        .flatten()
+                });
+                if let Some(merge_path) = merge_path {
+                    merge_annotation_file(&mut project, &merge_path, &mut notices);
+                }
+
+                // Show the image the way the annotations were made on it
+                let loaded_img = crate::io::media::orient_image(&loaded_img, &project);
+
+                Ok(LoadedImageData {
+                    image: loaded_img,
+                    project: Some(project),
+                    notices,
+                })
+            })();
+
+            let _ = sender.send(result);
+        });
+    }
+}
+
+/// Index after (or before) `current` in a list of `len`, wrapping around
+/// and skipping indices that aren't `selectable`. With nothing selected,
+/// starts from the first (or last) index.
+fn cycle_index(current: Option<usize>, len: usize, forward: bool, selectable: impl Fn(usize) -> bool) -> Option<usize> {
+    let start = match current.filter(|&i| i < len) {
+        Some(i) => i,
+        None if forward => len.checked_sub(1)?,
+        None => 0,
+    };
+    (1..=len)
+        .map(|step| if forward { (start + step) % len } else { (start + len - step) % len })
+        .find(|&i| selectable(i))
+}
+
+/// Merge annotations from a file into a project if the frame sizes match.
+fn merge_annotation_file(
+    project: &mut ProjectData,
+    path: &std::path::Path,
+    notices: &mut Vec<(NotificationLevel, String)>,
+) {
+    match crate::io::serialization::import_file(path) {
+        Ok(data) => {
+            // Rotate the (still empty) project to match the annotations
+            if project.annotations.is_empty() {
+                project.orient_like(&data);
+            }
+            let count = data.annotations.len();
+            match crate::io::serialization::merge_projects(project, data) {
+                Ok(_) => {
+                    log::info!("Loaded {} annotations from {}", count, path.display());
+                    notices.push((NotificationLevel::Info, format!("Loaded {} annotations from {}", count, path.display())));
+                }
+                Err(e) => {
+                    log::warn!("Ignoring {}: {:#}", path.display(), e);
+                    notices.push((NotificationLevel::Warning, format!("Ignored {}: {:#}", path.display(), e)));
+                }
+            }
+        }
+        Err(e) => {
+            log::warn!("Failed to load {}: {:#}", path.display(), e);
+            notices.push((NotificationLevel::Warning, format!(
+                "Failed to load {}: {:#}", path.display(), e
+            )));
+        }
+    }
+}
+
+impl eframe::App for RoidsApp {
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, SETTINGS_KEY, &self.settings);
+        eframe::set_value(storage, SESSION_KEY, &self.last_session);
+        eframe::set_value(storage, RECENT_DIRECTORIES_KEY, &self.recent_directories);
+        eframe::set_value(storage, KEYMAP_KEY, &self.keymap);
+    }
+
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        // Reopen the last session (deferred until a context is available)
+        if self.pending_restore {
+            self.pending_restore = false;
+            self.restore_session();
+        }
+        // Open the file given on the command line
+        if let Some(path) = self.pending_open.take() {
+            self.open_path(path, ctx);
+        }
+        self.show_restore_prompt(ctx);
+
+        // Check for a finished folder operation
+        self.poll_batch_job(ctx);
+        self.refresh_media_info(ctx);
+
+        // Check for completed image loading
+        if let Some(ref receiver) = self.image_loader {
+            if let Ok(result) = receiver.try_recv() {
+                self.image_loader = None;
+                self.loading_message = None;
+
+                match result {
+                    Ok(loaded_data) => {
+                        // Create egui texture from the loaded image data
+                        let loaded_image = loaded_data.image;
+                        if let Some(max_side) = self.upload_texture(ctx, &loaded_image) {
+                            self.notifications.warning(format!(
+                                "This {} × {} image is larger than the graphics card allows ({} px), so it is shown downscaled. Annotations and exports keep full resolution.",
+                                loaded_image.width, loaded_image.height, max_side
+                            ));
+                        }
+                        self.view.reset();
+                        self.source_image = Some(loaded_image);
+
+                        if let Some(project) = loaded_data.project {
                             // Update annotation counter based on loaded annotations
                             self.annotation_counter = project.annotations.len();
+                            self.last_session.media_path = Some(std::path::PathBuf::from(&project.media_file));
+                            self.autosaved = Some(Snapshot::of(&project));
+                            self.sidecar_annotations = Some(project.annotations.clone());
+                            if let Some((min, max)) = project.default_view {
+                                self.view.frame(min, max);
+                            }
+                            self.restore_image_state(std::path::Path::new(&project.media_file));
                             self.project = Some(project);
+                            self.selected_annotation = None;
+                            self.pinned_annotation = None;
+                            self.history.clear();
+                        }
+
+                        for (level, message) in loaded_data.notices {
+                            self.notifications.push(level, message);
                         }
 
                         log::info!("Image loaded successfully");
                     }
                     Err(e) => {
                         log::error!("Failed to load image: {}", e);
+                        self.notifications.error(e);
                     }
                 }
             }
@@ -355,45 +3188,250 @@ impl eframe::App for RoidsApp {
                 ui.menu_button("File", |ui| {
                     if ui.button("Open Image...").clicked() {
                         // Open native file picker
-                        if let Some(path) = rfd::FileDialog::new()
-                            .add_filter("Images", &["jpg", "jpeg", "png", "bmp", "tiff", "tif"])
+                        if let Some(path) = self.file_dialog(DialogPurpose::OpenImage)
+                            .add_filter("Images", crate::io::media::SUPPORTED_EXTENSIONS)
                             .pick_file()
                         {
+                            self.recent_directories.remember(DialogPurpose::OpenImage, &path);
+                            self.folder_images.clear();
                             self.load_image_file(path, ctx);
                         }
                         ui.close_menu();
                     }
+                    if ui.button("Open Folder...").clicked() {
+                        if let Some(dir) = self.file_dialog(DialogPurpose::OpenImage).pick_folder() {
+                            self.recent_directories.remember(DialogPurpose::OpenImage, &dir);
+                            self.open_folder(dir, ctx);
+                        }
+                        ui.close_menu();
+                    }
+                    let has_folder = !self.folder_images.is_empty();
+                    if ui.add_enabled(has_folder, egui::Button::new("Next Image").shortcut_text(self.keymap.shortcut_text(Action::NextImage))).clicked() {
+                        self.step_folder_image(1, ctx);
+                        ui.close_menu();
+                    }
+                    if ui.add_enabled(has_folder, egui::Button::new("Previous Image").shortcut_text(self.keymap.shortcut_text(Action::PreviousImage))).clicked() {
+                        self.step_folder_image(-1, ctx);
+                        ui.close_menu();
+                    }
+                    ui.separator();
                     if ui.button("Load Annotations...").clicked() {
-                        if let Some(path) = rfd::FileDialog::new()
-                            .add_filter("Annotations", &["yaml", "yml", "json"])
+                        if let Some(path) = self.file_dialog(DialogPurpose::OpenAnnotations)
+                            .add_filter("Annotations", &["yaml", "yml", "json", crate::io::serialization::BINARY_EXTENSION])
                             .pick_file()
                         {
+                            self.recent_directories.remember(DialogPurpose::OpenAnnotations, &path);
                             self.import_annotations(path, ctx);
                         }
                         ui.close_menu();
                     }
+                    if ui.button("Load Comparison...").clicked() {
+                        if let Some(path) = self.file_dialog(DialogPurpose::OpenAnnotations)
+                            .add_filter("Annotations", &["yaml", "yml", "json"])
+                            .pick_file()
+                        {
+                            self.recent_directories.remember(DialogPurpose::OpenAnnotations, &path);
+                            self.load_comparison(path);
+                        }
+                        ui.close_menu();
+                    }
+                    if ui.add_enabled(self.reference_project.is_some(), egui::Button::new("Clear Comparison")).clicked() {
+                        self.reference_project = None;
+                        ui.close_menu();
+                    }
+                    if ui.button("Import Annotation Folder...").clicked() {
+                        if let Some(dir) = self.file_dialog(DialogPurpose::OpenAnnotations).pick_folder() {
+                            self.recent_directories.remember(DialogPurpose::OpenAnnotations, &dir);
+                            self.import_annotation_folder(dir);
+                        }
+                        ui.close_menu();
+                    }
+                    if ui.button("Import GeoJSON...").clicked() {
+                        if let Some(path) = self.file_dialog(DialogPurpose::OpenAnnotations)
+                            .add_filter("GeoJSON", &["geojson", "json"])
+                            .pick_file()
+                        {
+                            self.recent_directories.remember(DialogPurpose::OpenAnnotations, &path);
+                            self.import_geojson(path);
+                        }
+                        ui.close_menu();
+                    }
+                    if ui.button("Import CSV...").clicked() {
+                        if let Some(path) = self.file_dialog(DialogPurpose::OpenAnnotations)
+                            .add_filter("CSV", &["csv", "txt"])
+                            .pick_file()
+                        {
+                            self.recent_directories.remember(DialogPurpose::OpenAnnotations, &path);
+                            self.pending_csv_import = Some(CsvImport {
+                                path,
+                                annotation_type: AnnotationType::Polygon,
+                                normalized: false,
+                            });
+                        }
+                        ui.close_menu();
+                    }
                     ui.separator();
                     ui.menu_button("Export Annotations", |ui| {
                         if ui.button("Export as YAML...").clicked() {
-                            if let Some(path) = rfd::FileDialog::new()
+                            if let Some(path) = self.file_dialog(DialogPurpose::Export)
                                 .add_filter("YAML", &["yaml", "yml"])
                                 .set_file_name("annotations.yaml")
                                 .save_file()
                             {
-                                self.export_annotations(path);
+                                self.recent_directories.remember(DialogPurpose::Export, &path);
+                                self.request_export(PendingExport::Annotations(path));
                             }
                             ui.close_menu();
                         }
                         if ui.button("Export as JSON...").clicked() {
-                            if let Some(path) = rfd::FileDialog::new()
+                            if let Some(path) = self.file_dialog(DialogPurpose::Export)
                                 .add_filter("JSON", &["json"])
                                 .set_file_name("annotations.json")
                                 .save_file()
                             {
-                                self.export_annotations(path);
+                                self.recent_directories.remember(DialogPurpose::Export, &path);
+                                self.request_export(PendingExport::Annotations(path));
+                            }
+                            ui.close_menu();
+                        }
+                        if ui.button("Export as YAML with Anchors...").on_hover_text("Write vertices shared between annotations once and alias them").clicked() {
+                            if let Some(path) = self.file_dialog(DialogPurpose::Export)
+                                .add_filter("YAML", &["yaml", "yml"])
+                                .set_file_name("annotations.yaml")
+                                .save_file()
+                            {
+                                self.recent_directories.remember(DialogPurpose::Export, &path);
+                                self.request_export(PendingExport::AnchoredYaml(path));
+                            }
+                            ui.close_menu();
+                        }
+                        if ui.button("Export as Binary Project...").on_hover_text("Compact format for projects with many vertices").clicked() {
+                            if let Some(path) = self.file_dialog(DialogPurpose::Export)
+                                .add_filter("Binary Project", &[crate::io::serialization::BINARY_EXTENSION])
+                                .set_file_name("annotations.roidsb")
+                                .save_file()
+                            {
+                                self.recent_directories.remember(DialogPurpose::Export, &path);
+                                self.request_export(PendingExport::Annotations(path));
+                            }
+                            ui.close_menu();
+                        }
+                        if ui.button("Export as COCO...").clicked() {
+                            if let Some(path) = self.file_dialog(DialogPurpose::Export)
+                                .add_filter("COCO JSON", &["json"])
+                                .set_file_name("annotations_coco.json")
+                                .save_file()
+                            {
+                                self.recent_directories.remember(DialogPurpose::Export, &path);
+                                self.request_export(PendingExport::Coco(path));
+                            }
+                            ui.close_menu();
+                        }
+                        if ui.button("Export as GeoJSON...").clicked() {
+                            if let Some(path) = self.file_dialog(DialogPurpose::Export)
+                                .add_filter("GeoJSON", &["geojson"])
+                                .set_file_name("annotations.geojson")
+                                .save_file()
+                            {
+                                self.recent_directories.remember(DialogPurpose::Export, &path);
+                                self.request_export(PendingExport::GeoJson(path));
+                            }
+                            ui.close_menu();
+                        }
+                        for (label, embed_image) in [("Export as SVG...", false), ("Export as SVG with Image...", true)] {
+                            if ui.button(label).clicked() {
+                                if let Some(path) = self.file_dialog(DialogPurpose::Export)
+                                    .add_filter("SVG", &["svg"])
+                                    .set_file_name("annotations.svg")
+                                    .save_file()
+                                {
+                                    self.recent_directories.remember(DialogPurpose::Export, &path);
+                                    self.request_export(PendingExport::Svg(path, embed_image));
+                                }
+                                ui.close_menu();
+                            }
+                        }
+                        if ui.button("Export as Triangle Mesh...").clicked() {
+                            if let Some(path) = self.file_dialog(DialogPurpose::Export)
+                                .add_filter("JSON", &["json"])
+                                .set_file_name("annotations_mesh.json")
+                                .save_file()
+                            {
+                                self.recent_directories.remember(DialogPurpose::Export, &path);
+                                self.request_export(PendingExport::Mesh(path));
+                            }
+                            ui.close_menu();
+                        }
+                        if ui.button("Export as YOLO OBB...").on_hover_text("Rectangular polygons as oriented boxes").clicked() {
+                            if let Some(path) = self.file_dialog(DialogPurpose::Export)
+                                .add_filter("YOLO labels", &["txt"])
+                                .set_file_name("labels.txt")
+                                .save_file()
+                            {
+                                self.recent_directories.remember(DialogPurpose::Export, &path);
+                                self.request_export(PendingExport::YoloObb(path));
+                            }
+                            ui.close_menu();
+                        }
+                        ui.separator();
+                        if ui.button("Export Overlay Image...").clicked() {
+                            if let Some(path) = self.file_dialog(DialogPurpose::Export)
+                                .add_filter("PNG", &["png"])
+                                .set_file_name("overlay.png")
+                                .save_file()
+                            {
+                                self.recent_directories.remember(DialogPurpose::Export, &path);
+                                self.request_export(PendingExport::Overlay(path));
+                            }
+                            ui.close_menu();
+                        }
+                        if ui.button("Export Individual Annotations...").clicked() {
+                            if let Some(dir) = self.file_dialog(DialogPurpose::Export).pick_folder() {
+                                self.recent_directories.remember(DialogPurpose::Export, &dir);
+                                self.request_export(PendingExport::Individual(dir));
+                            }
+                            ui.close_menu();
+                        }
+                        if ui.button("Export Cropped...").on_hover_text("Annotations relative to a crop rectangle").clicked() {
+                            let mut dialog = CropExportDialog::default();
+                            if let Some(image_size) = self.image_size {
+                                dialog.set_region(self.view.visible.unwrap_or((Point::new(0.0, 0.0), Point::new(1.0, 1.0))), image_size);
+                            }
+                            self.crop_export_dialog = Some(dialog);
+                            ui.close_menu();
+                        }
+                        let has_frames = self.project.as_ref().is_some_and(|p| !p.frames.is_empty());
+                        ui.add_enabled_ui(has_frames, |ui| {
+                            ui.menu_button("Export Per-Frame Annotations", |ui| {
+                                for format in [AnnotationFormat::Json, AnnotationFormat::Yaml] {
+                                    if ui.button(format!("as {}...", format.extension().to_uppercase())).clicked() {
+                                        if let Some(dir) = self.file_dialog(DialogPurpose::Export).pick_folder() {
+                                            self.recent_directories.remember(DialogPurpose::Export, &dir);
+                                            self.request_export(PendingExport::Frames(dir, format));
+                                        }
+                                        ui.close_menu();
+                                    }
+                                }
+                            });
+                        });
+                    });
+                    ui.menu_button("Activity Log", |ui| {
+                        ui.label(format!("{} events this session", self.activity.len()));
+                        if ui.button("Export as CSV...").clicked() {
+                            if let Some(path) = self.file_dialog(DialogPurpose::Export)
+                                .add_filter("CSV", &["csv"])
+                                .set_file_name("activity.csv")
+                                .save_file()
+                            {
+                                self.recent_directories.remember(DialogPurpose::Export, &path);
+                                self.export_activity_log(path);
                             }
                             ui.close_menu();
                         }
+                        if ui.add_enabled(!self.activity.is_empty(), egui::Button::new("Clear")).clicked() {
+                            self.activity.clear();
+                            ui.close_menu();
+                        }
                     });
                     ui.separator();
                     if ui.button("Quit").clicked() {
@@ -402,71 +3440,205 @@ impl eframe::App for RoidsApp {
                 });
 
                 ui.menu_button("Edit", |ui| {
-                    // Undo
-                    let can_undo = self.history.can_undo();
-                    if ui.add_enabled(can_undo, egui::Button::new("Undo (Ctrl+Z)")).clicked() {
-                        if let Some(ref mut project) = self.project {
-                            let current = project.annotations.clone();
-                            if let Some(previous) = self.history.undo(current) {
-                                project.annotations = previous;
-                                self.selected_annotation = None;
-                                log::info!("Undo from menu");
+                    // Undo (the last vertex while drawing)
+                    let drawing = self.in_progress_annotation.is_some();
+                    let can_undo = drawing || self.history.can_undo();
+                    let undo_label = if drawing { "Undo Vertex" } else { "Undo" };
+                    if ui.add_enabled(can_undo, egui::Button::new(undo_label).shortcut_text(self.keymap.shortcut_text(Action::Undo))).clicked() {
+                        if !self.undo_last_vertex() {
+                            self.undo();
+                        }
+                        ui.close_menu();
+                    }
+
+                    // Redo
+                    let can_redo = self.history.can_redo();
+                    if ui.add_enabled(can_redo, egui::Button::new("Redo").shortcut_text(self.keymap.shortcut_text(Action::Redo))).clicked() {
+                        self.redo();
+                        ui.close_menu();
+                    }
+
+                    ui.separator();
+
+                    // Delete Selected
+                    let has_selection = self.selected_annotation.is_some();
+                    if ui.add_enabled(has_selection, egui::Button::new("Delete Selected").shortcut_text(self.keymap.shortcut_text(Action::Delete))).clicked() {
+                        if self.lasso_selection.len() > 1 {
+                            self.delete_lasso_selection();
+                        } else if let Some(idx) = self.selected_annotation {
+                            if self.vertex_selection.vertices_of(idx).is_empty() {
+                                self.delete_annotation(idx);
+                            } else {
+                                self.delete_selected_vertices(idx);
                             }
                         }
                         ui.close_menu();
                     }
 
-                    // Redo
-                    let can_redo = self.history.can_redo();
-                    if ui.add_enabled(can_redo, egui::Button::new("Redo (Ctrl+Shift+Z)")).clicked() {
-                        if let Some(ref mut project) = self.project {
-                            let current = project.annotations.clone();
-                            if let Some(next) = self.history.redo(current) {
-                                project.annotations = next;
-                                self.selected_annotation = None;
-                                log::info!("Redo from menu");
-                            }
-                        }
+                    let selected_line = self
+                        .selected_annotation
+                        .and_then(|idx| self.project.as_ref()?.annotations.get(idx))
+                        .is_some_and(|a| a.annotation_type == AnnotationType::Line && a.vertex_count() > 2);
+                    if ui.add_enabled(selected_line, egui::Button::new("Straighten Line"))
+                        .on_hover_text("Keep only the first and last vertices of the selected line")
+                        .clicked()
+                    {
+                        self.request_straighten_selected();
+                        ui.close_menu();
+                    }
+
+                    if ui.add_enabled(self.lasso_selection.len() > 1, egui::Button::new("Flatten Group"))
+                        .on_hover_text("Merge the selected polygons of one group into a single multi-part polygon")
+                        .clicked()
+                    {
+                        self.flatten_lasso_selection();
                         ui.close_menu();
                     }
 
                     ui.separator();
 
-                    // Delete Selected
-                    let has_selection = self.selected_annotation.is_some();
-                    if ui.add_enabled(has_selection, egui::Button::new("Delete Selected")).clicked() {
-                        if let Some(idx) = self.selected_annotation {
-                            // Clone annotations for history
-                            let annotations_clone = self.project.as_ref()
-                                .filter(|p| idx < p.annotations.len())
-                                .map(|p| p.annotations.clone());
-
-                            // Save to history before making changes
-                            if let Some(annotations) = annotations_clone {
-                                self.save_to_history(&annotations);
-                            }
+                    let lock_label = match self.locked_class {
+                        Some(ref class) => format!("Unlock Class '{}'", class),
+                        None => "Lock Class".to_string(),
+                    };
+                    if ui.add_enabled(self.selected_annotation.is_some() || self.locked_class.is_some(), egui::Button::new(lock_label).shortcut_text(self.keymap.shortcut_text(Action::LockClass)))
+                        .on_hover_text("Name every new annotation after the selected annotation's class")
+                        .clicked()
+                    {
+                        self.toggle_class_lock();
+                        ui.close_menu();
+                    }
 
-                            // Now mutably borrow and make changes
-                            if let Some(ref mut project) = self.project {
-                                if idx < project.annotations.len() {
-                                    project.annotations.remove(idx);
-                                    self.selected_annotation = None;
-                                    log::info!("Deleted annotation from menu, total: {}", project.annotations.len());
-                                }
-                            }
-                        }
+                    if ui.add_enabled(self.project.is_some(), egui::Button::new("Rename...")).clicked() {
+                        self.rename_dialog = Some(RenameDialog::default());
+                        ui.close_menu();
+                    }
+
+                    if ui.add_enabled(self.project.is_some(), egui::Button::new("New From Coordinates...")).clicked() {
+                        let name = format!("{} {}", AnnotationType::Polygon.default_name_prefix(), self.annotation_counter + 1);
+                        self.coordinate_dialog = Some(CoordinateDialog {
+                            name,
+                            annotation_type: AnnotationType::Polygon,
+                            normalized: false,
+                            text: String::new(),
+                        });
+                        ui.close_menu();
+                    }
+
+                    if ui.add_enabled(self.project.is_some(), egui::Button::new("Find Out-of-Bounds...")).clicked() {
+                        self.show_out_of_bounds = true;
+                        ui.close_menu();
+                    }
+
+                    ui.separator();
+
+                    if ui.button("Preferences...").clicked() {
+                        self.show_preferences = true;
                         ui.close_menu();
                     }
                 });
 
                 ui.menu_button("View", |ui| {
                     if ui.button("Zoom In").clicked() {
+                        self.view.zoom_by(1.25);
                         ui.close_menu();
                     }
                     if ui.button("Zoom Out").clicked() {
+                        self.view.zoom_by(0.8);
                         ui.close_menu();
                     }
                     if ui.button("Reset Zoom").clicked() {
+                        self.view.reset();
+                        ui.close_menu();
+                    }
+                    ui.separator();
+                    let has_image = self.project.is_some() && self.source_image.is_some();
+                    if ui.add_enabled(has_image, egui::Button::new("Rotate 90° CW")).clicked() {
+                        self.rotate_image(true, ctx);
+                        ui.close_menu();
+                    }
+                    if ui.add_enabled(has_image, egui::Button::new("Rotate 90° CCW")).clicked() {
+                        self.rotate_image(false, ctx);
+                        ui.close_menu();
+                    }
+                    if ui.add_enabled(has_image, egui::Button::new("Flip Horizontal")).clicked() {
+                        self.flip_image(true, ctx);
+                        ui.close_menu();
+                    }
+                    if ui.add_enabled(has_image, egui::Button::new("Flip Vertical")).clicked() {
+                        self.flip_image(false, ctx);
+                        ui.close_menu();
+                    }
+                    ui.separator();
+                    let visible = self.view.visible;
+                    if ui.add_enabled(self.project.is_some() && visible.is_some(), egui::Button::new("Set as Default View")).clicked() {
+                        if let Some(ref mut project) = self.project {
+                            project.default_view = visible;
+                            log::info!("Set default view to {:?}", visible);
+                        }
+                        ui.close_menu();
+                    }
+                    let has_default = self.project.as_ref().is_some_and(|p| p.default_view.is_some());
+                    if ui.add_enabled(has_default, egui::Button::new("Clear Default View")).clicked() {
+                        if let Some(ref mut project) = self.project {
+                            project.default_view = None;
+                        }
+                        ui.close_menu();
+                    }
+                    if let Some(mut north) = self.project.as_ref().map(|p| p.north_offset_deg) {
+                        let mut edit_started = false;
+                        ui.horizontal(|ui| {
+                            let mut shown = north.is_some();
+                            if ui.checkbox(&mut shown, "North Arrow")
+                                .on_hover_text("Show which way north is on geo-referenced imagery")
+                                .changed()
+                            {
+                                north = shown.then_some(0.0);
+                                edit_started = true;
+                            }
+                            if let Some(ref mut degrees) = north {
+                                let response = ui.add(egui::DragValue::new(degrees).range(0.0..=359.9).speed(0.5).suffix("°"))
+                                    .on_hover_text("Direction of north, clockwise from the top of the image");
+                                edit_started |= response.drag_started() || response.gained_focus();
+                            }
+                        });
+                        // One undo step per toggle, drag, or typed value
+                        if edit_started {
+                            if let Some(annotations) = self.project.as_ref().map(|p| p.annotations.clone()) {
+                                self.save_to_history(&annotations);
+                            }
+                        }
+                        if let Some(ref mut project) = self.project {
+                            project.north_offset_deg = north;
+                        }
+                    }
+                    ui.separator();
+                    ui.checkbox(&mut self.settings.show_tool_palette, "Tool Palette");
+                    ui.checkbox(&mut self.settings.show_labels, "Show Labels");
+                    ui.checkbox(&mut self.settings.show_badges, "Show Number Badges")
+                        .on_hover_text("Number each annotation in list order, e.g. for screenshots");
+                    ui.checkbox(&mut self.settings.show_vertex_indices, "Show Vertex Indices");
+                    ui.checkbox(&mut self.settings.show_axes, "Show Axes");
+                    ui.checkbox(&mut self.settings.show_ruler_grid, "Ruler Grid");
+                    ui.checkbox(&mut self.settings.show_heatmap, "Density Heatmap");
+                    let pixels = self.settings.coordinate_unit == CoordinateUnit::Pixel;
+                    if ui.add(egui::SelectableLabel::new(pixels, "Pixel Coordinates"))
+                        .on_hover_text(self.keymap.shortcut_text(Action::ToggleCoordinateUnit))
+                        .clicked()
+                    {
+                        self.settings.coordinate_unit = self.settings.coordinate_unit.toggled();
+                        ui.close_menu();
+                    }
+                    if ui.add_enabled(self.selected_annotation.is_some() || self.isolate, egui::SelectableLabel::new(self.isolate, "Isolate Selected"))
+                        .on_hover_text(self.keymap.shortcut_text(Action::ToggleIsolate))
+                        .clicked()
+                    {
+                        self.toggle_isolate();
+                        ui.close_menu();
+                    }
+                    ui.separator();
+                    if ui.button("Statistics").clicked() {
+                        self.show_statistics = true;
                         ui.close_menu();
                     }
                 });
@@ -480,15 +3652,34 @@ impl eframe::App for RoidsApp {
         });
 
         // Toolbar
-        egui::TopBottomPanel::top("toolbar").show(ctx, |ui| {
-            toolbar::show(ui, &mut self.current_tool);
-        });
+        let toolbar_action = egui::TopBottomPanel::top("toolbar").show(ctx, |ui| {
+            toolbar::show(
+                ui,
+                &mut self.current_tool,
+                &mut self.settings,
+                self.in_progress_annotation.as_ref(),
+            )
+        }).inner;
+
+        if let canvas::CanvasAction::FinishAnnotation = toolbar_action {
+            self.finish_annotation();
+        }
 
         // Properties panel (right side)
         let properties_action = egui::SidePanel::right("properties")
             .default_width(250.0)
             .show(ctx, |ui| {
-                properties::show(ui, &mut self.project, self.selected_annotation)
+                layers_panel::show(ui, &self.project, &mut self.layers, &mut self.new_group_name);
+                ui.add_space(8.0);
+
+                let templates_action = templates_panel::show(ui, &self.templates, &mut self.active_template, self.selected_annotation.is_some());
+                self.handle_templates_action(templates_action);
+                ui.add_space(8.0);
+
+                let group_names = self.project.as_ref()
+                    .map(|p| self.layers.group_names(&p.annotations))
+                    .unwrap_or_default();
+                properties::show(ui, &mut self.project, self.selected_annotation, &mut self.pinned_annotation, &group_names, self.reference_project.as_ref(), &mut self.offset_distance, self.settings.coordinate_unit, self.media_info.as_ref(), &mut self.settings.recent_colors, &mut self.vertex_selection)
             }).inner;
 
         // Handle properties panel actions
@@ -496,31 +3687,19 @@ impl eframe::App for RoidsApp {
             properties::PropertiesAction::SelectAnnotation(idx) => {
                 self.selected_annotation = Some(idx);
             }
-            properties::PropertiesAction::DeleteAnnotation(idx) => {
-                // Clone annotations for history
-                let annotations_clone = self.project.as_ref()
-                    .filter(|p| idx < p.annotations.len())
-                    .map(|p| p.annotations.clone());
-
-                // Save to history before making changes
-                if let Some(annotations) = annotations_clone {
-                    self.save_to_history(&annotations);
-                }
-
-                // Now mutably borrow and make changes
-                if let Some(ref mut project) = self.project {
-                    if idx < project.annotations.len() {
-                        project.annotations.remove(idx);
-                        self.selected_annotation = None;
-                        log::info!("Deleted annotation from panel, total: {}", project.annotations.len());
-                    }
+            properties::PropertiesAction::DeleteAnnotation(idx) => self.delete_annotation(idx),
+            properties::PropertiesAction::DeleteVertices(idx) => self.delete_selected_vertices(idx),
+            properties::PropertiesAction::CreateEnclosingCircle(idx) => self.create_enclosing_circle(idx),
+            properties::PropertiesAction::OffsetAnnotation(idx) => {
+                if self.selected_annotation == Some(idx) {
+                    self.offset_selected();
                 }
             }
             properties::PropertiesAction::None => {}
         }
 
-        // Handle keyboard events
-        if ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+        // Handle keyboard events (bindings come from the keymap)
+        if ctx.input(|i| self.keymap.pressed(i, Action::Cancel)) {
             if self.current_tool == Tool::Line && self.in_progress_annotation.is_some() {
                 // Finish line on Escape
                 self.finish_annotation();
@@ -531,65 +3710,137 @@ impl eframe::App for RoidsApp {
             }
         }
 
+        // Hide every annotation only while the key is held
+        self.overlays_hidden = !ctx.wants_keyboard_input() && ctx.input(|i| self.keymap.held(i, Action::HideOverlays));
+
         // Handle Delete key to delete selected annotation
         // Only process if no text field is focused (to avoid deleting while editing names)
         if !ctx.wants_keyboard_input() {
-            if ctx.input(|i| i.key_pressed(egui::Key::Delete) || i.key_pressed(egui::Key::Backspace)) {
-                if let Some(idx) = self.selected_annotation {
-                    // Clone annotations for history
-                    let annotations_clone = self.project.as_ref()
-                        .filter(|p| idx < p.annotations.len())
-                        .map(|p| p.annotations.clone());
-
-                    // Save to history before making changes
-                    if let Some(annotations) = annotations_clone {
-                        self.save_to_history(&annotations);
-                    }
-
-                    // Now mutably borrow and make changes
-                    if let Some(ref mut project) = self.project {
-                        if idx < project.annotations.len() {
-                            project.annotations.remove(idx);
-                            self.selected_annotation = None;
-                            log::info!("Deleted annotation, total: {}", project.annotations.len());
-                        }
+            if ctx.input(|i| self.keymap.pressed(i, Action::Delete)) {
+                if self.undo_last_vertex() {
+                    // While drawing, Delete/Backspace removes the last vertex
+                } else if self.lasso_selection.len() > 1 {
+                    self.delete_lasso_selection();
+                } else if let Some(idx) = self.selected_annotation {
+                    if self.vertex_selection.vertices_of(idx).is_empty() {
+                        self.delete_annotation(idx);
+                    } else {
+                        self.delete_selected_vertices(idx);
                     }
                 }
             }
 
-            // Handle undo (Ctrl+Z)
-            if ctx.input(|i| i.modifiers.command && i.key_pressed(egui::Key::Z) && !i.modifiers.shift) {
-                if self.history.can_undo() {
-                    if let Some(ref mut project) = self.project {
-                        let current = project.annotations.clone();
-                        if let Some(previous) = self.history.undo(current) {
-                            project.annotations = previous;
-                            self.selected_annotation = None;
-                            log::info!("Undo");
-                        }
-                    }
+            // Tab through annotations; left alone while drawing or while vertices
+            // of the selected annotation are picked
+            let picking_vertices = self.selected_annotation.is_some_and(|idx| !self.vertex_selection.vertices_of(idx).is_empty());
+            if self.in_progress_annotation.is_none() && !picking_vertices {
+                // Consume Tab so it doesn't also move keyboard focus between widgets
+                if ctx.input_mut(|i| self.keymap.consume(i, Action::NextAnnotation)) {
+                    self.cycle_selection(true);
+                }
+                if ctx.input_mut(|i| self.keymap.consume(i, Action::PreviousAnnotation)) {
+                    self.cycle_selection(false);
                 }
             }
 
-            // Handle redo (Ctrl+Shift+Z or Ctrl+Y)
-            if ctx.input(|i| {
-                (i.modifiers.command && i.modifiers.shift && i.key_pressed(egui::Key::Z)) ||
-                (i.modifiers.command && i.key_pressed(egui::Key::Y))
-            }) {
-                if self.history.can_redo() {
-                    if let Some(ref mut project) = self.project {
-                        let current = project.annotations.clone();
-                        if let Some(next) = self.history.redo(current) {
-                            project.annotations = next;
-                            self.selected_annotation = None;
-                            log::info!("Redo");
-                        }
+            // Handle keyboard panning when zoomed in. Arrow keys are left alone while
+            // an annotation is selected or being drawn so they stay free for editing.
+            if self.view.is_zoomed_in()
+                && self.in_progress_annotation.is_none()
+                && self.selected_annotation.is_none()
+            {
+                let step = self.settings.pan_step;
+                let delta = ctx.input(|i| {
+                    let mut delta = egui::Vec2::ZERO;
+                    if self.keymap.pressed(i, Action::PanLeft) {
+                        delta.x += step;
                     }
-                }
+                    if self.keymap.pressed(i, Action::PanRight) {
+                        delta.x -= step;
+                    }
+                    if self.keymap.pressed(i, Action::PanUp) {
+                        delta.y += step;
+                    }
+                    if self.keymap.pressed(i, Action::PanDown) {
+                        delta.y -= step;
+                    }
+                    delta
+                });
+                self.view.pan_by(delta);
+            }
+
+            // Recenter the view
+            if ctx.input(|i| self.keymap.pressed(i, Action::RecenterView)) {
+                self.view.pan = egui::Vec2::ZERO;
+            }
+
+            // Toggle isolate mode
+            if ctx.input(|i| self.keymap.pressed(i, Action::ToggleIsolate)) {
+                self.toggle_isolate();
+            }
+
+            // Lock or unlock the class of new annotations
+            if ctx.input(|i| self.keymap.pressed(i, Action::LockClass)) {
+                self.toggle_class_lock();
+            }
+
+            // Switch between normalized and pixel coordinates
+            if ctx.input(|i| self.keymap.pressed(i, Action::ToggleCoordinateUnit)) {
+                self.settings.coordinate_unit = self.settings.coordinate_unit.toggled();
+            }
+
+            // Step through the open folder
+            if ctx.input(|i| self.keymap.pressed(i, Action::NextImage)) {
+                self.step_folder_image(1, ctx);
             }
+            if ctx.input(|i| self.keymap.pressed(i, Action::PreviousImage)) {
+                self.step_folder_image(-1, ctx);
+            }
+
+            // Handle undo; while drawing it removes the last vertex instead
+            if ctx.input(|i| self.keymap.pressed(i, Action::Undo)) && !self.undo_last_vertex() && self.history.can_undo()
+            {
+                self.undo();
+            }
+
+            // Handle redo
+            if ctx.input(|i| self.keymap.pressed(i, Action::Redo)) && self.history.can_redo()
+            {
+                self.redo();
+            }
+        }
+
+        // Complexity prompt after finishing a large annotation
+        self.show_simplify_prompt(ctx);
+        self.show_straighten_prompt(ctx);
+        self.show_overwrite_prompt(ctx);
+        self.show_export_issues_prompt(ctx);
+        self.show_import_preview(ctx);
+        self.show_csv_import_prompt(ctx);
+        self.show_batch_progress(ctx);
+        self.show_out_of_bounds_window(ctx);
+        self.show_rename_dialog(ctx);
+        self.show_coordinate_dialog(ctx);
+        self.show_crop_export_dialog(ctx);
+
+        // Preferences window
+        preferences::show(ctx, &mut self.show_preferences, &mut self.settings, &mut self.keymap);
+        statistics::show(ctx, &mut self.show_statistics, &self.project, &mut self.statistics_view, self.settings.fill_rule);
+        self.autosave_if_due(ctx);
+
+        // Log the frame's change unless a vertex drag is still under way
+        if self.dragging_vertex.is_none() && self.rotating_box.is_none() {
+            self.flush_activity();
         }
+        ctx.tessellation_options_mut(|options| options.feathering = self.settings.antialiasing);
+
+        // Notifications overlay
+        self.notifications.show(ctx);
 
         // Main canvas (center)
+        let offset_preview = self.offset_preview();
+        self.update_heatmap(ctx);
+        let canvas_rect = ctx.available_rect();
         let canvas_action = egui::CentralPanel::default().show(ctx, |ui| {
             // Show loading overlay if loading
             if let Some(ref message) = self.loading_message {
@@ -615,11 +3866,29 @@ impl eframe::App for RoidsApp {
                     self.image_size,
                     &self.in_progress_annotation,
                     self.selected_annotation,
+                    &self.lasso_selection,
+                    &self.selected_annotation.map(|idx| self.vertex_selection.vertices_of(idx)).unwrap_or_default(),
                     self.dragging_vertex,
+                    self.rotating_box,
+                    self.drag_segment,
+                    (!self.folder_images.is_empty()).then_some((self.folder_index + 1, self.folder_images.len())),
+                    &self.settings,
+                    &self.layers,
+                    self.isolate,
+                    self.overlays_hidden,
+                    self.reference_project.as_ref(),
+                    offset_preview.as_deref(),
+                    &mut self.view,
+                    &mut self.render_cache,
+                    self.heatmap.as_ref().map(|(_, texture)| texture),
                 )
             }
         }).inner;
 
+        if self.settings.show_tool_palette && self.project.is_some() {
+            toolbar::show_palette(ctx, canvas_rect, &mut self.current_tool, &mut self.settings);
+        }
+
         // Handle canvas actions
         match canvas_action {
             canvas::CanvasAction::AddVertex(point) => {
@@ -628,11 +3897,42 @@ impl eframe::App for RoidsApp {
                     self.start_annotation();
                 }
 
-                // Add vertex to in-progress annotation
+                // Add vertex (or borrowed neighbor vertices) to in-progress annotation
+                let points = self.borrowed_points(point);
+                let threshold = self.settings.complexity_threshold;
+                if let Some(ref mut annotation) = self.in_progress_annotation {
+                    let was_complex = annotation.exceeds_vertex_limit(threshold);
+                    for point in points {
+                        annotation.add_vertex(point);
+                        log::info!("Added vertex at ({:.3}, {:.3}), total vertices: {}",
+                            point.x, point.y, annotation.vertex_count());
+                    }
+                    if !was_complex && annotation.exceeds_vertex_limit(threshold) {
+                        log::warn!("Annotation exceeds {} vertices", threshold);
+                    }
+                }
+
+                // A point is complete as soon as it is placed
+                if self.current_tool == Tool::Point {
+                    self.finish_annotation();
+                }
+            }
+            canvas::CanvasAction::TraceVertices(points) => {
+                if self.in_progress_annotation.is_none() {
+                    self.start_annotation();
+                }
+                if let Some(ref mut annotation) = self.in_progress_annotation {
+                    for point in points {
+                        annotation.add_vertex(point);
+                    }
+                }
+            }
+            canvas::CanvasAction::StopTracing => {
+                // Tracing leaves the annotation open until explicitly finished
                 if let Some(ref mut annotation) = self.in_progress_annotation {
-                    annotation.add_vertex(point);
-                    log::info!("Added vertex at ({:.3}, {:.3}), total vertices: {}",
-                        point.x, point.y, annotation.vertex_count());
+                    let before = annotation.vertex_count();
+                    geometry::dedup_consecutive(&mut annotation.vertices.0, TRACE_DEDUP_DISTANCE);
+                    log::info!("Traced {} vertices ({} duplicates removed)", annotation.vertex_count(), before - annotation.vertex_count());
                 }
             }
             canvas::CanvasAction::FinishAnnotation => {
@@ -641,6 +3941,7 @@ impl eframe::App for RoidsApp {
             }
             canvas::CanvasAction::SelectAnnotation(idx) => {
                 self.selected_annotation = Some(idx);
+                self.lasso_selection.clear();
                 log::info!("Selected annotation {}", idx);
             }
             canvas::CanvasAction::DeselectAnnotation => {
@@ -675,8 +3976,589 @@ impl eframe::App for RoidsApp {
                     log::info!("Stopped dragging vertex {} of annotation {}", vertex_idx, ann_idx);
                 }
                 self.dragging_vertex = None;
+                self.rotating_box = None;
+            }
+            canvas::CanvasAction::DragSegment(start, end) => {
+                self.drag_segment = Some((start, end));
+            }
+            canvas::CanvasAction::Split(start, end) => {
+                self.drag_segment = None;
+                self.split_selected(start, end);
+            }
+            canvas::CanvasAction::Weld(ann_idx, first, second) => self.weld_vertices(ann_idx, first, second),
+            canvas::CanvasAction::Stamp(point) => self.stamp_template(point),
+            canvas::CanvasAction::CreateBox(center, corner) => {
+                self.drag_segment = None;
+                self.create_box(center, corner);
+            }
+            canvas::CanvasAction::StartRotatingBox(idx) => {
+                if let Some(annotations) = self.project.as_ref().map(|p| p.annotations.clone()) {
+                    self.save_to_history(&annotations);
+                }
+                self.rotating_box = Some(idx);
             }
+            canvas::CanvasAction::RotateBox(point) => self.rotate_box(point),
+            canvas::CanvasAction::LassoSelect(lasso) => self.lasso_select(&lasso),
             canvas::CanvasAction::None => {}
         }
+
+        // Isolate mode ends once nothing is selected
+        if self.selected_annotation.is_none() {
+            self.isolate = false;
+        }
+        // Any other change of selection ends the lasso selection
+        if self.selected_annotation.is_none_or(|idx| !self.lasso_selection.contains(&idx)) {
+            self.lasso_selection.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::scratch::scratch_dir;
+
+    /// Create an app with an empty project and a finishable polygon in progress.
+    fn app_drawing_polygon(after_finish: AfterFinish) -> RoidsApp {
+        let mut app = RoidsApp::new();
+        app.settings.after_finish = after_finish;
+        app.project = Some(ProjectData::new("frame.png".to_string(), 100, 100));
+        app.current_tool = Tool::Polygon;
+        app.start_annotation();
+        if let Some(ref mut annotation) = app.in_progress_annotation {
+            annotation.add_vertex(Point::new(0.1, 0.1));
+            annotation.add_vertex(Point::new(0.5, 0.1));
+            annotation.add_vertex(Point::new(0.3, 0.5));
+        }
+        app
+    }
+
+    #[test]
+    fn test_complex_annotation_shows_the_simplify_prompt() {
+        let prompt_shown = |app: &mut RoidsApp| {
+            let ctx = egui::Context::default();
+            let _ = ctx.run(egui::RawInput::default(), |ctx| app.show_simplify_prompt(ctx));
+            let layer = egui::LayerId::new(egui::Order::Middle, egui::Id::new("Complex Annotation"));
+            ctx.memory(|memory| memory.areas().is_visible(&layer))
+        };
+
+        // At the limit nothing is asked
+        let mut app = app_drawing_polygon(AfterFinish::Keep);
+        app.settings.complexity_threshold = 3;
+        app.finish_annotation();
+        assert_eq!(app.pending_simplify, None);
+        assert!(!prompt_shown(&mut app));
+
+        // One vertex over, the prompt names the annotation until answered
+        let mut app = app_drawing_polygon(AfterFinish::Keep);
+        app.settings.complexity_threshold = 3;
+        if let Some(ref mut annotation) = app.in_progress_annotation {
+            annotation.add_vertex(Point::new(0.1, 0.5));
+        }
+        app.finish_annotation();
+        assert_eq!(app.pending_simplify, Some(0));
+        assert!(prompt_shown(&mut app));
+        assert_eq!(app.pending_simplify, Some(0));
+
+        // Undoing or editing while it is open withdraws the prompt, whose
+        // index may no longer be the same annotation
+        app.undo();
+        assert_eq!(app.pending_simplify, None);
+        app.redo();
+        app.pending_simplify = Some(0);
+        app.delete_annotation(0);
+        assert_eq!(app.pending_simplify, None);
+    }
+
+    #[test]
+    fn test_activity_log_create_and_delete() {
+        use crate::models::activity::ActivityKind;
+
+        let mut app = app_drawing_polygon(AfterFinish::Keep);
+        app.finish_annotation();
+        app.delete_annotation(0);
+        app.flush_activity();
+
+        let events: Vec<_> = app.activity.events().map(|e| (e.kind, e.name.as_str(), e.vertex_count)).collect();
+        assert_eq!(events, [(ActivityKind::Created, "region 1", 3), (ActivityKind::Deleted, "region 1", 3)]);
+    }
+
+    #[test]
+    fn test_image_view_is_remembered_per_path() {
+        let mut app = RoidsApp::new();
+        app.project = Some(ProjectData::new("a.png".to_string(), 100, 100));
+        app.last_session.media_path = Some(std::path::PathBuf::from("a.png"));
+        app.view.zoom_by(2.0);
+        app.view.pan_by(egui::vec2(10.0, -5.0));
+        app.remember_image_state();
+
+        // An unseen image keeps the fit-to-window view
+        app.view.reset();
+        app.restore_image_state(std::path::Path::new("b.png"));
+        assert_eq!(app.view, ViewTransform::new());
+
+        app.restore_image_state(std::path::Path::new("a.png"));
+        assert_eq!((app.view.zoom, app.view.pan), (2.0, egui::vec2(10.0, -5.0)));
+    }
+
+    #[test]
+    fn test_undo_last_vertex() {
+        let mut app = app_drawing_polygon(AfterFinish::Keep);
+        app.finish_annotation();
+        app.start_annotation();
+        if let Some(ref mut annotation) = app.in_progress_annotation {
+            annotation.add_vertex(Point::new(0.6, 0.6));
+            annotation.add_vertex(Point::new(0.7, 0.6));
+        }
+
+        assert!(app.undo_last_vertex());
+        let vertices = &app.in_progress_annotation.as_ref().unwrap().vertices.0;
+        assert_eq!(vertices, &[Point::new(0.6, 0.6)]);
+
+        // Removing the only vertex cancels the annotation
+        assert!(app.undo_last_vertex());
+        assert!(app.in_progress_annotation.is_none());
+        assert!(!app.undo_last_vertex());
+
+        // The committed polygon and its history are untouched
+        assert_eq!(app.project.as_ref().unwrap().annotations.len(), 1);
+        assert!(app.history.can_undo());
+    }
+
+    #[test]
+    fn test_split_selected_polygon() {
+        let mut app = app_drawing_polygon(AfterFinish::Keep);
+        app.finish_annotation();
+        app.selected_annotation = Some(0);
+        app.split_selected(Point::new(0.3, 0.0), Point::new(0.3, 1.0));
+
+        let annotations = &app.project.as_ref().unwrap().annotations;
+        assert_eq!(annotations.len(), 2);
+        assert_eq!(annotations[0].name, "region 1 a");
+        assert_eq!(annotations[1].name, "region 1 b");
+        let previous = app.history.undo(Snapshot::of(app.project.as_ref().unwrap())).unwrap();
+        assert_eq!(previous.annotations.len(), 1);
+    }
+
+    #[test]
+    fn test_split_multi_part_polygon_keeps_parts_on_their_side() {
+        let mut app = app_drawing_polygon(AfterFinish::Keep);
+        app.finish_annotation();
+        let part = Vertices(vec![Point::new(0.8, 0.8), Point::new(0.9, 0.8), Point::new(0.9, 0.9)]);
+        app.project.as_mut().unwrap().annotations[0].extra_rings.push(part.clone());
+        app.selected_annotation = Some(0);
+        app.split_selected(Point::new(0.3, 0.0), Point::new(0.3, 1.0));
+
+        let annotations = &app.project.as_ref().unwrap().annotations;
+        assert_eq!(annotations.len(), 2);
+        let right = annotations.iter().find(|a| a.vertices.0.iter().all(|v| v.x >= 0.3 - 1e-9)).unwrap();
+        let left = annotations.iter().find(|a| a.vertices.0.iter().all(|v| v.x <= 0.3 + 1e-9)).unwrap();
+        assert_eq!(right.extra_rings, vec![part]);
+        assert!(left.extra_rings.is_empty());
+    }
+
+    #[test]
+    fn test_weld_vertices_is_undoable() {
+        let mut app = app_drawing_polygon(AfterFinish::Keep);
+        if let Some(ref mut annotation) = app.in_progress_annotation {
+            annotation.add_vertex(Point::new(0.1, 0.5));
+        }
+        app.finish_annotation();
+        app.weld_vertices(0, 3, 0);
+
+        let annotations = &app.project.as_ref().unwrap().annotations;
+        assert_eq!(annotations[0].vertex_count(), 3);
+        assert_eq!(annotations[0].vertices.0[0], Point::new(0.1, 0.3));
+        let previous = app.history.undo(Snapshot::of(app.project.as_ref().unwrap())).unwrap();
+        assert_eq!(previous.annotations[0].vertex_count(), 4);
+
+        // At the polygon minimum the weld is refused
+        app.weld_vertices(0, 0, 1);
+        assert_eq!(app.project.as_ref().unwrap().annotations[0].vertex_count(), 3);
+    }
+
+    #[test]
+    fn test_straighten_prompt_is_withdrawn_by_other_edits() {
+        let mut app = RoidsApp::new();
+        let mut project = ProjectData::new("frame.png".to_string(), 200, 100);
+        let mut line = Annotation::new("line 1".to_string(), AnnotationType::Line);
+        for i in 0..8 {
+            line.add_vertex(Point::new(i as f64 / 10.0, (i % 2) as f64 / 10.0));
+        }
+        project.annotations = vec![line.clone(), line];
+        app.project = Some(project);
+        app.selected_annotation = Some(1);
+        app.request_straighten_selected();
+        assert_eq!(app.pending_straighten, Some(1));
+
+        // Index 1 would be past the end after this deletion
+        app.delete_annotation(0);
+        assert_eq!(app.pending_straighten, None);
+        app.selected_annotation = Some(0);
+        app.request_straighten_selected();
+        app.undo();
+        assert_eq!(app.pending_straighten, None);
+    }
+
+    #[test]
+    fn test_pinned_annotation_follows_its_annotation() {
+        let mut app = RoidsApp::new();
+        let mut project = ProjectData::new("frame.png".to_string(), 200, 100);
+        for name in ["a", "b", "c"] {
+            project.annotations.push(Annotation::new(name.to_string(), AnnotationType::Point));
+        }
+        app.project = Some(project);
+        app.pinned_annotation = Some(2);
+
+        app.delete_annotation(0);
+        assert_eq!(app.pinned_annotation, Some(1));
+        app.delete_annotation(1);
+        assert_eq!(app.pinned_annotation, None);
+
+        // Other edits keep the pin, and undo finds its annotation again
+        app.pinned_annotation = Some(0);
+        let annotations = app.project.as_ref().unwrap().annotations.clone();
+        app.save_to_history(&annotations);
+        assert_eq!(app.pinned_annotation, Some(0));
+        app.undo();
+        app.undo();
+        assert_eq!(app.project.as_ref().unwrap().annotations.len(), 2);
+        app.undo();
+        assert_eq!(app.pinned_annotation, Some(1));
+        app.redo();
+        assert_eq!(app.pinned_annotation, Some(0));
+    }
+
+    #[test]
+    fn test_restoring_a_session_skips_the_import_preview() {
+        let path = scratch_dir("restore_skips_preview").join("annotations.json");
+        crate::io::serialization::export_json(&ProjectData::new("frame.png".to_string(), 10, 10), &path, false).unwrap();
+        let mut app = RoidsApp::new();
+        app.last_session.annotation_path = Some(path.clone());
+
+        app.restore_session();
+        assert!(app.pending_import.is_none());
+        assert!(app.image_loader.is_some());
+
+        // Picking the same file interactively asks first
+        app.image_loader = None;
+        app.import_annotations(path, &egui::Context::default());
+        assert!(app.pending_import.is_some());
+    }
+
+    #[test]
+    fn test_clamp_out_of_bounds_is_one_undo_step() {
+        let mut app = RoidsApp::new();
+        let mut project = ProjectData::new("frame.png".to_string(), 100, 100);
+        let mut line = Annotation::new("road 1".to_string(), AnnotationType::Line);
+        line.add_vertex(Point::new(0.5, 0.5));
+        line.add_vertex(Point::new(1.2, 0.5));
+        project.annotations.push(line);
+        app.project = Some(project);
+
+        app.clamp_out_of_bounds();
+        assert_eq!(app.project.as_ref().unwrap().annotations[0].vertices.0[1], Point::new(1.0, 0.5));
+        assert!(app.history.can_undo());
+
+        // With everything inside the image there's nothing to undo
+        app.history = History::new();
+        app.clamp_out_of_bounds();
+        assert!(!app.history.can_undo());
+    }
+
+    #[test]
+    fn test_create_and_rotate_box() {
+        let mut app = RoidsApp::new();
+        app.project = Some(ProjectData::new("frame.png".to_string(), 200, 100));
+        app.current_tool = Tool::OrientedBox;
+        app.create_box(Point::new(0.5, 0.5), Point::new(0.6, 0.6));
+        assert_eq!(app.selected_annotation, Some(0));
+        assert!(app.in_progress_annotation.is_none());
+
+        // Point the handle straight right of the center: a quarter turn clockwise
+        app.rotating_box = Some(0);
+        app.rotate_box(Point::new(0.9, 0.5));
+        let project = app.project.as_ref().unwrap();
+        let pixels = geometry::to_pixels(&project.annotations[0].vertices.0, (200, 100));
+        let obb = geometry::obb_from_polygon(&pixels).unwrap();
+        assert!((obb.angle - 90.0).abs() < 1e-9);
+        assert!((obb.width - 40.0).abs() < 1e-9 && (obb.height - 20.0).abs() < 1e-9);
+        assert!((obb.cx - 100.0).abs() < 1e-9 && (obb.cy - 50.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_stamps_and_turned_boxes_keep_integer_pixels() {
+        let on_pixels = |annotation: &Annotation| {
+            geometry::to_pixels(&annotation.vertices.0, (200, 100)).iter().all(|p| (p.x - p.x.round()).abs() < 1e-9 && (p.y - p.y.round()).abs() < 1e-9)
+        };
+        let mut app = RoidsApp::new();
+        app.settings.integer_pixels = true;
+        app.project = Some(ProjectData::new("frame.png".to_string(), 200, 100));
+
+        // A template whose corners are off the grid relative to its center
+        let mut template = Annotation::new("tree 1".to_string(), AnnotationType::Polygon);
+        for (x, y) in [(0.0, 0.0), (0.0333, 0.0), (0.0333, 0.0517), (0.0, 0.0517)] {
+            template.add_vertex(Point::new(x, y));
+        }
+        app.templates = vec![template];
+        app.active_template = Some(0);
+        app.stamp_template(Point::new(0.5, 0.5));
+        assert!(on_pixels(&app.project.as_ref().unwrap().annotations[0]));
+
+        app.current_tool = Tool::OrientedBox;
+        app.create_box(Point::new(0.5, 0.5), Point::new(0.6, 0.6));
+        app.rotating_box = Some(1);
+        app.rotate_box(Point::new(0.7, 0.3));
+        assert!(on_pixels(&app.project.as_ref().unwrap().annotations[1]));
+    }
+
+    #[test]
+    fn test_texture_version_bumps_only_on_pixel_changes() {
+        let ctx = egui::Context::default();
+        let mut app = RoidsApp::new();
+        let image = LoadedImage { width: 4, height: 2, pixels: vec![255; 4 * 2 * 4], color_type: image::ExtendedColorType::Rgba8 };
+        app.project = Some(ProjectData::new("frame.png".to_string(), 4, 2));
+        app.upload_texture(&ctx, &image);
+        app.source_image = Some(image);
+        assert_eq!(app.texture_version, 1);
+
+        // View and display changes only affect how the texture is drawn
+        app.view.zoom_about(2.0, egui::vec2(10.0, 10.0));
+        app.view.pan_by(egui::vec2(5.0, -5.0));
+        app.settings.image_opacity = 0.5;
+        assert_eq!(app.texture_version, 1);
+
+        app.rotate_image(true, &ctx);
+        assert_eq!(app.texture_version, 2);
+        assert_eq!(app.image_size, Some((2, 4)));
+    }
+
+    #[test]
+    fn test_load_rotated_comparison() {
+        let dir = scratch_dir("rotated_comparison");
+
+        // The reference was saved after rotating a 4x2 image a quarter turn
+        let mut reference = ProjectData::new("frame.png".to_string(), 4, 2);
+        let mut point = Annotation::new("point 1".to_string(), AnnotationType::Point);
+        point.add_vertex(Point::new(0.25, 0.0));
+        reference.annotations.push(point);
+        reference.rotate_90(true);
+        let path = dir.join("reference.json");
+        crate::io::serialization::export_json(&reference, &path, false).unwrap();
+
+        let mut app = RoidsApp::new();
+        app.project = Some(ProjectData::new("frame.png".to_string(), 4, 2));
+        app.load_comparison(path);
+
+        let reference = app.reference_project.as_ref().unwrap();
+        assert_eq!(reference.rotation, 0);
+        assert_eq!((reference.frame_width, reference.frame_height), (4, 2));
+        let vertex = reference.annotations[0].vertices.0[0];
+        assert!(vertex.distance(&Point::new(0.25, 0.0)) < 1e-9, "{:?}", vertex);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_rotate_and_flip_image_move_the_comparison_too() {
+        let ctx = egui::Context::default();
+        let mut app = RoidsApp::new();
+        let image = LoadedImage { width: 4, height: 2, pixels: vec![255; 4 * 2 * 4], color_type: image::ExtendedColorType::Rgba8 };
+        app.project = Some(ProjectData::new("frame.png".to_string(), 4, 2));
+        app.source_image = Some(image);
+        let mut reference = ProjectData::new("frame.png".to_string(), 4, 2);
+        let mut point = Annotation::new("point 1".to_string(), AnnotationType::Point);
+        point.add_vertex(Point::new(0.25, 0.0));
+        reference.annotations.push(point);
+        app.reference_project = Some(reference);
+
+        app.rotate_image(true, &ctx);
+        app.flip_image(true, &ctx);
+
+        // The reference follows the working project
+        let project = app.project.as_ref().unwrap();
+        let reference = app.reference_project.as_ref().unwrap();
+        assert_eq!((reference.rotation, reference.flipped_horizontally), (project.rotation, project.flipped_horizontally));
+        assert_eq!((reference.frame_width, reference.frame_height), (2, 4));
+        let vertex = reference.annotations[0].vertices.0[0];
+        assert!(vertex.distance(&Point::new(0.0, 0.25)) < 1e-9, "{:?}", vertex);
+    }
+
+    #[test]
+    fn test_folder_import_renames_and_flags_mismatched_frames() {
+        let mut app = RoidsApp::new();
+        let mut project = ProjectData::new("frame.png".to_string(), 100, 100);
+        project.annotations.push(Annotation::new("road 1".to_string(), AnnotationType::Line));
+        app.project = Some(project);
+
+        let mut matching = ProjectData::new("frame.png".to_string(), 100, 100);
+        matching.annotations.push(Annotation::new("road 1".to_string(), AnnotationType::Line));
+        let mut resized = ProjectData::new("frame.png".to_string(), 100, 50);
+        resized.annotations.push(Annotation::new("lane 1".to_string(), AnnotationType::Line));
+        let report = BatchImportReport {
+            imported: vec![("a.json".into(), matching), ("b.json".into(), resized)],
+            ..Default::default()
+        };
+        app.finish_folder_import("annotations".into(), Ok(report));
+
+        // The duplicate name is renamed, and the annotation from a
+        // differently shaped frame is kept but flagged
+        let project = app.project.as_ref().unwrap();
+        let names: Vec<&str> = project.annotations.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, ["road 1", "road 2", "lane 1"]);
+        assert_eq!(project.annotations[2].source_frame, Some((100, 50)));
+        assert_eq!(validation::find_aspect_mismatches(project), [2]);
+    }
+
+    #[test]
+    fn test_folder_navigation_stops_when_the_sidecar_cannot_be_saved() {
+        let ctx = egui::Context::default();
+        let mut app = RoidsApp::new();
+        let dir = crate::util::scratch::scratch_dir("sidecar_save_fails").join("missing");
+        app.folder_images = vec![dir.join("a.png"), dir.join("b.png")];
+        app.last_session.media_path = Some(dir.join("a.png"));
+        let mut project = ProjectData::new("a.png".to_string(), 10, 10);
+        project.annotations.push(Annotation::new("road 1".to_string(), AnnotationType::Line));
+        app.project = Some(project);
+
+        app.step_folder_image(1, &ctx);
+        assert_eq!(app.folder_index, 0);
+        assert_eq!(app.project.as_ref().unwrap().annotations.len(), 1);
+        assert!(app.image_loader.is_none());
+    }
+
+    #[test]
+    fn test_cropped_export_writes_the_cropped_image() {
+        let mut app = RoidsApp::new();
+        app.source_image = Some(LoadedImage { width: 4, height: 2, pixels: vec![255; 4 * 2 * 4], color_type: image::ExtendedColorType::Rgba8 });
+        let mut project = ProjectData::new("frame.png".to_string(), 4, 2);
+        let mut point = Annotation::new("car 1".to_string(), AnnotationType::Point);
+        point.add_vertex(Point::new(0.6, 0.5));
+        let mut hidden = point.clone();
+        hidden.name = "car 2".to_string();
+        hidden.export = false;
+        project.annotations = vec![point, hidden];
+        app.project = Some(project);
+
+        let path = crate::util::scratch::scratch_dir("cropped_export").join("cropped.json");
+        app.export_cropped(path.clone(), (Point::new(0.5, 0.0), Point::new(1.0, 1.0)));
+        let exported = crate::io::serialization::import_file(&path).unwrap();
+        assert_eq!(exported.annotations.len(), 1);
+        assert_eq!(std::path::Path::new(&exported.media_file), path.with_extension("png"));
+        let image = crate::io::media::load_image(&path.with_extension("png")).unwrap();
+        assert_eq!((image.width, image.height), (exported.frame_width, exported.frame_height));
+    }
+
+    #[test]
+    fn test_merge_import_follows_the_current_orientation() {
+        let mut app = RoidsApp::new();
+        let mut project = ProjectData::new("frame.png".to_string(), 100, 50);
+        project.rotate_90(true);
+        app.project = Some(project);
+
+        // Made on the unrotated image, so it needs turning to line up
+        let mut incoming = ProjectData::new("frame.png".to_string(), 100, 50);
+        let mut line = Annotation::new("road 1".to_string(), AnnotationType::Line);
+        line.add_vertex(Point::new(0.0, 0.0));
+        line.add_vertex(Point::new(1.0, 0.0));
+        incoming.annotations.push(line);
+        let mut expected = incoming.clone();
+        expected.rotate_90(true);
+        app.merge_imported_project(std::path::Path::new("a.json"), incoming);
+        assert_eq!(app.project.as_ref().unwrap().annotations[0].vertices.0, expected.annotations[0].vertices.0);
+        assert!(app.history.can_undo());
+
+        // A failed merge leaves no undo step behind
+        app.history = History::new();
+        let mut other = ProjectData::new("other.png".to_string(), 30, 30);
+        other.annotations.push(Annotation::new("road 1".to_string(), AnnotationType::Line));
+        app.merge_imported_project(std::path::Path::new("b.json"), other);
+        assert_eq!(app.project.as_ref().unwrap().annotations.len(), 1);
+        assert!(!app.history.can_undo());
+    }
+
+    #[test]
+    fn test_north_offset_is_undoable_and_autosaved() {
+        let ctx = egui::Context::default();
+        let mut app = RoidsApp::new();
+        let image = LoadedImage { width: 4, height: 2, pixels: vec![255; 4 * 2 * 4], color_type: image::ExtendedColorType::Rgba8 };
+        let mut project = ProjectData::new("frame.png".to_string(), 4, 2);
+        project.north_offset_deg = Some(30.0);
+        app.autosaved = Some(Snapshot::of(&project));
+        app.project = Some(project);
+        app.source_image = Some(image);
+
+        app.save_to_history(&[]);
+        app.project.as_mut().unwrap().north_offset_deg = Some(45.0);
+        assert_ne!(app.autosaved, Some(Snapshot::of(app.project.as_ref().unwrap())));
+
+        // The saved offset turns with the image, so undo restores it in place
+        app.rotate_image(true, &ctx);
+        let project = app.project.as_mut().unwrap();
+        assert_eq!(project.north_offset_deg, Some(135.0));
+        app.history.undo(Snapshot::of(project)).unwrap().restore(project);
+        assert_eq!(project.north_offset_deg, Some(120.0));
+    }
+
+    #[test]
+    fn test_oversized_texture_is_downscaled_for_display() {
+        let ctx = egui::Context::default();
+        let mut app = RoidsApp::new();
+        let max_side = ctx.input(|i| i.max_texture_side);
+        let width = max_side as u32 + 1000;
+        let image = LoadedImage { width, height: 2, pixels: vec![255; width as usize * 2 * 4], color_type: image::ExtendedColorType::Rgba8 };
+        assert_eq!(app.upload_texture(&ctx, &image), Some(max_side as u32));
+
+        // The texture fits the limit, but the image keeps its full size
+        assert_eq!(app.image_texture.as_ref().unwrap().size(), [max_side, 1]);
+        assert_eq!(app.image_size, Some((width, 2)));
+    }
+
+    #[test]
+    fn test_cycle_index_wraps() {
+        let all = |_| true;
+        // Nothing selected starts at either end
+        assert_eq!(cycle_index(None, 3, true, all), Some(0));
+        assert_eq!(cycle_index(None, 3, false, all), Some(2));
+        // Steps wrap around both ways
+        assert_eq!(cycle_index(Some(1), 3, true, all), Some(2));
+        assert_eq!(cycle_index(Some(2), 3, true, all), Some(0));
+        assert_eq!(cycle_index(Some(0), 3, false, all), Some(2));
+        // Hidden annotations are skipped, and a stale selection starts over
+        assert_eq!(cycle_index(Some(0), 4, true, |i| i != 1), Some(2));
+        assert_eq!(cycle_index(Some(9), 3, true, all), Some(0));
+        // A lone selectable annotation stays selected; none gives nothing
+        assert_eq!(cycle_index(Some(1), 3, true, |i| i == 1), Some(1));
+        assert_eq!(cycle_index(None, 0, true, all), None);
+        assert_eq!(cycle_index(Some(0), 2, true, |_| false), None);
+    }
+
+    #[test]
+    fn test_finish_keeps_tool() {
+        let mut app = app_drawing_polygon(AfterFinish::Keep);
+        app.finish_annotation();
+
+        assert_eq!(app.current_tool, Tool::Polygon);
+        assert!(app.in_progress_annotation.is_none());
+        assert_eq!(app.project.as_ref().unwrap().annotations.len(), 1);
+    }
+
+    #[test]
+    fn test_finish_continues_drawing() {
+        let mut app = app_drawing_polygon(AfterFinish::Continue);
+        app.finish_annotation();
+
+        assert_eq!(app.current_tool, Tool::Polygon);
+        let next = app.in_progress_annotation.as_ref().expect("next annotation started");
+        assert_eq!(next.name, "region 2");
+        assert_eq!(next.vertex_count(), 0);
+    }
+
+    #[test]
+    fn test_finish_switches_to_select() {
+        let mut app = app_drawing_polygon(AfterFinish::Select);
+        app.finish_annotation();
+
+        assert_eq!(app.current_tool, Tool::Select);
+        assert!(app.in_progress_annotation.is_none());
     }
 }
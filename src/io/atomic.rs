@@ -0,0 +1,93 @@
+// Copyright (c) 2025, Jason Jenkins
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Atomic file writes.
+//!
+//! Exports are written to a temporary file next to the target and renamed
+//! over it only once complete, so a failed export never truncates an
+//! existing file.
+
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+/// Temporary path in the same directory as `path`, keeping its extension
+/// so writers that pick a format by extension still work.
+fn temp_path_for(path: &Path) -> PathBuf {
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("export");
+    let name = match path.extension().and_then(|s| s.to_str()) {
+        Some(extension) => format!(".{}.{}.tmp.{}", stem, std::process::id(), extension),
+        None => format!(".{}.{}.tmp", stem, std::process::id()),
+    };
+    path.with_file_name(name)
+}
+
+/// Write a file atomically.
+///
+/// `write` creates the file at the temporary path it is given; on success
+/// that file is renamed over `path`. On failure the temporary file is
+/// removed and any existing file at `path` is left untouched.
+pub fn write_atomic(path: &Path, write: impl FnOnce(&Path) -> Result<()>) -> Result<()> {
+    let temp = temp_path_for(path);
+    let result = write(&temp).and_then(|_| {
+        std::fs::rename(&temp, path).with_context(|| format!("Failed to replace {}", path.display()))
+    });
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp);
+    }
+    result
+}
+
+/// Write bytes to a file atomically.
+pub fn write_bytes(path: &Path, contents: impl AsRef<[u8]>) -> Result<()> {
+    write_atomic(path, |temp| {
+        std::fs::write(temp, contents).with_context(|| format!("Failed to write {}", temp.display()))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::scratch::scratch_dir;
+    use anyhow::bail;
+
+    #[test]
+    fn test_failed_write_keeps_existing_file() {
+        let dir = scratch_dir("atomic_failure");
+        let target = dir.join("annotations.json");
+        std::fs::write(&target, "original").unwrap();
+
+        // The writer gets partway through and then fails
+        let result = write_atomic(&target, |temp| {
+            std::fs::write(temp, "trunc")?;
+            bail!("disk full");
+        });
+        assert!(result.is_err());
+        assert_eq!(std::fs::read_to_string(&target).unwrap(), "original");
+        // No temporary file is left behind
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_successful_write_replaces_file() {
+        let dir = scratch_dir("atomic_success");
+        let target = dir.join("overlay.png");
+        std::fs::write(&target, "original").unwrap();
+
+        write_atomic(&target, |temp| {
+            assert_eq!(temp.extension().unwrap(), "png");
+            assert_eq!(temp.parent(), target.parent());
+            std::fs::write(temp, "replaced")?;
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(std::fs::read_to_string(&target).unwrap(), "replaced");
+
+        write_bytes(&dir.join("new.json"), "{}").unwrap();
+        assert_eq!(std::fs::read_to_string(dir.join("new.json")).unwrap(), "{}");
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 2);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
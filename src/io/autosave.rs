@@ -0,0 +1,125 @@
+// Copyright (c) 2025, Jason Jenkins
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Periodic autosave of annotations.
+//!
+//! Autosave files are named after the media file with an `.autosave`
+//! suffix, e.g. `frame_0001.png` -> `frame_0001.autosave.json`, so they
+//! never shadow a sidecar annotation file.
+
+use crate::io::serialization;
+use crate::models::project::ProjectData;
+use crate::models::settings::{AnnotationFormat, AutosaveLocation};
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+
+/// Directory used for autosaves in the temp dir (and as the fallback).
+pub fn temp_autosave_dir() -> PathBuf {
+    std::env::temp_dir().join("roids_autosave")
+}
+
+/// Derive the autosave path for a media file.
+///
+/// `BesideMedia` falls back to the temp dir when the media path has no
+/// parent directory, and `Folder` does when no folder has been chosen.
+pub fn autosave_path(
+    media_path: &Path,
+    location: AutosaveLocation,
+    folder: &Path,
+    format: AnnotationFormat,
+) -> PathBuf {
+    let stem = media_path
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "untitled".to_string());
+    let file_name = format!("{}.autosave.{}", stem, format.extension());
+
+    let dir = match location {
+        AutosaveLocation::BesideMedia => match media_path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+            _ => temp_autosave_dir(),
+        },
+        AutosaveLocation::TempDir => temp_autosave_dir(),
+        AutosaveLocation::Folder if folder.as_os_str().is_empty() => temp_autosave_dir(),
+        AutosaveLocation::Folder => folder.to_path_buf(),
+    };
+    dir.join(file_name)
+}
+
+/// Write an autosave file, creating its directory if needed.
+pub fn write_autosave(project: &ProjectData, path: &Path, format: AnnotationFormat) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    serialization::export_as(project, path, format)
+}
+
+/// Remove every autosave for a media file, across all locations and
+/// formats. Returns the removed paths.
+pub fn remove_autosaves(media_path: &Path, folder: &Path) -> Vec<PathBuf> {
+    let locations = [AutosaveLocation::BesideMedia, AutosaveLocation::TempDir, AutosaveLocation::Folder];
+    let formats = [AnnotationFormat::Json, AnnotationFormat::Yaml];
+
+    let mut removed = Vec::new();
+    for location in locations {
+        for format in formats {
+            let path = autosave_path(media_path, location, folder, format);
+            if path.is_file() && std::fs::remove_file(&path).is_ok() {
+                removed.push(path);
+            }
+        }
+    }
+    removed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::scratch::scratch_dir;
+
+    #[test]
+    fn test_autosave_path_locations() {
+        let media = Path::new("/data/frames/frame_0001.png");
+        let folder = Path::new("/backups");
+
+        assert_eq!(
+            autosave_path(media, AutosaveLocation::BesideMedia, folder, AnnotationFormat::Json),
+            PathBuf::from("/data/frames/frame_0001.autosave.json")
+        );
+        assert_eq!(
+            autosave_path(media, AutosaveLocation::TempDir, folder, AnnotationFormat::Yaml),
+            temp_autosave_dir().join("frame_0001.autosave.yaml")
+        );
+        assert_eq!(
+            autosave_path(media, AutosaveLocation::Folder, folder, AnnotationFormat::Json),
+            PathBuf::from("/backups/frame_0001.autosave.json")
+        );
+
+        // A bare file name has no directory to save beside
+        assert_eq!(
+            autosave_path(Path::new("frame_0001.png"), AutosaveLocation::BesideMedia, folder, AnnotationFormat::Json),
+            temp_autosave_dir().join("frame_0001.autosave.json")
+        );
+        // Nor does an unchosen folder, rather than the working directory
+        assert_eq!(
+            autosave_path(media, AutosaveLocation::Folder, Path::new(""), AnnotationFormat::Json),
+            temp_autosave_dir().join("frame_0001.autosave.json")
+        );
+    }
+
+    #[test]
+    fn test_write_and_remove_autosaves() {
+        let dir = scratch_dir("autosave_test");
+        let media = dir.join("frame.png");
+        let project = ProjectData::new("frame.png".to_string(), 10, 10);
+
+        let path = autosave_path(&media, AutosaveLocation::Folder, &dir, AnnotationFormat::Yaml);
+        write_autosave(&project, &path, AnnotationFormat::Yaml).unwrap();
+        assert!(path.is_file());
+
+        assert_eq!(remove_autosaves(&media, &dir), vec![path.clone()]);
+        assert!(!path.exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
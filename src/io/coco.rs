@@ -0,0 +1,219 @@
+// Copyright (c) 2025, Jason Jenkins
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! COCO dataset export.
+//!
+//! Polygons are written as segmentation annotations and keypoints as
+//! single-keypoint annotations. Categories are taken from annotation
+//! names (see [`Annotation::category`]). Rectangular polygons also carry
+//! their oriented box as `obb: [cx, cy, w, h, angle]` in pixels and degrees.
+//! Multi-part polygons become one annotation with a segmentation polygon
+//! per ring.
+
+use crate::io::atomic;
+use crate::models::annotation::{Annotation, AnnotationType, Point};
+use crate::models::project::ProjectData;
+use crate::util::geometry;
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// COCO visibility flag for a labeled, visible keypoint.
+const KEYPOINT_VISIBLE: u8 = 2;
+
+/// Build a COCO document for a single-image project.
+///
+/// Lines have no COCO representation and are skipped.
+pub fn to_coco(data: &ProjectData) -> Value {
+    let (width, height) = (data.frame_width as f64, data.frame_height as f64);
+
+    // Assign category ids in name order, noting which hold keypoints
+    let mut categories: BTreeMap<&str, bool> = BTreeMap::new();
+    for annotation in exported(data) {
+        *categories.entry(annotation.category()).or_default() |= annotation.annotation_type == AnnotationType::Point;
+    }
+    let category_id = |name: &str| categories.keys().position(|c| *c == name).map_or(0, |i| i + 1);
+
+    let annotations: Vec<Value> = exported(data)
+        .enumerate()
+        .map(|(idx, annotation)| {
+            let pixels: Vec<[f64; 2]> = annotation.vertices.0.iter().map(|p| [p.x * width, p.y * height]).collect();
+            let origin = Point::new(0.0, 0.0);
+            let (min, max) = annotation.bounding_box().unwrap_or((origin, origin));
+            let bbox = [min.x * width, min.y * height, (max.x - min.x) * width, (max.y - min.y) * height];
+
+            let mut entry = json!({
+                "id": idx + 1,
+                "image_id": 1,
+                "category_id": category_id(annotation.category()),
+                "bbox": bbox,
+                "iscrowd": 0,
+                "attributes": { "role": annotation.role },
+            });
+            if let Some(ref notes) = annotation.notes {
+                entry["attributes"]["notes"] = json!(notes);
+            }
+            if annotation.annotation_type == AnnotationType::Point {
+                let [x, y] = pixels[0];
+                entry["keypoints"] = json!([x, y, KEYPOINT_VISIBLE]);
+                entry["num_keypoints"] = json!(1);
+                entry["area"] = json!(0.0);
+            } else {
+                let segmentation: Vec<Vec<f64>> = annotation
+                    .rings()
+                    .map(|ring| ring.iter().flat_map(|p| [p.x * width, p.y * height]).collect())
+                    .collect();
+                entry["segmentation"] = json!(segmentation);
+                entry["area"] = json!(annotation.area() * width * height);
+                let corners: Vec<Point> = pixels.iter().map(|&[x, y]| Point::new(x, y)).collect();
+                if let (false, Some(obb)) = (annotation.is_multi_part(), geometry::obb_from_polygon(&corners)) {
+                    entry["obb"] = json!([obb.cx, obb.cy, obb.width, obb.height, obb.angle]);
+                }
+            }
+            entry
+        })
+        .collect();
+
+    let categories: Vec<Value> = categories
+        .iter()
+        .enumerate()
+        .map(|(idx, (name, is_keypoint))| {
+            let mut category = json!({ "id": idx + 1, "name": name });
+            if *is_keypoint {
+                category["keypoints"] = json!([name]);
+                category["skeleton"] = json!([]);
+            }
+            category
+        })
+        .collect();
+
+    json!({
+        "images": [{
+            "id": 1,
+            "file_name": data.media_file,
+            "width": data.frame_width,
+            "height": data.frame_height,
+        }],
+        "categories": categories,
+        "annotations": annotations,
+    })
+}
+
+/// Export a project as a COCO JSON file.
+pub fn export_coco(data: &ProjectData, path: &Path) -> Result<()> {
+    let skipped = data.exported_annotations().count() - exported(data).count();
+    if skipped > 0 {
+        log::warn!("Skipped {} annotations with no COCO representation", skipped);
+    }
+    atomic::write_bytes(path, serde_json::to_string_pretty(&to_coco(data))?)?;
+    Ok(())
+}
+
+/// Annotations that can be represented in COCO.
+fn exported(data: &ProjectData) -> impl Iterator<Item = &Annotation> {
+    data.exported_annotations()
+        .filter(|a| a.annotation_type != AnnotationType::Line && a.can_finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::annotation::Vertices;
+
+    #[test]
+    fn test_coco_polygons_and_keypoints() {
+        let mut data = ProjectData::new("frame.png".to_string(), 100, 50);
+
+        let mut region = Annotation::new("car 1".to_string(), AnnotationType::Polygon);
+        region.add_vertex(Point::new(0.0, 0.0));
+        region.add_vertex(Point::new(0.5, 0.0));
+        region.add_vertex(Point::new(0.5, 1.0));
+        let mut point = Annotation::new("nose 1".to_string(), AnnotationType::Point);
+        point.add_vertex(Point::new(0.25, 0.5));
+        let mut line = Annotation::new("line 1".to_string(), AnnotationType::Line);
+        line.add_vertex(Point::new(0.0, 0.0));
+        line.add_vertex(Point::new(1.0, 1.0));
+        data.annotations = vec![region, point, line];
+
+        let coco = to_coco(&data);
+
+        assert_eq!(coco["categories"].as_array().unwrap().len(), 2);
+        assert_eq!(coco["categories"][0]["name"], "car");
+        assert!(coco["categories"][0].get("keypoints").is_none());
+        assert_eq!(coco["categories"][1]["keypoints"], json!(["nose"]));
+
+        let annotations = coco["annotations"].as_array().unwrap();
+        assert_eq!(annotations.len(), 2);
+        assert_eq!(annotations[0]["segmentation"], json!([[0.0, 0.0, 50.0, 0.0, 50.0, 50.0]]));
+        assert_eq!(annotations[0]["area"], json!(1250.0));
+        assert_eq!(annotations[0]["bbox"], json!([0.0, 0.0, 50.0, 50.0]));
+        assert!(annotations[0].get("obb").is_none());
+        assert_eq!(annotations[1]["keypoints"], json!([25.0, 25.0, 2]));
+        assert_eq!(annotations[1]["num_keypoints"], 1);
+        assert_eq!(annotations[1]["category_id"], 2);
+    }
+
+    #[test]
+    fn test_coco_multi_part_polygon() {
+        let mut data = ProjectData::new("frame.png".to_string(), 100, 100);
+        let mut trees = Annotation::new("tree 1".to_string(), AnnotationType::Polygon);
+        for (x, y) in [(0.0, 0.0), (0.5, 0.0), (0.5, 0.5), (0.0, 0.5)] {
+            trees.add_vertex(Point::new(x, y));
+        }
+        trees.extra_rings = vec![Vertices(vec![Point::new(0.75, 0.75), Point::new(1.0, 0.75), Point::new(1.0, 1.0)])];
+        data.annotations.push(trees);
+
+        let coco = to_coco(&data);
+        let annotations = coco["annotations"].as_array().unwrap();
+        assert_eq!(annotations.len(), 1);
+        assert_eq!(
+            annotations[0]["segmentation"],
+            json!([[0.0, 0.0, 50.0, 0.0, 50.0, 50.0, 0.0, 50.0], [75.0, 75.0, 100.0, 75.0, 100.0, 100.0]])
+        );
+        assert_eq!(annotations[0]["area"], json!(2500.0 + 312.5));
+        assert_eq!(annotations[0]["bbox"], json!([0.0, 0.0, 100.0, 100.0]));
+        assert!(annotations[0].get("obb").is_none());
+    }
+
+    #[test]
+    fn test_coco_oriented_box() {
+        let mut data = ProjectData::new("frame.png".to_string(), 200, 100);
+        let obb = geometry::OrientedBox { cx: 100.0, cy: 50.0, width: 60.0, height: 20.0, angle: 45.0 };
+        let mut region = Annotation::new("car 1".to_string(), AnnotationType::Polygon);
+        region.vertices.0 = geometry::from_pixels(&obb.corners(), (200, 100));
+        data.annotations.push(region);
+
+        let exported = to_coco(&data)["annotations"][0]["obb"].as_array().unwrap().clone();
+        let values: Vec<f64> = exported.iter().map(|v| v.as_f64().unwrap()).collect();
+        for (value, expected) in values.iter().zip([100.0, 50.0, 60.0, 20.0, 45.0]) {
+            assert!((value - expected).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_excluded_annotations() {
+        let mut data = ProjectData::new("frame.png".to_string(), 100, 50);
+        let mut region = Annotation::new("car 1".to_string(), AnnotationType::Polygon);
+        for (x, y) in [(0.0, 0.0), (0.5, 0.0), (0.5, 1.0)] {
+            region.add_vertex(Point::new(x, y));
+        }
+        let mut scratch = region.clone();
+        scratch.name = "scratch 1".to_string();
+        scratch.export = false;
+        data.annotations = vec![region, scratch];
+
+        let coco = to_coco(&data);
+        assert_eq!(coco["annotations"].as_array().unwrap().len(), 1);
+        assert_eq!(coco["categories"].as_array().unwrap().len(), 1);
+        assert_eq!(coco["categories"][0]["name"], "car");
+
+        // Project saves keep excluded annotations, and included ones stay terse
+        let json = serde_json::to_string(&data).unwrap();
+        assert_eq!(json.matches(r#""export":false"#).count(), 1);
+        assert!(!json.contains(r#""export":true"#));
+        let loaded: ProjectData = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.annotations.len(), 2);
+        assert!(loaded.annotations[0].export && !loaded.annotations[1].export);
+    }
+}
@@ -4,13 +4,24 @@
 //! Media file loading (images).
 //!
 //! This module handles loading image files and converting them
-//! to formats suitable for display in egui.
+//! to formats suitable for display in egui. Grayscale, 16-bit, float, and
+//! CMYK sources are converted to RGBA by the decoders rather than having
+//! their channels copied.
 
+use crate::io::atomic;
+use crate::models::project::ProjectData;
+use crate::util::progress::ProgressReporter;
 use anyhow::{Context, Result};
-use image::ImageReader;
-use std::path::Path;
+use image::{DynamicImage, ExtendedColorType, ImageDecoder, ImageReader};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Image file extensions that can be opened (lowercase).
+pub const SUPPORTED_EXTENSIONS: &[&str] = &["jpg", "jpeg", "png", "bmp", "tiff", "tif"];
 
 /// Loaded image data ready for display.
+#[derive(Clone)]
 pub struct LoadedImage {
     /// Image width in pixels
     pub width: u32,
@@ -18,6 +29,8 @@ pub struct LoadedImage {
     pub height: u32,
     /// RGBA pixel data (4 bytes per pixel)
     pub pixels: Vec<u8>,
+    /// Color type of the source file, before conversion to RGBA
+    pub color_type: ExtendedColorType,
 }
 
 /// Load an image from a file path.
@@ -25,11 +38,13 @@ pub struct LoadedImage {
 /// Supports common image formats: JPEG, PNG, BMP, TIFF, etc.
 /// The image is converted to RGBA8 format for display in egui.
 pub fn load_image(path: &Path) -> Result<LoadedImage> {
-    // Load and decode the image
-    let img = ImageReader::open(path)
+    // Load and decode the image, keeping the file's own color type
+    let decoder = ImageReader::open(path)
         .context("Failed to open image file")?
-        .decode()
+        .into_decoder()
         .context("Failed to decode image")?;
+    let color_type = decoder.original_color_type();
+    let img = DynamicImage::from_decoder(decoder).context("Failed to decode image")?;
 
     // Convert to RGBA8
     let rgba_img = img.to_rgba8();
@@ -41,16 +56,466 @@ pub fn load_image(path: &Path) -> Result<LoadedImage> {
         width,
         height,
         pixels,
+        color_type,
     })
 }
 
+/// Check whether a path has a supported image extension (case-insensitive).
+pub fn is_supported_image(path: &Path) -> bool {
+    path.extension()
+        .and_then(|s| s.to_str())
+        .is_some_and(|ext| SUPPORTED_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+}
+
+/// List the supported image files in a directory, sorted by path.
+pub fn list_images(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(dir)
+        .with_context(|| format!("Failed to read folder {}", dir.display()))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.is_file() && is_supported_image(path))
+        .collect();
+    paths.sort();
+    Ok(paths)
+}
+
+/// Where a project's media file is and what is known about it on disk.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MediaInfo {
+    pub path: PathBuf,
+    pub width: u32,
+    pub height: u32,
+    /// Last modification time, None if the file is missing or the
+    /// filesystem does not record it
+    pub modified: Option<SystemTime>,
+    /// Whether the file no longer exists
+    pub missing: bool,
+}
+
+impl MediaInfo {
+    /// Look up a project's media file on disk.
+    pub fn read(project: &ProjectData) -> Self {
+        let path = PathBuf::from(&project.media_file);
+        let metadata = std::fs::metadata(&path).ok();
+        Self {
+            modified: metadata.as_ref().and_then(|m| m.modified().ok()),
+            missing: metadata.is_none(),
+            path,
+            width: project.frame_width,
+            height: project.frame_height,
+        }
+    }
+
+    /// File name, or the whole path if it has none.
+    pub fn file_name(&self) -> String {
+        self.path.file_name().unwrap_or(self.path.as_os_str()).to_string_lossy().into_owned()
+    }
+
+    /// Window title naming the file, e.g. "frame.png (1920 × 1080) - ROIDS".
+    pub fn window_title(&self) -> String {
+        let missing = if self.missing { " [missing]" } else { "" };
+        format!("{} ({} × {}){} - ROIDS", self.file_name(), self.width, self.height, missing)
+    }
+
+    /// Modification time as shown to the user.
+    pub fn modified_text(&self) -> String {
+        match (self.missing, self.modified) {
+            (true, _) => "File missing".to_string(),
+            (false, Some(time)) => format!("Modified {}", crate::util::time::utc_timestamp(time)),
+            (false, None) => "Modification time unknown".to_string(),
+        }
+    }
+}
+
+/// Save an image to a file path.
+///
+/// The output format is chosen from the file extension.
+pub fn save_image(image: &LoadedImage, path: &Path) -> Result<()> {
+    let buffer = image::RgbaImage::from_raw(image.width, image.height, image.pixels.clone())
+        .context("Image buffer does not match its dimensions")?;
+    atomic::write_atomic(path, |temp| buffer.save(temp).context("Failed to save image"))
+}
+
+/// Encode an image as PNG in memory.
+pub fn encode_png(image: &LoadedImage) -> Result<Vec<u8>> {
+    let buffer = image::RgbaImage::from_raw(image.width, image.height, image.pixels.clone())
+        .context("Image buffer does not match its dimensions")?;
+    let mut png = std::io::Cursor::new(Vec::new());
+    buffer.write_to(&mut png, image::ImageFormat::Png).context("Failed to encode image")?;
+    Ok(png.into_inner())
+}
+
+/// Rotate an image clockwise by a multiple of 90 degrees.
+pub fn rotate_image(image: &LoadedImage, degrees_cw: u16) -> LoadedImage {
+    let Some(buffer) = image::RgbaImage::from_raw(image.width, image.height, image.pixels.clone()) else {
+        return image.clone();
+    };
+    let rotated = match degrees_cw % 360 {
+        90 => image::imageops::rotate90(&buffer),
+        180 => image::imageops::rotate180(&buffer),
+        270 => image::imageops::rotate270(&buffer),
+        _ => buffer,
+    };
+    LoadedImage {
+        width: rotated.width(),
+        height: rotated.height(),
+        pixels: rotated.into_raw(),
+        color_type: image.color_type,
+    }
+}
+
+/// Mirror an image left-right when `horizontal`, top-bottom otherwise.
+pub fn flip_image(image: &LoadedImage, horizontal: bool) -> LoadedImage {
+    let Some(buffer) = image::RgbaImage::from_raw(image.width, image.height, image.pixels.clone()) else {
+        return image.clone();
+    };
+    let flipped = if horizontal {
+        image::imageops::flip_horizontal(&buffer)
+    } else {
+        image::imageops::flip_vertical(&buffer)
+    };
+    LoadedImage {
+        width: flipped.width(),
+        height: flipped.height(),
+        pixels: flipped.into_raw(),
+        color_type: image.color_type,
+    }
+}
+
+/// Rotate and flip a freshly loaded image the way a project shows it.
+pub fn orient_image(image: &LoadedImage, project: &ProjectData) -> LoadedImage {
+    let mut oriented = rotate_image(image, project.rotation);
+    if project.flipped_horizontally {
+        oriented = flip_image(&oriented, true);
+    }
+    if project.flipped_vertically {
+        oriented = flip_image(&oriented, false);
+    }
+    oriented
+}
+
+/// Size to display an image at when it is larger than the GPU's maximum
+/// texture side, keeping its aspect ratio; `None` when it fits as is.
+pub fn display_size(width: u32, height: u32, max_side: u32) -> Option<(u32, u32)> {
+    let longest = width.max(height);
+    if longest <= max_side || max_side == 0 {
+        return None;
+    }
+    let scale = max_side as f64 / longest as f64;
+    let fit = |side: u32| ((side as f64 * scale).floor() as u32).clamp(1, max_side);
+    Some((fit(width), fit(height)))
+}
+
+/// Resize an image, e.g. to a [`display_size`].
+pub fn resize_image(image: &LoadedImage, width: u32, height: u32) -> LoadedImage {
+    let Some(buffer) = image::RgbaImage::from_raw(image.width, image.height, image.pixels.clone()) else {
+        return image.clone();
+    };
+    let resized = image::imageops::resize(&buffer, width, height, image::imageops::FilterType::Triangle);
+    LoadedImage {
+        width: resized.width(),
+        height: resized.height(),
+        pixels: resized.into_raw(),
+        color_type: image.color_type,
+    }
+}
+
+/// Crop an image to the pixel rectangle from `min_px` (inclusive) to
+/// `max_px` (exclusive). The rectangle is clamped to the image bounds.
+pub fn crop_image(image: &LoadedImage, min_px: (u32, u32), max_px: (u32, u32)) -> LoadedImage {
+    let x0 = min_px.0.min(image.width);
+    let y0 = min_px.1.min(image.height);
+    let x1 = max_px.0.clamp(x0, image.width);
+    let y1 = max_px.1.clamp(y0, image.height);
+
+    let (width, height) = (x1 - x0, y1 - y0);
+    let mut pixels = Vec::with_capacity((width * height * 4) as usize);
+    for y in y0..y1 {
+        let start = ((y * image.width + x0) * 4) as usize;
+        pixels.extend_from_slice(&image.pixels[start..start + (width * 4) as usize]);
+    }
+
+    LoadedImage {
+        width,
+        height,
+        pixels,
+        color_type: image.color_type,
+    }
+}
+
+/// Export each annotation as a cropped PNG of its bounding box plus a JSON
+/// file describing its vertices in crop pixel coordinates.
+///
+/// Files are named after the annotation (made filesystem-safe and unique).
+/// Each annotation is reported to `progress`, and cancellation stops before
+/// the next one. Returns the number of annotations exported.
+pub fn export_annotation_crops(project: &ProjectData, image: &LoadedImage, dir: &Path, progress: &ProgressReporter) -> Result<usize> {
+    let (width, height) = (image.width as f64, image.height as f64);
+    let mut used_names = HashSet::new();
+    let mut exported = 0;
+
+    progress.set_total(project.exported_annotations().count());
+    for annotation in project.exported_annotations() {
+        if progress.is_cancelled() {
+            break;
+        }
+        progress.step(annotation.name.as_str());
+        let Some((min, max)) = annotation.bounding_box() else {
+            continue;
+        };
+
+        let min_px = ((min.x * width).floor().max(0.0) as u32, (min.y * height).floor().max(0.0) as u32);
+        let max_px = ((max.x * width).ceil().max(0.0) as u32, (max.y * height).ceil().max(0.0) as u32);
+        let crop = crop_image(image, min_px, max_px);
+        if crop.width == 0 || crop.height == 0 {
+            log::warn!("Skipping \"{}\": bounding box is empty", annotation.name);
+            continue;
+        }
+
+        let stem = unique_file_stem(&annotation.name, &mut used_names);
+        save_image(&crop, &dir.join(format!("{}.png", stem)))?;
+
+        let vertices: Vec<[f64; 2]> = annotation
+            .vertices
+            .0
+            .iter()
+            .map(|p| [p.x * width - min_px.0 as f64, p.y * height - min_px.1 as f64])
+            .collect();
+        let description = serde_json::json!({
+            "name": annotation.name,
+            "type": annotation.annotation_type,
+            "media_file": project.media_file,
+            "crop": {
+                "x": min_px.0,
+                "y": min_px.1,
+                "width": crop.width,
+                "height": crop.height,
+            },
+            "vertices": vertices,
+        });
+        atomic::write_bytes(
+            &dir.join(format!("{}.json", stem)),
+            serde_json::to_string_pretty(&description)?,
+        )?;
+
+        exported += 1;
+    }
+
+    Ok(exported)
+}
+
+/// Make a filesystem-safe file stem from an annotation name, adding a
+/// numeric suffix if the stem has already been used.
+fn unique_file_stem(name: &str, used: &mut HashSet<String>) -> String {
+    let base: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    let base = if base.is_empty() { "annotation".to_string() } else { base };
+
+    let mut stem = base.clone();
+    let mut suffix = 2;
+    while !used.insert(stem.clone()) {
+        stem = format!("{}_{}", base, suffix);
+        suffix += 1;
+    }
+    stem
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::util::scratch::scratch_dir;
 
     #[test]
     fn test_load_image_invalid_path() {
         let result = load_image(Path::new("/nonexistent/image.png"));
         assert!(result.is_err());
     }
+
+    /// Create an image where each pixel's red channel is its x coordinate
+    /// and green channel its y coordinate.
+    fn gradient_image(width: u32, height: u32) -> LoadedImage {
+        let mut pixels = Vec::new();
+        for y in 0..height {
+            for x in 0..width {
+                pixels.extend_from_slice(&[x as u8, y as u8, 0, 255]);
+            }
+        }
+        LoadedImage { width, height, pixels, color_type: ExtendedColorType::Rgba8 }
+    }
+
+    #[test]
+    fn test_load_grayscale_image() {
+        let dir = scratch_dir("grayscale");
+
+        let path = dir.join("gray.png");
+        image::GrayImage::from_raw(2, 1, vec![10, 200]).unwrap().save(&path).unwrap();
+        let loaded = load_image(&path).unwrap();
+        assert_eq!(loaded.color_type, ExtendedColorType::L8);
+        assert_eq!(loaded.pixels, [10, 10, 10, 255, 200, 200, 200, 255]);
+
+        // 16-bit samples are scaled down, not truncated
+        let path = dir.join("gray16.png");
+        image::ImageBuffer::<image::Luma<u16>, _>::from_raw(1, 1, vec![0x8080u16]).unwrap().save(&path).unwrap();
+        let loaded = load_image(&path).unwrap();
+        assert_eq!(loaded.color_type, ExtendedColorType::L16);
+        assert_eq!(loaded.pixels, [128, 128, 128, 255]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// Build an uncompressed little-endian CMYK TIFF with one row of pixels.
+    fn cmyk_tiff(pixels: &[[u8; 4]]) -> Vec<u8> {
+        // (tag, type, count, value); type 3 is SHORT, 4 is LONG
+        let width = pixels.len() as u32;
+        let ifd_offset = 8u32;
+        let entry_count = 10u16;
+        let bits_offset = ifd_offset + 2 + entry_count as u32 * 12 + 4;
+        let data_offset = bits_offset + 8;
+        let entries: [(u16, u16, u32, u32); 10] = [
+            (256, 4, 1, width),           // ImageWidth
+            (257, 4, 1, 1),               // ImageLength
+            (258, 3, 4, bits_offset),     // BitsPerSample, 8 per channel
+            (259, 3, 1, 1),               // No compression
+            (262, 3, 1, 5),               // Photometric: separated (CMYK)
+            (273, 4, 1, data_offset),     // StripOffsets
+            (277, 3, 1, 4),               // SamplesPerPixel
+            (278, 4, 1, 1),               // RowsPerStrip
+            (279, 4, 1, width * 4),       // StripByteCounts
+            (284, 3, 1, 1),               // Chunky planar configuration
+        ];
+
+        let mut bytes = b"II*\0".to_vec();
+        bytes.extend_from_slice(&ifd_offset.to_le_bytes());
+        bytes.extend_from_slice(&entry_count.to_le_bytes());
+        for (tag, kind, count, value) in entries {
+            bytes.extend_from_slice(&tag.to_le_bytes());
+            bytes.extend_from_slice(&kind.to_le_bytes());
+            bytes.extend_from_slice(&count.to_le_bytes());
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        for _ in 0..4 {
+            bytes.extend_from_slice(&8u16.to_le_bytes());
+        }
+        bytes.extend(pixels.iter().flatten());
+        bytes
+    }
+
+    #[test]
+    fn test_load_cmyk_image() {
+        let dir = scratch_dir("cmyk");
+        let path = dir.join("cmyk.tif");
+        // White, black, cyan
+        std::fs::write(&path, cmyk_tiff(&[[0, 0, 0, 0], [0, 0, 0, 255], [255, 0, 0, 0]])).unwrap();
+
+        let loaded = load_image(&path).unwrap();
+        assert_eq!(loaded.color_type, ExtendedColorType::Cmyk8);
+        assert_eq!((loaded.width, loaded.height), (3, 1));
+        assert_eq!(&loaded.pixels[0..4], &[255, 255, 255, 255]);
+        assert_eq!(&loaded.pixels[4..8], &[0, 0, 0, 255]);
+        assert_eq!(&loaded.pixels[8..12], &[0, 255, 255, 255]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_media_info() {
+        let dir = scratch_dir("media_info");
+        let path = dir.join("frame.png");
+        std::fs::write(&path, b"not really a png").unwrap();
+        let project = ProjectData::new(path.to_string_lossy().into_owned(), 640, 480);
+
+        let info = MediaInfo::read(&project);
+        assert!(!info.missing);
+        assert!(info.modified.is_some());
+        assert_eq!(info.window_title(), "frame.png (640 × 480) - ROIDS");
+        assert!(info.modified_text().starts_with("Modified "));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        let info = MediaInfo::read(&project);
+        assert!(info.missing);
+        assert_eq!(info.modified, None);
+        assert_eq!(info.window_title(), "frame.png (640 × 480) [missing] - ROIDS");
+        assert_eq!(info.modified_text(), "File missing");
+    }
+
+    #[test]
+    fn test_rotate_image() {
+        let image = gradient_image(3, 2);
+        let rotated = rotate_image(&image, 90);
+        assert_eq!((rotated.width, rotated.height), (2, 3));
+        // The bottom-left pixel (0, 1) becomes the top-left
+        assert_eq!(&rotated.pixels[0..4], &[0, 1, 0, 255]);
+
+        let back = rotate_image(&rotated, 270);
+        assert_eq!((back.width, back.height), (3, 2));
+        assert_eq!(back.pixels, image.pixels);
+    }
+
+    #[test]
+    fn test_display_size() {
+        // Images within the limit are shown as is
+        assert_eq!(display_size(4096, 4096, 8192), None);
+        assert_eq!(display_size(8192, 100, 8192), None);
+
+        // Oversized images shrink to fit the longest side, keeping the aspect ratio
+        assert_eq!(display_size(20_000, 10_000, 8192), Some((8192, 4096)));
+        assert_eq!(display_size(3000, 12_000, 2048), Some((512, 2048)));
+        // ... without collapsing thin images to nothing
+        assert_eq!(display_size(100_000, 3, 2048), Some((2048, 1)));
+
+        let resized = resize_image(&gradient_image(10, 4), 5, 2);
+        assert_eq!((resized.width, resized.height, resized.pixels.len()), (5, 2, 5 * 2 * 4));
+    }
+
+    #[test]
+    fn test_crop_image() {
+        let image = gradient_image(10, 8);
+        let crop = crop_image(&image, (2, 3), (5, 7));
+
+        assert_eq!((crop.width, crop.height), (3, 4));
+        assert_eq!(&crop.pixels[0..4], &[2, 3, 0, 255]);
+        // Last pixel is (4, 6)
+        assert_eq!(&crop.pixels[crop.pixels.len() - 4..], &[4, 6, 0, 255]);
+    }
+
+    #[test]
+    fn test_crop_image_clamps_to_edges() {
+        let image = gradient_image(10, 8);
+
+        let crop = crop_image(&image, (7, 5), (20, 30));
+        assert_eq!((crop.width, crop.height), (3, 3));
+        assert_eq!(crop.pixels.len(), 3 * 3 * 4);
+
+        let outside = crop_image(&image, (15, 2), (20, 4));
+        assert_eq!((outside.width, outside.height), (0, 2));
+        assert!(outside.pixels.is_empty());
+    }
+
+    #[test]
+    fn test_unique_file_stem() {
+        let mut used = HashSet::new();
+        assert_eq!(unique_file_stem("region 1", &mut used), "region_1");
+        assert_eq!(unique_file_stem("region 1", &mut used), "region_1_2");
+        assert_eq!(unique_file_stem("lane/left", &mut used), "lane_left");
+    }
+
+    #[test]
+    fn test_list_images() {
+        let dir = scratch_dir("list_images");
+        std::fs::create_dir_all(dir.join("nested.png")).unwrap();
+        for name in ["b.PNG", "a.jpg", "c.tif", "notes.txt", "frame.json", "d"] {
+            std::fs::write(dir.join(name), "").unwrap();
+        }
+
+        let names: Vec<String> = list_images(&dir)
+            .unwrap()
+            .iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(names, vec!["a.jpg", "b.PNG", "c.tif"]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 }
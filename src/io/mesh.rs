@@ -0,0 +1,90 @@
+// Copyright (c) 2025, Jason Jenkins
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Triangulated mesh export.
+//!
+//! Polygons are written as vertex arrays plus triangle index lists (see
+//! [`geometry::triangulate`]) for rendering pipelines such as WebGL.
+
+use crate::io::atomic;
+use crate::models::annotation::AnnotationType;
+use crate::models::project::ProjectData;
+use crate::util::geometry;
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::path::Path;
+
+/// Build the mesh document for a project.
+///
+/// Vertices are normalized `[x, y]` pairs and triangles index into them;
+/// each part of a multi-part polygon is triangulated on its own and its
+/// vertices follow those of the previous parts. Lines and points have no
+/// area and are skipped; polygons that cannot be triangulated are kept
+/// with an empty triangle list.
+pub fn to_mesh(data: &ProjectData) -> Value {
+    let meshes: Vec<Value> = data
+        .exported_annotations()
+        .filter(|annotation| annotation.annotation_type == AnnotationType::Polygon)
+        .map(|annotation| {
+            let mut vertices: Vec<[f64; 2]> = Vec::new();
+            let mut triangles = Vec::new();
+            for ring in annotation.rings() {
+                let offset = vertices.len();
+                triangles.extend(geometry::triangulate(ring).into_iter().map(|t| t.map(|i| i + offset)));
+                vertices.extend(ring.iter().map(|p| [p.x, p.y]));
+            }
+            if triangles.is_empty() {
+                log::warn!("Could not triangulate \"{}\"", annotation.name);
+            }
+            json!({
+                "name": annotation.name,
+                "role": annotation.role,
+                "color": annotation.display_color(),
+                "vertices": vertices,
+                "triangles": triangles,
+            })
+        })
+        .collect();
+
+    json!({
+        "media_file": data.media_file,
+        "width": data.frame_width,
+        "height": data.frame_height,
+        "meshes": meshes,
+    })
+}
+
+/// Export polygons as a triangulated mesh JSON file.
+pub fn export_mesh(data: &ProjectData, path: &Path) -> Result<()> {
+    atomic::write_bytes(path, serde_json::to_string_pretty(&to_mesh(data))?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::annotation::{Annotation, Point, Vertices};
+
+    #[test]
+    fn test_to_mesh() {
+        let mut data = ProjectData::new("frame.png".to_string(), 100, 100);
+        let mut square = Annotation::new("region 1".to_string(), AnnotationType::Polygon);
+        for (x, y) in [(0.1, 0.1), (0.5, 0.1), (0.5, 0.5), (0.1, 0.5)] {
+            square.add_vertex(Point::new(x, y));
+        }
+        data.annotations.push(square);
+        data.annotations.push(Annotation::new("line 1".to_string(), AnnotationType::Line));
+
+        let mesh = to_mesh(&data);
+        let meshes = mesh["meshes"].as_array().unwrap();
+        assert_eq!(meshes.len(), 1);
+        assert_eq!(meshes[0]["vertices"].as_array().unwrap().len(), 4);
+        assert_eq!(meshes[0]["triangles"].as_array().unwrap().len(), 2);
+        assert_eq!(meshes[0]["triangles"][0].as_array().unwrap().len(), 3);
+
+        // A second part adds its vertices and triangles after the first's
+        data.annotations[0].extra_rings = vec![Vertices(vec![Point::new(0.6, 0.6), Point::new(0.9, 0.6), Point::new(0.9, 0.9)])];
+        let mesh = to_mesh(&data);
+        assert_eq!(mesh["meshes"][0]["vertices"].as_array().unwrap().len(), 7);
+        assert_eq!(mesh["meshes"][0]["triangles"][2], serde_json::json!([4, 5, 6]));
+    }
+}
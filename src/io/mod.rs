@@ -3,5 +3,12 @@
 
 //! I/O operations for media and project files.
 
+pub mod atomic;
+pub mod autosave;
+pub mod coco;
 pub mod media;
+pub mod mesh;
 pub mod serialization;
+pub mod templates;
+pub mod validation;
+pub mod yolo;
@@ -4,11 +4,19 @@
 //! Project data serialization and deserialization.
 //!
 //! This module handles exporting and importing project data in YAML
-//! and JSON formats.
+//! and JSON formats, plus GeoJSON interchange, plain CSV coordinate
+//! lists, SVG outlines, and a compact versioned binary format (`.roidsb`).
 
+use crate::io::{atomic, media::{self, LoadedImage}};
+use crate::models::annotation::{self, Annotation, AnnotationRole, AnnotationType, LineStyle, Point, Vertices};
 use crate::models::project::ProjectData;
-use anyhow::Result;
-use std::path::Path;
+use crate::models::settings::AnnotationFormat;
+use crate::util::{geometry, progress::ProgressReporter};
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
 
 /// Export project data to YAML format with flow style for vertices.
 pub fn export_yaml(data: &ProjectData, path: &Path) -> Result<()> {
@@ -17,8 +25,7 @@ pub fn export_yaml(data: &ProjectData, path: &Path) -> Result<()> {
     // Convert block-style vertices to flow style
     yaml = convert_vertices_to_flow_style(&yaml);
 
-    std::fs::write(path, yaml)?;
-    Ok(())
+    atomic::write_bytes(path, yaml)
 }
 
 /// Convert block-style vertex sequences to flow style with square brackets.
@@ -92,14 +99,181 @@ fn convert_vertices_to_flow_style(yaml: &str) -> String {
     result
 }
 
+/// Normalized distance within which vertices of different annotations
+/// count as the same shared vertex.
+const SHARED_VERTEX_EPSILON: f64 = 1e-6;
+
+/// Export project data to YAML, writing vertices shared by several
+/// annotations once with an anchor and referencing them by alias elsewhere.
+///
+/// [`import_yaml`] resolves the aliases back to duplicated points.
+pub fn export_yaml_anchored(data: &ProjectData, path: &Path) -> Result<()> {
+    atomic::write_bytes(path, to_anchored_yaml(data)?)
+}
+
+/// Serialize project data as flow-style YAML with anchored shared vertices.
+fn to_anchored_yaml(data: &ProjectData) -> Result<String> {
+    // Annotations in the order their vertices appear in the YAML
+    let annotations: Vec<&Annotation> = data.annotations.iter().chain(data.frames.values().flatten()).collect();
+
+    // Cluster vertices within epsilon (via a grid of epsilon-sized cells) and
+    // note which annotations use each
+    let cell = |point: &Point| ((point.x / SHARED_VERTEX_EPSILON).round() as i64, (point.y / SHARED_VERTEX_EPSILON).round() as i64);
+    let mut grid: HashMap<(i64, i64), Vec<usize>> = HashMap::new();
+    let mut canonical: Vec<(Point, Vec<usize>)> = Vec::new();
+    let mut vertex_ids: Vec<Vec<usize>> = Vec::with_capacity(annotations.len());
+    for (ann_idx, annotation) in annotations.iter().enumerate() {
+        let ids = annotation
+            .vertices
+            .0
+            .iter()
+            .map(|vertex| {
+                let (cx, cy) = cell(vertex);
+                let found = (-1..=1)
+                    .flat_map(|dx| (-1..=1).map(move |dy| (cx + dx, cy + dy)))
+                    .filter_map(|key| grid.get(&key))
+                    .flatten()
+                    .copied()
+                    .find(|&id| {
+                        let point = &canonical[id].0;
+                        (point.x - vertex.x).abs() <= SHARED_VERTEX_EPSILON && (point.y - vertex.y).abs() <= SHARED_VERTEX_EPSILON
+                    });
+                let id = found.unwrap_or_else(|| {
+                    canonical.push((*vertex, Vec::new()));
+                    grid.entry((cx, cy)).or_default().push(canonical.len() - 1);
+                    canonical.len() - 1
+                });
+                if !canonical[id].1.contains(&ann_idx) {
+                    canonical[id].1.push(ann_idx);
+                }
+                id
+            })
+            .collect();
+        vertex_ids.push(ids);
+    }
+
+    // Rewrite each vertices line, anchoring a shared vertex at its first use
+    let yaml = convert_vertices_to_flow_style(&serde_yaml::to_string(data)?);
+    let mut anchors: HashMap<usize, usize> = HashMap::new();
+    let mut remaining = vertex_ids.iter();
+    let mut result = String::with_capacity(yaml.len());
+    for line in yaml.lines() {
+        let Some(position) = line.find("vertices: [").filter(|&at| line[..at].trim_start_matches([' ', '-']).is_empty()) else {
+            result.push_str(line);
+            result.push('\n');
+            continue;
+        };
+        let ids = remaining.next().context("More vertex lists in the YAML than annotations")?;
+        let vertices: Vec<String> = ids
+            .iter()
+            .map(|&id| {
+                let (point, users) = &canonical[id];
+                let pair = format!("[{:?}, {:?}]", point.x, point.y);
+                if users.len() < 2 {
+                    return pair;
+                }
+                match anchors.get(&id) {
+                    Some(anchor) => format!("*v{}", anchor),
+                    None => {
+                        let anchor = anchors.len() + 1;
+                        anchors.insert(id, anchor);
+                        format!("&v{} {}", anchor, pair)
+                    }
+                }
+            })
+            .collect();
+        result.push_str(&line[..position]);
+        result.push_str(&format!("vertices: [{}]\n", vertices.join(", ")));
+    }
+    Ok(result)
+}
+
 /// Export project data to JSON format.
-pub fn export_json(data: &ProjectData, path: &Path) -> Result<()> {
-    let json = serde_json::to_string_pretty(data)?;
-    std::fs::write(path, json)?;
-    Ok(())
+///
+/// JSON has no NaN or infinity, so annotations with non-finite
+/// coordinates are rejected rather than written as nulls. With
+/// `vertex_indices`, each annotation also gets a `vertex_indices` array
+/// numbering its `vertices`, for cross-referencing while debugging;
+/// importing ignores it.
+pub fn export_json(data: &ProjectData, path: &Path, vertex_indices: bool) -> Result<()> {
+    if let Some(annotation) = data.annotations.iter().chain(data.frames.values().flatten()).find(|a| !a.is_finite()) {
+        bail!("{} has NaN or infinite coordinates, which JSON cannot store", annotation.name);
+    }
+    let json = if vertex_indices {
+        let mut value = serde_json::to_value(data)?;
+        add_vertex_indices(&mut value);
+        serde_json::to_string_pretty(&value)?
+    } else {
+        serde_json::to_string_pretty(data)?
+    };
+    atomic::write_bytes(path, json)
+}
+
+/// Add a `vertex_indices` array to every annotation, including per-frame
+/// ones, of a serialized project.
+fn add_vertex_indices(project: &mut serde_json::Value) {
+    let Some(project) = project.as_object_mut() else {
+        return;
+    };
+    let mut lists = Vec::new();
+    for (key, value) in project.iter_mut() {
+        match key.as_str() {
+            "annotations" => lists.push(value),
+            "frames" => lists.extend(value.as_object_mut().into_iter().flat_map(|frames| frames.values_mut())),
+            _ => {}
+        }
+    }
+    for annotation in lists.into_iter().filter_map(|list| list.as_array_mut()).flatten() {
+        let count = annotation.get("vertices").and_then(|v| v.as_array()).map_or(0, Vec::len);
+        if let Some(object) = annotation.as_object_mut() {
+            object.insert("vertex_indices".to_string(), (0..count).collect::<Vec<_>>().into());
+        }
+    }
+}
+
+/// Export project data in the given format.
+pub fn export_as(data: &ProjectData, path: &Path, format: AnnotationFormat) -> Result<()> {
+    match format {
+        AnnotationFormat::Json => export_json(data, path, false),
+        AnnotationFormat::Yaml => export_yaml(data, path),
+    }
+}
+
+/// File name for one frame's annotations, e.g. "clip_frame_000120.json".
+fn frame_file_name(media_file: &str, frame: u64, format: AnnotationFormat) -> String {
+    let stem = Path::new(media_file)
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("frame");
+    format!("{}_frame_{:06}.{}", stem, frame, format.extension())
+}
+
+/// Write one annotation file per annotated video frame into `dir`.
+///
+/// Frames without annotations are skipped. Returns the written paths in
+/// frame order.
+pub fn export_frames(data: &ProjectData, dir: &Path, format: AnnotationFormat) -> Result<Vec<PathBuf>> {
+    std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+
+    let mut written = Vec::new();
+    for (&frame, annotations) in &data.frames {
+        if annotations.is_empty() {
+            continue;
+        }
+        let Some(project) = data.frame_project(frame) else {
+            continue;
+        };
+        let path = dir.join(frame_file_name(&data.media_file, frame, format));
+        export_as(&project, &path, format).with_context(|| format!("Failed to write {}", path.display()))?;
+        written.push(path);
+    }
+    Ok(written)
 }
 
 /// Import project data from YAML format.
+///
+/// Anchors and aliases, as written by [`export_yaml_anchored`], are
+/// resolved to duplicated points.
 pub fn import_yaml(path: &Path) -> Result<ProjectData> {
     let yaml = std::fs::read_to_string(path)?;
     let data = serde_yaml::from_str(&yaml)?;
@@ -112,3 +286,1217 @@ pub fn import_json(path: &Path) -> Result<ProjectData> {
     let data = serde_json::from_str(&json)?;
     Ok(data)
 }
+
+/// Extension for binary project files.
+pub const BINARY_EXTENSION: &str = "roidsb";
+
+/// Magic bytes at the start of every binary project file.
+const BINARY_MAGIC: &[u8; 6] = b"ROIDSB";
+
+/// Binary format version written by this build.
+/// Bump it whenever the payload layout below changes.
+const BINARY_VERSION: u16 = 1;
+
+/// Binary payload for a project.
+///
+/// Mirrors [`ProjectData`] without the `skip_serializing_if` attributes,
+/// which the non-self-describing bincode encoding cannot read back.
+#[derive(Serialize, Deserialize)]
+struct BinaryProject {
+    media_file: String,
+    frame_width: u32,
+    frame_height: u32,
+    annotations: Vec<BinaryAnnotation>,
+    default_view: Option<(Point, Point)>,
+    rotation: u16,
+    flipped_horizontally: bool,
+    flipped_vertically: bool,
+    frames: BTreeMap<u64, Vec<BinaryAnnotation>>,
+    north_offset_deg: Option<f64>,
+}
+
+/// Binary payload for an annotation.
+#[derive(Serialize, Deserialize)]
+struct BinaryAnnotation {
+    name: String,
+    annotation_type: AnnotationType,
+    vertices: Vertices,
+    color: Option<[u8; 4]>,
+    group: Option<String>,
+    role: AnnotationRole,
+    notes: Option<String>,
+    line_style: LineStyle,
+    export: bool,
+    extra_rings: Vec<Vertices>,
+    source_frame: Option<(u32, u32)>,
+}
+
+impl From<&Annotation> for BinaryAnnotation {
+    fn from(annotation: &Annotation) -> Self {
+        Self {
+            name: annotation.name.clone(),
+            annotation_type: annotation.annotation_type,
+            vertices: annotation.vertices.clone(),
+            color: annotation.color,
+            group: annotation.group.clone(),
+            role: annotation.role,
+            notes: annotation.notes.clone(),
+            line_style: annotation.line_style,
+            export: annotation.export,
+            extra_rings: annotation.extra_rings.clone(),
+            source_frame: annotation.source_frame,
+        }
+    }
+}
+
+impl From<BinaryAnnotation> for Annotation {
+    fn from(annotation: BinaryAnnotation) -> Self {
+        Self {
+            name: annotation.name,
+            annotation_type: annotation.annotation_type,
+            vertices: annotation.vertices,
+            color: annotation.color,
+            group: annotation.group,
+            role: annotation.role,
+            notes: annotation.notes,
+            line_style: annotation.line_style,
+            export: annotation.export,
+            extra_rings: annotation.extra_rings,
+            source_frame: annotation.source_frame,
+        }
+    }
+}
+
+impl From<BinaryProject> for ProjectData {
+    fn from(project: BinaryProject) -> Self {
+        let convert = |annotations: Vec<BinaryAnnotation>| annotations.into_iter().map(Annotation::from).collect::<Vec<_>>();
+        Self {
+            media_file: project.media_file,
+            frame_width: project.frame_width,
+            frame_height: project.frame_height,
+            annotations: convert(project.annotations),
+            default_view: project.default_view,
+            rotation: project.rotation,
+            flipped_horizontally: project.flipped_horizontally,
+            flipped_vertically: project.flipped_vertically,
+            frames: project.frames.into_iter().map(|(frame, annotations)| (frame, convert(annotations))).collect(),
+            north_offset_deg: project.north_offset_deg,
+        }
+    }
+}
+
+/// Save the full project in the compact binary format.
+///
+/// The file is the magic bytes, a little-endian format version, and the
+/// bincode-encoded project.
+pub fn save_project_bin(data: &ProjectData, path: &Path) -> Result<()> {
+    let convert = |annotations: &[Annotation]| annotations.iter().map(BinaryAnnotation::from).collect::<Vec<_>>();
+    let project = BinaryProject {
+        media_file: data.media_file.clone(),
+        frame_width: data.frame_width,
+        frame_height: data.frame_height,
+        annotations: convert(&data.annotations),
+        default_view: data.default_view,
+        rotation: data.rotation,
+        flipped_horizontally: data.flipped_horizontally,
+        flipped_vertically: data.flipped_vertically,
+        frames: data.frames.iter().map(|(&frame, annotations)| (frame, convert(annotations))).collect(),
+        north_offset_deg: data.north_offset_deg,
+    };
+
+    let mut bytes = BINARY_MAGIC.to_vec();
+    bytes.extend_from_slice(&BINARY_VERSION.to_le_bytes());
+    bytes.extend(bincode::serialize(&project)?);
+    atomic::write_bytes(path, bytes)
+}
+
+/// Load a project saved with [`save_project_bin`].
+///
+/// Files with an invalid or newer format version are rejected rather than
+/// misread.
+pub fn load_project_bin(path: &Path) -> Result<ProjectData> {
+    let bytes = std::fs::read(path)?;
+    let payload = bytes.strip_prefix(BINARY_MAGIC.as_slice()).context("Not a binary project file")?;
+    if payload.len() < 2 {
+        bail!("Binary project file is truncated");
+    }
+    let version = u16::from_le_bytes([payload[0], payload[1]]);
+    match version {
+        0 => bail!("Binary project version 0 is invalid"),
+        BINARY_VERSION => Ok(bincode::deserialize::<BinaryProject>(&payload[2..])?.into()),
+        _ => bail!("Binary project version {} is newer than supported version {}", version, BINARY_VERSION),
+    }
+}
+
+/// Normalized distance within which a polygon's last vertex counts as a
+/// repeat of its first.
+const RING_CLOSE_EPSILON: f64 = 1e-9;
+
+/// Drop repeated closing vertices from every polygon in a project.
+fn normalize_rings(data: &mut ProjectData) {
+    for annotation in data.annotations.iter_mut().chain(data.frames.values_mut().flatten()) {
+        if annotation.annotation_type == AnnotationType::Polygon {
+            for ring in annotation.rings_mut() {
+                geometry::normalize_ring(ring, RING_CLOSE_EPSILON);
+            }
+        }
+    }
+}
+
+/// Import project data from a file, choosing the format by extension.
+///
+/// Polygon rings that repeat their first vertex at the end are normalized.
+pub fn import_file(path: &Path) -> Result<ProjectData> {
+    let extension = path.extension().and_then(|s| s.to_str());
+    let mut data = match extension {
+        Some("yaml") | Some("yml") => import_yaml(path).context("Failed to import YAML")?,
+        Some("json") => import_json(path).context("Failed to import JSON")?,
+        Some(BINARY_EXTENSION) => load_project_bin(path).context("Failed to load binary project")?,
+        _ => bail!("Unsupported file extension: {:?}", extension),
+    };
+    normalize_rings(&mut data);
+    let non_finite = data.annotations.iter().chain(data.frames.values().flatten()).filter(|a| !a.is_finite()).count();
+    if non_finite > 0 {
+        log::warn!("{} annotations in {} have NaN or infinite coordinates", non_finite, path.display());
+    }
+    Ok(data)
+}
+
+/// Check whether a path has an annotation file extension.
+pub fn is_annotation_file(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|s| s.to_str()),
+        Some("yaml") | Some("yml") | Some("json") | Some(BINARY_EXTENSION)
+    )
+}
+
+/// Append another project's annotations, including per-frame ones, to
+/// `base`.
+///
+/// Normalized coordinates only line up on an image of the same size, so
+/// projects with different frame sizes are refused. Returns the number of
+/// annotations renamed (see [`append_annotations`]).
+pub fn merge_projects(base: &mut ProjectData, other: ProjectData) -> Result<usize> {
+    if (other.frame_width, other.frame_height) != (base.frame_width, base.frame_height) {
+        bail!(
+            "Frame size {}x{} does not match the current {}x{}; the annotations would need reprojecting",
+            other.frame_width, other.frame_height, base.frame_width, base.frame_height
+        );
+    }
+    Ok(append_annotations(base, other))
+}
+
+/// Append another project's annotations, including per-frame ones, to
+/// `base` whatever its frame size.
+///
+/// Incoming annotations whose names are already taken are renamed to the
+/// first free number of their category (see [`annotation::unique_name`]),
+/// e.g. a second "car 1" becomes "car 2". Returns the number renamed.
+pub fn append_annotations(base: &mut ProjectData, other: ProjectData) -> usize {
+    let mut renamed = 0;
+    let mut append = |existing: &mut Vec<Annotation>, incoming: Vec<Annotation>| {
+        let mut used: HashSet<String> = existing.iter().map(|a| a.name.clone()).collect();
+        for mut annotation in incoming {
+            if used.contains(&annotation.name) {
+                annotation.name = annotation::unique_name(&annotation.name, |name| used.contains(name));
+                renamed += 1;
+            }
+            used.insert(annotation.name.clone());
+            existing.push(annotation);
+        }
+    };
+    append(&mut base.annotations, other.annotations);
+    for (frame, annotations) in other.frames {
+        append(base.frames.entry(frame).or_default(), annotations);
+    }
+    renamed
+}
+
+/// Candidate sidecar annotation paths for an image, in priority order.
+///
+/// A sidecar shares the image's directory and file stem,
+/// e.g. `frame_0001.png` -> `frame_0001.json`.
+pub fn sidecar_paths(image_path: &Path) -> Vec<PathBuf> {
+    ["json", "yaml", "yml"]
+        .iter()
+        .map(|ext| image_path.with_extension(ext))
+        .collect()
+}
+
+/// Find the first existing sidecar annotation file for an image.
+pub fn find_sidecar(image_path: &Path) -> Option<PathBuf> {
+    sidecar_paths(image_path).into_iter().find(|p| p.is_file())
+}
+
+/// Outcome of importing a folder of annotation files.
+#[derive(Debug, Default)]
+pub struct BatchImportReport {
+    /// Successfully imported files and their data
+    pub imported: Vec<(PathBuf, ProjectData)>,
+    /// Files that failed to import and the reason
+    pub failed: Vec<(PathBuf, String)>,
+    /// Whether the import was cancelled before every file was read
+    pub cancelled: bool,
+}
+
+impl BatchImportReport {
+    /// Short human-readable summary, e.g. "8 imported, 2 failed".
+    pub fn summary(&self) -> String {
+        format!("{} imported, {} failed", self.imported.len(), self.failed.len())
+    }
+}
+
+/// Import every annotation file in a folder.
+///
+/// Each file is processed independently so that one bad file does not
+/// abort the whole batch. Files are processed in sorted path order, each
+/// reported to `progress`, and cancellation stops before the next file.
+pub fn import_folder(dir: &Path, progress: &ProgressReporter) -> Result<BatchImportReport> {
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(dir)
+        .with_context(|| format!("Failed to read folder {}", dir.display()))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.is_file() && is_annotation_file(path))
+        .collect();
+    paths.sort();
+
+    let mut report = BatchImportReport::default();
+    progress.set_total(paths.len());
+    for path in paths {
+        if progress.is_cancelled() {
+            report.cancelled = true;
+            break;
+        }
+        let name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+        match import_file(&path) {
+            Ok(data) => report.imported.push((path, data)),
+            Err(e) => report.failed.push((path, format!("{:#}", e))),
+        }
+        progress.step(name);
+    }
+
+    Ok(report)
+}
+
+/// Map extent of a GeoJSON file, mapped onto the full image.
+///
+/// Map y increases upward (north), image y increases downward.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GeoBounds {
+    pub min_x: f64,
+    pub min_y: f64,
+    pub max_x: f64,
+    pub max_y: f64,
+}
+
+impl GeoBounds {
+    /// Bounds in image pixel units with the origin at the bottom-left.
+    pub fn from_image_size(width: u32, height: u32) -> Self {
+        Self {
+            min_x: 0.0,
+            min_y: 0.0,
+            max_x: width as f64,
+            max_y: height as f64,
+        }
+    }
+
+    /// Read bounds from a GeoJSON `bbox` member ([min_x, min_y, max_x, max_y]).
+    fn from_bbox(value: &Value) -> Option<Self> {
+        let bbox: Vec<f64> = value.as_array()?.iter().filter_map(Value::as_f64).collect();
+        match bbox[..] {
+            [min_x, min_y, max_x, max_y] if max_x > min_x && max_y > min_y => Some(Self { min_x, min_y, max_x, max_y }),
+            _ => None,
+        }
+    }
+
+    /// Convert map coordinates to a normalized image point.
+    pub fn map_to_image(&self, x: f64, y: f64) -> Point {
+        Point::new(
+            (x - self.min_x) / (self.max_x - self.min_x),
+            (self.max_y - y) / (self.max_y - self.min_y),
+        )
+    }
+
+    /// Convert a normalized image point to map coordinates.
+    pub fn image_to_map(&self, point: &Point) -> [f64; 2] {
+        [
+            self.min_x + point.x * (self.max_x - self.min_x),
+            self.max_y - point.y * (self.max_y - self.min_y),
+        ]
+    }
+}
+
+/// Import Polygon, LineString and Point features from a GeoJSON Feature
+/// or FeatureCollection.
+///
+/// Coordinates are mapped onto the image using the file's `bbox` if present,
+/// otherwise they are taken as pixels with the origin at the bottom-left.
+/// Feature `name` properties become annotation names. Polygon holes and
+/// other geometry types are skipped.
+pub fn import_geojson(path: &Path, image_size: (u32, u32)) -> Result<ProjectData> {
+    let text = std::fs::read_to_string(path)?;
+    let root: Value = serde_json::from_str(&text).context("Failed to parse GeoJSON")?;
+
+    let bounds = root
+        .get("bbox")
+        .and_then(GeoBounds::from_bbox)
+        .unwrap_or_else(|| GeoBounds::from_image_size(image_size.0, image_size.1));
+
+    let features = match root.get("type").and_then(Value::as_str) {
+        Some("FeatureCollection") => root
+            .get("features")
+            .and_then(Value::as_array)
+            .ok_or_else(|| anyhow!("FeatureCollection has no features array"))?
+            .iter()
+            .collect(),
+        Some("Feature") => vec![&root],
+        other => bail!("Unsupported GeoJSON type: {:?}", other),
+    };
+
+    let media_file = root.get("media_file").and_then(Value::as_str).unwrap_or_default();
+    let mut data = ProjectData::new(media_file.to_string(), image_size.0, image_size.1);
+
+    for (idx, feature) in features.into_iter().enumerate() {
+        let geometry = feature.get("geometry").unwrap_or(&Value::Null);
+        let (annotation_type, coordinates) = match geometry.get("type").and_then(Value::as_str) {
+            Some("Polygon") => (AnnotationType::Polygon, geometry.pointer("/coordinates/0")),
+            Some("MultiPolygon") => (AnnotationType::Polygon, geometry.pointer("/coordinates/0/0")),
+            Some("LineString") => (AnnotationType::Line, geometry.get("coordinates")),
+            Some("Point") => (AnnotationType::Point, None),
+            other => {
+                log::warn!("Skipping feature {} with unsupported geometry {:?}", idx, other);
+                continue;
+            }
+        };
+
+        let vertices = match annotation_type {
+            // A Point's coordinates are a single position rather than a list
+            AnnotationType::Point => parse_positions(&Value::Array(vec![geometry["coordinates"].clone()])),
+            _ => parse_positions(coordinates.unwrap_or(&Value::Null)),
+        }
+        .with_context(|| format!("Invalid coordinates in feature {}", idx))?;
+
+        let properties = feature.get("properties");
+        let name = properties
+            .and_then(|p| p.get("name"))
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("{} {}", annotation_type.default_name_prefix(), data.annotations.len() + 1));
+
+        let mut annotation = Annotation::new(name, annotation_type);
+        annotation.group = properties
+            .and_then(|p| p.get("group"))
+            .and_then(Value::as_str)
+            .map(str::to_string);
+        annotation.notes = properties
+            .and_then(|p| p.get("notes"))
+            .and_then(Value::as_str)
+            .map(str::to_string);
+        annotation.role = properties
+            .and_then(|p| p.get("role"))
+            .and_then(|role| serde_json::from_value(role.clone()).ok())
+            .unwrap_or_default();
+        for [x, y] in vertices {
+            annotation.add_vertex(bounds.map_to_image(x, y));
+        }
+        // Later parts of a multi-polygon, keeping only their exteriors
+        if let Some(parts) = geometry.get("coordinates").filter(|_| geometry["type"] == "MultiPolygon").and_then(Value::as_array) {
+            for part in parts.iter().skip(1) {
+                let positions = parse_positions(part.get(0).unwrap_or(&Value::Null))
+                    .with_context(|| format!("Invalid coordinates in feature {}", idx))?;
+                annotation.extra_rings.push(Vertices(positions.into_iter().map(|[x, y]| bounds.map_to_image(x, y)).collect()));
+            }
+        }
+        data.annotations.push(annotation);
+    }
+
+    // GeoJSON rings repeat the first position at the end
+    normalize_rings(&mut data);
+    Ok(data)
+}
+
+/// Parse an array of [x, y] positions.
+fn parse_positions(value: &Value) -> Result<Vec<[f64; 2]>> {
+    value
+        .as_array()
+        .ok_or_else(|| anyhow!("Expected an array of positions"))?
+        .iter()
+        .map(|position| match position.as_array().map(|p| p.iter().map(Value::as_f64).collect::<Vec<_>>()) {
+            Some(p) if p.len() >= 2 => match (p[0], p[1]) {
+                (Some(x), Some(y)) => Ok([x, y]),
+                _ => bail!("Position has non-numeric coordinates"),
+            },
+            _ => bail!("Position must have at least two coordinates"),
+        })
+        .collect()
+}
+
+/// Split a coordinate row of the form `x,y` or `name,x,y`.
+///
+/// Splits from the right so names may contain commas; quotes around the
+/// name are dropped. Returns None if the coordinates are not numbers.
+fn parse_coordinate_row(line: &str) -> Option<(Option<&str>, f64, f64)> {
+    let fields: Vec<&str> = line.rsplitn(3, ',').map(str::trim).collect();
+    let (y, x, name) = match fields.as_slice() {
+        [y, x] => (y, x, None),
+        [y, x, name] => (y, x, Some(name.trim_matches('"'))),
+        _ => return None,
+    };
+    Some((name, x.parse().ok()?, y.parse().ok()?))
+}
+
+/// Whether a row is a column header such as `name,x,y`, with no numbers
+/// among its fields.
+fn is_header_row(line: &str) -> bool {
+    line.split(',').map(str::trim).all(|field| field.parse::<f64>().is_err() && field.chars().any(char::is_alphabetic))
+}
+
+/// Parse a typed or pasted list of `x,y` rows into the vertices of an
+/// annotation of the given type.
+///
+/// Coordinates are pixels unless `normalized` is set, and must lie within
+/// the image. A leading header row, blank lines, and `#` comments are
+/// ignored. Errors name the offending line.
+pub fn parse_coordinates(text: &str, annotation_type: AnnotationType, normalized: bool, image_size: (u32, u32)) -> Result<Vec<Point>> {
+    let (width, height) = if normalized { (1.0, 1.0) } else { (image_size.0 as f64, image_size.1 as f64) };
+
+    let mut points = Vec::new();
+    let mut seen_data = false;
+    for (line_idx, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (x, y) = match parse_coordinate_row(line) {
+            Some((None, x, y)) => (x, y),
+            _ if !seen_data && is_header_row(line) => {
+                seen_data = true;
+                continue;
+            }
+            _ => bail!("Line {}: expected \"x,y\" but found \"{}\"", line_idx + 1, line),
+        };
+        seen_data = true;
+        if !(0.0..=width).contains(&x) || !(0.0..=height).contains(&y) {
+            bail!("Line {}: ({}, {}) is outside the image", line_idx + 1, x, y);
+        }
+        points.push(Point::new(x / width, y / height));
+    }
+
+    let needed = annotation_type.min_vertices();
+    match annotation_type {
+        AnnotationType::Point if points.len() != 1 => bail!("A point needs exactly one coordinate, found {}", points.len()),
+        _ if points.len() < needed => {
+            let type_name = format!("{:?}", annotation_type).to_lowercase();
+            bail!("A {} needs at least {} coordinates, found {}", type_name, needed, points.len())
+        }
+        _ => Ok(points),
+    }
+}
+
+/// Import annotations from a CSV coordinate list with `name,x,y` rows.
+///
+/// Consecutive rows with the same name form one annotation of the given
+/// type. Coordinates are pixels unless `normalized` is set. A leading
+/// header row, blank lines, and `#` comments are ignored; malformed rows,
+/// rows outside the image, and annotations with too few vertices are
+/// skipped with a warning. Polygons that repeat their first vertex at the
+/// end are normalized.
+pub fn import_csv(path: &Path, normalized: bool, image_size: (u32, u32), annotation_type: AnnotationType) -> Result<Vec<Annotation>> {
+    let text = std::fs::read_to_string(path)?;
+    let (width, height) = if normalized { (1.0, 1.0) } else { (image_size.0 as f64, image_size.1 as f64) };
+
+    let mut annotations: Vec<Annotation> = Vec::new();
+    let mut seen_data = false;
+    for (line_idx, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let Some((Some(name), x, y)) = parse_coordinate_row(line) else {
+            if !seen_data && is_header_row(line) {
+                log::info!("Treating CSV row {} as a header", line_idx + 1);
+            } else {
+                log::warn!("Skipping malformed CSV row {}: {}", line_idx + 1, line);
+            }
+            seen_data = true;
+            continue;
+        };
+        seen_data = true;
+        if !(0.0..=width).contains(&x) || !(0.0..=height).contains(&y) {
+            log::warn!("Skipping CSV row {} outside the image: ({}, {})", line_idx + 1, x, y);
+            continue;
+        }
+
+        if annotations.last().is_none_or(|a| a.name != name) {
+            annotations.push(Annotation::new(name.to_string(), annotation_type));
+        }
+        if let Some(annotation) = annotations.last_mut() {
+            annotation.add_vertex(Point::new(x / width, y / height));
+        }
+    }
+
+    if annotation_type == AnnotationType::Polygon {
+        for annotation in &mut annotations {
+            geometry::normalize_ring(&mut annotation.vertices.0, RING_CLOSE_EPSILON);
+        }
+    }
+    annotations.retain(|annotation| {
+        let keep = annotation.can_finish();
+        if !keep {
+            log::warn!("Skipping CSV annotation \"{}\" with {} vertices", annotation.name, annotation.vertex_count());
+        }
+        keep
+    });
+    Ok(annotations)
+}
+
+/// Export annotations as a GeoJSON FeatureCollection, the inverse of
+/// [`import_geojson`].
+///
+/// Coordinates are written in pixels with the origin at the bottom-left and
+/// the image extent recorded as the `bbox`.
+pub fn export_geojson(data: &ProjectData, path: &Path) -> Result<()> {
+    let bounds = GeoBounds::from_image_size(data.frame_width, data.frame_height);
+
+    let features: Vec<Value> = data
+        .exported_annotations()
+        .map(|annotation| {
+            let mut positions: Vec<[f64; 2]> = annotation.vertices.0.iter().map(|p| bounds.image_to_map(p)).collect();
+            let geometry = match annotation.annotation_type {
+                AnnotationType::Polygon if annotation.is_multi_part() => {
+                    // One single-ring polygon per part, each closed
+                    let parts: Vec<Vec<Vec<[f64; 2]>>> = annotation
+                        .rings()
+                        .map(|ring| {
+                            let mut positions: Vec<[f64; 2]> = ring.iter().map(|p| bounds.image_to_map(p)).collect();
+                            positions.extend(positions.first().copied());
+                            vec![positions]
+                        })
+                        .collect();
+                    json!({ "type": "MultiPolygon", "coordinates": parts })
+                }
+                AnnotationType::Polygon => {
+                    if let Some(&first) = positions.first() {
+                        positions.push(first);
+                    }
+                    json!({ "type": "Polygon", "coordinates": [positions] })
+                }
+                AnnotationType::Line => json!({ "type": "LineString", "coordinates": positions }),
+                AnnotationType::Point => json!({ "type": "Point", "coordinates": positions.first() }),
+            };
+
+            let mut properties = json!({ "name": annotation.name, "role": annotation.role });
+            if let Some(ref group) = annotation.group {
+                properties["group"] = json!(group);
+            }
+            if let Some(ref notes) = annotation.notes {
+                properties["notes"] = json!(notes);
+            }
+            json!({ "type": "Feature", "properties": properties, "geometry": geometry })
+        })
+        .collect();
+
+    let collection = json!({
+        "type": "FeatureCollection",
+        "bbox": [bounds.min_x, bounds.min_y, bounds.max_x, bounds.max_y],
+        "media_file": data.media_file,
+        "features": features,
+    });
+    atomic::write_bytes(path, serde_json::to_string_pretty(&collection)?)?;
+    Ok(())
+}
+
+/// Radius in pixels of the circle drawn for a point annotation in SVG.
+const SVG_POINT_RADIUS: f64 = 4.0;
+
+/// Font size in pixels of the vertex index labels in SVG.
+const SVG_INDEX_FONT_SIZE: f64 = 10.0;
+
+/// Escape text for use in SVG content and attribute values.
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Image file as a base64 `data:` URL, re-encoded as PNG unless the
+/// browser-friendly original can be embedded as-is.
+fn image_data_url(image: &LoadedImage) -> Result<String> {
+    use base64::Engine;
+
+    let png = media::encode_png(image)?;
+    Ok(format!("data:image/png;base64,{}", base64::engine::general_purpose::STANDARD.encode(png)))
+}
+
+/// Build an SVG document of the annotation outlines in pixel coordinates,
+/// optionally over an image, as shown on the canvas, embedded as a PNG
+/// background.
+///
+/// Polygons become `<polygon>` elements (one per ring), lines `<polyline>`
+/// elements, and points `<circle>` elements, each stroked in its
+/// annotation's display color and titled with its name. With
+/// `vertex_indices`, each shape is followed by a group of `<text>` labels
+/// numbering its vertices, each carrying a `data-index` attribute.
+pub fn to_svg(data: &ProjectData, background: Option<&LoadedImage>, vertex_indices: bool) -> Result<String> {
+    let (width, height) = (data.frame_width, data.frame_height);
+    if width == 0 || height == 0 {
+        bail!("Frame size is missing, so the SVG can't be sized");
+    }
+
+    let mut svg = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">\n",
+        w = width,
+        h = height
+    );
+    if let Some(image) = background {
+        let url = image_data_url(image).context("Failed to embed the image")?;
+        svg.push_str(&format!("  <image href=\"{}\" x=\"0\" y=\"0\" width=\"{}\" height=\"{}\"/>\n", url, width, height));
+    }
+
+    for annotation in data.exported_annotations() {
+        let [r, g, b, a] = annotation.display_color();
+        let mut style = format!("stroke=\"#{:02x}{:02x}{:02x}\"", r, g, b);
+        if a < 255 {
+            style.push_str(&format!(" stroke-opacity=\"{:.3}\"", a as f64 / 255.0));
+        }
+        if annotation.line_style == LineStyle::Dashed {
+            style.push_str(" stroke-dasharray=\"6 4\"");
+        }
+        let title = format!("<title>{}</title>", escape_xml(&annotation.name));
+        let points = |ring: &[Point]| -> String {
+            geometry::to_pixels(ring, (width, height))
+                .iter()
+                .map(|p| format!("{:.2},{:.2}", p.x, p.y))
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        let indices = |ring: &[Point]| -> String {
+            if !vertex_indices {
+                return String::new();
+            }
+            let labels: String = geometry::to_pixels(ring, (width, height))
+                .iter()
+                .enumerate()
+                .map(|(i, p)| format!("    <text x=\"{:.2}\" y=\"{:.2}\" data-index=\"{}\">{}</text>\n", p.x, p.y, i, i))
+                .collect();
+            format!(
+                "  <g class=\"vertex-indices\" font-size=\"{}\" fill=\"#{:02x}{:02x}{:02x}\">\n{}  </g>\n",
+                SVG_INDEX_FONT_SIZE, r, g, b, labels
+            )
+        };
+
+        match annotation.annotation_type {
+            AnnotationType::Polygon => {
+                for ring in annotation.rings() {
+                    svg.push_str(&format!("  <polygon points=\"{}\" fill=\"none\" stroke-width=\"2\" {}>{}</polygon>\n", points(ring), style, title));
+                    svg.push_str(&indices(ring));
+                }
+            }
+            AnnotationType::Line => {
+                svg.push_str(&format!("  <polyline points=\"{}\" fill=\"none\" stroke-width=\"2\" {}>{}</polyline>\n", points(&annotation.vertices.0), style, title));
+                svg.push_str(&indices(&annotation.vertices.0));
+            }
+            AnnotationType::Point => {
+                if let Some(p) = geometry::to_pixels(&annotation.vertices.0, (width, height)).first() {
+                    svg.push_str(&format!(
+                        "  <circle cx=\"{:.2}\" cy=\"{:.2}\" r=\"{}\" fill=\"#{:02x}{:02x}{:02x}\" {}>{}</circle>\n",
+                        p.x, p.y, SVG_POINT_RADIUS, r, g, b, style, title
+                    ));
+                    svg.push_str(&indices(&annotation.vertices.0));
+                }
+            }
+        }
+    }
+
+    svg.push_str("</svg>\n");
+    Ok(svg)
+}
+
+/// Export annotation outlines as an SVG sized to the frame.
+pub fn export_svg(data: &ProjectData, path: &Path, vertex_indices: bool) -> Result<()> {
+    atomic::write_bytes(path, to_svg(data, None, vertex_indices)?)
+}
+
+/// Export annotation outlines as an SVG over the embedded image.
+pub fn export_svg_with_image(data: &ProjectData, path: &Path, image: &LoadedImage, vertex_indices: bool) -> Result<()> {
+    atomic::write_bytes(path, to_svg(data, Some(image), vertex_indices)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::scratch::scratch_dir;
+
+    #[test]
+    fn test_binary_round_trip() {
+        let dir = scratch_dir("binary_round_trip");
+        let mut data = ProjectData::new("frame.png".to_string(), 1920, 1080);
+        let mut dense = Annotation::new("region 1".to_string(), AnnotationType::Polygon);
+        for i in 0..2000 {
+            let angle = i as f64 / 2000.0 * std::f64::consts::TAU;
+            dense.add_vertex(Point::new(0.5 + 0.4 * angle.cos(), 0.5 + 0.4 * angle.sin()));
+        }
+        dense.color = Some([255, 0, 0, 255]);
+        dense.notes = Some("traced by hand".to_string());
+        dense.extra_rings = vec![Vertices(vec![Point::new(0.0, 0.0), Point::new(0.05, 0.0), Point::new(0.0, 0.05)])];
+        let mut line = Annotation::new("line 1".to_string(), AnnotationType::Line);
+        line.add_vertex(Point::new(0.1, 0.2));
+        line.add_vertex(Point::new(0.3, 0.4));
+        line.group = Some("counts".to_string());
+        line.line_style = LineStyle::Dashed;
+        line.export = false;
+        line.source_frame = Some((640, 480));
+        data.annotations = vec![dense, line.clone()];
+        data.default_view = Some((Point::new(0.1, 0.1), Point::new(0.9, 0.9)));
+        data.flipped_vertically = true;
+        data.north_offset_deg = Some(12.5);
+        data.frames.insert(120, vec![line]);
+
+        let bin_path = dir.join("project.roidsb");
+        let json_path = dir.join("project.json");
+        save_project_bin(&data, &bin_path).unwrap();
+        export_json(&data, &json_path, false).unwrap();
+
+        let loaded = import_file(&bin_path).unwrap();
+        assert_eq!(loaded.media_file, data.media_file);
+        assert_eq!((loaded.frame_width, loaded.frame_height), (1920, 1080));
+        assert_eq!(loaded.annotations, data.annotations);
+        assert_eq!(loaded.default_view, data.default_view);
+        assert!(loaded.flipped_vertically && !loaded.flipped_horizontally);
+        assert_eq!(loaded.north_offset_deg, Some(12.5));
+        assert_eq!(loaded.frames, data.frames);
+        assert!(std::fs::metadata(&bin_path).unwrap().len() < std::fs::metadata(&json_path).unwrap().len());
+
+        // Files with an invalid or newer format version are rejected
+        for version in [0, BINARY_VERSION + 1] {
+            let mut bytes = std::fs::read(&bin_path).unwrap();
+            bytes[BINARY_MAGIC.len()..BINARY_MAGIC.len() + 2].copy_from_slice(&version.to_le_bytes());
+            std::fs::write(&bin_path, bytes).unwrap();
+            assert!(load_project_bin(&bin_path).is_err(), "version {version}");
+        }
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_import_normalizes_closed_rings() {
+        let dir = scratch_dir("closed_rings");
+        let mut data = ProjectData::new("frame.png".to_string(), 100, 100);
+        let mut ring = Annotation::new("region 1".to_string(), AnnotationType::Polygon);
+        for (x, y) in [(0.1, 0.1), (0.5, 0.1), (0.5, 0.5), (0.1, 0.1)] {
+            ring.add_vertex(Point::new(x, y));
+        }
+        let mut line = Annotation::new("line 1".to_string(), AnnotationType::Line);
+        for (x, y) in [(0.1, 0.1), (0.5, 0.5), (0.1, 0.1)] {
+            line.add_vertex(Point::new(x, y));
+        }
+        data.annotations = vec![ring, line];
+
+        let path = dir.join("closed.json");
+        export_json(&data, &path, false).unwrap();
+        let imported = import_file(&path).unwrap();
+        assert_eq!(imported.annotations[0].vertex_count(), 3);
+        // Lines may legitimately return to their start
+        assert_eq!(imported.annotations[1].vertex_count(), 3);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_notes_round_trip() {
+        let dir = scratch_dir("notes_round_trip");
+        let mut data = ProjectData::new("frame.png".to_string(), 100, 100);
+        let mut noted = Annotation::new("region 1".to_string(), AnnotationType::Polygon);
+        noted.add_vertex(Point::new(0.1, 0.1));
+        noted.add_vertex(Point::new(0.5, 0.1));
+        noted.add_vertex(Point::new(0.5, 0.5));
+        noted.notes = Some("partially occluded by pole\nsecond line: [0.1, 0.2]".to_string());
+        let mut plain = noted.clone();
+        plain.name = "region 2".to_string();
+        plain.notes = None;
+        data.annotations = vec![noted, plain];
+
+        let yaml_path = dir.join("notes.yaml");
+        export_yaml(&data, &yaml_path).unwrap();
+        assert_eq!(import_yaml(&yaml_path).unwrap().annotations, data.annotations);
+
+        let json_path = dir.join("notes.json");
+        export_json(&data, &json_path, false).unwrap();
+        assert_eq!(import_json(&json_path).unwrap().annotations, data.annotations);
+        // Absent notes are not written
+        assert_eq!(std::fs::read_to_string(&json_path).unwrap().matches("\"notes\"").count(), 1);
+
+        let geojson_path = dir.join("notes.geojson");
+        export_geojson(&data, &geojson_path).unwrap();
+        let imported = import_geojson(&geojson_path, (100, 100)).unwrap();
+        assert_eq!(imported.annotations[0].notes, data.annotations[0].notes);
+        assert_eq!(imported.annotations[1].notes, None);
+
+        // Files from before notes existed
+        let old = r#"{"name": "region 1", "type": "polygon", "vertices": []}"#;
+        assert_eq!(serde_json::from_str::<Annotation>(old).unwrap().notes, None);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_export_frames() {
+        let dir = scratch_dir("export_frames");
+        let mut data = ProjectData::new("clip.mp4".to_string(), 640, 480);
+        for frame in [0, 5, 10] {
+            let mut annotation = Annotation::new(format!("region {}", frame), AnnotationType::Polygon);
+            annotation.add_vertex(Point::new(0.1, 0.1));
+            annotation.add_vertex(Point::new(0.5, 0.1));
+            annotation.add_vertex(Point::new(0.5, frame as f64 / 20.0 + 0.2));
+            data.frames.insert(frame, vec![annotation]);
+        }
+        // Frames with no annotations produce no file
+        data.frames.insert(7, Vec::new());
+
+        let written = export_frames(&data, &dir, AnnotationFormat::Json).unwrap();
+        let names: Vec<_> = written.iter().map(|p| p.file_name().unwrap().to_str().unwrap()).collect();
+        assert_eq!(names, ["clip_frame_000000.json", "clip_frame_000005.json", "clip_frame_000010.json"]);
+
+        for (path, frame) in written.iter().zip([0, 5, 10]) {
+            let loaded = import_json(path).unwrap();
+            assert_eq!(loaded.media_file, "clip.mp4");
+            assert_eq!((loaded.frame_width, loaded.frame_height), (640, 480));
+            assert_eq!(loaded.annotations, data.frames[&frame]);
+            assert!(loaded.frames.is_empty());
+        }
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    /// Create an empty scratch directory unique to the calling test.
+    #[test]
+    fn test_export_svg() {
+        let dir = scratch_dir("export_svg");
+        let mut data = ProjectData::new("frame.png".to_string(), 200, 100);
+        let mut region = Annotation::new("Bay <A> & B".to_string(), AnnotationType::Polygon);
+        for (x, y) in [(0.1, 0.1), (0.5, 0.1), (0.5, 0.5), (0.1, 0.5)] {
+            region.add_vertex(Point::new(x, y));
+        }
+        region.color = Some([255, 0, 0, 128]);
+        let mut parts = Annotation::new("region 2".to_string(), AnnotationType::Polygon);
+        for (x, y) in [(0.6, 0.6), (0.7, 0.6), (0.7, 0.7)] {
+            parts.add_vertex(Point::new(x, y));
+        }
+        parts.extra_rings = vec![Vertices(vec![Point::new(0.8, 0.8), Point::new(0.9, 0.8), Point::new(0.9, 0.9)])];
+        let mut line = Annotation::new("line 1".to_string(), AnnotationType::Line);
+        for (x, y) in [(0.0, 0.0), (0.5, 1.0), (1.0, 0.0)] {
+            line.add_vertex(Point::new(x, y));
+        }
+        line.line_style = LineStyle::Dashed;
+        let mut point = Annotation::new("point 1".to_string(), AnnotationType::Point);
+        point.add_vertex(Point::new(0.25, 0.75));
+        let mut scratch = Annotation::new("scratch".to_string(), AnnotationType::Line);
+        scratch.add_vertex(Point::new(0.0, 0.0));
+        scratch.add_vertex(Point::new(1.0, 1.0));
+        scratch.export = false;
+        data.annotations = vec![region, parts, line, point, scratch];
+
+        let path = dir.join("outlines.svg");
+        export_svg(&data, &path, false).unwrap();
+        let svg = std::fs::read_to_string(&path).unwrap();
+        assert!(svg.contains(r#"width="200" height="100" viewBox="0 0 200 100""#));
+        assert!(!svg.contains("<image"));
+
+        // One element per ring, with one point pair per vertex
+        let point_counts = |tag: &str| -> Vec<usize> {
+            svg.lines()
+                .filter(|l| l.trim_start().starts_with(&format!("<{} ", tag)))
+                .map(|l| l.split("points=\"").nth(1).unwrap().split('"').next().unwrap().split(' ').count())
+                .collect()
+        };
+        assert_eq!(point_counts("polygon"), [4, 3, 3]);
+        assert_eq!(point_counts("polyline"), [3]);
+        assert_eq!(svg.matches("<circle ").count(), 1);
+
+        // Pixel coordinates, colors, and escaped names
+        assert!(svg.contains(r#"points="20.00,10.00 100.00,10.00 100.00,50.00 20.00,50.00""#));
+        assert!(svg.contains(r##"stroke="#ff0000" stroke-opacity="0.502""##));
+        assert!(svg.contains(r#"stroke-dasharray="6 4""#));
+        assert!(svg.contains(r#"cx="50.00" cy="75.00""#));
+        assert!(svg.contains("<title>Bay &lt;A&gt; &amp; B</title>"));
+        assert!(!svg.contains("scratch"));
+
+        // The image in memory is embedded as a PNG background
+        let image = LoadedImage { width: 2, height: 1, pixels: vec![255; 2 * 4], color_type: image::ExtendedColorType::Rgba8 };
+        export_svg_with_image(&data, &path, &image, false).unwrap();
+        let svg = std::fs::read_to_string(&path).unwrap();
+        assert!(svg.contains(r#"<image href="data:image/png;base64,iVBORw0KGgo"#));
+
+        data.frame_width = 0;
+        assert!(export_svg(&data, &path, false).is_err());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_export_vertex_indices() {
+        let dir = scratch_dir("vertex_indices");
+        let mut data = sample_project();
+        let mut line = Annotation::new("line 1".to_string(), AnnotationType::Line);
+        line.add_vertex(Point::new(0.0, 0.0));
+        line.add_vertex(Point::new(0.5, 0.5));
+        data.frames.insert(3, vec![line]);
+
+        // Off: no index metadata
+        let (json_path, svg_path) = (dir.join("plain.json"), dir.join("plain.svg"));
+        export_json(&data, &json_path, false).unwrap();
+        export_svg(&data, &svg_path, false).unwrap();
+        assert!(!std::fs::read_to_string(&json_path).unwrap().contains("vertex_indices"));
+        assert!(!std::fs::read_to_string(&svg_path).unwrap().contains("data-index"));
+
+        // On: an index array per annotation, including per-frame ones
+        let (json_path, svg_path) = (dir.join("indexed.json"), dir.join("indexed.svg"));
+        export_json(&data, &json_path, true).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&json_path).unwrap()).unwrap();
+        assert_eq!(json["annotations"][0]["vertex_indices"], serde_json::json!([0, 1, 2]));
+        assert_eq!(json["frames"]["3"][0]["vertex_indices"], serde_json::json!([0, 1]));
+        // ... which importing ignores
+        assert_eq!(import_json(&json_path).unwrap().annotations, data.annotations);
+
+        // On: a label per vertex, at its pixel position
+        export_svg(&data, &svg_path, true).unwrap();
+        let svg = std::fs::read_to_string(&svg_path).unwrap();
+        assert_eq!(svg.matches("data-index=").count(), 3);
+        assert!(svg.contains(r#"<text x="320.00" y="240.00" data-index="2">2</text>"#));
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    fn sample_project() -> ProjectData {
+        let mut project = ProjectData::new("image.png".to_string(), 640, 480);
+        let mut annotation = Annotation::new("region 1".to_string(), AnnotationType::Polygon);
+        annotation.add_vertex(Point::new(0.1, 0.1));
+        annotation.add_vertex(Point::new(0.5, 0.1));
+        annotation.add_vertex(Point::new(0.5, 0.5));
+        project.annotations.push(annotation);
+        project
+    }
+
+    #[test]
+    fn test_import_folder_partial_failure() {
+        let dir = scratch_dir("import_folder");
+        let project = sample_project();
+
+        export_json(&project, &dir.join("a.json"), false).unwrap();
+        export_yaml(&project, &dir.join("b.yaml")).unwrap();
+        std::fs::write(dir.join("c.json"), "{ not valid json").unwrap();
+        std::fs::write(dir.join("notes.txt"), "ignored").unwrap();
+
+        let report = import_folder(&dir, &ProgressReporter::detached()).unwrap();
+        assert_eq!(report.imported.len(), 2);
+        assert_eq!(report.failed.len(), 1);
+        assert!(report.failed[0].0.ends_with("c.json"));
+        assert_eq!(report.imported[0].1.annotations, project.annotations);
+        assert_eq!(report.summary(), "2 imported, 1 failed");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_sidecar_paths() {
+        let paths = sidecar_paths(Path::new("/data/frames/frame_0001.png"));
+        assert_eq!(
+            paths,
+            vec![
+                PathBuf::from("/data/frames/frame_0001.json"),
+                PathBuf::from("/data/frames/frame_0001.yaml"),
+                PathBuf::from("/data/frames/frame_0001.yml"),
+            ]
+        );
+
+        // Only the final extension is replaced
+        let paths = sidecar_paths(Path::new("shot.v2.jpg"));
+        assert_eq!(paths[0], PathBuf::from("shot.v2.json"));
+    }
+
+    #[test]
+    fn test_import_folder_missing_dir() {
+        assert!(import_folder(Path::new("/nonexistent/folder"), &ProgressReporter::detached()).is_err());
+    }
+
+    #[test]
+    fn test_geojson_round_trip() {
+        let dir = scratch_dir("geojson");
+        let path = dir.join("regions.geojson");
+        let mut project = sample_project();
+        project.annotations[0].group = Some("zones".to_string());
+
+        export_geojson(&project, &path).unwrap();
+        let imported = import_geojson(&path, (640, 480)).unwrap();
+
+        assert_eq!(imported.media_file, project.media_file);
+        assert_eq!(imported.annotations.len(), 1);
+        let (original, round_tripped) = (&project.annotations[0], &imported.annotations[0]);
+        assert_eq!(round_tripped.name, original.name);
+        assert_eq!(round_tripped.group, original.group);
+        assert_eq!(round_tripped.annotation_type, AnnotationType::Polygon);
+        assert_eq!(round_tripped.vertex_count(), original.vertex_count());
+        for (a, b) in round_tripped.vertices.0.iter().zip(&original.vertices.0) {
+            assert!(a.distance(b) < 1e-9);
+        }
+
+        // Multi-part polygons become a MultiPolygon and come back whole
+        let triangle = vec![Point::new(0.75, 0.75), Point::new(1.0, 0.75), Point::new(1.0, 1.0)];
+        project.annotations[0].extra_rings = vec![Vertices(triangle.clone())];
+        export_geojson(&project, &path).unwrap();
+        assert!(std::fs::read_to_string(&path).unwrap().contains("MultiPolygon"));
+        let imported = import_geojson(&path, (640, 480)).unwrap();
+        let rings: Vec<_> = imported.annotations[0].rings().collect();
+        assert_eq!(rings.len(), 2);
+        assert_eq!(rings[0].len(), project.annotations[0].vertex_count());
+        for (a, b) in rings[1].iter().zip(&triangle) {
+            assert!(a.distance(b) < 1e-9);
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_anchored_yaml_round_trip() {
+        let dir = scratch_dir("anchored_yaml");
+        let path = dir.join("shared.yaml");
+        let mut data = ProjectData::new("frame.png".to_string(), 200, 100);
+        let mut left = Annotation::new("left".to_string(), AnnotationType::Polygon);
+        for (x, y) in [(0.1, 0.1), (0.5, 0.1), (0.5, 0.9), (0.1, 0.9)] {
+            left.add_vertex(Point::new(x, y));
+        }
+        let mut right = Annotation::new("right".to_string(), AnnotationType::Polygon);
+        // Shares the left polygon's right edge, one end within epsilon
+        for (x, y) in [(0.5, 0.1), (0.9, 0.1), (0.9, 0.9), (0.5 + 1e-9, 0.9)] {
+            right.add_vertex(Point::new(x, y));
+        }
+        data.annotations = vec![left, right];
+
+        export_yaml_anchored(&data, &path).unwrap();
+        let yaml = std::fs::read_to_string(&path).unwrap();
+        assert!(yaml.contains("vertices: [[0.1, 0.1], &v1 [0.5, 0.1], &v2 [0.5, 0.9], [0.1, 0.9]]"), "{}", yaml);
+        assert!(yaml.contains("vertices: [*v1, [0.9, 0.1], [0.9, 0.9], *v2]"), "{}", yaml);
+
+        let loaded = import_file(&path).unwrap();
+        assert_eq!(loaded.annotations[0], data.annotations[0]);
+        assert_eq!(loaded.annotations[1].vertices.0[3], Point::new(0.5, 0.9));
+        assert_eq!(loaded.annotations[1].vertices.0[..3], data.annotations[1].vertices.0[..3]);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_parse_coordinates() {
+        let polygon = parse_coordinates("x,y\n10, 20\n\n# corner\n110,20\n110,70\n", AnnotationType::Polygon, false, (200, 100)).unwrap();
+        assert_eq!(polygon, [Point::new(0.05, 0.2), Point::new(0.55, 0.2), Point::new(0.55, 0.7)]);
+
+        let line = parse_coordinates("0.1,0.2\n0.3,0.4", AnnotationType::Line, true, (200, 100)).unwrap();
+        assert_eq!(line, [Point::new(0.1, 0.2), Point::new(0.3, 0.4)]);
+
+        // Too few vertices for the type
+        let error = parse_coordinates("10,20\n110,20", AnnotationType::Polygon, false, (200, 100)).unwrap_err();
+        assert!(error.to_string().contains("at least 3"), "{}", error);
+        assert!(parse_coordinates("0.1,0.2\n0.3,0.4", AnnotationType::Point, true, (200, 100)).is_err());
+
+        // Malformed rows, named rows, and out-of-range points
+        let error = parse_coordinates("10,20\n110;20\n110,70", AnnotationType::Polygon, false, (200, 100)).unwrap_err();
+        assert_eq!(error.to_string(), "Line 2: expected \"x,y\" but found \"110;20\"");
+        for first in ["10;20", "10,2O", "x,20"] {
+            let error = parse_coordinates(&format!("{first}\n110,20\n110,70\n10,70"), AnnotationType::Polygon, false, (200, 100)).unwrap_err();
+            assert!(error.to_string().starts_with("Line 1:"), "{error}");
+        }
+        assert!(parse_coordinates("10,20\nlane,110,20\n110,70", AnnotationType::Polygon, false, (200, 100)).is_err());
+        let error = parse_coordinates("10,20\n210,20\n110,70", AnnotationType::Polygon, false, (200, 100)).unwrap_err();
+        assert_eq!(error.to_string(), "Line 2: (210, 20) is outside the image");
+        assert!(parse_coordinates("0.1,0.2\n1.5,0.4", AnnotationType::Line, true, (200, 100)).is_err());
+    }
+
+    #[test]
+    fn test_import_csv() {
+        let dir = scratch_dir("import_csv");
+        let path = dir.join("regions.csv");
+        let csv = "name,x,y\n\
+                   lane 1,10,20\n\
+                   lane 1,110,20\n\
+                   lane 1,110,70\n\
+                   # second region\n\
+                   \"lane, west\",0,0\n\
+                   \"lane, west\",50,not a number\n\
+                   \"lane, west\",50,0\n\
+                   \"lane, west\",50,50\n\
+                   \"lane, west\",0,0\n\
+                   stray,1,1\n";
+        std::fs::write(&path, csv).unwrap();
+
+        let annotations = import_csv(&path, false, (200, 100), AnnotationType::Polygon).unwrap();
+        assert_eq!(annotations.len(), 2);
+        assert_eq!(annotations[0].name, "lane 1");
+        assert_eq!(annotations[0].vertices.0, [Point::new(0.05, 0.2), Point::new(0.55, 0.2), Point::new(0.55, 0.7)]);
+        assert_eq!(annotations[1].name, "lane, west");
+        assert_eq!(annotations[1].vertex_count(), 3);
+
+        // As polylines the closing vertex is kept and the single-vertex group is still too short
+        let lines = import_csv(&path, false, (200, 100), AnnotationType::Line).unwrap();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].annotation_type, AnnotationType::Line);
+        assert_eq!(lines[1].vertex_count(), 4);
+
+        // Pixel coordinates read as normalized mostly fall outside the image
+        // and are skipped, leaving only the rows that happen to fit
+        let normalized = import_csv(&path, true, (200, 100), AnnotationType::Line).unwrap();
+        assert_eq!(normalized.len(), 1);
+        assert_eq!(normalized[0].name, "lane, west");
+        assert_eq!(normalized[0].vertices.0, [Point::new(0.0, 0.0), Point::new(0.0, 0.0)]);
+
+        for row in ["a,NaN,1", "a,1,inf", "a,201,1", "a,1,-0.5"] {
+            std::fs::write(&path, format!("a,0,0\na,10,10\n{row}\n")).unwrap();
+            let skipped = import_csv(&path, false, (200, 100), AnnotationType::Line).unwrap();
+            assert_eq!(skipped.len(), 1, "{row}");
+            assert_eq!(skipped[0].vertex_count(), 2, "{row}");
+        }
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_import_geojson_uses_bbox() {
+        let dir = scratch_dir("geojson_bbox");
+        let path = dir.join("map.geojson");
+        std::fs::write(
+            &path,
+            r#"{
+                "type": "Feature",
+                "bbox": [10.0, 50.0, 12.0, 51.0],
+                "properties": {},
+                "geometry": { "type": "LineString", "coordinates": [[10.0, 51.0], [12.0, 50.0]] }
+            }"#,
+        )
+        .unwrap();
+
+        let imported = import_geojson(&path, (200, 100)).unwrap();
+        let line = &imported.annotations[0];
+        assert_eq!(line.name, "line 1");
+        assert_eq!(line.vertices.0, vec![Point::new(0.0, 0.0), Point::new(1.0, 1.0)]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_merge_projects_renames_duplicates() {
+        let named = |names: &[&str]| -> Vec<Annotation> {
+            names.iter().map(|name| Annotation::new(name.to_string(), AnnotationType::Point)).collect()
+        };
+        let mut base = ProjectData::new("frame.png".to_string(), 640, 480);
+        base.annotations = named(&["car 1", "car 2", "road"]);
+        let mut other = ProjectData::new("frame.png".to_string(), 640, 480);
+        other.annotations = named(&["car 1", "car 1", "road", "truck 1"]);
+        other.frames.insert(5, named(&["car 1"]));
+
+        assert_eq!(merge_projects(&mut base, other.clone()).unwrap(), 3);
+        let names: Vec<&str> = base.annotations.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, ["car 1", "car 2", "road", "car 3", "car 4", "road 1", "truck 1"]);
+        // Frames merge into their own lists
+        assert_eq!(base.frames[&5][0].name, "car 1");
+
+        let mut smaller = ProjectData::new("frame.png".to_string(), 320, 240);
+        let error = merge_projects(&mut smaller, other).unwrap_err();
+        assert!(format!("{:#}", error).contains("reprojecting"));
+        assert!(smaller.annotations.is_empty());
+    }
+}
@@ -0,0 +1,115 @@
+// Copyright (c) 2025, Jason Jenkins
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Annotation template library.
+//!
+//! A templates file is a JSON list of named annotations, e.g. a team's
+//! standard regions, which can be stamped onto any image. Stamping moves
+//! a template so its bounding box is centered on the chosen point.
+
+use crate::io::atomic;
+use crate::models::annotation::{self, Annotation, Point};
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::path::Path;
+
+/// Load a templates file.
+pub fn load_templates(path: &Path) -> Result<Vec<Annotation>> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read templates file {}", path.display()))?;
+    let templates: Vec<Annotation> = serde_json::from_str(&contents)
+        .with_context(|| format!("Failed to parse templates file {}", path.display()))?;
+
+    if let Some(template) = templates.iter().find(|t| !t.is_finite()) {
+        anyhow::bail!("Template '{}' has NaN or infinite coordinates", template.name);
+    }
+    Ok(templates)
+}
+
+/// Save a template library as a JSON list.
+pub fn save_templates(path: &Path, templates: &[Annotation]) -> Result<()> {
+    let json = serde_json::to_string_pretty(templates).context("Failed to serialize templates")?;
+    atomic::write_bytes(path, json)
+}
+
+/// Copy of `template` centered on `at`, kept inside the image and named
+/// so it does not clash with any of `existing`.
+///
+/// A template that would hang off an edge is shifted back inside; one
+/// larger than the image has its vertices clamped instead.
+pub fn stamp_template(template: &Annotation, at: Point, existing: &[Annotation]) -> Annotation {
+    let mut stamp = template.clone();
+
+    if let Some((min, max)) = template.bounding_box() {
+        let shift = |min: f64, max: f64, at: f64| {
+            let center = (min + max) / 2.0;
+            let (low, high) = (min - center + at, max - center + at);
+            let offset = at - center;
+            if high - low > 1.0 {
+                offset
+            } else if low < 0.0 {
+                offset - low
+            } else if high > 1.0 {
+                offset - (high - 1.0)
+            } else {
+                offset
+            }
+        };
+        let (dx, dy) = (shift(min.x, max.x, at.x), shift(min.y, max.y, at.y));
+        for ring in stamp.rings_mut() {
+            for p in ring.iter_mut() {
+                p.x = (p.x + dx).clamp(0.0, 1.0);
+                p.y = (p.y + dy).clamp(0.0, 1.0);
+            }
+        }
+    }
+
+    let used: HashSet<&str> = existing.iter().map(|a| a.name.as_str()).collect();
+    stamp.name = annotation::unique_name(&stamp.name, |name| used.contains(name));
+    stamp
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::annotation::AnnotationType;
+    use crate::util::scratch::scratch_dir;
+
+    fn square(name: &str, x: f64, y: f64, size: f64) -> Annotation {
+        let mut annotation = Annotation::new(name.to_string(), AnnotationType::Polygon);
+        for (dx, dy) in [(0.0, 0.0), (size, 0.0), (size, size), (0.0, size)] {
+            annotation.add_vertex(Point::new(x + dx, y + dy));
+        }
+        annotation
+    }
+
+    #[test]
+    fn test_load_and_stamp_template() {
+        let path = scratch_dir("templates").join("templates.json");
+        save_templates(&path, &[square("Bay", 0.0, 0.0, 0.25), square("Lane", 0.0, 0.0, 0.5)]).unwrap();
+        let templates = load_templates(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+        assert_eq!(templates.len(), 2);
+        assert_eq!(templates[0].name, "Bay");
+
+        // Centered on the click point
+        let stamp = stamp_template(&templates[0], Point::new(0.5, 0.5), &[]);
+        assert_eq!(stamp.name, "Bay");
+        assert_eq!(stamp.vertices.0[0], Point::new(0.375, 0.375));
+        assert_eq!(stamp.vertices.0[2], Point::new(0.625, 0.625));
+
+        // Shifted back inside near an edge, and given a unique name
+        let existing = [square("Bay", 0.0, 0.0, 0.1), square("Bay 2", 0.0, 0.0, 0.1)];
+        let stamp = stamp_template(&templates[0], Point::new(0.0, 1.0), &existing);
+        assert_eq!(stamp.name, "Bay 1");
+        assert_eq!(stamp.bounding_box(), Some((Point::new(0.0, 0.75), Point::new(0.25, 1.0))));
+    }
+
+    #[test]
+    fn test_load_templates_rejects_invalid() {
+        let path = scratch_dir("bad_templates").join("templates.json");
+        std::fs::write(&path, "{\"name\": \"not a list\"}").unwrap();
+        assert!(load_templates(&path).is_err());
+        std::fs::remove_file(&path).ok();
+    }
+}
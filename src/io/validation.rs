@@ -0,0 +1,592 @@
+// Copyright (c) 2025, Jason Jenkins
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Pre-export and import validation.
+//!
+//! Degenerate polygons (self-intersecting, zero-area, or with too few
+//! vertices) corrupt training data, so exports check for them first and
+//! let the user decide what to do. Imports are previewed the same way
+//! before they replace the current project.
+
+use crate::io::serialization;
+use crate::models::annotation::{Annotation, AnnotationType, Point};
+use crate::models::project::ProjectData;
+use crate::util::geometry;
+use anyhow::Result;
+use std::path::Path;
+
+/// Polygons smaller than this many square pixels count as zero-area.
+const MIN_AREA_PX: f64 = 1.0;
+
+/// What is wrong with a polygon.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IssueKind {
+    /// NaN or infinite coordinates, e.g. from a corrupt file
+    NonFinite,
+    TooFewVertices,
+    SelfIntersecting,
+    ZeroArea,
+    /// A polygon with several parts, for a format that holds only one
+    MultiPart,
+}
+
+impl IssueKind {
+    /// Human-readable description.
+    pub fn description(&self) -> &'static str {
+        match self {
+            IssueKind::NonFinite => "NaN or infinite coordinates",
+            IssueKind::TooFewVertices => "fewer than 3 vertices",
+            IssueKind::SelfIntersecting => "self-intersecting",
+            IssueKind::ZeroArea => "near-zero area",
+            IssueKind::MultiPart => "multi-part polygon, which this format can't hold",
+        }
+    }
+}
+
+/// An annotation that should not be exported as-is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportIssue {
+    /// Index into the project's annotations
+    pub index: usize,
+    pub kind: IssueKind,
+}
+
+/// Check every annotation marked for export for non-finite coordinates,
+/// and every such polygon for the first of too few vertices or
+/// self-intersection in any of its rings, zero area, and (unless
+/// `multi_part` formats are supported) more than one ring. At most one
+/// issue is reported per annotation.
+pub fn validate_for_export(data: &ProjectData, multi_part: bool) -> Vec<ExportIssue> {
+    // Measure area in pixels when the frame size is known
+    let pixel_scale = if data.frame_width > 0 && data.frame_height > 0 {
+        data.frame_width as f64 * data.frame_height as f64
+    } else {
+        1.0
+    };
+
+    data.annotations
+        .iter()
+        .enumerate()
+        .filter(|(_, annotation)| annotation.export)
+        .filter_map(|(index, annotation)| {
+            let kind = if !annotation.is_finite() {
+                IssueKind::NonFinite
+            } else if annotation.annotation_type != AnnotationType::Polygon {
+                return None;
+            } else if annotation.rings().any(|ring| ring.len() < 3) {
+                IssueKind::TooFewVertices
+            } else if annotation.rings().any(|ring| geometry::has_self_intersection(ring, true)) {
+                IssueKind::SelfIntersecting
+            } else if annotation.area() * pixel_scale < MIN_AREA_PX {
+                IssueKind::ZeroArea
+            } else if !multi_part && annotation.is_multi_part() {
+                IssueKind::MultiPart
+            } else {
+                return None;
+            };
+            Some(ExportIssue { index, kind })
+        })
+        .collect()
+}
+
+/// Copy of the project without the annotations that have issues.
+pub fn without_issues(data: &ProjectData, issues: &[ExportIssue]) -> ProjectData {
+    let mut filtered = data.clone();
+    let mut index = 0;
+    filtered.annotations.retain(|_| {
+        let keep = !issues.iter().any(|issue| issue.index == index);
+        index += 1;
+        keep
+    });
+    filtered
+}
+
+/// Whether a point lies inside the normalized image, 0..=1 on both axes.
+fn in_bounds(point: &Point) -> bool {
+    (0.0..=1.0).contains(&point.x) && (0.0..=1.0).contains(&point.y)
+}
+
+/// Indices of annotations with any vertex, in any ring, outside the image.
+///
+/// Non-finite vertices aren't counted, as they can't be clamped.
+pub fn find_out_of_bounds(data: &ProjectData) -> Vec<usize> {
+    data.annotations
+        .iter()
+        .enumerate()
+        .filter(|(_, annotation)| annotation.rings().flatten().any(|v| v.is_finite() && !in_bounds(v)))
+        .map(|(index, _)| index)
+        .collect()
+}
+
+/// Move vertices of every ring outside the image onto its nearest edge.
+///
+/// Returns how many vertices were clamped.
+pub fn clamp_annotation_to_bounds(annotation: &mut Annotation) -> usize {
+    let mut clamped = 0;
+    for vertex in annotation.rings_mut().flatten().filter(|v| v.is_finite() && !in_bounds(v)) {
+        *vertex = Point::new(vertex.x.clamp(0.0, 1.0), vertex.y.clamp(0.0, 1.0));
+        clamped += 1;
+    }
+    clamped
+}
+
+/// Polygons smaller than this many square pixels are flagged as tiny.
+const TINY_AREA_PX: f64 = 25.0;
+
+/// Vertices within this many pixels of the line through their neighbors
+/// are flagged as near-collinear.
+const COLLINEAR_PX: f64 = 0.5;
+
+/// A quality problem with one annotation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QualityFlag {
+    TooFewVertices,
+    /// Number of vertices that add no shape
+    NearCollinear(usize),
+    TinyArea,
+    SelfIntersecting,
+    /// Number of vertices outside the image
+    OutOfBounds(usize),
+}
+
+impl QualityFlag {
+    /// Points taken off the score.
+    pub fn penalty(&self) -> u8 {
+        match self {
+            QualityFlag::TooFewVertices => 60,
+            QualityFlag::SelfIntersecting => 50,
+            QualityFlag::TinyArea => 30,
+            QualityFlag::OutOfBounds(_) => 20,
+            QualityFlag::NearCollinear(_) => 10,
+        }
+    }
+
+    /// Human-readable description.
+    pub fn description(&self) -> String {
+        match self {
+            QualityFlag::TooFewVertices => "too few vertices".to_string(),
+            QualityFlag::NearCollinear(count) => format!("{} near-collinear vertices", count),
+            QualityFlag::TinyArea => format!("area under {} px²", TINY_AREA_PX),
+            QualityFlag::SelfIntersecting => "self-intersecting".to_string(),
+            QualityFlag::OutOfBounds(count) => format!("{} vertices outside the image", count),
+        }
+    }
+}
+
+/// Heuristic quality of an annotation for review triage.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QualityReport {
+    /// 100 for a clean annotation, lower for each problem found
+    pub score: u8,
+    pub flags: Vec<QualityFlag>,
+}
+
+/// Score an annotation by checking it for common drawing mistakes.
+pub fn quality_report(annotation: &Annotation, image_size: (u32, u32)) -> QualityReport {
+    let vertices = &annotation.vertices.0;
+    let pixels = geometry::to_pixels(vertices, image_size);
+    let closed = annotation.is_closed();
+    let mut flags = Vec::new();
+
+    if vertices.len() < annotation.annotation_type.min_vertices() {
+        flags.push(QualityFlag::TooFewVertices);
+    }
+
+    // Interior vertices of lines, and every vertex of a polygon, should turn
+    if annotation.annotation_type != AnnotationType::Point && pixels.len() >= 3 {
+        let n = pixels.len();
+        let corners = if closed { 0..n } else { 1..n - 1 };
+        let collinear = corners
+            .filter(|&i| geometry::point_segment_distance(&pixels[i], &pixels[(i + n - 1) % n], &pixels[(i + 1) % n]) < COLLINEAR_PX)
+            .count();
+        if collinear > 0 {
+            flags.push(QualityFlag::NearCollinear(collinear));
+        }
+    }
+
+    // The area of a self-intersecting ring is meaningless, so only one is flagged
+    if annotation.annotation_type == AnnotationType::Polygon && vertices.len() >= 3 {
+        if geometry::has_self_intersection(vertices, true) {
+            flags.push(QualityFlag::SelfIntersecting);
+        } else if geometry::polygon_area(&pixels) < TINY_AREA_PX {
+            flags.push(QualityFlag::TinyArea);
+        }
+    }
+
+    let outside = vertices.iter().filter(|v| !in_bounds(v)).count();
+    if outside > 0 {
+        flags.push(QualityFlag::OutOfBounds(outside));
+    }
+
+    let penalty: u32 = flags.iter().map(|flag| flag.penalty() as u32).sum();
+    QualityReport { score: 100u32.saturating_sub(penalty) as u8, flags }
+}
+
+/// A polygon whose implied closing edge is more than this many times its
+/// longest drawn edge looks like an outline that was never closed.
+const UNCLOSED_GAP_RATIO: f64 = 3.0;
+
+/// An outline that turns by less than this (in degrees) onto its closing
+/// edge, at both ends, carries on toward the gap rather than cornering.
+const UNCLOSED_MAX_TURN_DEG: f64 = 60.0;
+
+/// Frame size for pixel-space checks, or a unit square when unknown.
+pub fn pixel_size(data: &ProjectData) -> (u32, u32) {
+    if data.frame_width > 0 && data.frame_height > 0 {
+        (data.frame_width, data.frame_height)
+    } else {
+        (1, 1)
+    }
+}
+
+/// Whether a polygon looks like an open path rather than a closed outline.
+///
+/// ROIDS closes polygons implicitly, so a file whose outline stopped short
+/// of its start still draws as closed, with one long edge across the gap.
+/// This flags a ring whose closing edge (last vertex back to the first) is
+/// far longer than any of its drawn edges and which the outline runs into
+/// smoothly at both ends. A deliberate straight side, like the flat of a
+/// "D" or the fourth side of a rectangle, is met at a corner instead.
+pub fn looks_unclosed(vertices: &[Point], image_size: (u32, u32)) -> bool {
+    let n = vertices.len();
+    if n < 3 {
+        return false;
+    }
+    let pixels = geometry::to_pixels(vertices, image_size);
+    let gap = pixels[n - 1].distance(&pixels[0]);
+    let longest = pixels.windows(2).map(|w| w[0].distance(&w[1])).fold(0.0, f64::max);
+    if longest <= 0.0 || gap <= UNCLOSED_GAP_RATIO * longest {
+        return false;
+    }
+
+    // Angle between the directions a→b and b→c
+    let turn = |a: &Point, b: &Point, c: &Point| {
+        let (ux, uy, vx, vy) = (b.x - a.x, b.y - a.y, c.x - b.x, c.y - b.y);
+        (ux * vy - uy * vx).atan2(ux * vx + uy * vy).abs().to_degrees()
+    };
+    turn(&pixels[n - 2], &pixels[n - 1], &pixels[0]) < UNCLOSED_MAX_TURN_DEG
+        && turn(&pixels[n - 1], &pixels[0], &pixels[1]) < UNCLOSED_MAX_TURN_DEG
+}
+
+/// Indices of polygons that look unclosed (see [`looks_unclosed`]).
+pub fn find_unclosed(data: &ProjectData) -> Vec<usize> {
+    let image_size = pixel_size(data);
+    data.annotations
+        .iter()
+        .enumerate()
+        .filter(|(_, annotation)| annotation.annotation_type == AnnotationType::Polygon && annotation.is_finite())
+        .filter(|(_, annotation)| looks_unclosed(&annotation.vertices.0, image_size))
+        .map(|(index, _)| index)
+        .collect()
+}
+
+/// Fix a polygon that looks unclosed by turning it into the open line it
+/// was traced as, so no format draws or writes an edge across the gap.
+///
+/// Multi-part polygons are left alone. Returns whether it was converted.
+pub fn open_unclosed(annotation: &mut Annotation, image_size: (u32, u32)) -> bool {
+    if annotation.annotation_type != AnnotationType::Polygon
+        || annotation.is_multi_part()
+        || !looks_unclosed(&annotation.vertices.0, image_size)
+    {
+        return false;
+    }
+    annotation.annotation_type = AnnotationType::Line;
+    true
+}
+
+/// Aspect ratios differing by more than this fraction count as mismatched.
+const ASPECT_TOLERANCE: f64 = 0.01;
+
+/// Source frame size of an annotation drawn on a frame with a different
+/// aspect ratio than `frame`, so that its normalized shape is stretched.
+pub fn aspect_mismatch(annotation: &Annotation, frame: (u32, u32)) -> Option<(u32, u32)> {
+    let source = annotation.source_frame?;
+    let aspect = |(width, height): (u32, u32)| (height > 0).then(|| width as f64 / height as f64);
+    let (from, to) = (aspect(source)?, aspect(frame)?);
+    ((from - to).abs() > ASPECT_TOLERANCE * to).then_some(source)
+}
+
+/// Indices of annotations whose source frame aspect ratio differs from
+/// the project's (see [`aspect_mismatch`]).
+pub fn find_aspect_mismatches(data: &ProjectData) -> Vec<usize> {
+    let frame = (data.frame_width, data.frame_height);
+    data.annotations
+        .iter()
+        .enumerate()
+        .filter(|(_, annotation)| aspect_mismatch(annotation, frame).is_some())
+        .map(|(index, _)| index)
+        .collect()
+}
+
+/// A parsed annotation file, summarized for confirmation before loading.
+#[derive(Debug, Clone)]
+pub struct ImportPreview {
+    pub data: ProjectData,
+    pub polygons: usize,
+    pub lines: usize,
+    pub points: usize,
+    /// Problems worth knowing about before replacing the current project
+    pub warnings: Vec<String>,
+    /// Polygons that look like outlines that were never closed
+    pub unclosed: Vec<usize>,
+}
+
+/// Parse and check an annotation file without loading its image.
+pub fn preview_import(path: &Path) -> Result<ImportPreview> {
+    let data = serialization::import_file(path)?;
+    let count = |kind: AnnotationType| data.annotations.iter().filter(|a| a.annotation_type == kind).count();
+
+    let mut warnings = Vec::new();
+    if !Path::new(&data.media_file).is_file() {
+        warnings.push(format!("Referenced image not found: {}", data.media_file));
+    }
+    if data.frame_width == 0 || data.frame_height == 0 {
+        warnings.push("Frame size is missing".to_string());
+    }
+    for issue in validate_for_export(&data, true) {
+        warnings.push(format!("{}: {}", data.annotations[issue.index].name, issue.kind.description()));
+    }
+    let outside = find_out_of_bounds(&data).len();
+    if outside > 0 {
+        warnings.push(format!("{} annotations have vertices outside the image", outside));
+    }
+
+    Ok(ImportPreview {
+        polygons: count(AnnotationType::Polygon),
+        lines: count(AnnotationType::Line),
+        points: count(AnnotationType::Point),
+        unclosed: find_unclosed(&data),
+        data,
+        warnings,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::annotation::Vertices;
+    use crate::util::scratch::scratch_dir;
+
+    fn polygon(name: &str, points: &[(f64, f64)]) -> Annotation {
+        let mut annotation = Annotation::new(name.to_string(), AnnotationType::Polygon);
+        for &(x, y) in points {
+            annotation.add_vertex(Point::new(x, y));
+        }
+        annotation
+    }
+
+    #[test]
+    fn test_clamp_out_of_bounds() {
+        let mut data = ProjectData::new("frame.png".to_string(), 100, 100);
+        data.annotations = vec![
+            polygon("inside", &[(0.0, 0.0), (1.0, 0.0), (0.5, 1.0)]),
+            polygon("overhang", &[(0.1, 0.1), (1.2, 0.1), (0.5, 0.5)]),
+        ];
+        assert_eq!(find_out_of_bounds(&data), vec![1]);
+
+        assert_eq!(clamp_annotation_to_bounds(&mut data.annotations[1]), 1);
+        assert_eq!(data.annotations[1].vertices.0[1], Point::new(1.0, 0.1));
+        assert_eq!(data.annotations[1].vertices.0[0], Point::new(0.1, 0.1));
+        assert!(find_out_of_bounds(&data).is_empty());
+
+        // Nothing left to clamp
+        assert_eq!(clamp_annotation_to_bounds(&mut data.annotations[1]), 0);
+
+        // Extra rings of multi-part polygons are checked and clamped too
+        data.annotations[0].extra_rings = vec![Vertices(vec![Point::new(0.5, -0.2), Point::new(0.6, 0.1), Point::new(0.4, 0.1)])];
+        assert_eq!(find_out_of_bounds(&data), vec![0]);
+        assert_eq!(clamp_annotation_to_bounds(&mut data.annotations[0]), 1);
+        assert_eq!(data.annotations[0].extra_rings[0].0[0], Point::new(0.5, 0.0));
+    }
+
+    #[test]
+    fn test_validate_for_export() {
+        let mut data = ProjectData::new("frame.png".to_string(), 100, 100);
+        data.annotations = vec![
+            polygon("ok", &[(0.1, 0.1), (0.5, 0.1), (0.5, 0.5), (0.1, 0.5)]),
+            polygon("short", &[(0.1, 0.1), (0.5, 0.1)]),
+            polygon("bow tie", &[(0.1, 0.1), (0.5, 0.5), (0.5, 0.1), (0.1, 0.5)]),
+            polygon("sliver", &[(0.1, 0.1), (0.5, 0.1), (0.9, 0.1001)]),
+        ];
+        let mut line = Annotation::new("line 1".to_string(), AnnotationType::Line);
+        line.add_vertex(Point::new(0.1, 0.1));
+        data.annotations.push(line);
+
+        let issues = validate_for_export(&data, true);
+        assert_eq!(
+            issues,
+            vec![
+                ExportIssue { index: 1, kind: IssueKind::TooFewVertices },
+                ExportIssue { index: 2, kind: IssueKind::SelfIntersecting },
+                ExportIssue { index: 3, kind: IssueKind::ZeroArea },
+            ]
+        );
+
+        // Every ring of a multi-part polygon is checked, and formats that
+        // hold one ring per annotation flag multi-part polygons
+        let mut parts = polygon("parts", &[(0.6, 0.6), (0.9, 0.6), (0.9, 0.9)]);
+        parts.extra_rings = vec![Vertices(vec![Point::new(0.1, 0.8), Point::new(0.2, 0.8)])];
+        let mut single = ProjectData::new("frame.png".to_string(), 100, 100);
+        single.annotations = vec![parts];
+        assert_eq!(validate_for_export(&single, true), [ExportIssue { index: 0, kind: IssueKind::TooFewVertices }]);
+        single.annotations[0].extra_rings[0].0.push(Point::new(0.2, 0.9));
+        assert_eq!(validate_for_export(&single, true), []);
+        assert_eq!(validate_for_export(&single, false), [ExportIssue { index: 0, kind: IssueKind::MultiPart }]);
+
+        let filtered = without_issues(&data, &issues);
+        let names: Vec<&str> = filtered.annotations.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, ["ok", "line 1"]);
+    }
+
+    #[test]
+    fn test_preview_import() {
+        let path = scratch_dir("preview_import").join("import.json");
+        let mut data = ProjectData::new("missing_frame.png".to_string(), 100, 100);
+        let mut line = Annotation::new("line 1".to_string(), AnnotationType::Line);
+        line.add_vertex(Point::new(0.1, 0.1));
+        line.add_vertex(Point::new(0.9, 0.9));
+        data.annotations = vec![
+            polygon("region 1", &[(0.0, 0.0), (1.0, 0.0), (0.5, 1.0)]),
+            polygon("bowtie", &[(0.0, 0.0), (1.0, 1.0), (1.0, 0.0), (0.0, 1.0)]),
+            line,
+        ];
+        serialization::export_json(&data, &path, false).unwrap();
+
+        let preview = preview_import(&path).unwrap();
+        assert_eq!((preview.polygons, preview.lines, preview.points), (2, 1, 0));
+        assert_eq!((preview.data.frame_width, preview.data.frame_height), (100, 100));
+        assert_eq!(
+            preview.warnings,
+            ["Referenced image not found: missing_frame.png", "bowtie: self-intersecting"]
+        );
+        std::fs::remove_file(&path).ok();
+
+        assert!(preview_import(&path).is_err());
+    }
+
+    #[test]
+    fn test_non_finite_coordinates() {
+        let path = scratch_dir("non_finite").join("non_finite.yaml");
+        let mut data = ProjectData::new("frame.png".to_string(), 100, 100);
+        let mut line = Annotation::new("line 1".to_string(), AnnotationType::Line);
+        line.add_vertex(Point::new(0.1, 0.1));
+        line.add_vertex(Point::new(f64::NAN, 0.9));
+        data.annotations = vec![polygon("region 1", &[(0.0, 0.0), (1.0, 0.0), (0.5, f64::INFINITY)]), line];
+
+        // YAML can hold them, so the file loads and is flagged
+        serialization::export_yaml(&data, &path).unwrap();
+        let preview = preview_import(&path).unwrap();
+        assert_eq!(
+            preview.warnings,
+            ["Referenced image not found: frame.png", "region 1: NaN or infinite coordinates", "line 1: NaN or infinite coordinates"]
+        );
+        assert!(find_out_of_bounds(&preview.data).is_empty());
+        assert_eq!(preview.data.exported_annotations().count(), 0);
+        quality_report(&preview.data.annotations[0], (100, 100));
+        std::fs::remove_file(&path).ok();
+
+        // JSON can't, so saving it is refused
+        assert!(serialization::export_json(&data, &path.with_extension("json"), false).is_err());
+        assert!(!path.with_extension("json").exists());
+    }
+
+    #[test]
+    fn test_looks_unclosed() {
+        // Closed shapes: the closing edge is no longer than the drawn ones
+        let square = [Point::new(0.1, 0.1), Point::new(0.5, 0.1), Point::new(0.5, 0.5), Point::new(0.1, 0.5)];
+        assert!(!looks_unclosed(&square, (100, 100)));
+        let triangle = [Point::new(0.0, 0.0), Point::new(1.0, 0.0), Point::new(0.5, 0.1)];
+        assert!(!looks_unclosed(&triangle, (100, 100)));
+
+        let arc = |degrees: f64, steps: usize| -> Vec<Point> {
+            (0..=steps)
+                .map(|i| (i as f64 / steps as f64 * degrees).to_radians())
+                .map(|a| Point::new(0.5 + 0.4 * a.cos(), 0.5 + 0.4 * a.sin()))
+                .collect()
+        };
+        // A circle traced three quarters of the way round and never brought back
+        let traced = arc(270.0, 24);
+        assert!(looks_unclosed(&traced, (100, 100)));
+        // The gap is judged in pixels, so it depends on the aspect ratio
+        assert!(!looks_unclosed(&traced, (10, 1000)));
+
+        // A "D" and a rectangle traced along three sides meet their long
+        // straight side at a corner
+        assert!(!looks_unclosed(&arc(180.0, 24), (100, 100)));
+        let u_shape: Vec<Point> = (0..=4).map(|i| Point::new(0.1, 0.1 + i as f64 * 0.1))
+            .chain((1..=8).map(|i| Point::new(0.1 + i as f64 * 0.1, 0.5)))
+            .chain((0..4).rev().map(|i| Point::new(0.9, 0.1 + i as f64 * 0.1)))
+            .collect();
+        assert!(!looks_unclosed(&u_shape, (100, 100)));
+
+        // Too few vertices or no drawn length to compare against
+        assert!(!looks_unclosed(&traced[..2], (100, 100)));
+        assert!(!looks_unclosed(&[Point::new(0.1, 0.1), Point::new(0.1, 0.1), Point::new(0.9, 0.9)], (100, 100)));
+
+        let mut data = ProjectData::new("frame.png".to_string(), 100, 100);
+        data.annotations = vec![polygon("square", &[(0.1, 0.1), (0.5, 0.1), (0.5, 0.5), (0.1, 0.5)]), polygon("traced", &[])];
+        data.annotations[1].vertices.0 = traced;
+        let mut line = Annotation::new("line 1".to_string(), AnnotationType::Line);
+        line.vertices.0 = data.annotations[1].vertices.0.clone();
+        data.annotations.push(line);
+        assert_eq!(find_unclosed(&data), [1]);
+
+        // The fix keeps the traced vertices as an open line
+        assert!(!open_unclosed(&mut data.annotations[0], (100, 100)));
+        assert!(open_unclosed(&mut data.annotations[1], (100, 100)));
+        assert_eq!(data.annotations[1].annotation_type, AnnotationType::Line);
+        assert_eq!(data.annotations[1].vertices.0, data.annotations[2].vertices.0);
+        assert!(find_unclosed(&data).is_empty());
+    }
+
+    #[test]
+    fn test_aspect_mismatch() {
+        let mut data = ProjectData::new("frame.png".to_string(), 1920, 1080);
+        let mut same = polygon("same", &[(0.1, 0.1), (0.5, 0.1), (0.5, 0.5)]);
+        same.source_frame = Some((1280, 720));
+        let mut square = same.clone();
+        square.name = "square".to_string();
+        square.source_frame = Some((1000, 1000));
+        let mut padded = same.clone();
+        padded.name = "padded".to_string();
+        padded.source_frame = Some((1920, 1088));
+        let untagged = polygon("untagged", &[(0.1, 0.1), (0.5, 0.1), (0.5, 0.5)]);
+        data.annotations = vec![same, square, padded, untagged];
+
+        // Only a genuinely different shape of frame is flagged, not scale
+        // or a few rows of codec padding
+        assert_eq!(aspect_mismatch(&data.annotations[1], (1920, 1080)), Some((1000, 1000)));
+        assert_eq!(find_aspect_mismatches(&data), [1]);
+
+        // Nothing to compare against with an unknown frame size
+        assert_eq!(aspect_mismatch(&data.annotations[1], (1920, 0)), None);
+        data.annotations[1].source_frame = Some((0, 0));
+        assert!(find_aspect_mismatches(&data).is_empty());
+
+        // Rotating turns the source frame with the image
+        data.annotations.remove(1);
+        data.rotate_90(true);
+        assert_eq!(data.annotations[0].source_frame, Some((720, 1280)));
+        assert!(find_aspect_mismatches(&data).is_empty());
+    }
+
+    #[test]
+    fn test_quality_report() {
+        let clean = polygon("region 1", &[(0.1, 0.1), (0.6, 0.1), (0.6, 0.5), (0.1, 0.5)]);
+        let report = quality_report(&clean, (640, 480));
+        assert_eq!(report, QualityReport { score: 100, flags: Vec::new() });
+
+        let bowtie = polygon("bowtie", &[(0.1, 0.1), (0.6, 0.5), (0.6, 0.1), (0.1, 0.5)]);
+        let report = quality_report(&bowtie, (640, 480));
+        assert_eq!(report.flags, [QualityFlag::SelfIntersecting]);
+        assert!(report.score <= 50);
+
+        // Problems add up, and the score never goes below zero
+        let sloppy = polygon("sloppy", &[(0.99, 0.99), (1.01, 0.99), (1.03, 0.99), (1.0, 1.02)]);
+        let report = quality_report(&sloppy, (100, 100));
+        assert_eq!(report.flags, [QualityFlag::NearCollinear(1), QualityFlag::TinyArea, QualityFlag::OutOfBounds(3)]);
+        assert_eq!(report.score, 40);
+        assert_eq!(quality_report(&polygon("empty", &[]), (100, 100)).score, 40);
+    }
+}
@@ -0,0 +1,87 @@
+// Copyright (c) 2025, Jason Jenkins
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! YOLO oriented bounding box export.
+//!
+//! Writes a label file in the YOLO-OBB format: one line per box with a
+//! class index and the four normalized corners, `class x1 y1 x2 y2 x3 y3
+//! x4 y4`. Class indices follow category name order (see
+//! [`Annotation::category`]), starting at 0.
+
+use crate::io::atomic;
+use crate::models::annotation::{Annotation, AnnotationType};
+use crate::models::project::ProjectData;
+use crate::util::geometry;
+use anyhow::Result;
+use std::collections::BTreeSet;
+use std::path::Path;
+
+/// Build the YOLO-OBB label file for a project.
+///
+/// Only single-part polygons that are rectangles in pixel space are
+/// written; their corners come from the oriented box so the output is
+/// always a true rectangle. Multi-part polygons are flagged before export
+/// (see [`crate::io::validation::validate_for_export`]).
+pub fn to_yolo_obb(data: &ProjectData) -> String {
+    let image_size = (data.frame_width, data.frame_height);
+    let boxes: Vec<(&Annotation, geometry::OrientedBox)> = data
+        .exported_annotations()
+        .filter(|a| a.annotation_type == AnnotationType::Polygon && !a.is_multi_part())
+        .filter_map(|a| geometry::obb_from_polygon(&geometry::to_pixels(&a.vertices.0, image_size)).map(|obb| (a, obb)))
+        .collect();
+    let classes: BTreeSet<&str> = boxes.iter().map(|(a, _)| a.category()).collect();
+
+    let mut labels = String::new();
+    for (annotation, obb) in &boxes {
+        let class = classes.iter().position(|c| *c == annotation.category()).unwrap_or(0);
+        let corners: Vec<String> = geometry::from_pixels(&obb.corners(), image_size)
+            .iter()
+            .map(|p| format!("{:.6} {:.6}", p.x, p.y))
+            .collect();
+        labels.push_str(&format!("{} {}\n", class, corners.join(" ")));
+    }
+    labels
+}
+
+/// Export a project's oriented boxes as a YOLO-OBB label file.
+pub fn export_yolo_obb(data: &ProjectData, path: &Path) -> Result<()> {
+    let labels = to_yolo_obb(data);
+    let skipped = data.exported_annotations().count() - labels.lines().count();
+    if skipped > 0 {
+        log::warn!("Skipped {} annotations that are not oriented boxes", skipped);
+    }
+    atomic::write_bytes(path, labels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::annotation::Point;
+
+    #[test]
+    fn test_yolo_obb_labels() {
+        let mut data = ProjectData::new("frame.png".to_string(), 200, 100);
+        let obb = geometry::OrientedBox { cx: 100.0, cy: 50.0, width: 40.0, height: 20.0, angle: 90.0 };
+        let mut truck = Annotation::new("truck 1".to_string(), AnnotationType::Polygon);
+        truck.vertices.0 = geometry::from_pixels(&obb.corners(), (200, 100));
+        let mut car = truck.clone();
+        car.name = "car 1".to_string();
+        let mut triangle = Annotation::new("car 2".to_string(), AnnotationType::Polygon);
+        for (x, y) in [(0.1, 0.1), (0.5, 0.1), (0.3, 0.5)] {
+            triangle.add_vertex(Point::new(x, y));
+        }
+        data.annotations = vec![truck, car, triangle];
+
+        let labels = to_yolo_obb(&data);
+        let lines: Vec<&str> = labels.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0], "1 0.550000 0.300000 0.550000 0.700000 0.450000 0.700000 0.450000 0.300000");
+        assert!(lines[1].starts_with("0 "));
+
+        // Annotations excluded from export are left out
+        data.annotations[0].export = false;
+        let labels = to_yolo_obb(&data);
+        assert_eq!(labels.lines().count(), 1);
+        assert!(labels.starts_with("0 "));
+    }
+}
@@ -14,17 +14,24 @@ mod util;
 
 use app::RoidsApp;
 use anyhow::Result;
+use std::path::PathBuf;
 
 fn main() -> Result<()> {
     // Initialize logging
     env_logger::init();
 
+    // Optional image or annotation file to open, as a path or file:// URL
+    let launch_file = std::env::args_os().nth(1).map(|arg| match arg.to_str().and_then(|s| s.strip_prefix("file://")) {
+        Some(path) => PathBuf::from(path),
+        None => PathBuf::from(arg),
+    });
+
     // Configure egui options
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_inner_size([1280.0, 720.0])
             .with_min_inner_size([800.0, 600.0])
-            .with_title("ROIDS - Region Of Interest Designation System"),
+            .with_title(app::DEFAULT_TITLE),
         ..Default::default()
     };
 
@@ -32,7 +39,7 @@ fn main() -> Result<()> {
     eframe::run_native(
         "ROIDS",
         options,
-        Box::new(|_cc| Ok(Box::new(RoidsApp::new()))),
+        Box::new(|cc| Ok(Box::new(RoidsApp::from_storage(cc.storage).with_launch_file(launch_file)))),
     )
     .map_err(|e| anyhow::anyhow!("Application error: {}", e))?;
 
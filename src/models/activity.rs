@@ -0,0 +1,156 @@
+// Copyright (c) 2025, Jason Jenkins
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Annotation activity log.
+//!
+//! Records when annotations are created, deleted, and edited during a
+//! session so the work can be exported for productivity tracking.
+
+use crate::models::annotation::Annotation;
+use std::collections::VecDeque;
+
+/// Most events kept; the oldest are dropped first.
+const MAX_EVENTS: usize = 10_000;
+
+/// What happened to an annotation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActivityKind {
+    Created,
+    Deleted,
+    Edited,
+}
+
+impl ActivityKind {
+    /// Name used in the exported log.
+    pub fn label(&self) -> &'static str {
+        match self {
+            ActivityKind::Created => "created",
+            ActivityKind::Deleted => "deleted",
+            ActivityKind::Edited => "edited",
+        }
+    }
+}
+
+/// One logged change to an annotation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActivityEvent {
+    /// ISO 8601 UTC time of the change
+    pub timestamp: String,
+    pub kind: ActivityKind,
+    pub name: String,
+    /// Vertex count after the change (before it, for deletions)
+    pub vertex_count: usize,
+}
+
+/// Bounded log of annotation changes.
+#[derive(Debug, Clone, Default)]
+pub struct ActivityLog {
+    events: VecDeque<ActivityEvent>,
+}
+
+impl ActivityLog {
+    /// Logged events, oldest first.
+    pub fn events(&self) -> impl Iterator<Item = &ActivityEvent> {
+        self.events.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.events.clear();
+    }
+
+    /// Add an event, dropping the oldest if the log is full.
+    pub fn push(&mut self, event: ActivityEvent) {
+        if self.events.len() >= MAX_EVENTS {
+            self.events.pop_front();
+        }
+        self.events.push_back(event);
+    }
+
+    /// Log the differences between two versions of the annotation list.
+    ///
+    /// Annotations that changed but kept their name are logged as edited;
+    /// any others only in `after` are created and only in `before` deleted.
+    pub fn record_changes(&mut self, before: &[Annotation], after: &[Annotation], timestamp: &str) {
+        let mut removed: Vec<&Annotation> = before.iter().filter(|a| !after.contains(a)).collect();
+        let added = after.iter().filter(|a| !before.contains(a));
+
+        let event = |kind, annotation: &Annotation| ActivityEvent {
+            timestamp: timestamp.to_string(),
+            kind,
+            name: annotation.name.clone(),
+            vertex_count: annotation.vertex_count(),
+        };
+        for annotation in added {
+            match removed.iter().position(|old| old.name == annotation.name) {
+                Some(i) => {
+                    removed.remove(i);
+                    self.push(event(ActivityKind::Edited, annotation));
+                }
+                None => self.push(event(ActivityKind::Created, annotation)),
+            }
+        }
+        for annotation in removed {
+            self.push(event(ActivityKind::Deleted, annotation));
+        }
+    }
+
+    /// Format the log as CSV with a header row.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("timestamp,action,name,vertices\n");
+        for event in self.events() {
+            csv.push_str(&format!("{},{},{},{}\n", event.timestamp, event.kind.label(), csv_field(&event.name), event.vertex_count));
+        }
+        csv
+    }
+}
+
+/// Quote a CSV field if it contains a separator, quote, or line break.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::annotation::{AnnotationType, Point};
+
+    #[test]
+    fn test_record_changes_and_csv() {
+        let mut first = Annotation::new("region, 1".to_string(), AnnotationType::Polygon);
+        first.add_vertex(Point::new(0.1, 0.1));
+        let second = Annotation::new("line 1".to_string(), AnnotationType::Line);
+
+        let mut log = ActivityLog::default();
+        log.record_changes(&[], &[first.clone(), second.clone()], "t0");
+
+        let mut moved = first.clone();
+        moved.add_vertex(Point::new(0.2, 0.2));
+        log.record_changes(&[first, second.clone()], &[moved], "t1");
+
+        let kinds: Vec<ActivityKind> = log.events().map(|e| e.kind).collect();
+        assert_eq!(kinds, [ActivityKind::Created, ActivityKind::Created, ActivityKind::Edited, ActivityKind::Deleted]);
+        assert_eq!(
+            log.to_csv(),
+            "timestamp,action,name,vertices\n\
+             t0,created,\"region, 1\",1\n\
+             t0,created,line 1,0\n\
+             t1,edited,\"region, 1\",2\n\
+             t1,deleted,line 1,0\n"
+        );
+
+        log.clear();
+        assert!(log.is_empty());
+    }
+}
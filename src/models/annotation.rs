@@ -6,6 +6,7 @@
 //! This module defines the core data structures for representing
 //! polygons, lines, and their properties.
 
+use crate::util::geometry;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 /// A 2D point with normalized coordinates (0.0 to 1.0).
@@ -53,14 +54,93 @@ impl Point {
     pub fn distance(&self, other: &Point) -> f64 {
         self.distance_squared(other).sqrt()
     }
+
+    /// Check that neither coordinate is NaN or infinite, as can happen
+    /// with a corrupt file.
+    pub fn is_finite(&self) -> bool {
+        self.x.is_finite() && self.y.is_finite()
+    }
 }
 
 /// Type of annotation.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum AnnotationType {
     Polygon,
     Line,
+    /// Single keypoint
+    Point,
+}
+
+impl AnnotationType {
+    /// Minimum number of vertices needed for a complete annotation.
+    pub fn min_vertices(&self) -> usize {
+        match self {
+            AnnotationType::Polygon => 3,
+            AnnotationType::Line => 2,
+            AnnotationType::Point => 1,
+        }
+    }
+
+    /// Prefix used for default annotation names, e.g. "region 3".
+    pub fn default_name_prefix(&self) -> &'static str {
+        match self {
+            AnnotationType::Polygon => "region",
+            AnnotationType::Line => "line",
+            AnnotationType::Point => "point",
+        }
+    }
+}
+
+/// How an annotation's edges are stroked on the canvas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineStyle {
+    #[default]
+    Solid,
+    Dashed,
+}
+
+impl LineStyle {
+    /// Whether this is the default solid style (omitted when saving).
+    pub fn is_solid(&self) -> bool {
+        *self == LineStyle::Solid
+    }
+}
+
+/// How downstream counting systems should interpret an annotation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnnotationRole {
+    /// Region in which objects are detected and counted
+    #[default]
+    Include,
+    /// Region excluded from detection
+    Exclude,
+    /// Line that objects are counted crossing
+    CountLine,
+}
+
+impl AnnotationRole {
+    /// All roles, in display order.
+    pub const ALL: [AnnotationRole; 3] = [AnnotationRole::Include, AnnotationRole::Exclude, AnnotationRole::CountLine];
+
+    /// Default role for newly drawn annotations of the given type.
+    pub fn default_for(annotation_type: AnnotationType) -> Self {
+        match annotation_type {
+            AnnotationType::Line => AnnotationRole::CountLine,
+            AnnotationType::Polygon | AnnotationType::Point => AnnotationRole::Include,
+        }
+    }
+
+    /// Human-readable name for menus.
+    pub fn label(&self) -> &'static str {
+        match self {
+            AnnotationRole::Include => "Include",
+            AnnotationRole::Exclude => "Exclude",
+            AnnotationRole::CountLine => "Count line",
+        }
+    }
 }
 
 /// Wrapper for vertices that serializes with flow style in YAML.
@@ -98,6 +178,41 @@ pub struct Annotation {
     #[serde(rename = "type")]
     pub annotation_type: AnnotationType,
     pub vertices: Vertices,
+    /// Explicit display color (RGBA). Falls back to a category color when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<[u8; 4]>,
+    /// Name of the group (layer) this annotation belongs to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    /// How counting systems should interpret this annotation
+    #[serde(default)]
+    pub role: AnnotationRole,
+    /// Free-text notes, e.g. "partially occluded by pole"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    /// Edge stroke style, e.g. dashed for reference regions
+    #[serde(default, skip_serializing_if = "LineStyle::is_solid")]
+    pub line_style: LineStyle,
+    /// Whether dataset exports include this annotation; scratch and
+    /// reference annotations are kept only in project saves
+    #[serde(default = "default_export", skip_serializing_if = "is_exported")]
+    pub export: bool,
+    /// Further exterior rings of a multi-part polygon, e.g. a flattened
+    /// group. Vertex editing only applies to the first ring, `vertices`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extra_rings: Vec<Vertices>,
+    /// Frame size (width, height) of the file this annotation was merged in
+    /// from; shapes drawn on a different aspect ratio come out distorted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_frame: Option<(u32, u32)>,
+}
+
+fn default_export() -> bool {
+    true
+}
+
+fn is_exported(export: &bool) -> bool {
+    *export
 }
 
 impl Annotation {
@@ -107,11 +222,83 @@ impl Annotation {
             name,
             annotation_type,
             vertices: Vertices(Vec::new()),
+            color: None,
+            group: None,
+            role: AnnotationRole::default_for(annotation_type),
+            notes: None,
+            line_style: LineStyle::Solid,
+            export: true,
+            extra_rings: Vec::new(),
+            source_frame: None,
+        }
+    }
+
+    /// Every ring of the annotation: `vertices` first, then any extra
+    /// rings of a multi-part polygon.
+    pub fn rings(&self) -> impl Iterator<Item = &Vec<Point>> {
+        std::iter::once(&self.vertices.0).chain(self.extra_rings.iter().map(|ring| &ring.0))
+    }
+
+    /// Mutable access to every ring (see [`Annotation::rings`]).
+    pub fn rings_mut(&mut self) -> impl Iterator<Item = &mut Vec<Point>> {
+        std::iter::once(&mut self.vertices.0).chain(self.extra_rings.iter_mut().map(|ring| &mut ring.0))
+    }
+
+    /// Whether this polygon has more than one ring.
+    pub fn is_multi_part(&self) -> bool {
+        !self.extra_rings.is_empty()
+    }
+
+    /// Area of a polygon, summed over its rings. Lines and points have
+    /// no area.
+    pub fn area(&self) -> f64 {
+        if !self.is_closed() {
+            return 0.0;
+        }
+        self.rings().map(|ring| geometry::polygon_area(ring)).sum()
+    }
+
+    /// Rotate every vertex by 90 degrees with the image, along with the
+    /// size of the frame it was drawn on.
+    pub fn rotate_90(&mut self, cw: bool) {
+        for vertex in self.rings_mut().flatten() {
+            *vertex = geometry::rotate_point_90(vertex, cw);
+        }
+        if let Some((width, height)) = self.source_frame {
+            self.source_frame = Some((height, width));
         }
     }
 
+    /// Mirror every vertex with the image.
+    pub fn flip(&mut self, horizontal: bool) {
+        for vertex in self.rings_mut().flatten() {
+            *vertex = geometry::flip_point(vertex, horizontal);
+        }
+    }
+
+    /// Get the category of this annotation.
+    /// The category is the name with any trailing number removed,
+    /// so "region 1" and "region 2" both belong to "region".
+    pub fn category(&self) -> &str {
+        name_category(&self.name)
+    }
+
+    /// Get the display color for this annotation.
+    /// Uses the explicit color if set, otherwise a color derived from the group
+    /// (so layers are colored together) or the category.
+    pub fn display_color(&self) -> [u8; 4] {
+        self.color.unwrap_or_else(|| {
+            let key = self.group.as_deref().unwrap_or_else(|| self.category());
+            crate::util::color::color_for_category(key)
+        })
+    }
+
     /// Add a vertex to the annotation.
+    /// Point annotations hold exactly one vertex, so adding replaces it.
     pub fn add_vertex(&mut self, point: Point) {
+        if self.annotation_type == AnnotationType::Point {
+            self.vertices.0.clear();
+        }
         self.vertices.0.push(point);
     }
 
@@ -126,42 +313,140 @@ impl Annotation {
         }
     }
 
-    /// Find the index of the vertex closest to the given point.
-    /// Returns None if the annotation has no vertices.
-    pub fn find_nearest_vertex(&self, point: &Point) -> Option<usize> {
-        if self.vertices.0.is_empty() {
-            return None;
+    /// Remove several vertices at once, given by their current indices.
+    ///
+    /// Duplicate and out-of-bounds indices are ignored. Returns how many
+    /// vertices were removed, or 0 without changing anything if that would
+    /// leave fewer than the annotation type's minimum.
+    pub fn remove_vertices(&mut self, indices: &[usize]) -> usize {
+        let count = self.vertices.0.len();
+        let doomed: std::collections::BTreeSet<usize> = indices.iter().copied().filter(|&i| i < count).collect();
+        if doomed.is_empty() || count - doomed.len() < self.annotation_type.min_vertices() {
+            return 0;
         }
 
-        let mut min_distance = f64::MAX;
-        let mut nearest_index = 0;
+        // Keep by original index, so earlier removals don't shift later ones
+        let mut index = 0;
+        self.vertices.0.retain(|_| {
+            let keep = !doomed.contains(&index);
+            index += 1;
+            keep
+        });
+        doomed.len()
+    }
+
+    /// Merge two adjacent vertices into one at their midpoint.
+    ///
+    /// The lower index survives (for a polygon's first and last vertices,
+    /// the first). Returns false without changing anything if either index
+    /// is out of bounds, the vertices are the same or not adjacent, or the
+    /// annotation would drop below its minimum vertex count.
+    pub fn weld_vertices(&mut self, i: usize, j: usize) -> bool {
+        let count = self.vertices.0.len();
+        let (low, high) = (i.min(j), i.max(j));
+        if high >= count || low == high || count <= self.annotation_type.min_vertices() {
+            return false;
+        }
+        let (keep, remove) = if high - low == 1 {
+            (low, high)
+        } else if self.is_closed() && low == 0 && high == count - 1 {
+            (0, high)
+        } else {
+            return false;
+        };
+
+        let (a, b) = (self.vertices.0[keep], self.vertices.0[remove]);
+        self.vertices.0[keep] = Point::new((a.x + b.x) / 2.0, (a.y + b.y) / 2.0);
+        self.vertices.0.remove(remove);
+        true
+    }
 
-        for (i, vertex) in self.vertices.0.iter().enumerate() {
+    /// Collapse a line to its first and last vertices, e.g. to make a
+    /// clean counting tripwire. Returns how many vertices were discarded;
+    /// other annotation types are left alone.
+    pub fn straighten(&mut self) -> usize {
+        let count = self.vertices.0.len();
+        if self.annotation_type != AnnotationType::Line || count <= 2 {
+            return 0;
+        }
+        self.vertices.0.drain(1..count - 1);
+        count - 2
+    }
+
+    /// Find the index of the vertex closest to the given point.
+    /// Returns None if the annotation has no finite vertices.
+    pub fn find_nearest_vertex(&self, point: &Point) -> Option<usize> {
+        let mut min_distance = f64::INFINITY;
+        let mut nearest_index = None;
+
+        for (i, vertex) in self.vertices.0.iter().enumerate().filter(|(_, v)| v.is_finite()) {
             let dist = vertex.distance_squared(point);
-            if dist < min_distance {
+            if nearest_index.is_none() || dist < min_distance {
                 min_distance = dist;
-                nearest_index = i;
+                nearest_index = Some(i);
             }
         }
 
-        Some(nearest_index)
+        nearest_index
     }
 
     /// Find the vertex closest to the given point within a threshold distance.
     /// Returns None if no vertex is within the threshold.
     pub fn find_vertex_within_threshold(&self, point: &Point, threshold: f64) -> Option<usize> {
+        self.find_nearest_vertex(point)
+            .filter(|&i| self.vertices.0[i].distance_squared(point) <= threshold * threshold)
+    }
+
+    /// Whether any vertex of any ring lies within a threshold distance.
+    pub fn has_vertex_within(&self, point: &Point, threshold: f64) -> bool {
         let threshold_sq = threshold * threshold;
+        self.rings().flatten().any(|v| v.is_finite() && v.distance_squared(point) <= threshold_sq)
+    }
+
+    /// Get the centroid: the area centroid of a polygon, or the vertex
+    /// average of a line or point.
+    pub fn centroid(&self) -> Option<Point> {
+        let vertices = &self.vertices.0;
+        if self.is_closed() {
+            geometry::polygon_centroid(vertices)
+        } else if vertices.is_empty() {
+            None
+        } else {
+            let n = vertices.len() as f64;
+            let (sx, sy) = vertices.iter().fold((0.0, 0.0), |(sx, sy), p| (sx + p.x, sy + p.y));
+            Some(Point::new(sx / n, sy / n))
+        }
+    }
+
+    /// Get the point a name label is anchored to: the centroid of a
+    /// polygon, or the middle vertex of a line.
+    pub fn label_anchor(&self) -> Option<Point> {
+        if self.is_closed() {
+            geometry::polygon_centroid(&self.vertices.0)
+        } else {
+            self.vertices.0.get(self.vertices.0.len() / 2).copied()
+        }
+    }
+
+    /// Find the closest point on this annotation's edges, including the
+    /// closing edge of a polygon.
+    /// Returns the point and its distance, or None with fewer than 2 vertices.
+    pub fn nearest_edge_point(&self, point: &Point) -> Option<(Point, f64)> {
+        let vertices = &self.vertices.0;
+        if vertices.len() < 2 {
+            return None;
+        }
 
-        self.vertices.0
-            .iter()
-            .enumerate()
-            .filter(|(_, v)| v.distance_squared(point) <= threshold_sq)
-            .min_by(|(_, a), (_, b)| {
-                a.distance_squared(point)
-                    .partial_cmp(&b.distance_squared(point))
-                    .unwrap_or(std::cmp::Ordering::Equal)
+        let closing_edge = self.is_closed().then(|| (&vertices[vertices.len() - 1], &vertices[0]));
+        vertices
+            .windows(2)
+            .map(|pair| (&pair[0], &pair[1]))
+            .chain(closing_edge)
+            .map(|(a, b)| {
+                let projected = geometry::project_onto_segment(point, a, b);
+                (projected, projected.distance(point))
             })
-            .map(|(i, _)| i)
+            .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
     }
 
     /// Update the position of a vertex at the given index.
@@ -184,6 +469,84 @@ impl Annotation {
     pub fn vertex_count(&self) -> usize {
         self.vertices.0.len()
     }
+
+    /// Get the axis-aligned bounding box of every ring as (min, max)
+    /// corners. Returns None if the annotation has no vertices.
+    pub fn bounding_box(&self) -> Option<(Point, Point)> {
+        let mut finite = self.rings().flatten().filter(|p| p.is_finite());
+        let first = *finite.next()?;
+        Some(finite.fold((first, first), |(min, max), p| {
+            (
+                Point::new(min.x.min(p.x), min.y.min(p.y)),
+                Point::new(max.x.max(p.x), max.y.max(p.y)),
+            )
+        }))
+    }
+
+    /// Check that every vertex has finite coordinates.
+    pub fn is_finite(&self) -> bool {
+        self.rings().flatten().all(Point::is_finite)
+    }
+
+    /// Check if the annotation has enough vertices to be finished.
+    pub fn can_finish(&self) -> bool {
+        self.vertex_count() >= self.annotation_type.min_vertices()
+    }
+
+    /// Check if the annotation has more vertices than the given limit.
+    pub fn exceeds_vertex_limit(&self, limit: usize) -> bool {
+        self.vertex_count() > limit
+    }
+}
+
+/// Category of an annotation name (see [`Annotation::category`]).
+pub fn name_category(name: &str) -> &str {
+    let trimmed = name.trim_end_matches(|c: char| c.is_ascii_digit()).trim_end();
+    if trimmed.is_empty() {
+        name
+    } else {
+        trimmed
+    }
+}
+
+/// `name` itself if it is free, otherwise its category plus the first
+/// unused number counting from 1, e.g. "car 3" for a taken "car 1" when
+/// "car 2" is taken too.
+pub fn unique_name(name: &str, is_used: impl Fn(&str) -> bool) -> String {
+    if !is_used(name) {
+        return name.to_string();
+    }
+    let category = name_category(name);
+    (1..)
+        .map(|n| format!("{} {}", category, n))
+        .find(|candidate| !is_used(candidate))
+        .unwrap_or_default()
+}
+
+/// Class a new annotation of `annotation_type` inherits: the category of the
+/// most recent annotation of that type, unless it still has a default name.
+pub fn inherited_class(annotations: &[Annotation], annotation_type: AnnotationType) -> Option<&str> {
+    let last = annotations.iter().rev().find(|a| a.annotation_type == annotation_type)?;
+    let class = last.category();
+    (class != annotation_type.default_name_prefix()).then_some(class)
+}
+
+/// Name for a new annotation.
+///
+/// With a class, this is the class plus the first unused number, e.g.
+/// "car 3" after "car 1" and "car 2"; otherwise the type's default prefix
+/// plus `counter + 1`.
+pub fn new_annotation_name(
+    annotation_type: AnnotationType,
+    class: Option<&str>,
+    counter: usize,
+    existing: &[Annotation],
+) -> String {
+    let Some(class) = class else {
+        return format!("{} {}", annotation_type.default_name_prefix(), counter + 1);
+    };
+    let used: std::collections::HashSet<&str> = existing.iter().map(|a| a.name.as_str()).collect();
+    unique_name(&format!("{} 1", class), |name| used.contains(name))
 }
 
 #[cfg(test)]
@@ -206,6 +569,40 @@ mod tests {
         assert_eq!(p1.distance_squared(&p2), 25.0);
     }
 
+    #[test]
+    fn test_new_annotation_name_inherits_class() {
+        let named = |name: &str, annotation_type| Annotation::new(name.to_string(), annotation_type);
+
+        // Default names are not a class worth repeating
+        let mut annotations = vec![named("region 1", AnnotationType::Polygon)];
+        assert_eq!(inherited_class(&annotations, AnnotationType::Polygon), None);
+        assert_eq!(new_annotation_name(AnnotationType::Polygon, None, 1, &annotations), "region 2");
+
+        // A renamed annotation's class carries over with the next free number
+        annotations.push(named("car", AnnotationType::Polygon));
+        annotations.push(named("car 1", AnnotationType::Polygon));
+        annotations.push(named("lane 4", AnnotationType::Line));
+        let class = inherited_class(&annotations, AnnotationType::Polygon);
+        assert_eq!(class, Some("car"));
+        assert_eq!(new_annotation_name(AnnotationType::Polygon, class, 4, &annotations), "car 2");
+
+        // Only annotations of the same type are considered
+        assert_eq!(inherited_class(&annotations, AnnotationType::Line), Some("lane"));
+        assert_eq!(inherited_class(&annotations, AnnotationType::Point), None);
+        assert_eq!(new_annotation_name(AnnotationType::Line, Some("lane"), 4, &annotations), "lane 1");
+    }
+
+    #[test]
+    fn test_unique_name() {
+        let used = |name: &str| ["car 1", "car 3", "road", "7"].contains(&name);
+        assert_eq!(unique_name("truck 1", used), "truck 1");
+        assert_eq!(unique_name("car 1", used), "car 2");
+        assert_eq!(unique_name("car 3", used), "car 2");
+        assert_eq!(unique_name("road", used), "road 1");
+        // A name that is all digits is its own category
+        assert_eq!(unique_name("7", used), "7 1");
+    }
+
     #[test]
     fn test_annotation_new() {
         let annotation = Annotation::new("region 1".to_string(), AnnotationType::Polygon);
@@ -239,6 +636,68 @@ mod tests {
         assert!(!annotation.remove_vertex(10));
     }
 
+    #[test]
+    fn test_annotation_remove_vertices() {
+        let mut hexagon = Annotation::new("region 1".to_string(), AnnotationType::Polygon);
+        for i in 0..6 {
+            hexagon.add_vertex(Point::new(i as f64, 0.0));
+        }
+
+        // Two non-adjacent vertices, given out of order with a duplicate
+        assert_eq!(hexagon.remove_vertices(&[4, 1, 4, 99]), 2);
+        let xs: Vec<f64> = hexagon.vertices.0.iter().map(|p| p.x).collect();
+        assert_eq!(xs, [0.0, 2.0, 3.0, 5.0]);
+
+        // Refused rather than leaving fewer than 3 polygon vertices
+        assert_eq!(hexagon.remove_vertices(&[0, 1]), 0);
+        assert_eq!(hexagon.vertex_count(), 4);
+        assert_eq!(hexagon.remove_vertices(&[]), 0);
+    }
+
+    #[test]
+    fn test_straighten() {
+        let mut line = Annotation::new("line 1".to_string(), AnnotationType::Line);
+        for (x, y) in [(0.1, 0.5), (0.3, 0.52), (0.5, 0.49), (0.7, 0.51), (0.9, 0.5)] {
+            line.add_vertex(Point::new(x, y));
+        }
+        assert_eq!(line.straighten(), 3);
+        assert_eq!(line.vertices.0, [Point::new(0.1, 0.5), Point::new(0.9, 0.5)]);
+        assert_eq!(line.straighten(), 0);
+
+        // Polygons are never straightened
+        let mut region = Annotation::new("region 1".to_string(), AnnotationType::Polygon);
+        for (x, y) in [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0)] {
+            region.add_vertex(Point::new(x, y));
+        }
+        assert_eq!(region.straighten(), 0);
+        assert_eq!(region.vertex_count(), 3);
+    }
+
+    #[test]
+    fn test_weld_vertices() {
+        let mut annotation = Annotation::new("region 1".to_string(), AnnotationType::Polygon);
+        annotation.add_vertex(Point::new(0.0, 0.0));
+        annotation.add_vertex(Point::new(0.5, 0.0));
+        annotation.add_vertex(Point::new(0.52, 0.02));
+        annotation.add_vertex(Point::new(1.0, 1.0));
+
+        assert!(annotation.weld_vertices(2, 1));
+        assert_eq!(annotation.vertex_count(), 3);
+        assert!(annotation.vertices.0[1].distance(&Point::new(0.51, 0.01)) < 1e-12);
+        assert_eq!(annotation.vertices.0[2], Point::new(1.0, 1.0));
+
+        // A triangle is already at the polygon minimum
+        assert!(!annotation.weld_vertices(0, 2));
+        assert!(!annotation.weld_vertices(0, 10));
+
+        // Non-adjacent vertices would pinch the ring; first and last wrap around
+        annotation.add_vertex(Point::new(0.0, 1.0));
+        assert!(!annotation.weld_vertices(0, 2));
+        assert!(!annotation.weld_vertices(1, 1));
+        assert!(annotation.weld_vertices(3, 0));
+        assert_eq!(annotation.vertices.0[0], Point::new(0.0, 0.5));
+    }
+
     #[test]
     fn test_annotation_update_vertex() {
         let mut annotation = Annotation::new("region 1".to_string(), AnnotationType::Polygon);
@@ -251,6 +710,109 @@ mod tests {
         assert!(!annotation.update_vertex(10, Point::new(0.0, 0.0)));
     }
 
+    #[test]
+    fn test_bounding_box() {
+        let mut annotation = Annotation::new("region 1".to_string(), AnnotationType::Polygon);
+        assert_eq!(annotation.bounding_box(), None);
+
+        annotation.add_vertex(Point::new(0.5, 0.2));
+        annotation.add_vertex(Point::new(0.1, 0.7));
+        annotation.add_vertex(Point::new(0.3, 0.4));
+        assert_eq!(
+            annotation.bounding_box(),
+            Some((Point::new(0.1, 0.2), Point::new(0.5, 0.7)))
+        );
+    }
+
+    #[test]
+    fn test_nearest_edge_point() {
+        let mut polygon = Annotation::new("region 1".to_string(), AnnotationType::Polygon);
+        polygon.add_vertex(Point::new(0.2, 0.2));
+        polygon.add_vertex(Point::new(0.6, 0.2));
+        polygon.add_vertex(Point::new(0.6, 0.6));
+        polygon.add_vertex(Point::new(0.2, 0.6));
+
+        let (snapped, distance) = polygon.nearest_edge_point(&Point::new(0.4, 0.25)).unwrap();
+        assert_eq!(snapped, Point::new(0.4, 0.2));
+        assert!((distance - 0.05).abs() < 1e-12);
+
+        // The closing edge of a polygon counts
+        let (snapped, _) = polygon.nearest_edge_point(&Point::new(0.15, 0.4)).unwrap();
+        assert_eq!(snapped, Point::new(0.2, 0.4));
+
+        // ...but not for an open line
+        polygon.annotation_type = AnnotationType::Line;
+        let (snapped, _) = polygon.nearest_edge_point(&Point::new(0.15, 0.35)).unwrap();
+        assert_eq!(snapped, Point::new(0.2, 0.2));
+
+        let single = Annotation::new("line 1".to_string(), AnnotationType::Line);
+        assert_eq!(single.nearest_edge_point(&Point::new(0.0, 0.0)), None);
+    }
+
+    #[test]
+    fn test_point_annotation() {
+        let mut point = Annotation::new("point 1".to_string(), AnnotationType::Point);
+        assert!(!point.can_finish());
+
+        point.add_vertex(Point::new(0.2, 0.3));
+        point.add_vertex(Point::new(0.4, 0.5));
+        assert_eq!(point.vertex_count(), 1);
+        assert_eq!(point.vertices.0[0], Point::new(0.4, 0.5));
+        assert!(point.can_finish());
+        assert!(!point.is_closed());
+
+        let json = serde_json::to_string(&point).unwrap();
+        assert!(json.contains(r#""type":"point""#));
+        let round_tripped: Annotation = serde_json::from_str(&json).unwrap();
+        assert_eq!(round_tripped.annotation_type, AnnotationType::Point);
+        assert_eq!(round_tripped, point);
+    }
+
+    #[test]
+    fn test_role_round_trip() {
+        assert_eq!(Annotation::new("region 1".to_string(), AnnotationType::Polygon).role, AnnotationRole::Include);
+        assert_eq!(Annotation::new("line 1".to_string(), AnnotationType::Line).role, AnnotationRole::CountLine);
+
+        let mut annotation = Annotation::new("region 1".to_string(), AnnotationType::Polygon);
+        annotation.role = AnnotationRole::Exclude;
+        let json = serde_json::to_string(&annotation).unwrap();
+        assert!(json.contains(r#""role":"exclude""#));
+        let round_tripped: Annotation = serde_json::from_str(&json).unwrap();
+        assert_eq!(round_tripped.role, AnnotationRole::Exclude);
+
+        // Files written before roles existed default to Include
+        let old: Annotation = serde_json::from_str(r#"{"name":"line 1","type":"line","vertices":[]}"#).unwrap();
+        assert_eq!(old.role, AnnotationRole::Include);
+    }
+
+    #[test]
+    fn test_can_finish() {
+        let mut polygon = Annotation::new("region 1".to_string(), AnnotationType::Polygon);
+        let mut line = Annotation::new("line 1".to_string(), AnnotationType::Line);
+        for i in 0..2 {
+            polygon.add_vertex(Point::new(i as f64 * 0.1, 0.0));
+            line.add_vertex(Point::new(i as f64 * 0.1, 0.0));
+        }
+
+        assert!(!polygon.can_finish());
+        assert!(line.can_finish());
+
+        polygon.add_vertex(Point::new(0.5, 0.5));
+        assert!(polygon.can_finish());
+    }
+
+    #[test]
+    fn test_exceeds_vertex_limit() {
+        let mut annotation = Annotation::new("region 1".to_string(), AnnotationType::Polygon);
+        for i in 0..3 {
+            annotation.add_vertex(Point::new(i as f64 * 0.1, 0.0));
+        }
+        assert!(!annotation.exceeds_vertex_limit(3));
+
+        annotation.add_vertex(Point::new(0.5, 0.5));
+        assert!(annotation.exceeds_vertex_limit(3));
+    }
+
     #[test]
     fn test_find_nearest_vertex() {
         let mut annotation = Annotation::new("region 1".to_string(), AnnotationType::Polygon);
@@ -266,6 +828,23 @@ mod tests {
         assert_eq!(empty_annotation.find_nearest_vertex(&search_point), None);
     }
 
+    #[test]
+    fn test_find_nearest_vertex_skips_nan() {
+        let mut annotation = Annotation::new("region 1".to_string(), AnnotationType::Polygon);
+        annotation.add_vertex(Point::new(f64::NAN, 0.0));
+        annotation.add_vertex(Point::new(1.0, 1.0));
+        assert!(!annotation.is_finite());
+        assert_eq!(annotation.find_nearest_vertex(&Point::new(0.0, 0.0)), Some(1));
+        assert_eq!(annotation.find_vertex_within_threshold(&Point::new(f64::NAN, 0.0), 1.0), None);
+
+        // A NaN search point or only NaN vertices find nothing sensible,
+        // but don't panic
+        annotation.find_nearest_vertex(&Point::new(f64::NAN, f64::NAN));
+        annotation.vertices.0.remove(1);
+        assert_eq!(annotation.find_nearest_vertex(&Point::new(0.0, 0.0)), None);
+        assert_eq!(annotation.bounding_box(), None);
+    }
+
     #[test]
     fn test_find_vertex_within_threshold() {
         let mut annotation = Annotation::new("region 1".to_string(), AnnotationType::Polygon);
@@ -279,6 +858,36 @@ mod tests {
 
         let found_none = annotation.find_vertex_within_threshold(&search_point, 0.01);
         assert_eq!(found_none, None);
+
+        // Extra rings are hit but have no draggable index
+        annotation.extra_rings.push(Vertices(vec![Point::new(0.2, 0.8)]));
+        assert_eq!(annotation.find_vertex_within_threshold(&Point::new(0.2, 0.8), 0.01), None);
+        assert!(annotation.has_vertex_within(&Point::new(0.2, 0.8), 0.01));
+        assert!(!annotation.has_vertex_within(&search_point, 0.01));
+    }
+
+    #[test]
+    fn test_annotation_category() {
+        let a = Annotation::new("region 1".to_string(), AnnotationType::Polygon);
+        let b = Annotation::new("region 12".to_string(), AnnotationType::Polygon);
+        let c = Annotation::new("42".to_string(), AnnotationType::Line);
+
+        assert_eq!(a.category(), "region");
+        assert_eq!(a.display_color(), b.display_color());
+        assert_eq!(c.category(), "42");
+    }
+
+    #[test]
+    fn test_group_serialization_defaults_to_none() {
+        let json = r#"{"name":"region 1","type":"polygon","vertices":[[0.1,0.2]]}"#;
+        let annotation: Annotation = serde_json::from_str(json).unwrap();
+        assert_eq!(annotation.group, None);
+
+        let mut grouped = annotation.clone();
+        grouped.group = Some("lanes".to_string());
+        let json = serde_json::to_string(&grouped).unwrap();
+        let deserialized: Annotation = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized.group.as_deref(), Some("lanes"));
     }
 
     #[test]
@@ -0,0 +1,107 @@
+// Copyright (c) 2025, Jason Jenkins
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Annotation groups (layers).
+//!
+//! This module tracks which groups are visible and which group new
+//! annotations are assigned to. Group membership itself is stored on
+//! each annotation.
+
+use super::annotation::Annotation;
+use std::collections::BTreeSet;
+
+/// Visibility and selection state for annotation groups.
+#[derive(Debug, Clone, Default)]
+pub struct Layers {
+    /// Groups whose members are hidden
+    hidden: BTreeSet<String>,
+    /// Group assigned to newly created annotations
+    pub active: Option<String>,
+}
+
+impl Layers {
+    /// Create a layer state with every group visible.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Check if a group is visible.
+    pub fn is_group_visible(&self, group: &str) -> bool {
+        !self.hidden.contains(group)
+    }
+
+    /// Show or hide a group.
+    pub fn set_group_visible(&mut self, group: &str, visible: bool) {
+        if visible {
+            self.hidden.remove(group);
+        } else {
+            self.hidden.insert(group.to_string());
+        }
+    }
+
+    /// Check if an annotation should be drawn.
+    /// Ungrouped annotations are always visible.
+    pub fn is_visible(&self, annotation: &Annotation) -> bool {
+        annotation
+            .group
+            .as_deref()
+            .is_none_or(|group| self.is_group_visible(group))
+    }
+
+    /// Collect the sorted, de-duplicated group names used by annotations,
+    /// plus the active group.
+    pub fn group_names(&self, annotations: &[Annotation]) -> Vec<String> {
+        let mut names: BTreeSet<String> = annotations
+            .iter()
+            .filter_map(|a| a.group.clone())
+            .collect();
+        if let Some(ref active) = self.active {
+            names.insert(active.clone());
+        }
+        names.into_iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::annotation::AnnotationType;
+
+    fn grouped(name: &str, group: Option<&str>) -> Annotation {
+        let mut annotation = Annotation::new(name.to_string(), AnnotationType::Polygon);
+        annotation.group = group.map(str::to_string);
+        annotation
+    }
+
+    #[test]
+    fn test_hiding_group_affects_only_members() {
+        let lane = grouped("lane 1", Some("lanes"));
+        let zone = grouped("zone 1", Some("zones"));
+        let loose = grouped("region 1", None);
+
+        let mut layers = Layers::new();
+        layers.set_group_visible("lanes", false);
+
+        assert!(!layers.is_visible(&lane));
+        assert!(layers.is_visible(&zone));
+        assert!(layers.is_visible(&loose));
+
+        layers.set_group_visible("lanes", true);
+        assert!(layers.is_visible(&lane));
+    }
+
+    #[test]
+    fn test_group_names() {
+        let annotations = vec![
+            grouped("a", Some("zones")),
+            grouped("b", Some("lanes")),
+            grouped("c", Some("zones")),
+            grouped("d", None),
+        ];
+
+        let mut layers = Layers::new();
+        layers.active = Some("new".to_string());
+
+        assert_eq!(layers.group_names(&annotations), vec!["lanes", "new", "zones"]);
+    }
+}
@@ -3,5 +3,10 @@
 
 //! Data models for ROIDS application.
 
+pub mod activity;
 pub mod annotation;
+pub mod layers;
 pub mod project;
+pub mod session;
+pub mod settings;
+pub mod vertex_selection;
@@ -6,8 +6,11 @@
 //! This module manages the overall project state including loaded media,
 //! annotations, and application settings.
 
-use super::annotation::Annotation;
+use super::annotation::{Annotation, AnnotationType, Point, Vertices};
+use crate::util::geometry;
+use anyhow::{bail, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 /// Complete project data for serialization.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -16,6 +19,25 @@ pub struct ProjectData {
     pub frame_width: u32,
     pub frame_height: u32,
     pub annotations: Vec<Annotation>,
+    /// Normalized image region (min, max) to frame when the project is opened
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_view: Option<(Point, Point)>,
+    /// Clockwise rotation (0, 90, 180, or 270 degrees) applied to the media
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub rotation: u16,
+    /// Whether the media is mirrored left-right, after rotating
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub flipped_horizontally: bool,
+    /// Whether the media is mirrored top-bottom, after rotating
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub flipped_vertically: bool,
+    /// Annotations on individual video frames, keyed by frame index
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub frames: BTreeMap<u64, Vec<Annotation>>,
+    /// Direction of north for geo-referenced imagery, in degrees clockwise
+    /// from the top of the image; shown as a north arrow when set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub north_offset_deg: Option<f64>,
 }
 
 impl ProjectData {
@@ -26,6 +48,403 @@ impl ProjectData {
             frame_width,
             frame_height,
             annotations: Vec::new(),
+            default_view: None,
+            rotation: 0,
+            flipped_horizontally: false,
+            flipped_vertically: false,
+            frames: BTreeMap::new(),
+            north_offset_deg: None,
         }
     }
+
+    /// Rotate the frame by 90 degrees, remapping every annotation so it
+    /// stays aligned with the rotated image.
+    pub fn rotate_90(&mut self, cw: bool) {
+        for annotation in self.annotations.iter_mut().chain(self.frames.values_mut().flatten()) {
+            annotation.rotate_90(cw);
+        }
+        if let Some((min, max)) = self.default_view {
+            let (a, b) = (geometry::rotate_point_90(&min, cw), geometry::rotate_point_90(&max, cw));
+            self.default_view = Some((Point::new(a.x.min(b.x), a.y.min(b.y)), Point::new(a.x.max(b.x), a.y.max(b.y))));
+        }
+        std::mem::swap(&mut self.frame_width, &mut self.frame_height);
+        self.rotation = (self.rotation + if cw { 90 } else { 270 }) % 360;
+        // A quarter turn of a mirrored image equals mirroring the turned
+        // image across the other axis
+        std::mem::swap(&mut self.flipped_horizontally, &mut self.flipped_vertically);
+        self.north_offset_deg = self.north_offset_deg.map(|north| rotate_north_90(north, cw));
+    }
+
+    /// Rotate and mirror the frame until its orientation matches `other`'s,
+    /// so annotations made on differently oriented copies of an image line up.
+    pub fn orient_like(&mut self, other: &ProjectData) {
+        for _ in 0..3 {
+            if self.rotation == other.rotation % 360 {
+                break;
+            }
+            self.rotate_90(true);
+        }
+        if self.flipped_horizontally != other.flipped_horizontally {
+            self.flip(true);
+        }
+        if self.flipped_vertically != other.flipped_vertically {
+            self.flip(false);
+        }
+    }
+
+    /// Mirror the frame, remapping every annotation so it stays aligned
+    /// with the flipped image.
+    pub fn flip(&mut self, horizontal: bool) {
+        for annotation in self.annotations.iter_mut().chain(self.frames.values_mut().flatten()) {
+            annotation.flip(horizontal);
+        }
+        if let Some((min, max)) = self.default_view {
+            let (a, b) = (geometry::flip_point(&min, horizontal), geometry::flip_point(&max, horizontal));
+            self.default_view = Some((Point::new(a.x.min(b.x), a.y.min(b.y)), Point::new(a.x.max(b.x), a.y.max(b.y))));
+        }
+        if horizontal {
+            self.flipped_horizontally = !self.flipped_horizontally;
+        } else {
+            self.flipped_vertically = !self.flipped_vertically;
+        }
+        self.north_offset_deg = self.north_offset_deg.map(|north| flip_north(north, horizontal));
+    }
+
+    /// Drop vertices along straight runs of every polygon and line (see
+    /// [`geometry::remove_collinear`]), leaving their shapes unchanged.
+    pub fn remove_collinear(&mut self, angle_eps: f64) {
+        for annotation in self.annotations.iter_mut().chain(self.frames.values_mut().flatten()) {
+            if annotation.annotation_type != AnnotationType::Point {
+                let closed = annotation.is_closed();
+                for ring in annotation.rings_mut() {
+                    *ring = geometry::remove_collinear(ring, angle_eps, closed);
+                }
+            }
+        }
+    }
+
+    /// Record this project's frame size as the source of every annotation
+    /// (including per-frame ones) that doesn't have one yet, before they
+    /// are merged into another project.
+    pub fn tag_source_frame(&mut self) {
+        if self.frame_width == 0 || self.frame_height == 0 {
+            return;
+        }
+        let frame = (self.frame_width, self.frame_height);
+        for annotation in self.annotations.iter_mut().chain(self.frames.values_mut().flatten()) {
+            annotation.source_frame.get_or_insert(frame);
+        }
+    }
+
+    /// Annotations that dataset exports should include: those marked for
+    /// export whose coordinates are all finite.
+    pub fn exported_annotations(&self) -> impl Iterator<Item = &Annotation> {
+        self.annotations.iter().filter(|annotation| annotation.export && annotation.is_finite())
+    }
+
+    /// A copy of the project's exported annotations in the coordinate
+    /// system of a normalized crop rectangle (min, max). Annotations outside
+    /// the crop are dropped and those partly inside are clipped to it; a
+    /// line the crop cuts into several pieces becomes one annotation per
+    /// piece.
+    ///
+    /// The media file no longer matches the frame, so it is left empty for
+    /// the caller to point at a cropped copy of the image as shown, i.e.
+    /// already rotated and mirrored.
+    pub fn cropped(&self, crop: (Point, Point)) -> ProjectData {
+        let (min, max) = crop;
+        let crop_annotations = |annotations: &[Annotation]| -> Vec<Annotation> {
+            annotations
+                .iter()
+                .filter(|annotation| annotation.export && annotation.is_finite())
+                .flat_map(|annotation| crop_annotation(annotation, crop))
+                .collect()
+        };
+        ProjectData {
+            media_file: String::new(),
+            frame_width: ((max.x - min.x) * self.frame_width as f64).round().max(1.0) as u32,
+            frame_height: ((max.y - min.y) * self.frame_height as f64).round().max(1.0) as u32,
+            annotations: crop_annotations(&self.annotations),
+            default_view: None,
+            rotation: 0,
+            flipped_horizontally: false,
+            flipped_vertically: false,
+            frames: self.frames.iter().map(|(&frame, annotations)| (frame, crop_annotations(annotations))).collect(),
+            north_offset_deg: self.north_offset_deg,
+        }
+    }
+
+    /// Flatten polygons from one group into a single multi-part polygon
+    /// holding each of their rings, in place of the first of them.
+    ///
+    /// Returns the index of the flattened annotation.
+    pub fn flatten(&mut self, indices: &[usize]) -> Result<usize> {
+        let mut indices = indices.to_vec();
+        indices.sort_unstable();
+        indices.dedup();
+        if indices.len() < 2 {
+            bail!("Select at least two polygons to flatten");
+        }
+        let Some(parts) = indices.iter().map(|&i| self.annotations.get(i)).collect::<Option<Vec<_>>>() else {
+            bail!("Selection refers to a deleted annotation");
+        };
+        if let Some(part) = parts.iter().find(|a| a.annotation_type != AnnotationType::Polygon) {
+            bail!("{} is not a polygon", part.name);
+        }
+        let group = &parts[0].group;
+        if group.is_none() || parts.iter().any(|a| &a.group != group) {
+            bail!("Only polygons sharing a group can be flattened");
+        }
+
+        let mut flattened = parts[0].clone();
+        flattened.extra_rings = parts
+            .iter()
+            .flat_map(|part| part.rings())
+            .skip(1)
+            .map(|ring| Vertices(ring.clone()))
+            .collect();
+        let first = indices[0];
+        for &idx in indices.iter().skip(1).rev() {
+            self.annotations.remove(idx);
+        }
+        self.annotations[first] = flattened;
+        Ok(first)
+    }
+
+    /// A standalone project holding one frame's annotations.
+    pub fn frame_project(&self, frame: u64) -> Option<ProjectData> {
+        let annotations = self.frames.get(&frame)?;
+        Some(ProjectData {
+            media_file: self.media_file.clone(),
+            frame_width: self.frame_width,
+            frame_height: self.frame_height,
+            annotations: annotations.clone(),
+            default_view: self.default_view,
+            rotation: self.rotation,
+            flipped_horizontally: self.flipped_horizontally,
+            flipped_vertically: self.flipped_vertically,
+            frames: BTreeMap::new(),
+            north_offset_deg: self.north_offset_deg,
+        })
+    }
+}
+
+/// Direction of north, in degrees clockwise from the top, after rotating
+/// the image by 90 degrees.
+pub fn rotate_north_90(north: f64, cw: bool) -> f64 {
+    (north + if cw { 90.0 } else { 270.0 }).rem_euclid(360.0)
+}
+
+/// Direction of north, in degrees clockwise from the top, after mirroring
+/// the image left-right when `horizontal`, otherwise top-bottom.
+pub fn flip_north(north: f64, horizontal: bool) -> f64 {
+    if horizontal { -north } else { 180.0 - north }.rem_euclid(360.0)
+}
+
+/// Clip one annotation to a crop and re-normalize it (see
+/// [`ProjectData::cropped`]).
+fn crop_annotation(annotation: &Annotation, crop: (Point, Point)) -> Vec<Annotation> {
+    let reproject = |ring: &[Point]| ring.iter().map(|p| geometry::reproject_to_crop(p, crop)).collect::<Option<Vec<_>>>();
+    let pieces = match annotation.annotation_type {
+        AnnotationType::Point => vec![annotation.vertices.0.clone()],
+        AnnotationType::Polygon => {
+            // Each ring of a multi-part polygon is clipped on its own
+            let mut rings = annotation
+                .rings()
+                .filter_map(|ring| reproject(&geometry::clip_polygon_to_rect(ring, crop)))
+                .filter(|ring| ring.len() >= 3)
+                .map(Vertices);
+            let Some(first) = rings.next() else {
+                return Vec::new();
+            };
+            let mut cropped = annotation.clone();
+            cropped.vertices = first;
+            cropped.extra_rings = rings.collect();
+            return vec![cropped];
+        }
+        AnnotationType::Line => geometry::clip_polyline_to_rect(&annotation.vertices.0, crop),
+    };
+    let piece_count = pieces.len();
+    pieces
+        .into_iter()
+        .filter_map(|piece| reproject(&piece))
+        .filter(|vertices| vertices.len() >= annotation.annotation_type.min_vertices())
+        .enumerate()
+        .map(|(i, vertices)| {
+            let mut piece = annotation.clone();
+            if piece_count > 1 {
+                piece.name = format!("{} ({})", annotation.name, i + 1);
+            }
+            piece.vertices.0 = vertices;
+            piece
+        })
+        .collect()
+}
+
+fn is_zero(value: &u16) -> bool {
+    *value == 0
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotate_90() {
+        let mut data = ProjectData::new("frame.png".to_string(), 200, 100);
+        let mut annotation = Annotation::new("region 1".to_string(), AnnotationType::Polygon);
+        annotation.add_vertex(Point::new(0.1, 0.2));
+        annotation.add_vertex(Point::new(0.6, 0.2));
+        annotation.add_vertex(Point::new(0.6, 0.9));
+        data.annotations.push(annotation);
+        data.default_view = Some((Point::new(0.0, 0.0), Point::new(0.5, 0.5)));
+        let original = data.clone();
+
+        data.rotate_90(true);
+        assert_eq!((data.frame_width, data.frame_height, data.rotation), (100, 200, 90));
+        assert_eq!(data.default_view, Some((Point::new(0.5, 0.0), Point::new(1.0, 0.5))));
+
+        // Four quarter turns return to the start
+        for _ in 0..3 {
+            data.rotate_90(true);
+        }
+        assert_eq!((data.frame_width, data.frame_height, data.rotation), (200, 100, 0));
+        for (rotated, original) in data.annotations[0].vertices.0.iter().zip(&original.annotations[0].vertices.0) {
+            assert!(rotated.distance(original) < 1e-12);
+        }
+
+        // North turns with the image
+        data.north_offset_deg = Some(30.0);
+        data.rotate_90(false);
+        assert_eq!(data.rotation, 270);
+        assert_eq!(data.north_offset_deg, Some(300.0));
+        assert!(serde_json::to_string(&data).unwrap().contains(r#""rotation":270"#));
+    }
+
+    #[test]
+    fn test_flip() {
+        let mut data = ProjectData::new("frame.png".to_string(), 200, 100);
+        let mut annotation = Annotation::new("region 1".to_string(), AnnotationType::Polygon);
+        annotation.add_vertex(Point::new(0.1, 0.2));
+        annotation.add_vertex(Point::new(0.6, 0.2));
+        annotation.add_vertex(Point::new(0.6, 0.9));
+        data.annotations.push(annotation);
+        data.default_view = Some((Point::new(0.0, 0.0), Point::new(0.5, 0.5)));
+        let original = data.clone();
+        assert!(!serde_json::to_string(&data).unwrap().contains("flipped"));
+
+        data.flip(true);
+        assert!(data.flipped_horizontally && !data.flipped_vertically);
+        assert!(data.annotations[0].vertices.0[0].distance(&Point::new(0.9, 0.2)) < 1e-12);
+        assert_eq!(data.default_view, Some((Point::new(0.5, 0.0), Point::new(1.0, 0.5))));
+        assert!(serde_json::to_string(&data).unwrap().contains(r#""flipped_horizontally":true"#));
+
+        // Flipping twice returns the original coordinates
+        data.flip(true);
+        assert!(!data.flipped_horizontally);
+        assert_eq!(data.default_view, original.default_view);
+        for (flipped, original) in data.annotations[0].vertices.0.iter().zip(&original.annotations[0].vertices.0) {
+            assert!(flipped.distance(original) < 1e-12);
+        }
+
+        // Mirroring reflects the direction of north
+        data.north_offset_deg = Some(30.0);
+        data.flip(true);
+        assert_eq!(data.north_offset_deg, Some(330.0));
+        data.flip(false);
+        assert_eq!(data.north_offset_deg, Some(210.0));
+        data.flip(false);
+        data.flip(true);
+        assert_eq!(data.north_offset_deg, Some(30.0));
+
+        // Rotating carries the flip over to the other axis
+        data.flip(false);
+        data.rotate_90(true);
+        assert!(data.flipped_horizontally && !data.flipped_vertically);
+    }
+
+    #[test]
+    fn test_cropped() {
+        let mut data = ProjectData::new("frame.png".to_string(), 200, 100);
+        let mut inside = Annotation::new("inside".to_string(), AnnotationType::Point);
+        inside.add_vertex(Point::new(0.5, 0.75));
+        let mut outside = Annotation::new("outside".to_string(), AnnotationType::Point);
+        outside.add_vertex(Point::new(0.1, 0.1));
+        let mut straddling = Annotation::new("straddling".to_string(), AnnotationType::Polygon);
+        for (x, y) in [(0.0, 0.75), (0.5, 0.75), (0.5, 1.0), (0.0, 1.0)] {
+            straddling.add_vertex(Point::new(x, y));
+        }
+        let mut excluded = Annotation::new("excluded".to_string(), AnnotationType::Point);
+        excluded.add_vertex(Point::new(0.5, 0.75));
+        excluded.export = false;
+        data.annotations = vec![inside, outside, straddling, excluded];
+        data.default_view = Some((Point::new(0.0, 0.0), Point::new(0.5, 0.5)));
+        data.rotation = 90;
+
+        let cropped = data.cropped((Point::new(0.25, 0.5), Point::new(0.75, 1.0)));
+        assert_eq!((cropped.frame_width, cropped.frame_height), (100, 50));
+        assert_eq!(cropped.default_view, None);
+        assert_eq!((cropped.media_file.as_str(), cropped.rotation), ("", 0));
+        let names: Vec<_> = cropped.annotations.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, ["inside", "straddling"]);
+        assert_eq!(cropped.annotations[0].vertices.0, [Point::new(0.5, 0.5)]);
+        // The polygon is cut at the crop's left edge
+        let xs: Vec<_> = cropped.annotations[1].vertices.0.iter().map(|p| p.x).collect();
+        assert!(xs.iter().all(|x| (0.0..=0.5).contains(x)));
+        assert!((geometry::polygon_area(&cropped.annotations[1].vertices.0) - 0.25).abs() < 1e-12);
+    }
+
+    fn square(name: &str, group: Option<&str>, x: f64, y: f64, size: f64) -> Annotation {
+        let mut annotation = Annotation::new(name.to_string(), AnnotationType::Polygon);
+        for (dx, dy) in [(0.0, 0.0), (size, 0.0), (size, size), (0.0, size)] {
+            annotation.add_vertex(Point::new(x + dx, y + dy));
+        }
+        annotation.group = group.map(str::to_string);
+        annotation
+    }
+
+    #[test]
+    fn test_flatten() {
+        let mut data = ProjectData::new("frame.png".to_string(), 100, 100);
+        data.annotations = vec![
+            square("tree 1", Some("trees"), 0.0, 0.0, 0.2),
+            square("road 1", None, 0.4, 0.4, 0.1),
+            square("tree 2", Some("trees"), 0.6, 0.6, 0.3),
+        ];
+
+        // Polygons must share a group
+        assert!(data.flatten(&[0, 1]).is_err());
+        assert!(data.flatten(&[0]).is_err());
+        assert_eq!(data.annotations.len(), 3);
+
+        assert_eq!(data.flatten(&[2, 0]).unwrap(), 0);
+        assert_eq!(data.annotations.len(), 2);
+        let flattened = &data.annotations[0];
+        assert_eq!(flattened.name, "tree 1");
+        assert_eq!(flattened.rings().count(), 2);
+        assert!((flattened.area() - (0.04 + 0.09)).abs() < 1e-12);
+        let (min, max) = flattened.bounding_box().unwrap();
+        assert!(min == Point::new(0.0, 0.0) && max.distance(&Point::new(0.9, 0.9)) < 1e-12);
+
+        // Rings survive a JSON round trip
+        let round_tripped: ProjectData = serde_json::from_str(&serde_json::to_string(&data).unwrap()).unwrap();
+        assert_eq!(round_tripped.annotations[0], data.annotations[0]);
+        assert!(!serde_json::to_string(&data.annotations[1]).unwrap().contains("extra_rings"));
+    }
+
+    #[test]
+    fn test_default_view_is_optional() {
+        let json = r#"{"media_file": "frame.png", "frame_width": 10, "frame_height": 10, "annotations": []}"#;
+        let mut data: ProjectData = serde_json::from_str(json).unwrap();
+        assert_eq!(data.default_view, None);
+        assert!(!serde_json::to_string(&data).unwrap().contains("default_view"));
+
+        data.default_view = Some((Point::new(0.25, 0.5), Point::new(0.75, 1.0)));
+        let round_tripped: ProjectData = serde_json::from_str(&serde_json::to_string(&data).unwrap()).unwrap();
+        assert_eq!(round_tripped.default_view, data.default_view);
+    }
 }
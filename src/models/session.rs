@@ -0,0 +1,137 @@
+// Copyright (c) 2025, Jason Jenkins
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Last-session state persisted between application runs.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Files that were open when the application last exited.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LastSession {
+    /// Last opened image
+    pub media_path: Option<PathBuf>,
+    /// Last imported or exported annotation file
+    pub annotation_path: Option<PathBuf>,
+}
+
+impl LastSession {
+    /// Check if there is anything to restore.
+    pub fn is_empty(&self) -> bool {
+        self.media_path.is_none() && self.annotation_path.is_none()
+    }
+
+    /// Forget paths whose files no longer exist.
+    /// Returns the paths that were cleared.
+    pub fn clear_missing(&mut self) -> Vec<PathBuf> {
+        let mut missing = Vec::new();
+        for slot in [&mut self.media_path, &mut self.annotation_path] {
+            if slot.as_ref().is_some_and(|p| !p.is_file()) {
+                missing.extend(slot.take());
+            }
+        }
+        missing
+    }
+}
+
+/// What a file dialog is for; each remembers its own directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DialogPurpose {
+    OpenImage,
+    OpenAnnotations,
+    Export,
+}
+
+/// Directory each kind of file dialog was last used in.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RecentDirectories {
+    pub directories: HashMap<DialogPurpose, PathBuf>,
+}
+
+impl RecentDirectories {
+    /// Remember where a file or folder was picked.
+    ///
+    /// A picked folder is remembered itself, a picked file by its parent.
+    pub fn remember(&mut self, purpose: DialogPurpose, picked: &Path) {
+        let directory = if picked.is_dir() { Some(picked) } else { picked.parent() };
+        if let Some(directory) = directory.filter(|d| !d.as_os_str().is_empty()) {
+            self.directories.insert(purpose, directory.to_path_buf());
+        }
+    }
+
+    /// Directory to start a dialog in: the remembered one if it still
+    /// exists, otherwise the home directory.
+    pub fn start_directory(&self, purpose: DialogPurpose) -> Option<PathBuf> {
+        self.directories
+            .get(&purpose)
+            .filter(|d| d.is_dir())
+            .cloned()
+            .or_else(home_directory)
+    }
+}
+
+/// The user's home directory, if it can be found.
+fn home_directory() -> Option<PathBuf> {
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .map(PathBuf::from)
+        .filter(|d| d.is_dir())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::scratch::scratch_dir;
+
+    #[test]
+    fn test_clear_missing() {
+        let existing = scratch_dir("session").join("session.json");
+        std::fs::write(&existing, "{}").unwrap();
+
+        let mut session = LastSession {
+            media_path: Some(PathBuf::from("/nonexistent/image.png")),
+            annotation_path: Some(existing.clone()),
+        };
+
+        let missing = session.clear_missing();
+        assert_eq!(missing, vec![PathBuf::from("/nonexistent/image.png")]);
+        assert_eq!(session.media_path, None);
+        assert_eq!(session.annotation_path, Some(existing.clone()));
+        assert!(!session.is_empty());
+
+        std::fs::remove_file(&existing).unwrap();
+    }
+
+    #[test]
+    fn test_recent_directories() {
+        let dir = scratch_dir("recent_dirs");
+        let mut recent = RecentDirectories::default();
+
+        // Files are remembered by their folder, folders as themselves
+        recent.remember(DialogPurpose::OpenImage, &dir.join("frame.png"));
+        recent.remember(DialogPurpose::Export, &dir);
+        assert_eq!(recent.start_directory(DialogPurpose::OpenImage), Some(dir.clone()));
+        assert_eq!(recent.start_directory(DialogPurpose::Export), Some(dir.clone()));
+        assert_eq!(recent.start_directory(DialogPurpose::OpenAnnotations), home_directory());
+
+        // Purposes are independent, and a later pick replaces an earlier one
+        let nested = dir.join("labels");
+        std::fs::create_dir_all(&nested).unwrap();
+        recent.remember(DialogPurpose::OpenImage, &nested.join("frame.png"));
+        assert_eq!(recent.start_directory(DialogPurpose::OpenImage), Some(nested.clone()));
+        assert_eq!(recent.start_directory(DialogPurpose::Export), Some(dir.clone()));
+
+        // A bare file name has no directory to remember
+        recent.remember(DialogPurpose::Export, Path::new("annotations.json"));
+        assert_eq!(recent.start_directory(DialogPurpose::Export), Some(dir.clone()));
+
+        // Removed directories fall back to home
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(recent.start_directory(DialogPurpose::OpenImage), home_directory());
+        assert_eq!(recent.directories.len(), 2);
+    }
+}
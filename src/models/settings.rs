@@ -0,0 +1,375 @@
+// Copyright (c) 2025, Jason Jenkins
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! User-adjustable application settings.
+//!
+//! This module holds display and behavior preferences that are independent
+//! of the loaded project.
+
+use crate::models::annotation::Point;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Application settings.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Opacity of the background image (0.0 = hidden, 1.0 = opaque)
+    pub image_opacity: f32,
+
+    /// Snap new vertices to neighbor vertices and follow their edges
+    pub borrow_edges: bool,
+
+    /// Snap new vertices onto the nearest edge of other annotations
+    pub snap_to_edges: bool,
+
+    /// Round placed and dragged vertices to whole pixels instead of keeping
+    /// sub-pixel precision
+    pub integer_pixels: bool,
+
+    /// Name new annotations after the class of the last one of the same type
+    /// when it was given a custom name
+    pub inherit_class: bool,
+
+    /// Capture radius (screen points) for vertex, edge, and close-polygon snapping
+    pub snap_radius: f32,
+
+    /// Width-to-height ratio the Box tool keeps while Shift is held
+    pub box_aspect_ratio: f64,
+
+    /// Longest pause (seconds) between the clicks of a double-click that closes a polygon
+    pub double_click_time: f64,
+
+    /// Place vertices continuously while dragging with a drawing tool
+    pub trace_mode: bool,
+
+    /// Minimum distance (screen points) between vertices placed by tracing
+    pub min_segment_length: f32,
+
+    /// Smooth (anti-aliased) annotation outlines
+    pub antialiasing: bool,
+
+    /// Shape of annotation line ends and joins
+    pub line_cap: LineCap,
+
+    /// Shape of vertex handles
+    pub handle_shape: HandleShape,
+
+    /// Length (screen points) of each dash, and of the gap after it, on dashed edges
+    pub dash_length: f32,
+
+    /// Rule deciding which parts of self-overlapping polygons are inside
+    pub fill_rule: FillRule,
+
+    /// Whether annotations keep full color or take on the image's display
+    /// adjustment (opacity)
+    pub overlay_compositing: OverlayCompositing,
+
+    /// Show the floating tool palette over the canvas
+    pub show_tool_palette: bool,
+
+    /// Screen position of the tool palette's top-left corner, once moved
+    pub tool_palette_pos: Option<[f32; 2]>,
+
+    /// Annotation colors picked by hand, most recent first
+    pub recent_colors: Vec<[u8; 4]>,
+
+    /// Label each vertex of the selected annotation with its index
+    pub show_vertex_indices: bool,
+
+    /// Draw annotation names on the canvas
+    pub show_labels: bool,
+
+    /// Draw each annotation's list number in a badge on the canvas
+    pub show_badges: bool,
+
+    /// Draw pixel axes with tick marks along the image's top and left edges
+    pub show_axes: bool,
+
+    /// Draw a labeled grid of major and minor pixel lines over the image
+    pub show_ruler_grid: bool,
+
+    /// Pixels between labeled major grid lines
+    pub ruler_major_spacing: u32,
+
+    /// Number of parts each major grid cell is split into by minor lines
+    pub ruler_subdivisions: u32,
+
+    /// Shade the image by how many annotations cover each part of it
+    pub show_heatmap: bool,
+
+    /// Number of heatmap cells across the image width
+    pub heatmap_resolution: usize,
+
+    /// Colors the heatmap runs through from sparse to dense
+    pub heatmap_ramp: ColorRamp,
+
+    /// Units coordinates are displayed and copied in
+    pub coordinate_unit: CoordinateUnit,
+
+    /// Vertex count above which an annotation is considered too complex
+    pub complexity_threshold: usize,
+
+    /// Vertex count above which annotations are drawn in a cheaper form:
+    /// one path for the edges, and vertex handles only while selected
+    pub lod_vertex_threshold: usize,
+
+    /// Tolerance (normalized units) used when simplifying complex annotations
+    pub simplify_tolerance: f64,
+
+    /// Drop vertices along straight edges from exported annotations
+    pub merge_collinear_on_export: bool,
+
+    /// Largest bend (degrees) at a vertex that still counts as straight
+    pub collinear_tolerance: f64,
+
+    /// Number the vertices in JSON and SVG exports, for debugging
+    pub export_vertex_indices: bool,
+
+    /// Load a same-named .json/.yaml annotation file when opening an image
+    pub load_sidecar: bool,
+
+    /// Distance (screen points) panned per arrow/WASD key press
+    pub pan_step: f32,
+
+    /// What to do with the previous session on startup
+    pub restore_session: SessionRestore,
+
+    /// Tool behavior after an annotation is finished
+    pub after_finish: AfterFinish,
+
+    /// Seconds between autosaves (0 disables autosave)
+    pub autosave_interval: u64,
+
+    /// File format for autosaves
+    pub autosave_format: AnnotationFormat,
+
+    /// Where autosave files are written
+    pub autosave_location: AutosaveLocation,
+
+    /// Directory used when `autosave_location` is `Folder`
+    pub autosave_folder: PathBuf,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            image_opacity: 1.0,
+            borrow_edges: false,
+            snap_to_edges: false,
+            integer_pixels: false,
+            inherit_class: true,
+            snap_radius: 12.0,
+            box_aspect_ratio: 1.0,
+            double_click_time: 0.3,
+            trace_mode: false,
+            min_segment_length: 8.0,
+            antialiasing: true,
+            line_cap: LineCap::Round,
+            handle_shape: HandleShape::Circle,
+            dash_length: 8.0,
+            fill_rule: FillRule::EvenOdd,
+            overlay_compositing: OverlayCompositing::AfterAdjustment,
+            show_tool_palette: false,
+            tool_palette_pos: None,
+            recent_colors: Vec::new(),
+            show_vertex_indices: false,
+            show_labels: false,
+            show_badges: false,
+            show_axes: false,
+            show_ruler_grid: false,
+            ruler_major_spacing: 100,
+            ruler_subdivisions: 4,
+            show_heatmap: false,
+            heatmap_resolution: 64,
+            heatmap_ramp: ColorRamp::Heat,
+            coordinate_unit: CoordinateUnit::Normalized,
+            complexity_threshold: 500,
+            lod_vertex_threshold: 5000,
+            simplify_tolerance: 0.001,
+            merge_collinear_on_export: false,
+            collinear_tolerance: 0.5,
+            export_vertex_indices: false,
+            load_sidecar: true,
+            pan_step: 50.0,
+            restore_session: SessionRestore::Ask,
+            after_finish: AfterFinish::Keep,
+            autosave_interval: 120,
+            autosave_format: AnnotationFormat::Json,
+            autosave_location: AutosaveLocation::TempDir,
+            autosave_folder: PathBuf::new(),
+        }
+    }
+}
+
+/// Order of the image display adjustment and the annotation overlay.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OverlayCompositing {
+    /// Annotations are drawn at full color over the adjusted image
+    AfterAdjustment,
+    /// Annotations are adjusted along with the image
+    WithAdjustment,
+}
+
+/// Rule deciding whether a point is inside a self-overlapping polygon.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FillRule {
+    /// Inside when a ray from the point crosses the boundary an odd number of times
+    EvenOdd,
+    /// Inside when the boundary winds around the point at all
+    NonZero,
+}
+
+impl FillRule {
+    /// Whether a point with the given winding number is inside.
+    pub fn contains(&self, winding: i32) -> bool {
+        match self {
+            FillRule::EvenOdd => winding % 2 != 0,
+            FillRule::NonZero => winding != 0,
+        }
+    }
+}
+
+/// Shape used for the ends and joins of annotation lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineCap {
+    Round,
+    Square,
+}
+
+/// Color scale for values from 0 (low) to 1 (high).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorRamp {
+    /// Blue through green and yellow to red
+    Heat,
+    /// Dark purple through teal to yellow, readable in grayscale
+    Viridis,
+    /// Dark to light gray
+    Grayscale,
+}
+
+/// Shape drawn at each annotation vertex.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HandleShape {
+    Circle,
+    Square,
+    Diamond,
+}
+
+/// Units used to display coordinates (storage is always normalized).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CoordinateUnit {
+    /// Fractions of the image size, 0..1
+    Normalized,
+    /// Image pixels
+    Pixel,
+}
+
+impl CoordinateUnit {
+    /// Short label for toggles.
+    pub fn label(&self) -> &'static str {
+        match self {
+            CoordinateUnit::Normalized => "0–1",
+            CoordinateUnit::Pixel => "px",
+        }
+    }
+
+    /// The other unit.
+    pub fn toggled(&self) -> Self {
+        match self {
+            CoordinateUnit::Normalized => CoordinateUnit::Pixel,
+            CoordinateUnit::Pixel => CoordinateUnit::Normalized,
+        }
+    }
+
+    /// Convert a normalized point to this unit.
+    pub fn convert(&self, point: &Point, image_size: (u32, u32)) -> (f64, f64) {
+        match self {
+            CoordinateUnit::Normalized => (point.x, point.y),
+            CoordinateUnit::Pixel => (point.x * image_size.0 as f64, point.y * image_size.1 as f64),
+        }
+    }
+
+    /// Format a normalized point for display, e.g. "0.250, 0.500" or
+    /// "160.0, 240.0 px".
+    pub fn format_point(&self, point: &Point, image_size: (u32, u32)) -> String {
+        let (x, y) = self.convert(point, image_size);
+        match self {
+            CoordinateUnit::Normalized => format!("{:.3}, {:.3}", x, y),
+            CoordinateUnit::Pixel => format!("{:.1}, {:.1} px", x, y),
+        }
+    }
+}
+
+/// Startup behavior for reopening the previous session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionRestore {
+    /// Start with an empty workspace
+    Off,
+    /// Ask before reopening
+    Ask,
+    /// Reopen automatically
+    Auto,
+}
+
+/// Tool behavior after an annotation is finished.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AfterFinish {
+    /// Keep the drawing tool; the next click starts a new annotation
+    Keep,
+    /// Keep the drawing tool and immediately start a new annotation
+    Continue,
+    /// Switch back to the Select tool
+    Select,
+}
+
+/// Annotation file format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AnnotationFormat {
+    Json,
+    Yaml,
+}
+
+impl AnnotationFormat {
+    /// File extension (without the dot).
+    pub fn extension(&self) -> &'static str {
+        match self {
+            AnnotationFormat::Json => "json",
+            AnnotationFormat::Yaml => "yaml",
+        }
+    }
+}
+
+/// Directory that autosave files are written to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AutosaveLocation {
+    /// Next to the media file
+    BesideMedia,
+    /// In the system temp directory
+    TempDir,
+    /// In a user-chosen folder
+    Folder,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_point() {
+        let point = Point::new(0.25, 0.5);
+        assert_eq!(CoordinateUnit::Normalized.format_point(&point, (640, 480)), "0.250, 0.500");
+        assert_eq!(CoordinateUnit::Pixel.format_point(&point, (640, 480)), "160.0, 240.0 px");
+        assert_eq!(CoordinateUnit::Pixel.toggled(), CoordinateUnit::Normalized);
+    }
+}
@@ -0,0 +1,96 @@
+// Copyright (c) 2025, Jason Jenkins
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Vertex selection.
+//!
+//! This module tracks the vertices of one annotation picked for bulk
+//! editing, e.g. deleting a run of vertices from a dense polygon.
+
+use std::collections::BTreeSet;
+
+/// Vertices picked from a single annotation.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VertexSelection {
+    /// Annotation the vertices belong to
+    annotation: Option<usize>,
+    vertices: BTreeSet<usize>,
+    /// Vertex that Shift-clicks extend a range from
+    anchor: Option<usize>,
+}
+
+impl VertexSelection {
+    /// Selected vertices of an annotation, in index order.
+    pub fn vertices_of(&self, annotation: usize) -> Vec<usize> {
+        if self.annotation == Some(annotation) {
+            self.vertices.iter().copied().collect()
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Check if a vertex of an annotation is selected.
+    pub fn contains(&self, annotation: usize, vertex: usize) -> bool {
+        self.annotation == Some(annotation) && self.vertices.contains(&vertex)
+    }
+
+    /// Update the selection for a click on a vertex.
+    ///
+    /// A plain click selects just that vertex, `toggle` (Ctrl) adds or
+    /// removes it, and `extend` (Shift) selects the range from the last
+    /// clicked vertex. Clicking another annotation's vertex starts over.
+    pub fn click(&mut self, annotation: usize, vertex: usize, extend: bool, toggle: bool) {
+        if self.annotation != Some(annotation) {
+            self.clear();
+            self.annotation = Some(annotation);
+        }
+
+        match (extend, self.anchor) {
+            (true, Some(anchor)) => {
+                self.vertices = (anchor.min(vertex)..=anchor.max(vertex)).collect();
+                return;
+            }
+            _ if toggle => {
+                if !self.vertices.remove(&vertex) {
+                    self.vertices.insert(vertex);
+                }
+            }
+            _ => self.vertices = BTreeSet::from([vertex]),
+        }
+        self.anchor = Some(vertex);
+    }
+
+    /// Deselect every vertex.
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vertex_selection_clicks() {
+        let mut selection = VertexSelection::default();
+        selection.click(2, 1, false, false);
+        assert_eq!(selection.vertices_of(2), [1]);
+        assert!(selection.vertices_of(0).is_empty());
+
+        // Shift selects a range from the last click, either direction
+        selection.click(2, 4, true, false);
+        assert_eq!(selection.vertices_of(2), [1, 2, 3, 4]);
+        selection.click(2, 0, true, false);
+        assert_eq!(selection.vertices_of(2), [0, 1]);
+
+        // Ctrl toggles single vertices
+        selection.click(2, 5, false, true);
+        selection.click(2, 0, false, true);
+        assert_eq!(selection.vertices_of(2), [1, 5]);
+        assert!(selection.contains(2, 5) && !selection.contains(3, 5));
+
+        // Another annotation starts a new selection
+        selection.click(3, 2, true, false);
+        assert_eq!(selection.vertices_of(3), [2]);
+        assert!(selection.vertices_of(2).is_empty());
+    }
+}
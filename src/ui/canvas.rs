@@ -7,21 +7,103 @@
 //! images and draw polygons and lines for region annotation.
 
 use crate::app::Tool;
-use crate::models::{annotation::{Annotation, Point}, project::ProjectData};
+use crate::ui::{labels, render_cache::{self, RenderCache}, view::ViewTransform};
+use crate::util::{color, geometry};
+use crate::models::{
+    annotation::{Annotation, AnnotationRole, AnnotationType, LineStyle, Point},
+    layers::Layers,
+    project::ProjectData,
+    settings::{ColorRamp, FillRule, HandleShape, LineCap, OverlayCompositing, Settings},
+};
+
+/// Distance (in normalized coordinates) within which a click hits a vertex.
+pub const VERTEX_HIT_THRESHOLD: f64 = 0.02;
+
+/// Annotation outline width (screen points) at zoom 1.
+const BASE_STROKE_WIDTH: f32 = 2.0;
+
+/// Thinnest outline drawn when zoomed in.
+const MIN_STROKE_WIDTH: f32 = 1.0;
+
+/// Thickest outline drawn when zoomed out.
+const MAX_STROKE_WIDTH: f32 = 4.0;
+
+/// Color of the comparison annotation set.
+const REFERENCE_COLOR: egui::Color32 = egui::Color32::from_rgb(255, 0, 255);
+
+/// Screen-space distance between recorded lasso points.
+const LASSO_SPACING: f32 = 4.0;
+
+/// Screen-space distance between hatch lines on excluded regions.
+const HATCH_SPACING: f32 = 8.0;
+
+/// Screen-space distance from an oriented box's edge to its rotation knob.
+const ROTATION_HANDLE_LENGTH: f32 = 24.0;
+
+/// Screen-space radius of the rotation knob.
+const ROTATION_HANDLE_RADIUS: f32 = 5.0;
+
+/// Screen-space radius of vertex handles.
+const VERTEX_HANDLE_RADIUS: f32 = 4.0;
+
+/// Screen-space radius of the selected annotation's vertex handles.
+const SELECTED_VERTEX_HANDLE_RADIUS: f32 = 5.5;
+
+/// Screen-space radius of the ring marking a vertex held at the image border.
+const CLAMP_RING_RADIUS: f32 = 9.0;
+
+/// Color of the clamped-vertex ring and status text.
+const CLAMP_COLOR: egui::Color32 = egui::Color32::from_rgb(255, 80, 80);
+
+/// Smallest screen-space distance between drawn ruler grid lines, and
+/// between labeled major lines.
+const RULER_MIN_LINE_SPACING: f32 = 4.0;
+const RULER_MIN_LABEL_SPACING: f32 = 40.0;
+
+/// Opacity of the sparsest and densest heatmap cells.
+const HEATMAP_MIN_ALPHA: u8 = 60;
+const HEATMAP_MAX_ALPHA: u8 = 180;
+
+/// Smallest screen-space distance between labeled axis ticks.
+const AXIS_TICK_SPACING: f32 = 60.0;
+
+/// Screen-space thickness of the axis bands holding the tick labels.
+const AXIS_BAND_WIDTH: f32 = 18.0;
+
+/// Screen-space gap between a badge number and the edge of its disk.
+const BADGE_PADDING: f32 = 3.0;
+
+/// Screen-space length of the north arrow and its gap from the canvas corner.
+const NORTH_ARROW_LENGTH: f32 = 40.0;
+const NORTH_ARROW_MARGIN: f32 = 16.0;
+
+/// Screen-space distance within which two clicks count as a double-click.
+const DOUBLE_CLICK_DISTANCE: f32 = 6.0;
 
 /// Result of canvas interaction.
 pub enum CanvasAction {
     None,
     AddVertex(Point),
+    TraceVertices(Vec<Point>),
+    StopTracing,
     FinishAnnotation,
     SelectAnnotation(usize),
     DeselectAnnotation,
     StartDraggingVertex(usize, usize), // (annotation_index, vertex_index)
     DragVertex(Point),
     StopDragging,
+    DragSegment(Point, Point), // (start, end) for the Measure and Split tools
+    Split(Point, Point),       // (start, end) of a finished cut line
+    Weld(usize, usize, usize), // (annotation_index, first_vertex, second_vertex)
+    Stamp(Point),              // Where to place the active template
+    CreateBox(Point, Point),   // (center, corner) of a dragged-out box
+    StartRotatingBox(usize),   // Annotation whose rotation handle was grabbed
+    RotateBox(Point),          // Point the rotation handle is dragged to
+    LassoSelect(Vec<Point>),   // Closed loop drawn in Select mode
 }
 
 /// Display the main canvas area and handle mouse interactions.
+#[allow(clippy::too_many_arguments)]
 pub fn show(
     ui: &mut egui::Ui,
     project: &Option<ProjectData>,
@@ -30,9 +112,30 @@ pub fn show(
     image_size: Option<(u32, u32)>,
     in_progress_annotation: &Option<Annotation>,
     selected_annotation: Option<usize>,
+    lasso_selected: &[usize],
+    selected_vertices: &[usize],
     dragging_vertex: Option<(usize, usize)>,
+    rotating_box: Option<usize>,
+    drag_segment: Option<(Point, Point)>,
+    folder_position: Option<(usize, usize)>,
+    settings: &Settings,
+    layers: &Layers,
+    isolate: bool,
+    hide_overlays: bool,
+    reference: Option<&ProjectData>,
+    offset_preview: Option<&[Point]>,
+    view: &mut ViewTransform,
+    render_cache: &mut RenderCache,
+    heatmap: Option<&egui::TextureHandle>,
 ) -> CanvasAction {
+    // Annotations that are drawn and can be hit
+    let shown = |idx: usize, annotation: &Annotation| is_shown(idx, annotation, layers, selected_annotation, isolate);
+
     let mut action = CanvasAction::None;
+    // Image position under the mouse, shown in the status bar
+    let mut cursor: Option<Point> = None;
+    // Border position a dragged vertex is held at while the pointer is off the image
+    let mut clamped_drag: Option<Point> = None;
     // Set background color
     ui.style_mut().visuals.extreme_bg_color = egui::Color32::from_gray(40);
 
@@ -62,30 +165,74 @@ pub fn show(
                     (width, height)
                 };
 
-                // Center the image
-                let x_offset = (available.x - display_width) / 2.0;
-                let y_offset = (available.y - display_height) / 2.0;
+                let canvas_rect = egui::Rect::from_min_size(ui.min_rect().min, available);
+                let fit_size = egui::vec2(display_width, display_height);
 
-                let image_rect = egui::Rect::from_min_size(
-                    ui.min_rect().min + egui::vec2(x_offset, y_offset),
-                    egui::vec2(display_width, display_height),
-                );
+                // Zoom with Ctrl+scroll / pinch about the cursor, pan with scroll
+                if let Some(hover) = ui.ctx().pointer_hover_pos().filter(|p| canvas_rect.contains(*p)) {
+                    let (zoom_delta, scroll_delta) = ui.input(|i| (i.zoom_delta(), i.smooth_scroll_delta));
+                    if zoom_delta != 1.0 {
+                        view.zoom_about(zoom_delta, hover - canvas_rect.center());
+                    } else if scroll_delta != egui::Vec2::ZERO {
+                        view.pan_by(scroll_delta);
+                    }
+                }
+                view.apply_pending_frame(fit_size, available);
+                view.clamp_pan(fit_size * view.zoom, available);
+                view.visible = view.visible_region(canvas_rect, fit_size);
+                view.fit_scale = display_width / img_width as f32;
+                let snap_threshold = view.normalized_distance(settings.snap_radius, (img_width, img_height));
+
+                // Position the (zoomed and panned) image
+                let image_rect = view.image_rect(canvas_rect, fit_size);
+                let (display_width, display_height) = (image_rect.width(), image_rect.height());
 
-                // Draw the image
+                // Draw the image (tinted by the opacity setting so the background shows through)
+                ui.set_clip_rect(canvas_rect.intersect(ui.clip_rect()));
                 ui.painter().image(
                     texture.id(),
                     image_rect,
                     egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0)),
-                    egui::Color32::WHITE,
+                    egui::Color32::WHITE.gamma_multiply(settings.image_opacity.clamp(0.0, 1.0)),
                 );
 
                 // Handle mouse interactions
-                let response = ui.allocate_rect(image_rect, egui::Sense::click_and_drag());
+                let response = ui.allocate_rect(image_rect.intersect(canvas_rect), egui::Sense::click_and_drag());
+                cursor = response.hover_pos().filter(|pos| image_rect.contains(*pos)).map(|pos| {
+                    Point::new(((pos.x - image_rect.min.x) / display_width) as f64, ((pos.y - image_rect.min.y) / display_height) as f64)
+                });
+
+                // First vertex picked by the Weld tool
+                let mut weld_anchor: Option<(usize, usize)> = None;
 
-                if current_tool == Tool::Select {
+                // Grabbing the selected box's rotation handle starts turning it
+                let grabbed_handle = selected_annotation.filter(|_| response.drag_started() && matches!(current_tool, Tool::Select | Tool::OrientedBox)).filter(|&idx| {
+                    let handle = project.as_ref().and_then(|p| p.annotations.get(idx)).and_then(|a| rotation_handle(a, &image_rect));
+                    let pointer = response.interact_pointer_pos();
+                    matches!((handle, pointer), (Some((_, knob)), Some(pos)) if knob.distance(pos) <= ROTATION_HANDLE_RADIUS * 2.0)
+                });
+
+                if let Some(idx) = rotating_box.or(grabbed_handle) {
+                    // Rotate mode: the handle points from the box center to the cursor
+                    if response.drag_started() {
+                        action = CanvasAction::StartRotatingBox(idx);
+                    } else if let (true, Some(pos)) = (response.dragged(), response.interact_pointer_pos()) {
+                        action = CanvasAction::RotateBox(Point::new(
+                            ((pos.x - image_rect.min.x) / display_width) as f64,
+                            ((pos.y - image_rect.min.y) / display_height) as f64,
+                        ));
+                    } else if response.drag_stopped() {
+                        action = CanvasAction::StopDragging;
+                    }
+                } else if current_tool == Tool::Select {
                     // Select mode: handle annotation/vertex selection and dragging
                     if let Some(pos) = response.interact_pointer_pos() {
-                        if image_rect.contains(pos) {
+                        if dragging_vertex.is_some() && !response.drag_started() && (response.dragged() || response.drag_stopped()) {
+                            // Continue dragging, holding the vertex on the border when the pointer leaves the image
+                            let (point, clamped) = clamp_to_image(pos, &image_rect);
+                            action = if response.drag_stopped() { CanvasAction::StopDragging } else { CanvasAction::DragVertex(point) };
+                            clamped_drag = clamped.then_some(point).filter(|_| response.dragged());
+                        } else if image_rect.contains(pos) {
                             let rel_x = (pos.x - image_rect.min.x) / display_width;
                             let rel_y = (pos.y - image_rect.min.y) / display_height;
                             let click_point = Point::new(rel_x as f64, rel_y as f64);
@@ -94,15 +241,15 @@ pub fn show(
                                 // Check if clicking on a vertex
                                 if let Some(proj) = project {
                                     for (ann_idx, annotation) in proj.annotations.iter().enumerate() {
-                                        if let Some(vertex_idx) = annotation.find_vertex_within_threshold(&click_point, 0.02) {
+                                        if !shown(ann_idx, annotation) {
+                                            continue;
+                                        }
+                                        if let Some(vertex_idx) = annotation.find_vertex_within_threshold(&click_point, VERTEX_HIT_THRESHOLD) {
                                             action = CanvasAction::StartDraggingVertex(ann_idx, vertex_idx);
                                             break;
                                         }
                                     }
                                 }
-                            } else if response.dragged() && dragging_vertex.is_some() {
-                                // Continue dragging
-                                action = CanvasAction::DragVertex(click_point);
                             } else if response.drag_stopped() {
                                 action = CanvasAction::StopDragging;
                             } else if response.clicked() {
@@ -110,7 +257,10 @@ pub fn show(
                                 let mut found_annotation = false;
                                 if let Some(proj) = project {
                                     for (ann_idx, annotation) in proj.annotations.iter().enumerate() {
-                                        if let Some(_) = annotation.find_vertex_within_threshold(&click_point, 0.02) {
+                                        if !shown(ann_idx, annotation) {
+                                            continue;
+                                        }
+                                        if annotation.has_vertex_within(&click_point, VERTEX_HIT_THRESHOLD) {
                                             action = CanvasAction::SelectAnnotation(ann_idx);
                                             found_annotation = true;
                                             break;
@@ -124,45 +274,332 @@ pub fn show(
                             }
                         }
                     }
+
+                    // A drag that doesn't grab a vertex draws a lasso loop
+                    let lasso_id = ui.id().with("lasso");
+                    let mut lasso: Option<Vec<Point>> = ui.data(|d| d.get_temp(lasso_id));
+                    let to_image = |pos: egui::Pos2| {
+                        Point::new(
+                            ((pos.x - image_rect.min.x) / display_width).clamp(0.0, 1.0) as f64,
+                            ((pos.y - image_rect.min.y) / display_height).clamp(0.0, 1.0) as f64,
+                        )
+                    };
+                    if let Some(pos) = response.interact_pointer_pos() {
+                        if response.drag_started() && !matches!(action, CanvasAction::StartDraggingVertex(..)) {
+                            lasso = Some(vec![to_image(pos)]);
+                        } else if let (true, Some(path)) = (response.dragged(), lasso.as_mut()) {
+                            let last = path.last().map(|p| {
+                                egui::pos2(image_rect.min.x + p.x as f32 * display_width, image_rect.min.y + p.y as f32 * display_height)
+                            });
+                            if last.is_none_or(|last| last.distance(pos) >= LASSO_SPACING) {
+                                path.push(to_image(pos));
+                            }
+                        }
+                    }
+                    if response.drag_stopped() {
+                        if let Some(path) = lasso.take().filter(|path| path.len() >= 3) {
+                            action = CanvasAction::LassoSelect(path);
+                        }
+                    }
+                    if let Some(ref path) = lasso {
+                        let mut points: Vec<egui::Pos2> = path
+                            .iter()
+                            .map(|p| egui::pos2(image_rect.min.x + p.x as f32 * display_width, image_rect.min.y + p.y as f32 * display_height))
+                            .collect();
+                        if let Some(&first) = points.first() {
+                            points.push(first);
+                        }
+                        ui.painter().extend(egui::Shape::dashed_line(&points, egui::Stroke::new(1.5, egui::Color32::WHITE), 4.0, 4.0));
+                    }
+                    ui.data_mut(|d| d.insert_temp(lasso_id, lasso));
+                } else if matches!(current_tool, Tool::Measure | Tool::Split | Tool::OrientedBox) {
+                    // Measure, split, and box modes: drag a segment, Shift constrains it
+                    if let Some(pos) = response.interact_pointer_pos() {
+                        let rel_x = ((pos.x - image_rect.min.x) / display_width).clamp(0.0, 1.0);
+                        let rel_y = ((pos.y - image_rect.min.y) / display_height).clamp(0.0, 1.0);
+                        let point = Point::new(rel_x as f64, rel_y as f64);
+
+                        if response.drag_started() {
+                            action = CanvasAction::DragSegment(point, point);
+                        } else if let (true, Some((start, _))) = (response.dragged(), drag_segment) {
+                            let shift = ui.input(|i| i.modifiers.shift);
+                            let end = if shift && current_tool == Tool::OrientedBox {
+                                // Boxes keep the configured aspect ratio instead
+                                let (w, h) = (img_width as f64, img_height as f64);
+                                let (x, y) = geometry::constrain_rect_aspect((start.x * w, start.y * h), (point.x * w, point.y * h), settings.box_aspect_ratio);
+                                Point::new(x / w, y / h)
+                            } else if shift {
+                                geometry::constrain_segment(&start, &point, (img_width, img_height))
+                            } else {
+                                point
+                            };
+                            action = CanvasAction::DragSegment(start, end);
+                        } else if let (true, Some((start, end))) = (response.drag_stopped(), drag_segment) {
+                            match current_tool {
+                                Tool::Split => action = CanvasAction::Split(start, end),
+                                Tool::OrientedBox => action = CanvasAction::CreateBox(start, end),
+                                _ => {}
+                            }
+                        }
+                    }
+                } else if current_tool == Tool::Weld {
+                    // Weld mode: click a vertex, then an adjacent vertex of the same annotation
+                    let anchor_id = ui.id().with("weld_anchor");
+                    weld_anchor = ui
+                        .data(|d| d.get_temp::<Option<(usize, usize)>>(anchor_id))
+                        .flatten()
+                        .filter(|&(ann_idx, _)| selected_annotation == Some(ann_idx));
+                    if let (true, Some(click_point), Some(proj)) = (response.clicked(), cursor, project) {
+                        // The selected annotation's vertices take priority over overlapping ones
+                        let hit = selected_annotation
+                            .into_iter()
+                            .chain(0..proj.annotations.len())
+                            .filter_map(|idx| proj.annotations.get(idx).map(|annotation| (idx, annotation)))
+                            .filter(|&(idx, annotation)| shown(idx, annotation))
+                            .find_map(|(idx, annotation)| annotation.find_vertex_within_threshold(&click_point, VERTEX_HIT_THRESHOLD).map(|v| (idx, v)));
+                        match (weld_anchor, hit) {
+                            (Some((ann_idx, first)), Some((idx, second))) if idx == ann_idx && first != second => {
+                                action = CanvasAction::Weld(ann_idx, first, second);
+                                weld_anchor = None;
+                            }
+                            (_, Some((idx, vertex))) => {
+                                action = CanvasAction::SelectAnnotation(idx);
+                                weld_anchor = Some((idx, vertex));
+                            }
+                            (_, None) => {
+                                action = CanvasAction::DeselectAnnotation;
+                                weld_anchor = None;
+                            }
+                        }
+                    }
+                    ui.data_mut(|d| d.insert_temp(anchor_id, weld_anchor));
+                } else if current_tool == Tool::Stamp {
+                    // Stamp mode: each click places a copy of the active template
+                    if let (true, Some(click_point)) = (response.clicked(), cursor) {
+                        action = CanvasAction::Stamp(click_point);
+                    }
                 } else {
-                    // Drawing mode: add vertices
+                    // Drawing mode: find the edge snap target under the cursor
+                    let snap = if settings.snap_to_edges {
+                        response.hover_pos().or(response.interact_pointer_pos()).and_then(|pos| {
+                            let rel_x = (pos.x - image_rect.min.x) / display_width;
+                            let rel_y = (pos.y - image_rect.min.y) / display_height;
+                            snap_to_edge(project, shown, &Point::new(rel_x as f64, rel_y as f64), snap_threshold)
+                        })
+                    } else {
+                        None
+                    };
+
+                    // Trace mode: place vertices at fixed spacing while dragging
+                    let tracing = settings.trace_mode && matches!(current_tool, Tool::Polygon | Tool::Line);
+                    if tracing && (response.drag_started() || response.dragged()) {
+                        if let Some(pos) = response.interact_pointer_pos().filter(|pos| image_rect.contains(*pos)) {
+                            let to_screen = |p: &Point| {
+                                egui::pos2(image_rect.min.x + p.x as f32 * display_width, image_rect.min.y + p.y as f32 * display_height)
+                            };
+                            let last = if response.drag_started() {
+                                None
+                            } else {
+                                in_progress_annotation.as_ref().and_then(|a| a.vertices.0.last()).map(to_screen)
+                            };
+                            let points: Vec<Point> = trace_samples(last, pos, settings.min_segment_length)
+                                .into_iter()
+                                .map(|p| {
+                                    let rel_x = (p.x - image_rect.min.x) / display_width;
+                                    let rel_y = (p.y - image_rect.min.y) / display_height;
+                                    Point::new(rel_x as f64, rel_y as f64)
+                                })
+                                .collect();
+                            if !points.is_empty() {
+                                action = CanvasAction::TraceVertices(points);
+                            }
+                        }
+                    } else if tracing && response.drag_stopped() {
+                        action = CanvasAction::StopTracing;
+                    }
+
+                    // Add vertices (on the snapped edge point if any)
                     if response.clicked() {
                         if let Some(pos) = response.interact_pointer_pos() {
-                            if image_rect.contains(pos) {
+                            // A second click soon after and near the first closes the polygon
+                            let click_id = ui.id().with("last_click");
+                            let click = (ui.input(|i| i.time), pos);
+                            let previous = ui.data(|d| d.get_temp::<Option<(f64, egui::Pos2)>>(click_id)).flatten();
+                            let double = current_tool == Tool::Polygon
+                                && is_double_click(previous, click, settings.double_click_time);
+                            ui.data_mut(|d| d.insert_temp(click_id, if double { None } else { Some(click) }));
+
+                            if double {
+                                action = CanvasAction::FinishAnnotation;
+                            } else if image_rect.contains(pos) {
                                 let rel_x = (pos.x - image_rect.min.x) / display_width;
                                 let rel_y = (pos.y - image_rect.min.y) / display_height;
-                                action = CanvasAction::AddVertex(Point::new(
-                                    rel_x as f64,
-                                    rel_y as f64,
-                                ));
+                                let mut point = Point::new(rel_x as f64, rel_y as f64);
+
+                                // Shift constrains the new edge to 45 degree steps
+                                let last_vertex = in_progress_annotation.as_ref().and_then(|a| a.vertices.0.last());
+                                if let (true, Some(last)) = (ui.input(|i| i.modifiers.shift), last_vertex) {
+                                    point = geometry::constrain_segment(last, &point, (img_width, img_height));
+                                }
+
+                                // Clicking near the first vertex closes the polygon
+                                let closes = in_progress_annotation.as_ref().is_some_and(|a| {
+                                    a.annotation_type == AnnotationType::Polygon
+                                        && a.can_finish()
+                                        && a.vertices.0.first().is_some_and(|first| first.distance(&point) <= snap_threshold)
+                                });
+                                action = if closes {
+                                    CanvasAction::FinishAnnotation
+                                } else {
+                                    CanvasAction::AddVertex(snap.unwrap_or(point))
+                                };
                             }
                         }
                     }
 
-                    if response.double_clicked() && current_tool == Tool::Polygon {
-                        action = CanvasAction::FinishAnnotation;
+                    // Mark the snap target on the edge
+                    if let Some(point) = snap {
+                        let pos = egui::pos2(
+                            image_rect.min.x + point.x as f32 * display_width,
+                            image_rect.min.y + point.y as f32 * display_height,
+                        );
+                        ui.painter().circle_stroke(pos, 6.0, egui::Stroke::new(2.0, egui::Color32::YELLOW));
+                    }
+
+                    // Show the snap capture zone around the cursor
+                    if settings.snap_to_edges || settings.borrow_edges {
+                        if let Some(pos) = response.hover_pos() {
+                            ui.painter().circle_stroke(pos, settings.snap_radius, egui::Stroke::new(1.0, egui::Color32::from_white_alpha(60)));
+                        }
                     }
                 }
 
                 // Draw annotations on top of the image
                 let painter = ui.painter();
 
-                // Draw completed annotations
-                if let Some(proj) = project {
-                    for (idx, annotation) in proj.annotations.iter().enumerate() {
-                        let is_selected = selected_annotation == Some(idx);
-                        let color = if is_selected {
-                            egui::Color32::from_rgb(0, 255, 0) // Green for selected
-                        } else {
-                            egui::Color32::YELLOW
+                // Holding the hide-overlays key shows the bare image
+                if let (Some(heatmap), false) = (heatmap, hide_overlays) {
+                    painter.image(
+                        heatmap.id(),
+                        image_rect,
+                        egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0)),
+                        egui::Color32::WHITE,
+                    );
+                }
+                if !hide_overlays {
+                    // Draw completed annotations, rebuilding their shapes only when
+                    // the annotations, selection, visibility, or view changed
+                    if let Some(proj) = project {
+                        let key = render_cache::cache_key(&proj.annotations, selected_annotation, lasso_selected, shown, image_rect, (img_width, img_height), settings);
+                        let shapes = render_cache.get_or_build(key, || {
+                            let mut shapes = Vec::new();
+                            for (idx, annotation) in proj.annotations.iter().enumerate() {
+                                if !shown(idx, annotation) {
+                                    continue;
+                                }
+                                let is_selected = selected_annotation == Some(idx);
+                                let color = if is_selected || lasso_selected.contains(&idx) {
+                                    egui::Color32::from_rgb(0, 255, 0) // Green for selected
+                                } else {
+                                    let [r, g, b, a] = annotation.display_color();
+                                    egui::Color32::from_rgba_unmultiplied(r, g, b, a)
+                                };
+                                let color = color.gamma_multiply(overlay_opacity(settings));
+                                draw_annotation(&mut shapes, painter, annotation, &image_rect, color, false, is_selected.then_some((img_width, img_height)), settings, view.zoom);
+                            }
+                            shapes
+                        });
+                        painter.extend(shapes.iter().cloned());
+
+                        // Ring the vertices picked in the properties table
+                        let picked = selected_annotation.and_then(|idx| proj.annotations.get(idx)).map_or(&[][..], |a| &a.vertices.0[..]);
+                        for vertex in selected_vertices.iter().filter_map(|&i| picked.get(i)).filter(|v| v.is_finite()) {
+                            let center = egui::pos2(image_rect.min.x + vertex.x as f32 * image_rect.width(), image_rect.min.y + vertex.y as f32 * image_rect.height());
+                            painter.circle_stroke(center, SELECTED_VERTEX_HANDLE_RADIUS + 3.0, egui::Stroke::new(2.0, egui::Color32::YELLOW));
+                        }
+                    }
+
+                    // Draw the comparison set dashed over the current annotations
+                    if let Some(reference) = reference {
+                        draw_reference(painter, reference, &image_rect);
+                    }
+
+                    // Draw the pending polygon offset dashed
+                    if let Some(vertices) = offset_preview {
+                        let mut points: Vec<egui::Pos2> = vertices
+                            .iter()
+                            .map(|p| egui::pos2(image_rect.min.x + p.x as f32 * image_rect.width(), image_rect.min.y + p.y as f32 * image_rect.height()))
+                            .collect();
+                        if let Some(&first) = points.first() {
+                            points.push(first);
+                        }
+                        painter.extend(egui::Shape::dashed_line(&points, egui::Stroke::new(2.0, egui::Color32::YELLOW), 6.0, 4.0));
+                    }
+
+                    // Ring the dragged vertex while it is held at the image border
+                    if let Some(point) = clamped_drag {
+                        let center = egui::pos2(image_rect.min.x + point.x as f32 * image_rect.width(), image_rect.min.y + point.y as f32 * image_rect.height());
+                        painter.circle_stroke(center, CLAMP_RING_RADIUS, egui::Stroke::new(2.0, CLAMP_COLOR));
+                    }
+
+                    // Draw name labels and number badges, laid out to avoid overlaps
+                    if let (Some(proj), true) = (project, settings.show_labels || settings.show_badges) {
+                        draw_labels(painter, proj, shown, &image_rect, &canvas_rect.intersect(image_rect), settings.show_labels, settings.show_badges);
+                    }
+
+                    // Draw in-progress annotation (live, never cached)
+                    if let Some(annotation) = in_progress_annotation {
+                        let mut shapes = Vec::new();
+                        let color = egui::Color32::LIGHT_BLUE.gamma_multiply(overlay_opacity(settings));
+                        draw_annotation(&mut shapes, painter, annotation, &image_rect, color, true, None, settings, view.zoom);
+                        painter.extend(shapes);
+                    }
+                }
+
+                if settings.show_ruler_grid {
+                    draw_ruler_grid(painter, &image_rect, (img_width, img_height), &canvas_rect, settings);
+                }
+                if settings.show_axes {
+                    draw_axes(painter, &image_rect, (img_width, img_height), &canvas_rect);
+                }
+                if let Some(north) = project.as_ref().and_then(|p| p.north_offset_deg) {
+                    draw_north_arrow(painter, &canvas_rect, north);
+                }
+
+                // Draw the measurement or cut line
+                match (current_tool, drag_segment) {
+                    (Tool::Measure, Some((start, end))) => {
+                        draw_measurement(painter, &start, &end, &image_rect, (img_width, img_height));
+                    }
+                    (Tool::Split, Some((start, end))) => {
+                        let to_screen = |p: &Point| {
+                            egui::pos2(image_rect.min.x + p.x as f32 * display_width, image_rect.min.y + p.y as f32 * display_height)
                         };
-                        draw_annotation(painter, annotation, &image_rect, color, false, is_selected);
+                        painter.extend(egui::Shape::dashed_line(
+                            &[to_screen(&start), to_screen(&end)],
+                            egui::Stroke::new(2.0, egui::Color32::RED),
+                            6.0,
+                            4.0,
+                        ));
                     }
+                    (Tool::OrientedBox, Some((center, corner))) => {
+                        let to_screen = |x: f64, y: f64| {
+                            egui::pos2(image_rect.min.x + x as f32 * display_width, image_rect.min.y + y as f32 * display_height)
+                        };
+                        let (dx, dy) = (corner.x - center.x, corner.y - center.y);
+                        let outline: Vec<egui::Pos2> = [(-dx, -dy), (dx, -dy), (dx, dy), (-dx, dy), (-dx, -dy)]
+                            .iter()
+                            .map(|(x, y)| to_screen(center.x + x, center.y + y))
+                            .collect();
+                        painter.extend(egui::Shape::dashed_line(&outline, egui::Stroke::new(2.0, egui::Color32::LIGHT_BLUE), 6.0, 4.0));
+                    }
+                    _ => {}
                 }
 
-                // Draw in-progress annotation
-                if let Some(annotation) = in_progress_annotation {
-                    draw_annotation(painter, annotation, &image_rect, egui::Color32::LIGHT_BLUE, true, false);
+                // Ring the vertex waiting to be welded
+                if let Some(vertex) = weld_anchor.and_then(|(ann_idx, v)| project.as_ref()?.annotations.get(ann_idx)?.vertices.0.get(v)) {
+                    let pos = egui::pos2(image_rect.min.x + vertex.x as f32 * display_width, image_rect.min.y + vertex.y as f32 * display_height);
+                    painter.circle_stroke(pos, 8.0, egui::Stroke::new(2.0, egui::Color32::from_rgb(255, 140, 0)));
                 }
             }
         } else if project.is_some() {
@@ -210,56 +647,616 @@ pub fn show(
         ui.label(format!("Current tool: {:?}", current_tool));
         if project.is_some() {
             ui.separator();
-            ui.label("Ready");
+            match in_progress_annotation {
+                Some(annotation) if annotation.exceeds_vertex_limit(settings.complexity_threshold) => {
+                    ui.label(
+                        egui::RichText::new(format!(
+                            "⚠ {} vertices - consider simplifying",
+                            annotation.vertex_count()
+                        ))
+                        .color(egui::Color32::from_rgb(255, 200, 80)),
+                    );
+                }
+                _ => {
+                    ui.label("Ready");
+                }
+            }
         } else {
             ui.separator();
             ui.label("No file loaded");
         }
+
+        // Position within an open folder (e.g. "image 12 / 340") and isolate mode
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            if let Some((current, total)) = folder_position {
+                ui.label(format!("image {} / {}", current, total));
+            }
+            if let (Some(point), Some(size)) = (clamped_drag, image_size) {
+                ui.label(egui::RichText::new(format!("Clamped to {}", settings.coordinate_unit.format_point(&point, size))).color(CLAMP_COLOR))
+                    .on_hover_text("The pointer is outside the image, so the vertex is held at its border");
+            } else if let (Some(point), Some(size)) = (cursor, image_size) {
+                ui.label(settings.coordinate_unit.format_point(&point, size));
+            }
+            if lasso_selected.len() > 1 {
+                ui.label(format!("{} selected", lasso_selected.len()));
+            }
+            if hide_overlays {
+                ui.label(egui::RichText::new("Overlays hidden").weak().italics());
+            }
+            if isolate {
+                ui.label(egui::RichText::new("◉ Isolated").color(egui::Color32::from_rgb(255, 200, 80)))
+                    .on_hover_text("Only the selected annotation is shown");
+            }
+        });
     });
 
-    action
+    match image_size {
+        Some(size) if settings.integer_pixels => round_action_to_pixels(action, size),
+        _ => action,
+    }
+}
+
+/// Round the vertices an action places or moves to whole pixels.
+fn round_action_to_pixels(action: CanvasAction, image_size: (u32, u32)) -> CanvasAction {
+    let round = |point: Point| geometry::round_to_pixel(&point, image_size);
+    match action {
+        CanvasAction::AddVertex(point) => CanvasAction::AddVertex(round(point)),
+        CanvasAction::DragVertex(point) => CanvasAction::DragVertex(round(point)),
+        CanvasAction::Stamp(point) => CanvasAction::Stamp(round(point)),
+        CanvasAction::CreateBox(center, corner) => CanvasAction::CreateBox(round(center), round(corner)),
+        CanvasAction::RotateBox(point) => CanvasAction::RotateBox(round(point)),
+        CanvasAction::TraceVertices(points) => {
+            let mut points: Vec<Point> = points.into_iter().map(round).collect();
+            // Trace samples closer than a pixel collapse onto the same one
+            points.dedup();
+            CanvasAction::TraceVertices(points)
+        }
+        other => other,
+    }
+}
+
+/// Color a density grid (see [`crate::util::stats::density_grid`]) along a ramp, with
+/// empty cells transparent and denser cells more opaque.
+pub fn heatmap_image(grid: &[Vec<u32>], ramp: ColorRamp) -> egui::ColorImage {
+    let (rows, columns) = (grid.len(), grid.first().map_or(0, Vec::len));
+    let max = grid.iter().flatten().copied().max().unwrap_or(0).max(1);
+    let pixels: Vec<egui::Color32> = grid
+        .iter()
+        .flatten()
+        .map(|&count| {
+            if count == 0 {
+                return egui::Color32::TRANSPARENT;
+            }
+            let t = count as f64 / max as f64;
+            let [r, g, b] = color::ramp_color(ramp, t);
+            egui::Color32::from_rgba_unmultiplied(r, g, b, (HEATMAP_MIN_ALPHA as f64 + (HEATMAP_MAX_ALPHA - HEATMAP_MIN_ALPHA) as f64 * t) as u8)
+        })
+        .collect();
+    egui::ColorImage { size: [columns, rows], pixels }
+}
+
+/// Grid lines at whole pixels from `from` to `to` (inclusive): major lines
+/// every `spacing` pixels and minor lines splitting each major cell into
+/// `subdivisions` parts, as (pixel, is_major). Minor lines fall on whole
+/// pixels only, so uneven subdivisions round.
+fn grid_lines(from: f64, to: f64, spacing: u32, subdivisions: u32) -> Vec<(u32, bool)> {
+    let spacing = spacing.max(1) as f64;
+    let subdivisions = subdivisions.max(1);
+    let step = spacing / subdivisions as f64;
+    let first = (from.max(0.0) / step).ceil() as u64;
+    let mut lines: Vec<(u32, bool)> = Vec::new();
+    for index in first.. {
+        let pixel = (index as f64 * step).round();
+        if pixel > to {
+            break;
+        }
+        let line = (pixel as u32, index % subdivisions as u64 == 0);
+        // Rounding can land several lines on one pixel; a major one wins
+        match lines.last_mut() {
+            Some(last) if last.0 == line.0 => last.1 |= line.1,
+            _ => lines.push(line),
+        }
+    }
+    lines
+}
+
+/// Draw the ruler grid over the visible part of the image, thinning it out
+/// when zoomed too far out for its lines to be told apart.
+fn draw_ruler_grid(painter: &egui::Painter, image_rect: &egui::Rect, image_size: (u32, u32), canvas_rect: &egui::Rect, settings: &Settings) {
+    let visible = canvas_rect.intersect(*image_rect);
+    if !visible.is_positive() {
+        return;
+    }
+    let (width, height) = image_size;
+    let scale = egui::vec2(image_rect.width() / width as f32, image_rect.height() / height as f32);
+    let major_gap = settings.ruler_major_spacing.max(1) as f32 * scale.x.min(scale.y);
+    let minor_gap = major_gap / settings.ruler_subdivisions.max(1) as f32;
+    if major_gap < RULER_MIN_LINE_SPACING {
+        return;
+    }
+    let subdivisions = if minor_gap < RULER_MIN_LINE_SPACING { 1 } else { settings.ruler_subdivisions };
+    let major = egui::Stroke::new(1.0, egui::Color32::from_white_alpha(110));
+    let minor = egui::Stroke::new(1.0, egui::Color32::from_white_alpha(40));
+    let font = egui::FontId::monospace(10.0);
+    let labeled = major_gap >= RULER_MIN_LABEL_SPACING;
+
+    let to_pixel = |screen: f32, start: f32, scale: f32| ((screen - start) / scale) as f64;
+    let columns = grid_lines(
+        to_pixel(visible.min.x, image_rect.min.x, scale.x),
+        to_pixel(visible.max.x, image_rect.min.x, scale.x).min(width as f64),
+        settings.ruler_major_spacing,
+        subdivisions,
+    );
+    for (pixel, is_major) in columns {
+        let x = image_rect.min.x + pixel as f32 * scale.x;
+        painter.line_segment([egui::pos2(x, visible.min.y), egui::pos2(x, visible.max.y)], if is_major { major } else { minor });
+        if is_major && labeled {
+            painter.text(egui::pos2(x + 2.0, visible.max.y - 2.0), egui::Align2::LEFT_BOTTOM, pixel.to_string(), font.clone(), egui::Color32::WHITE);
+        }
+    }
+    let rows = grid_lines(
+        to_pixel(visible.min.y, image_rect.min.y, scale.y),
+        to_pixel(visible.max.y, image_rect.min.y, scale.y).min(height as f64),
+        settings.ruler_major_spacing,
+        subdivisions,
+    );
+    for (pixel, is_major) in rows {
+        let y = image_rect.min.y + pixel as f32 * scale.y;
+        painter.line_segment([egui::pos2(visible.min.x, y), egui::pos2(visible.max.x, y)], if is_major { major } else { minor });
+        if is_major && labeled {
+            painter.text(egui::pos2(visible.max.x - 2.0, y - 2.0), egui::Align2::RIGHT_BOTTOM, pixel.to_string(), font.clone(), egui::Color32::WHITE);
+        }
+    }
+}
+
+/// Distance in image pixels between labeled axis ticks: the smallest
+/// 1, 2, or 5 times a power of ten that keeps ticks at least `min_spacing`
+/// screen points apart at `points_per_pixel` zoom.
+fn tick_step(points_per_pixel: f32, min_spacing: f32) -> u32 {
+    let mut magnitude = 1u32;
+    loop {
+        for factor in [1, 2, 5] {
+            let step = factor * magnitude;
+            if step as f32 * points_per_pixel >= min_spacing {
+                return step;
+            }
+        }
+        match magnitude.checked_mul(10) {
+            Some(next) => magnitude = next,
+            None => return u32::MAX,
+        }
+    }
+}
+
+/// Draw pixel axes along the top and left edges of the image, held inside
+/// the visible canvas, with an origin marker at the image's top-left.
+fn draw_axes(painter: &egui::Painter, image_rect: &egui::Rect, image_size: (u32, u32), canvas_rect: &egui::Rect) {
+    let visible = canvas_rect.intersect(*image_rect);
+    if !visible.is_positive() {
+        return;
+    }
+    let (width, height) = image_size;
+    let scale = egui::vec2(image_rect.width() / width as f32, image_rect.height() / height as f32);
+    let band = egui::Color32::from_black_alpha(140);
+    let stroke = egui::Stroke::new(1.0, egui::Color32::WHITE);
+    let font = egui::FontId::monospace(10.0);
+
+    // Bands along the (visible) top and left edges
+    let top = egui::Rect::from_min_max(visible.min, egui::pos2(visible.max.x, visible.min.y + AXIS_BAND_WIDTH));
+    let left = egui::Rect::from_min_max(visible.min, egui::pos2(visible.min.x + AXIS_BAND_WIDTH * 2.0, visible.max.y));
+    painter.rect_filled(top, 0.0, band);
+    painter.rect_filled(left, 0.0, band);
+
+    // Ticks every step pixels, skipping the origin which gets its own marker
+    let ticks = |size: u32, scale: f32, start: f32, from: f32, to: f32| {
+        let step = tick_step(scale, AXIS_TICK_SPACING);
+        let first = (((from - start) / scale).max(0.0).ceil() as u32).div_ceil(step) * step;
+        (first..=size)
+            .step_by(step as usize)
+            .filter(|&pixel| pixel > 0)
+            .map(move |pixel| (pixel, start + pixel as f32 * scale))
+            .take_while(move |&(_, screen)| screen <= to)
+    };
+    for (pixel, x) in ticks(width, scale.x, image_rect.min.x, top.min.x, top.max.x) {
+        painter.line_segment([egui::pos2(x, top.min.y), egui::pos2(x, top.max.y)], stroke);
+        painter.text(egui::pos2(x + 2.0, top.min.y + 2.0), egui::Align2::LEFT_TOP, pixel.to_string(), font.clone(), egui::Color32::WHITE);
+    }
+    for (pixel, y) in ticks(height, scale.y, image_rect.min.y, left.min.y, left.max.y) {
+        painter.line_segment([egui::pos2(left.min.x, y), egui::pos2(left.min.x + AXIS_BAND_WIDTH / 2.0, y)], stroke);
+        painter.text(egui::pos2(left.min.x + 2.0, y + 2.0), egui::Align2::LEFT_TOP, pixel.to_string(), font.clone(), egui::Color32::WHITE);
+    }
+
+    // Origin marker, with arrows showing which way x and y grow
+    let origin = image_rect.min;
+    if canvas_rect.contains(origin) {
+        let arrow = egui::Stroke::new(2.0, egui::Color32::YELLOW);
+        painter.arrow(origin, egui::vec2(AXIS_TICK_SPACING / 2.0, 0.0), arrow);
+        painter.arrow(origin, egui::vec2(0.0, AXIS_TICK_SPACING / 2.0), arrow);
+        painter.circle_filled(origin, 4.0, egui::Color32::YELLOW);
+        painter.text(origin + egui::vec2(6.0, 6.0), egui::Align2::LEFT_TOP, "0,0", font, egui::Color32::YELLOW);
+    }
+}
+
+/// Tail and tip of an arrow of `length` centered on `center`, pointing
+/// `degrees` clockwise from screen up.
+fn north_arrow_endpoints(center: egui::Pos2, length: f32, degrees: f64) -> (egui::Pos2, egui::Pos2) {
+    let angle = degrees.to_radians() as f32;
+    let half = egui::vec2(angle.sin(), -angle.cos()) * (length / 2.0);
+    (center - half, center + half)
+}
+
+/// Draw a compass in the top-right corner of the canvas, with its arrow
+/// pointing `north` degrees clockwise from up.
+fn draw_north_arrow(painter: &egui::Painter, canvas_rect: &egui::Rect, north: f64) {
+    let radius = NORTH_ARROW_LENGTH / 2.0 + 4.0;
+    let center = canvas_rect.right_top() + egui::vec2(-(NORTH_ARROW_MARGIN + radius), NORTH_ARROW_MARGIN + radius);
+    painter.circle(center, radius, egui::Color32::from_black_alpha(140), egui::Stroke::new(1.0, egui::Color32::WHITE));
+
+    let (tail, tip) = north_arrow_endpoints(center, NORTH_ARROW_LENGTH, north);
+    painter.arrow(tail, tip - tail, egui::Stroke::new(2.0, egui::Color32::WHITE));
+    // Label just beyond the tip, outside the ring
+    let label = center + (tip - center).normalized() * (radius + 8.0);
+    painter.text(label, egui::Align2::CENTER_CENTER, "N", egui::FontId::proportional(12.0), egui::Color32::WHITE);
+}
+
+/// Draw a measurement line labeled with its pixel length and angle.
+fn draw_measurement(painter: &egui::Painter, start: &Point, end: &Point, image_rect: &egui::Rect, image_size: (u32, u32)) {
+    let to_screen = |p: &Point| {
+        egui::pos2(
+            image_rect.min.x + p.x as f32 * image_rect.width(),
+            image_rect.min.y + p.y as f32 * image_rect.height(),
+        )
+    };
+    let (from, to) = (to_screen(start), to_screen(end));
+    let stroke = egui::Stroke::new(2.0, egui::Color32::YELLOW);
+    painter.line_segment([from, to], egui::Stroke::new(4.0, egui::Color32::from_black_alpha(160)));
+    painter.line_segment([from, to], stroke);
+    painter.circle_filled(from, 3.0, egui::Color32::YELLOW);
+    painter.circle_filled(to, 3.0, egui::Color32::YELLOW);
+
+    let (length, angle) = geometry::measure_segment(start, end, image_size);
+    let galley = painter.layout_no_wrap(
+        format!("{:.1} px  {:.1}°", length, angle),
+        egui::FontId::monospace(12.0),
+        egui::Color32::WHITE,
+    );
+    let label_pos = to + egui::vec2(8.0, 8.0);
+    let padding = egui::vec2(4.0, 2.0);
+    painter.rect_filled(
+        egui::Rect::from_min_size(label_pos - padding, galley.size() + padding * 2.0),
+        2.0,
+        egui::Color32::from_black_alpha(200),
+    );
+    painter.galley(label_pos, galley, egui::Color32::WHITE);
+}
+
+/// Draw comparison annotations as dashed outlines in a fixed color.
+fn draw_reference(painter: &egui::Painter, reference: &ProjectData, image_rect: &egui::Rect) {
+    let stroke = egui::Stroke::new(2.0, REFERENCE_COLOR);
+    for annotation in &reference.annotations {
+        let mut points: Vec<egui::Pos2> = annotation
+            .vertices
+            .0
+            .iter()
+            .map(|p| {
+                egui::pos2(
+                    image_rect.min.x + p.x as f32 * image_rect.width(),
+                    image_rect.min.y + p.y as f32 * image_rect.height(),
+                )
+            })
+            .collect();
+        match annotation.annotation_type {
+            AnnotationType::Point => {
+                if let Some(&pos) = points.first() {
+                    painter.circle_stroke(pos, 6.0, stroke);
+                }
+            }
+            AnnotationType::Polygon | AnnotationType::Line => {
+                if annotation.is_closed() {
+                    if let Some(&first) = points.first() {
+                        points.push(first);
+                    }
+                }
+                painter.extend(egui::Shape::dashed_line(&points, stroke, 6.0, 4.0));
+            }
+        }
+    }
+}
+
+/// Draw a keypoint as a ringed crosshair with its name beside it.
+fn draw_point_marker(
+    shapes: &mut Vec<egui::Shape>,
+    painter: &egui::Painter,
+    pos: egui::Pos2,
+    name: &str,
+    color: egui::Color32,
+) {
+    let radius = 6.0;
+    let stroke = egui::Stroke::new(2.0, color);
+    shapes.push(egui::Shape::circle_stroke(pos, radius, egui::Stroke::new(4.0, egui::Color32::from_black_alpha(160))));
+    shapes.push(egui::Shape::circle_stroke(pos, radius, stroke));
+    shapes.push(egui::Shape::line_segment([pos - egui::vec2(radius, 0.0), pos + egui::vec2(radius, 0.0)], stroke));
+    shapes.push(egui::Shape::line_segment([pos - egui::vec2(0.0, radius), pos + egui::vec2(0.0, radius)], stroke));
+    shapes.push(text_shape(
+        painter,
+        pos + egui::vec2(radius + 4.0, -radius),
+        egui::Align2::LEFT_BOTTOM,
+        name.to_string(),
+        egui::FontId::proportional(12.0),
+        color,
+    ));
+}
+
+/// Draw the names and/or list-number badges of visible annotations at
+/// their label anchors.
+///
+/// Names and badges are laid out together and nudged apart so they don't
+/// overlap, with a leader line back to the anchor when one had to move off
+/// it. Badges are numbered from 1 in list order, on a disk outlined in the
+/// annotation's color.
+fn draw_labels(
+    painter: &egui::Painter,
+    project: &ProjectData,
+    shown: impl Fn(usize, &Annotation) -> bool,
+    image_rect: &egui::Rect,
+    bounds: &egui::Rect,
+    names: bool,
+    badges: bool,
+) {
+    let padding = egui::vec2(4.0, 2.0);
+    let mut anchors = Vec::new();
+    // Each label's text, and the badge outline color for badges
+    let mut galleys = Vec::new();
+
+    for (idx, annotation) in project.annotations.iter().enumerate().filter(|(idx, a)| shown(*idx, a)) {
+        let Some(anchor) = annotation.label_anchor() else {
+            continue;
+        };
+        let anchor = egui::pos2(
+            image_rect.min.x + anchor.x as f32 * image_rect.width(),
+            image_rect.min.y + anchor.y as f32 * image_rect.height(),
+        );
+        if badges {
+            let galley = painter.layout_no_wrap((idx + 1).to_string(), egui::FontId::proportional(11.0), egui::Color32::WHITE);
+            let diameter = galley.size().max_elem() + BADGE_PADDING * 2.0;
+            let [r, g, b, _] = annotation.display_color();
+            anchors.push((anchor, egui::Vec2::splat(diameter)));
+            galleys.push((galley, Some(egui::Color32::from_rgb(r, g, b))));
+        }
+        if names {
+            let galley = painter.layout_no_wrap(
+                annotation.name.clone(),
+                egui::FontId::proportional(12.0),
+                egui::Color32::WHITE,
+            );
+            anchors.push((anchor, galley.size() + padding * 2.0));
+            galleys.push((galley, None));
+        }
+    }
+
+    let rects = labels::layout_labels(&anchors, *bounds);
+    for ((rect, (galley, badge)), (anchor, _)) in rects.into_iter().zip(galleys).zip(anchors) {
+        if !rect.contains(anchor) {
+            painter.line_segment([anchor, rect.center()], egui::Stroke::new(1.0, egui::Color32::from_white_alpha(160)));
+        }
+        match badge {
+            Some(outline) => {
+                painter.circle(rect.center(), rect.width() / 2.0, egui::Color32::from_black_alpha(200), egui::Stroke::new(1.5, outline));
+                painter.galley(rect.center() - galley.size() / 2.0, galley, egui::Color32::WHITE);
+            }
+            None => {
+                painter.rect_filled(rect, 2.0, egui::Color32::from_black_alpha(180));
+                painter.galley(rect.min + padding, galley, egui::Color32::WHITE);
+            }
+        }
+    }
+}
+
+/// Indices of the shown annotations whose centroid lies inside a lasso loop.
+pub fn lasso_selection(annotations: &[Annotation], lasso: &[Point], shown: impl Fn(usize, &Annotation) -> bool) -> Vec<usize> {
+    if lasso.len() < 3 {
+        return Vec::new();
+    }
+    annotations
+        .iter()
+        .enumerate()
+        .filter(|(idx, annotation)| shown(*idx, annotation))
+        .filter(|(_, annotation)| annotation.centroid().is_some_and(|c| geometry::point_in_polygon(&c, lasso)))
+        .map(|(idx, _)| idx)
+        .collect()
+}
+
+/// Find the closest point on the edge of any shown annotation within
+/// the snap threshold (normalized units).
+fn snap_to_edge(project: &Option<ProjectData>, shown: impl Fn(usize, &Annotation) -> bool, point: &Point, threshold: f64) -> Option<Point> {
+    project
+        .as_ref()?
+        .annotations
+        .iter()
+        .enumerate()
+        .filter(|(idx, annotation)| shown(*idx, annotation))
+        .filter_map(|(_, annotation)| annotation.nearest_edge_point(point))
+        .filter(|(_, distance)| *distance <= threshold)
+        .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
+        .map(|(snapped, _)| snapped)
+}
+
+/// Screen positions at which to place traced vertices as the pointer moves
+/// from the last placed vertex to `pos`.
+///
+/// Points are spaced exactly `spacing` apart along the movement so fast
+/// strokes are sampled as evenly as slow ones. With no previous vertex the
+/// pointer position itself is placed.
+fn trace_samples(last: Option<egui::Pos2>, pos: egui::Pos2, spacing: f32) -> Vec<egui::Pos2> {
+    let Some(last) = last else {
+        return vec![pos];
+    };
+    let distance = last.distance(pos);
+    if spacing <= 0.0 || distance < spacing {
+        return Vec::new();
+    }
+    let direction = (pos - last) / distance;
+    let count = (distance / spacing).floor() as usize;
+    (1..=count).map(|i| last + direction * spacing * i as f32).collect()
+}
+
+/// Whether an annotation is drawn and can be hit.
+///
+/// Annotations in hidden groups never are; in isolate mode only the
+/// selected annotation is.
+pub fn is_shown(idx: usize, annotation: &Annotation, layers: &Layers, selected: Option<usize>, isolate: bool) -> bool {
+    layers.is_visible(annotation) && (!isolate || selected == Some(idx))
+}
+
+/// Opacity annotations are drawn with: full when they are composited
+/// after the image adjustment, or the image's opacity when adjusted with it.
+pub fn overlay_opacity(settings: &Settings) -> f32 {
+    match settings.overlay_compositing {
+        OverlayCompositing::AfterAdjustment => 1.0,
+        OverlayCompositing::WithAdjustment => settings.image_opacity.clamp(0.0, 1.0),
+    }
+}
+
+/// Scale a stroke width inversely with zoom, clamped so outlines stay
+/// visible when zoomed out and thin when zoomed in.
+pub fn screen_stroke(base: f32, zoom: f32) -> f32 {
+    (base / zoom.max(f32::EPSILON)).clamp(MIN_STROKE_WIDTH, MAX_STROKE_WIDTH)
+}
+
+/// Base and knob of the rotation handle for a polygon that is an oriented
+/// box, or None for any other annotation.
+///
+/// The screen has equal x and y scales, so the box is recognized there.
+fn rotation_handle(annotation: &Annotation, image_rect: &egui::Rect) -> Option<(egui::Pos2, egui::Pos2)> {
+    if annotation.annotation_type != AnnotationType::Polygon {
+        return None;
+    }
+    let screen: Vec<Point> = annotation
+        .vertices
+        .0
+        .iter()
+        .map(|p| Point::new(image_rect.min.x as f64 + p.x * image_rect.width() as f64, image_rect.min.y as f64 + p.y * image_rect.height() as f64))
+        .collect();
+    let obb = geometry::obb_from_polygon(&screen)?;
+    let (sin, cos) = obb.angle.to_radians().sin_cos();
+    let normal = egui::vec2(sin as f32, -cos as f32);
+    let base = egui::pos2(obb.cx as f32, obb.cy as f32) + normal * (obb.height as f32 / 2.0);
+    Some((base, base + normal * ROTATION_HANDLE_LENGTH))
 }
 
-/// Draw an annotation on the canvas.
+/// Build the shapes for an annotation.
+///
+/// The painter is only used to lay out text; shapes are appended to `shapes`
+/// so they can be cached between frames.
+#[allow(clippy::too_many_arguments)]
 fn draw_annotation(
+    shapes: &mut Vec<egui::Shape>,
     painter: &egui::Painter,
     annotation: &Annotation,
     image_rect: &egui::Rect,
     color: egui::Color32,
     is_in_progress: bool,
-    show_coordinates: bool,
+    label_coordinates: Option<(u32, u32)>,
+    settings: &Settings,
+    zoom: f32,
 ) {
-    let vertices = &annotation.vertices.0;
-    if vertices.is_empty() {
+    // Skip corrupt non-finite vertices, keeping their original indices
+    let vertices: Vec<(usize, Point)> = annotation.vertices.0.iter().copied().enumerate().filter(|(_, p)| p.is_finite()).collect();
+
+    // Convert normalized coordinates to screen coordinates
+    let to_screen = |p: &Point| {
+        egui::pos2(
+            image_rect.min.x + (p.x as f32) * image_rect.width(),
+            image_rect.min.y + (p.y as f32) * image_rect.height(),
+        )
+    };
+    let screen_points: Vec<egui::Pos2> = vertices.iter().map(|(_, p)| to_screen(p)).collect();
+    if screen_points.is_empty() {
         return;
     }
 
-    // Convert normalized coordinates to screen coordinates
-    let screen_points: Vec<egui::Pos2> = vertices
-        .iter()
-        .map(|p| {
-            egui::pos2(
-                image_rect.min.x + (p.x as f32) * image_rect.width(),
-                image_rect.min.y + (p.y as f32) * image_rect.height(),
-            )
-        })
-        .collect();
+    // Draw keypoints as a labeled marker
+    if annotation.annotation_type == AnnotationType::Point {
+        draw_point_marker(shapes, painter, screen_points[0], &annotation.name, color);
+        return;
+    }
+
+    // Huge annotations (e.g. traced from masks) are drawn as one path,
+    // without dashes, ticks, or handles unless selected, so the shape count
+    // stays bounded
+    let is_selected = label_coordinates.is_some();
+    let simplified = !is_in_progress && screen_points.len() > settings.lod_vertex_threshold;
 
     // Draw lines connecting vertices
-    for i in 0..screen_points.len() {
-        let next_i = (i + 1) % screen_points.len();
+    let stroke_width = screen_stroke(BASE_STROKE_WIDTH, zoom);
+    if simplified {
+        let stroke = egui::Stroke::new(stroke_width, color);
+        shapes.push(if annotation.annotation_type == AnnotationType::Polygon {
+            egui::Shape::closed_line(screen_points.clone(), stroke)
+        } else {
+            egui::Shape::line(screen_points.clone(), stroke)
+        });
+    } else {
+        for i in 0..screen_points.len() {
+            let next_i = (i + 1) % screen_points.len();
 
-        // For in-progress annotations, don't connect last vertex back to first
-        if is_in_progress && next_i == 0 {
-            break;
+            // For in-progress annotations, don't connect last vertex back to first
+            if is_in_progress && next_i == 0 {
+                break;
+            }
+
+            // Dashed styles only apply once the annotation is finished
+            let stroke = egui::Stroke::new(stroke_width, color);
+            if annotation.line_style == LineStyle::Dashed && !is_in_progress {
+                for [from, to] in dash_segments(screen_points[i], screen_points[next_i], settings.dash_length, settings.dash_length) {
+                    stroke_segment(shapes, from, to, stroke, settings.line_cap);
+                }
+            } else {
+                stroke_segment(shapes, screen_points[i], screen_points[next_i], stroke, settings.line_cap);
+            }
         }
+    }
 
-        // For closed polygons, draw all edges including back to first
-        if !is_in_progress || i < screen_points.len() - 1 {
-            painter.line_segment(
-                [screen_points[i], screen_points[next_i]],
-                egui::Stroke::new(2.0, color),
-            );
+    // Outline the other rings of a multi-part polygon; only the first
+    // ring has handles
+    let extra_rings: Vec<Vec<egui::Pos2>> = annotation
+        .extra_rings
+        .iter()
+        .map(|ring| ring.0.iter().filter(|p| p.is_finite()).map(to_screen).collect())
+        .collect();
+    for points in &extra_rings {
+        shapes.push(egui::Shape::closed_line(points.clone(), egui::Stroke::new(stroke_width, color)));
+    }
+
+    // Distinguish counting roles: hatch excluded regions, tick count lines
+    if !is_in_progress {
+        match annotation.role {
+            AnnotationRole::Exclude if annotation.annotation_type == AnnotationType::Polygon => {
+                let hatch = egui::Stroke::new(1.0, color.gamma_multiply(0.6));
+                let rings: Vec<Vec<egui::Pos2>> = std::iter::once(screen_points.clone()).chain(extra_rings).collect();
+                for segment in hatch_segments(&rings, HATCH_SPACING, settings.fill_rule) {
+                    shapes.push(egui::Shape::line_segment(segment, hatch));
+                }
+            }
+            AnnotationRole::CountLine if !simplified => {
+                let tick = egui::Stroke::new(stroke_width, color);
+                for pair in screen_points.windows(2) {
+                    let direction = (pair[1] - pair[0]).normalized();
+                    let normal = egui::vec2(-direction.y, direction.x) * 8.0;
+                    let middle = pair[0] + (pair[1] - pair[0]) / 2.0;
+                    shapes.push(egui::Shape::line_segment([middle - normal, middle + normal], tick));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Selected oriented boxes get a rotation handle off their first edge
+    if let (Some(_), false) = (label_coordinates, is_in_progress) {
+        if let Some((base, knob)) = rotation_handle(annotation, image_rect) {
+            shapes.push(egui::Shape::line_segment([base, knob], egui::Stroke::new(1.5, color)));
+            shapes.push(egui::Shape::circle_filled(knob, ROTATION_HANDLE_RADIUS, egui::Color32::WHITE));
+            shapes.push(egui::Shape::circle_stroke(knob, ROTATION_HANDLE_RADIUS, egui::Stroke::new(1.5, color)));
         }
     }
 
@@ -270,14 +1267,16 @@ fn draw_annotation(
         color
     };
 
-    for (i, point) in screen_points.iter().enumerate() {
-        painter.circle_filled(*point, 4.0, vertex_color);
-        painter.circle_stroke(*point, 4.0, egui::Stroke::new(1.0, egui::Color32::BLACK));
+    if simplified && !is_selected {
+        return;
+    }
+    for (&(i, vertex), point) in vertices.iter().zip(&screen_points) {
+        let radius = if is_selected { SELECTED_VERTEX_HANDLE_RADIUS } else { VERTEX_HANDLE_RADIUS };
+        vertex_handle(shapes, *point, radius, vertex_color, settings.handle_shape);
 
         // Draw coordinate labels for selected annotations
-        if show_coordinates {
-            let vertex = &vertices[i];
-            let label_text = format!("{:.3}, {:.3}", vertex.x, vertex.y);
+        if let (Some(image_size), false) = (label_coordinates, simplified) {
+            let label_text = settings.coordinate_unit.format_point(&vertex, image_size);
 
             // Calculate text size and position
             let font_id = egui::FontId::proportional(12.0);
@@ -298,21 +1297,427 @@ fn draw_annotation(
             );
 
             // Draw light background
-            painter.rect_filled(
+            shapes.push(egui::Shape::rect_filled(
                 bg_rect,
                 2.0,
                 egui::Color32::from_rgba_premultiplied(240, 240, 240, 230),
-            );
+            ));
 
             // Draw border
-            painter.rect_stroke(
+            shapes.push(egui::Shape::rect_stroke(
                 bg_rect,
                 2.0,
                 egui::Stroke::new(1.0, egui::Color32::BLACK),
-            );
+            ));
 
             // Draw text
-            painter.galley(label_pos, galley, egui::Color32::BLACK);
+            shapes.push(egui::Shape::galley(label_pos, galley, egui::Color32::BLACK));
+        }
+
+        // Draw the vertex index above and to the left of selected vertices
+        if is_selected && !simplified && settings.show_vertex_indices {
+            shapes.push(text_shape(
+                painter,
+                egui::pos2(point.x - 6.0, point.y - 6.0),
+                egui::Align2::RIGHT_BOTTOM,
+                i.to_string(),
+                egui::FontId::monospace(12.0),
+                egui::Color32::YELLOW,
+            ));
+        }
+    }
+}
+
+/// Draw a vertex handle with a black outline.
+///
+/// The shape is purely visual; vertex hit testing uses
+/// [`VERTEX_HIT_THRESHOLD`] whatever the handle looks like.
+fn vertex_handle(shapes: &mut Vec<egui::Shape>, center: egui::Pos2, radius: f32, fill: egui::Color32, shape: HandleShape) {
+    let outline = egui::Stroke::new(1.0, egui::Color32::BLACK);
+    match shape {
+        HandleShape::Circle => {
+            shapes.push(egui::Shape::circle_filled(center, radius, fill));
+            shapes.push(egui::Shape::circle_stroke(center, radius, outline));
+        }
+        HandleShape::Square => {
+            let rect = egui::Rect::from_center_size(center, egui::Vec2::splat(radius * 2.0));
+            shapes.push(egui::Shape::rect_filled(rect, 0.0, fill));
+            shapes.push(egui::Shape::rect_stroke(rect, 0.0, outline));
+        }
+        HandleShape::Diamond => {
+            // Slightly larger so its area roughly matches the circle
+            let r = radius * 1.25;
+            let points = vec![center - egui::vec2(0.0, r), center + egui::vec2(r, 0.0), center + egui::vec2(0.0, r), center - egui::vec2(r, 0.0)];
+            shapes.push(egui::Shape::convex_polygon(points, fill, outline));
         }
     }
 }
+
+/// Draw a line segment with the given cap style.
+///
+/// egui strokes have butt ends, so round caps are drawn as discs at the
+/// endpoints and square caps by extending the segment by half the width.
+fn stroke_segment(
+    shapes: &mut Vec<egui::Shape>,
+    from: egui::Pos2,
+    to: egui::Pos2,
+    stroke: egui::Stroke,
+    cap: LineCap,
+) {
+    let half_width = stroke.width / 2.0;
+    match cap {
+        LineCap::Round => {
+            shapes.push(egui::Shape::line_segment([from, to], stroke));
+            shapes.push(egui::Shape::circle_filled(from, half_width, stroke.color));
+            shapes.push(egui::Shape::circle_filled(to, half_width, stroke.color));
+        }
+        LineCap::Square => {
+            let extension = (to - from).normalized() * half_width;
+            shapes.push(egui::Shape::line_segment([from - extension, to + extension], stroke));
+        }
+    }
+}
+
+/// Whether a click at (time in seconds, position) completes a double-click
+/// with the previous one.
+fn is_double_click(previous: Option<(f64, egui::Pos2)>, click: (f64, egui::Pos2), max_delay: f64) -> bool {
+    previous.is_some_and(|(time, pos)| click.0 - time <= max_delay && pos.distance(click.1) <= DOUBLE_CLICK_DISTANCE)
+}
+
+/// Convert a screen position to image coordinates clamped to the image.
+///
+/// Also returns whether the position had to be clamped.
+fn clamp_to_image(pos: egui::Pos2, image_rect: &egui::Rect) -> (Point, bool) {
+    let x = ((pos.x - image_rect.min.x) / image_rect.width()) as f64;
+    let y = ((pos.y - image_rect.min.y) / image_rect.height()) as f64;
+    let point = Point::new(x.clamp(0.0, 1.0), y.clamp(0.0, 1.0));
+    let clamped = point.x != x || point.y != y;
+    (point, clamped)
+}
+
+/// Split an edge into dashes of length `dash` separated by `gap`.
+///
+/// Each edge starts with a full dash so polygon corners stay visible; the
+/// last dash is clipped to the end of the edge.
+fn dash_segments(from: egui::Pos2, to: egui::Pos2, dash: f32, gap: f32) -> Vec<[egui::Pos2; 2]> {
+    let length = from.distance(to);
+    if dash <= 0.0 || length <= dash {
+        return vec![[from, to]];
+    }
+
+    let direction = (to - from) / length;
+    let mut segments = Vec::new();
+    let mut start = 0.0;
+    while start < length {
+        let end = (start + dash).min(length);
+        segments.push([from + direction * start, from + direction * end]);
+        start = end + gap.max(0.0);
+    }
+    segments
+}
+
+/// Diagonal hatch lines clipped to the inside of a polygon.
+///
+/// Lines run at 45° and are `spacing` pixels apart; each is split at the
+/// edges it crosses, over all rings of the polygon, using the given fill
+/// rule.
+fn hatch_segments(rings: &[Vec<egui::Pos2>], spacing: f32, rule: FillRule) -> Vec<[egui::Pos2; 2]> {
+    let mut segments = Vec::new();
+    let rings: Vec<&Vec<egui::Pos2>> = rings.iter().filter(|ring| ring.len() >= 3).collect();
+    if rings.is_empty() || spacing <= 0.0 {
+        return segments;
+    }
+
+    // Hatch lines are x - y = c, swept across the polygon's range of c
+    let offset = |p: &egui::Pos2| p.x - p.y;
+    let min = rings.iter().flat_map(|ring| ring.iter()).map(offset).fold(f32::INFINITY, f32::min);
+    let max = rings.iter().flat_map(|ring| ring.iter()).map(offset).fold(f32::NEG_INFINITY, f32::max);
+
+    let mut c = (min / spacing).ceil() * spacing;
+    while c <= max {
+        let mut crossings: Vec<(egui::Pos2, i32)> = Vec::new();
+        let edges = rings.iter().flat_map(|ring| ring.iter().zip(ring.iter().cycle().skip(1)));
+        for (from, to) in edges {
+            let (a, b) = (offset(from) - c, offset(to) - c);
+            // Half-open test so shared vertices are counted once
+            if (a <= 0.0) != (b <= 0.0) {
+                let t = a / (a - b);
+                crossings.push((*from + (*to - *from) * t, if a <= 0.0 { 1 } else { -1 }));
+            }
+        }
+        crossings.sort_by(|(a, _), (b, _)| (a.x + a.y).total_cmp(&(b.x + b.y)));
+        let mut winding = 0;
+        for pair in crossings.windows(2) {
+            winding += pair[0].1;
+            if rule.contains(winding) && pair[0].0 != pair[1].0 {
+                segments.push([pair[0].0, pair[1].0]);
+            }
+        }
+        c += spacing;
+    }
+    segments
+}
+
+/// Lay out text as a shape anchored the same way as `Painter::text`.
+fn text_shape(
+    painter: &egui::Painter,
+    pos: egui::Pos2,
+    anchor: egui::Align2,
+    text: String,
+    font_id: egui::FontId,
+    color: egui::Color32,
+) -> egui::Shape {
+    let galley = painter.layout_no_wrap(text, font_id, color);
+    let rect = anchor.anchor_size(pos, galley.size());
+    egui::Shape::galley(rect.min, galley, color)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_screen_stroke() {
+        // Zoomed out: thicker, up to the maximum
+        assert_eq!(screen_stroke(2.0, 0.25), MAX_STROKE_WIDTH);
+        assert_eq!(screen_stroke(2.0, 1.0), 2.0);
+        // Zoomed in: thinner, down to the minimum
+        assert_eq!(screen_stroke(2.0, 4.0), MIN_STROKE_WIDTH);
+        assert_eq!(screen_stroke(2.0, 0.8), 2.5);
+    }
+
+    #[test]
+    fn test_north_arrow_endpoints() {
+        let center = egui::pos2(100.0, 100.0);
+        let close = |a: egui::Pos2, b: egui::Pos2| a.distance(b) < 1e-4;
+
+        // Unrotated, north is straight up (screen y grows downward)
+        let (tail, tip) = north_arrow_endpoints(center, 40.0, 0.0);
+        assert!(close(tail, egui::pos2(100.0, 120.0)) && close(tip, egui::pos2(100.0, 80.0)));
+
+        // Rotations are clockwise
+        let (tail, tip) = north_arrow_endpoints(center, 40.0, 90.0);
+        assert!(close(tail, egui::pos2(80.0, 100.0)) && close(tip, egui::pos2(120.0, 100.0)));
+        let (_, tip) = north_arrow_endpoints(center, 40.0, 225.0);
+        let offset = 20.0 * std::f32::consts::FRAC_1_SQRT_2;
+        assert!(close(tip, egui::pos2(100.0 - offset, 100.0 + offset)));
+    }
+
+    #[test]
+    fn test_round_action_to_pixels() {
+        // A click at pixel (10.4, 20.6) of a 100 × 50 image lands on (10, 21)
+        let click = Point::new(10.4 / 100.0, 20.6 / 50.0);
+        let CanvasAction::AddVertex(point) = round_action_to_pixels(CanvasAction::AddVertex(click), (100, 50)) else {
+            panic!("expected AddVertex");
+        };
+        assert_eq!(geometry::denormalize_coordinates(&point, 100, 50), (10.0, 21.0));
+
+        // Dragged vertices are rounded too, and kept inside the image
+        let CanvasAction::DragVertex(point) = round_action_to_pixels(CanvasAction::DragVertex(Point::new(0.999, 0.001)), (100, 50)) else {
+            panic!("expected DragVertex");
+        };
+        assert_eq!(point, Point::new(1.0, 0.0));
+
+        // Traced samples that round to the same pixel are merged
+        let traced = vec![Point::new(0.101, 0.1), Point::new(0.104, 0.1), Point::new(0.2, 0.1)];
+        let CanvasAction::TraceVertices(points) = round_action_to_pixels(CanvasAction::TraceVertices(traced), (100, 50)) else {
+            panic!("expected TraceVertices");
+        };
+        assert_eq!(points, [Point::new(0.1, 0.1), Point::new(0.2, 0.1)]);
+
+        // Stamps land on a whole pixel
+        let CanvasAction::Stamp(point) = round_action_to_pixels(CanvasAction::Stamp(click), (100, 50)) else {
+            panic!("expected Stamp");
+        };
+        assert_eq!(geometry::denormalize_coordinates(&point, 100, 50), (10.0, 21.0));
+
+        // A box's center and corner are both rounded, so its sides are too
+        let CanvasAction::CreateBox(center, corner) =
+            round_action_to_pixels(CanvasAction::CreateBox(click, Point::new(0.456, 0.789)), (100, 50))
+        else {
+            panic!("expected CreateBox");
+        };
+        assert_eq!(geometry::denormalize_coordinates(&center, 100, 50), (10.0, 21.0));
+        assert_eq!(geometry::denormalize_coordinates(&corner, 100, 50), (46.0, 39.0));
+
+        // The rotation handle follows whole pixels
+        let CanvasAction::RotateBox(point) = round_action_to_pixels(CanvasAction::RotateBox(click), (100, 50)) else {
+            panic!("expected RotateBox");
+        };
+        assert_eq!(geometry::denormalize_coordinates(&point, 100, 50), (10.0, 21.0));
+    }
+
+    #[test]
+    fn test_trace_samples() {
+        // The first sample is placed where the drag starts
+        assert_eq!(trace_samples(None, egui::pos2(3.0, 4.0), 10.0), [egui::pos2(3.0, 4.0)]);
+
+        // Too short a move places nothing
+        assert!(trace_samples(Some(egui::pos2(0.0, 0.0)), egui::pos2(6.0, 8.0), 20.0).is_empty());
+
+        // A long move is sampled at even spacing, leaving the remainder
+        let samples = trace_samples(Some(egui::pos2(0.0, 0.0)), egui::pos2(25.0, 0.0), 10.0);
+        assert_eq!(samples, [egui::pos2(10.0, 0.0), egui::pos2(20.0, 0.0)]);
+    }
+
+    #[test]
+    fn test_is_shown_in_isolate_mode() {
+        let layers = Layers::new();
+        let annotation = Annotation::new("region 1".to_string(), AnnotationType::Polygon);
+
+        // Normally everything visible is shown
+        assert!(is_shown(0, &annotation, &layers, None, false));
+        assert!(is_shown(1, &annotation, &layers, Some(0), false));
+
+        // Isolated, only the selection is
+        assert!(is_shown(0, &annotation, &layers, Some(0), true));
+        assert!(!is_shown(1, &annotation, &layers, Some(0), true));
+        assert!(!is_shown(0, &annotation, &layers, None, true));
+    }
+
+    #[test]
+    fn test_lasso_selection_uses_centroids() {
+        let annotation = |name: &str, annotation_type, points: &[(f64, f64)]| {
+            let mut annotation = Annotation::new(name.to_string(), annotation_type);
+            for &(x, y) in points {
+                annotation.add_vertex(Point::new(x, y));
+            }
+            annotation
+        };
+        let annotations = vec![
+            // Centroid (0.2, 0.2) inside the lasso
+            annotation("inside", AnnotationType::Polygon, &[(0.1, 0.1), (0.3, 0.1), (0.3, 0.3), (0.1, 0.3)]),
+            // Overlaps the lasso but its centroid (0.5, 0.2) is outside
+            annotation("straddling", AnnotationType::Polygon, &[(0.3, 0.1), (0.7, 0.1), (0.7, 0.3), (0.3, 0.3)]),
+            annotation("point", AnnotationType::Point, &[(0.35, 0.35)]),
+            annotation("far line", AnnotationType::Line, &[(0.8, 0.8), (0.9, 0.9)]),
+        ];
+        let lasso = [Point::new(0.0, 0.0), Point::new(0.4, 0.0), Point::new(0.4, 0.4), Point::new(0.0, 0.4)];
+
+        assert_eq!(lasso_selection(&annotations, &lasso, |_, _| true), vec![0, 2]);
+        assert_eq!(lasso_selection(&annotations, &lasso, |idx, _| idx != 0), vec![2]);
+        assert!(lasso_selection(&annotations, &lasso[..2], |_, _| true).is_empty());
+    }
+
+    #[test]
+    fn test_hatch_segments_stay_inside() {
+        let square = [egui::pos2(0.0, 0.0), egui::pos2(40.0, 0.0), egui::pos2(40.0, 40.0), egui::pos2(0.0, 40.0)];
+        let segments = hatch_segments(&[square.to_vec()], 10.0, FillRule::EvenOdd);
+        // x - y ranges over -40..=40; the corner lines touch at a single point
+        assert_eq!(segments.len(), 7);
+        for [a, b] in segments {
+            for p in [a, b] {
+                assert!((-0.001..=40.001).contains(&p.x) && (-0.001..=40.001).contains(&p.y));
+            }
+            assert!(((a.x - a.y) - (b.x - b.y)).abs() < 0.001);
+        }
+        assert!(hatch_segments(&[square[..2].to_vec()], 10.0, FillRule::EvenOdd).is_empty());
+
+        // A second, disjoint part is hatched too
+        let shifted: Vec<egui::Pos2> = square.iter().map(|p| *p + egui::vec2(100.0, 0.0)).collect();
+        let segments = hatch_segments(&[square.to_vec(), shifted], 10.0, FillRule::EvenOdd);
+        assert!(segments.iter().any(|[a, _]| a.x >= 100.0));
+        assert!(segments.iter().all(|[a, b]| (a.x <= 40.001) == (b.x <= 40.001)));
+    }
+
+    #[test]
+    fn test_clamp_to_image() {
+        let rect = egui::Rect::from_min_size(egui::pos2(100.0, 50.0), egui::vec2(200.0, 100.0));
+        assert_eq!(clamp_to_image(egui::pos2(150.0, 75.0), &rect), (Point::new(0.25, 0.25), false));
+        assert_eq!(clamp_to_image(egui::pos2(350.0, 75.0), &rect), (Point::new(1.0, 0.25), true));
+        assert_eq!(clamp_to_image(egui::pos2(50.0, 0.0), &rect), (Point::new(0.0, 0.0), true));
+    }
+
+    #[test]
+    fn test_huge_annotation_shape_count() {
+        let mut annotation = Annotation::new("mask 1".to_string(), AnnotationType::Polygon);
+        for i in 0..50_000 {
+            let angle = i as f64 / 50_000.0 * std::f64::consts::TAU;
+            annotation.add_vertex(Point::new(0.5 + 0.4 * angle.cos(), 0.5 + 0.4 * angle.sin()));
+        }
+        let settings = Settings::default();
+        let rect = egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(1000.0, 1000.0));
+
+        let ctx = egui::Context::default();
+        let mut counts = Vec::new();
+        let _ = ctx.run(egui::RawInput::default(), |ctx| {
+            let painter = ctx.layer_painter(egui::LayerId::background());
+            for selected in [None, Some((1000, 1000))] {
+                let mut shapes = Vec::new();
+                draw_annotation(&mut shapes, &painter, &annotation, &rect, egui::Color32::RED, false, selected, &settings, 1.0);
+                counts.push(shapes.len());
+            }
+        });
+
+        // Unselected, the whole outline is a single path
+        assert_eq!(counts[0], 1);
+        // Selected, only the vertex handles are added, without labels
+        assert!(counts[1] <= 1 + 2 * 50_000);
+    }
+
+    #[test]
+    fn test_grid_lines() {
+        // Majors every 100 px with quarter subdivisions
+        let lines = grid_lines(130.0, 260.0, 100, 4);
+        assert_eq!(lines, [(150, false), (175, false), (200, true), (225, false), (250, false)]);
+
+        // Uneven subdivisions round to whole pixels
+        assert_eq!(grid_lines(0.0, 10.0, 10, 3), [(0, true), (3, false), (7, false), (10, true)]);
+
+        // More subdivisions than pixels never repeat a line
+        assert_eq!(grid_lines(0.0, 2.0, 2, 8), [(0, true), (1, false), (2, true)]);
+
+        // No subdivisions leaves only major lines; negative ranges start at 0
+        assert_eq!(grid_lines(-50.0, 120.0, 50, 1), [(0, true), (50, true), (100, true)]);
+    }
+
+    #[test]
+    fn test_overlay_opacity() {
+        let mut settings = Settings { image_opacity: 0.4, ..Settings::default() };
+        // Composited after the adjustment, annotations keep full color
+        assert_eq!(overlay_opacity(&settings), 1.0);
+
+        settings.overlay_compositing = OverlayCompositing::WithAdjustment;
+        assert_eq!(overlay_opacity(&settings), 0.4);
+        settings.image_opacity = 1.5;
+        assert_eq!(overlay_opacity(&settings), 1.0);
+    }
+
+    #[test]
+    fn test_tick_step() {
+        // One screen point per pixel needs 100 px between 60pt-spaced ticks
+        assert_eq!(tick_step(1.0, 60.0), 100);
+        // Zoomed in, ticks get closer in pixels
+        assert_eq!(tick_step(4.0, 60.0), 20);
+        assert_eq!(tick_step(100.0, 60.0), 1);
+        // Zoomed out, they spread out through the 1-2-5 sequence
+        assert_eq!(tick_step(0.5, 60.0), 200);
+        assert_eq!(tick_step(0.1, 60.0), 1000);
+        assert_eq!(tick_step(0.02, 60.0), 5000);
+    }
+
+    #[test]
+    fn test_is_double_click() {
+        let first = (10.0, egui::pos2(100.0, 100.0));
+        assert!(!is_double_click(None, first, 0.3));
+        assert!(is_double_click(Some(first), (10.25, egui::pos2(103.0, 101.0)), 0.3));
+        // Too slow for the default window, but fine with a longer one
+        assert!(!is_double_click(Some(first), (10.5, egui::pos2(100.0, 100.0)), 0.3));
+        assert!(is_double_click(Some(first), (10.5, egui::pos2(100.0, 100.0)), 0.6));
+        // Too far apart
+        assert!(!is_double_click(Some(first), (10.1, egui::pos2(120.0, 100.0)), 0.6));
+    }
+
+    #[test]
+    fn test_dash_segments() {
+        // A 25pt edge holds dashes at 0-6, 10-16, and a clipped 20-25
+        let dashes = dash_segments(egui::pos2(0.0, 0.0), egui::pos2(0.0, 25.0), 6.0, 4.0);
+        let spans: Vec<(f32, f32)> = dashes.iter().map(|[a, b]| (a.y, b.y)).collect();
+        assert_eq!(spans, [(0.0, 6.0), (10.0, 16.0), (20.0, 25.0)]);
+        assert!(dashes.iter().all(|[a, b]| a.x == 0.0 && b.x == 0.0));
+
+        // Edges no longer than a dash are drawn whole
+        let short = dash_segments(egui::pos2(0.0, 0.0), egui::pos2(3.0, 4.0), 6.0, 4.0);
+        assert_eq!(short, [[egui::pos2(0.0, 0.0), egui::pos2(3.0, 4.0)]]);
+    }
+}
@@ -0,0 +1,454 @@
+// Copyright (c) 2025, Jason Jenkins
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Configurable keyboard shortcuts.
+//!
+//! This module maps logical actions to key bindings, checks input against
+//! them, and provides the shortcut editor shown in the preferences window.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Logical action that can be bound to keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Action {
+    Cancel,
+    Delete,
+    Undo,
+    Redo,
+    PanLeft,
+    PanRight,
+    PanUp,
+    PanDown,
+    RecenterView,
+    NextImage,
+    PreviousImage,
+    ToggleIsolate,
+    ToggleCoordinateUnit,
+    HideOverlays,
+    LockClass,
+    NextAnnotation,
+    PreviousAnnotation,
+}
+
+impl Action {
+    /// Every action, in display order.
+    pub const ALL: &'static [Action] = &[
+        Action::Cancel,
+        Action::Delete,
+        Action::Undo,
+        Action::Redo,
+        Action::PanLeft,
+        Action::PanRight,
+        Action::PanUp,
+        Action::PanDown,
+        Action::RecenterView,
+        Action::NextImage,
+        Action::PreviousImage,
+        Action::ToggleIsolate,
+        Action::ToggleCoordinateUnit,
+        Action::HideOverlays,
+        Action::LockClass,
+        Action::NextAnnotation,
+        Action::PreviousAnnotation,
+    ];
+
+    /// Human-readable action name.
+    pub fn label(&self) -> &'static str {
+        match self {
+            Action::Cancel => "Cancel / finish line",
+            Action::Delete => "Delete selected",
+            Action::Undo => "Undo",
+            Action::Redo => "Redo",
+            Action::PanLeft => "Pan left",
+            Action::PanRight => "Pan right",
+            Action::PanUp => "Pan up",
+            Action::PanDown => "Pan down",
+            Action::RecenterView => "Recenter view",
+            Action::NextImage => "Next image",
+            Action::PreviousImage => "Previous image",
+            Action::ToggleIsolate => "Isolate selected",
+            Action::ToggleCoordinateUnit => "Toggle pixel coordinates",
+            Action::HideOverlays => "Hide annotations (hold)",
+            Action::LockClass => "Lock class for new annotations",
+            Action::NextAnnotation => "Select next annotation",
+            Action::PreviousAnnotation => "Select previous annotation",
+        }
+    }
+}
+
+/// A key plus the modifiers that must be held with it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Binding {
+    pub key: egui::Key,
+    /// Ctrl (Cmd on macOS)
+    pub command: bool,
+    pub shift: bool,
+    pub alt: bool,
+}
+
+impl Binding {
+    /// Binding for a key with no modifiers.
+    pub const fn key(key: egui::Key) -> Self {
+        Self {
+            key,
+            command: false,
+            shift: false,
+            alt: false,
+        }
+    }
+
+    /// Binding for Shift plus a key.
+    pub const fn shift(key: egui::Key) -> Self {
+        Self {
+            shift: true,
+            ..Self::key(key)
+        }
+    }
+
+    /// Binding for Ctrl/Cmd plus a key.
+    pub const fn command(key: egui::Key) -> Self {
+        Self {
+            command: true,
+            ..Self::key(key)
+        }
+    }
+
+    /// Binding for Ctrl/Cmd+Shift plus a key.
+    pub const fn command_shift(key: egui::Key) -> Self {
+        Self {
+            shift: true,
+            ..Self::command(key)
+        }
+    }
+
+    /// Create a binding from a key press and its modifiers.
+    pub fn from_input(key: egui::Key, modifiers: egui::Modifiers) -> Self {
+        Self {
+            key,
+            command: modifiers.command,
+            shift: modifiers.shift,
+            alt: modifiers.alt,
+        }
+    }
+
+    /// Check if this binding was pressed this frame with exactly its modifiers.
+    pub fn pressed(&self, input: &egui::InputState) -> bool {
+        input.key_pressed(self.key)
+            && input.modifiers.command == self.command
+            && input.modifiers.shift == self.shift
+            && input.modifiers.alt == self.alt
+    }
+
+    /// Check if this binding was pressed this frame, and if so remove the
+    /// key press so egui doesn't also act on it (e.g. Tab moving focus).
+    pub fn consume(&self, input: &mut egui::InputState) -> bool {
+        self.pressed(input) && input.consume_key(input.modifiers, self.key)
+    }
+
+    /// Check if this binding's key is held down with exactly its modifiers.
+    pub fn held(&self, input: &egui::InputState) -> bool {
+        input.key_down(self.key)
+            && input.modifiers.command == self.command
+            && input.modifiers.shift == self.shift
+            && input.modifiers.alt == self.alt
+    }
+
+    /// Format for display, e.g. "Ctrl+Shift+Z".
+    pub fn label(&self) -> String {
+        let mut label = String::new();
+        if self.command {
+            label.push_str("Ctrl+");
+        }
+        if self.shift {
+            label.push_str("Shift+");
+        }
+        if self.alt {
+            label.push_str("Alt+");
+        }
+        label.push_str(self.key.name());
+        label
+    }
+}
+
+/// Key bindings for every action.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Keymap {
+    bindings: BTreeMap<Action, Vec<Binding>>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        use egui::Key;
+
+        let bindings = BTreeMap::from([
+            (Action::Cancel, vec![Binding::key(Key::Escape)]),
+            (Action::Delete, vec![Binding::key(Key::Delete), Binding::key(Key::Backspace)]),
+            (Action::Undo, vec![Binding::command(Key::Z)]),
+            (Action::Redo, vec![Binding::command_shift(Key::Z), Binding::command(Key::Y)]),
+            (Action::PanLeft, vec![Binding::key(Key::ArrowLeft), Binding::key(Key::A)]),
+            (Action::PanRight, vec![Binding::key(Key::ArrowRight), Binding::key(Key::D)]),
+            (Action::PanUp, vec![Binding::key(Key::ArrowUp), Binding::key(Key::W)]),
+            (Action::PanDown, vec![Binding::key(Key::ArrowDown), Binding::key(Key::S)]),
+            (Action::RecenterView, vec![Binding::key(Key::Home)]),
+            (Action::NextImage, vec![Binding::key(Key::PageDown)]),
+            (Action::PreviousImage, vec![Binding::key(Key::PageUp)]),
+            (Action::ToggleIsolate, vec![Binding::key(Key::I)]),
+            (Action::ToggleCoordinateUnit, vec![Binding::key(Key::U)]),
+            (Action::HideOverlays, vec![Binding::key(Key::H)]),
+            (Action::LockClass, vec![Binding::key(Key::L)]),
+            (Action::NextAnnotation, vec![Binding::key(Key::Tab)]),
+            (Action::PreviousAnnotation, vec![Binding::shift(Key::Tab)]),
+        ]);
+        Self { bindings }
+    }
+}
+
+impl Keymap {
+    /// Get the bindings for an action.
+    pub fn bindings(&self, action: Action) -> &[Binding] {
+        self.bindings.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    /// Check if any binding for an action was pressed this frame.
+    pub fn pressed(&self, input: &egui::InputState, action: Action) -> bool {
+        self.bindings(action).iter().any(|b| b.pressed(input))
+    }
+
+    /// Check if any binding for an action was pressed this frame, consuming
+    /// the key press.
+    pub fn consume(&self, input: &mut egui::InputState, action: Action) -> bool {
+        self.bindings(action).iter().any(|b| b.consume(input))
+    }
+
+    /// Check if any binding for an action is held down.
+    pub fn held(&self, input: &egui::InputState, action: Action) -> bool {
+        self.bindings(action).iter().any(|b| b.held(input))
+    }
+
+    /// Label of an action's first binding, for menus (empty if unbound).
+    pub fn shortcut_text(&self, action: Action) -> String {
+        self.bindings(action).first().map(Binding::label).unwrap_or_default()
+    }
+
+    /// Find the action a binding is assigned to.
+    pub fn action_for(&self, binding: &Binding) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(_, bindings)| bindings.contains(binding))
+            .map(|(action, _)| *action)
+    }
+
+    /// Bind a key to an action.
+    ///
+    /// Returns the conflicting action, without changing anything, if the
+    /// binding is already assigned to a different action.
+    pub fn bind(&mut self, action: Action, binding: Binding) -> Result<(), Action> {
+        match self.action_for(&binding) {
+            Some(existing) if existing != action => Err(existing),
+            Some(_) => Ok(()),
+            None => {
+                self.bindings.entry(action).or_default().push(binding);
+                Ok(())
+            }
+        }
+    }
+
+    /// Remove a binding from an action.
+    pub fn unbind(&mut self, action: Action, binding: &Binding) {
+        if let Some(bindings) = self.bindings.get_mut(&action) {
+            bindings.retain(|b| b != binding);
+        }
+    }
+
+    /// Add default bindings for actions missing from the map (e.g. actions
+    /// added since the keymap was saved), skipping any that now conflict.
+    pub fn fill_missing(&mut self) {
+        for (action, bindings) in Keymap::default().bindings {
+            if self.bindings.contains_key(&action) {
+                continue;
+            }
+            for binding in bindings {
+                let _ = self.bind(action, binding);
+            }
+        }
+    }
+
+    /// Find bindings assigned to more than one action.
+    pub fn conflicts(&self) -> Vec<(Binding, Action, Action)> {
+        let all: Vec<(Action, Binding)> = self
+            .bindings
+            .iter()
+            .flat_map(|(action, bindings)| bindings.iter().map(move |b| (*action, *b)))
+            .collect();
+
+        let mut conflicts = Vec::new();
+        for (i, (first, binding)) in all.iter().enumerate() {
+            for (second, other) in &all[i + 1..] {
+                if binding == other {
+                    conflicts.push((*binding, *first, *second));
+                }
+            }
+        }
+        conflicts
+    }
+}
+
+/// Binding slot being captured in the editor.
+#[derive(Debug, Clone, Copy)]
+struct Capture {
+    action: Action,
+    /// Binding being replaced, or None to add a new one
+    replacing: Option<Binding>,
+}
+
+/// Display the shortcut editor.
+///
+/// Click a binding to rebind it or "+" to add one, then press the new key
+/// combination (Escape cancels). Bindings already used by another action
+/// are rejected.
+pub fn show_editor(ui: &mut egui::Ui, keymap: &mut Keymap) {
+    let capture_id = ui.id().with("keymap_capture");
+    let error_id = ui.id().with("keymap_error");
+    let mut capture: Option<Capture> = ui.data(|d| d.get_temp(capture_id)).flatten();
+    let mut error: Option<String> = ui.data(|d| d.get_temp(error_id)).flatten();
+
+    // Take the next key press for the slot being captured
+    if let Some(current) = capture {
+        let pressed = ui.input(|i| {
+            i.events.iter().find_map(|event| match event {
+                egui::Event::Key { key, pressed: true, modifiers, .. } => Some(Binding::from_input(*key, *modifiers)),
+                _ => None,
+            })
+        });
+        if let Some(binding) = pressed {
+            capture = None;
+            if binding != Binding::key(egui::Key::Escape) || current.action == Action::Cancel {
+                if let Some(old) = current.replacing {
+                    keymap.unbind(current.action, &old);
+                }
+                match keymap.bind(current.action, binding) {
+                    Ok(()) => error = None,
+                    Err(existing) => {
+                        // Put back the binding being replaced
+                        if let Some(old) = current.replacing {
+                            let _ = keymap.bind(current.action, old);
+                        }
+                        error = Some(format!("{} is already used by \"{}\"", binding.label(), existing.label()));
+                    }
+                }
+            }
+        }
+    }
+
+    egui::Grid::new("keymap_grid").num_columns(2).striped(true).show(ui, |ui| {
+        for &action in Action::ALL {
+            ui.label(action.label());
+            ui.horizontal(|ui| {
+                for binding in keymap.bindings(action).to_vec() {
+                    let capturing = capture.is_some_and(|c| c.action == action && c.replacing == Some(binding));
+                    let text = if capturing { "Press a key...".to_string() } else { binding.label() };
+                    let response = ui.selectable_label(capturing, text).on_hover_text("Click to rebind, right-click to remove");
+                    if response.clicked() {
+                        capture = Some(Capture { action, replacing: Some(binding) });
+                        response.request_focus();
+                    }
+                    if response.secondary_clicked() {
+                        keymap.unbind(action, &binding);
+                    }
+                }
+
+                let adding = capture.is_some_and(|c| c.action == action && c.replacing.is_none());
+                let response = ui.selectable_label(adding, if adding { "Press a key..." } else { "+" });
+                if response.clicked() {
+                    capture = Some(Capture { action, replacing: None });
+                    response.request_focus();
+                }
+            });
+            ui.end_row();
+        }
+    });
+
+    if let Some(ref message) = error {
+        ui.colored_label(ui.visuals().error_fg_color, message);
+    }
+    if ui.button("Reset Shortcuts").clicked() {
+        *keymap = Keymap::default();
+        capture = None;
+        error = None;
+    }
+
+    ui.data_mut(|d| {
+        d.insert_temp(capture_id, capture);
+        d.insert_temp(error_id, error);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_keymap_has_no_conflicts() {
+        let keymap = Keymap::default();
+        assert_eq!(keymap.conflicts(), vec![]);
+        for action in Action::ALL {
+            assert!(!keymap.bindings(*action).is_empty(), "{:?} is unbound", action);
+        }
+    }
+
+    #[test]
+    fn test_bind_rejects_conflicts() {
+        let mut keymap = Keymap::default();
+
+        assert_eq!(keymap.bind(Action::NextImage, Binding::command(egui::Key::Z)), Err(Action::Undo));
+        assert_eq!(keymap.bindings(Action::NextImage), &[Binding::key(egui::Key::PageDown)]);
+
+        assert_eq!(keymap.bind(Action::NextImage, Binding::key(egui::Key::N)), Ok(()));
+        assert_eq!(keymap.action_for(&Binding::key(egui::Key::N)), Some(Action::NextImage));
+        assert_eq!(keymap.conflicts(), vec![]);
+    }
+
+    #[test]
+    fn test_held() {
+        let keymap = Keymap::default();
+        let mut input = egui::InputState::default();
+        assert!(!keymap.held(&input, Action::HideOverlays));
+
+        input.keys_down.insert(egui::Key::H);
+        assert!(keymap.held(&input, Action::HideOverlays));
+
+        // Other modifiers make it a different shortcut
+        input.modifiers.command = true;
+        assert!(!keymap.held(&input, Action::HideOverlays));
+    }
+
+    #[test]
+    fn test_consume() {
+        let keymap = Keymap::default();
+        let mut input = egui::InputState::default();
+        let tab = |modifiers| egui::Event::Key { key: egui::Key::Tab, physical_key: None, pressed: true, repeat: false, modifiers };
+        input.modifiers = egui::Modifiers::SHIFT;
+        input.events.push(tab(egui::Modifiers::SHIFT));
+
+        // Shift+Tab is not a plain Tab, and is only reported once
+        assert!(!keymap.consume(&mut input, Action::NextAnnotation));
+        assert!(keymap.consume(&mut input, Action::PreviousAnnotation));
+        assert!(!keymap.consume(&mut input, Action::PreviousAnnotation));
+        assert!(input.events.is_empty());
+    }
+
+    #[test]
+    fn test_fill_missing() {
+        let mut keymap = Keymap::default();
+        keymap.bindings.remove(&Action::RecenterView);
+        keymap.bindings.insert(Action::NextImage, vec![Binding::key(egui::Key::Home)]);
+
+        keymap.fill_missing();
+
+        // The default Home binding now belongs to another action
+        assert_eq!(keymap.bindings(Action::RecenterView), &[]);
+        assert_eq!(keymap.conflicts(), vec![]);
+    }
+}
@@ -0,0 +1,117 @@
+// Copyright (c) 2025, Jason Jenkins
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Annotation name label layout.
+//!
+//! Labels start centered on their anchor (usually the annotation centroid)
+//! and are greedily nudged to the nearest free spot so that labels of
+//! nested or adjacent annotations don't overlap.
+
+/// Space (screen points) kept between neighboring labels.
+const LABEL_GAP: f32 = 2.0;
+
+/// Number of rings of candidate positions tried around each anchor.
+const MAX_RINGS: usize = 8;
+
+/// Place labels without overlap.
+///
+/// Each label is given as an (anchor, size) pair and is placed in order at
+/// the first free candidate position closest to its anchor, clamped to
+/// `bounds`. If no free position is found the label stays on its anchor.
+/// Returns one rectangle per label.
+pub fn layout_labels(labels: &[(egui::Pos2, egui::Vec2)], bounds: egui::Rect) -> Vec<egui::Rect> {
+    let mut placed: Vec<egui::Rect> = Vec::with_capacity(labels.len());
+
+    for &(anchor, size) in labels {
+        let base = egui::Rect::from_center_size(anchor, size);
+        let free = (0..MAX_RINGS)
+            .flat_map(|ring| candidate_offsets(ring, size))
+            .map(|offset| clamp_to_bounds(base.translate(offset), bounds))
+            .find(|rect| !placed.iter().any(|other| overlaps(rect, other)));
+        placed.push(free.unwrap_or_else(|| clamp_to_bounds(base, bounds)));
+    }
+
+    placed
+}
+
+/// Offsets tried at a given distance ring: below, above, right, left,
+/// then the diagonals.
+fn candidate_offsets(ring: usize, size: egui::Vec2) -> Vec<egui::Vec2> {
+    if ring == 0 {
+        return vec![egui::Vec2::ZERO];
+    }
+    let dy = ring as f32 * (size.y + LABEL_GAP);
+    let dx = ring as f32 * (size.x / 2.0 + LABEL_GAP);
+    vec![
+        egui::vec2(0.0, dy),
+        egui::vec2(0.0, -dy),
+        egui::vec2(dx, 0.0),
+        egui::vec2(-dx, 0.0),
+        egui::vec2(dx, dy),
+        egui::vec2(-dx, dy),
+        egui::vec2(dx, -dy),
+        egui::vec2(-dx, -dy),
+    ]
+}
+
+/// Check if two rectangles overlap, including the gap between labels.
+fn overlaps(a: &egui::Rect, b: &egui::Rect) -> bool {
+    a.min.x < b.max.x + LABEL_GAP
+        && b.min.x < a.max.x + LABEL_GAP
+        && a.min.y < b.max.y + LABEL_GAP
+        && b.min.y < a.max.y + LABEL_GAP
+}
+
+/// Shift a rectangle so it lies inside `bounds` (aligning to the top-left
+/// if it doesn't fit).
+fn clamp_to_bounds(rect: egui::Rect, bounds: egui::Rect) -> egui::Rect {
+    let shift = |min: f32, max: f32, bounds_min: f32, bounds_max: f32| {
+        let mut delta = (bounds_max - max).min(0.0);
+        if min + delta < bounds_min {
+            delta = bounds_min - min;
+        }
+        delta
+    };
+    rect.translate(egui::vec2(
+        shift(rect.min.x, rect.max.x, bounds.min.x, bounds.max.x),
+        shift(rect.min.y, rect.max.y, bounds.min.y, bounds.max.y),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bounds() -> egui::Rect {
+        egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(400.0, 300.0))
+    }
+
+    #[test]
+    fn test_separate_labels_stay_centered() {
+        let size = egui::vec2(40.0, 14.0);
+        let rects = layout_labels(&[(egui::pos2(100.0, 100.0), size), (egui::pos2(300.0, 200.0), size)], bounds());
+        assert_eq!(rects[0].center(), egui::pos2(100.0, 100.0));
+        assert_eq!(rects[1].center(), egui::pos2(300.0, 200.0));
+    }
+
+    #[test]
+    fn test_overlapping_labels_are_nudged_apart() {
+        let size = egui::vec2(40.0, 14.0);
+        let labels = vec![(egui::pos2(200.0, 150.0), size); 5];
+        let rects = layout_labels(&labels, bounds());
+
+        assert_eq!(rects[0].center(), egui::pos2(200.0, 150.0));
+        for (i, a) in rects.iter().enumerate() {
+            assert!(bounds().contains_rect(*a));
+            for b in &rects[i + 1..] {
+                assert!(!a.intersects(*b), "{:?} overlaps {:?}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn test_labels_clamped_to_bounds() {
+        let rects = layout_labels(&[(egui::pos2(395.0, -5.0), egui::vec2(40.0, 14.0))], bounds());
+        assert_eq!(rects[0], egui::Rect::from_min_size(egui::pos2(360.0, 0.0), egui::vec2(40.0, 14.0)));
+    }
+}
@@ -0,0 +1,54 @@
+// Copyright (c) 2025, Jason Jenkins
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Layers panel.
+//!
+//! This module provides the panel listing annotation groups with
+//! visibility toggles and the active group for new annotations.
+
+use crate::models::{layers::Layers, project::ProjectData};
+
+/// Display the layers panel.
+pub fn show(
+    ui: &mut egui::Ui,
+    project: &Option<ProjectData>,
+    layers: &mut Layers,
+    new_group_name: &mut String,
+) {
+    ui.heading("Layers");
+    ui.separator();
+
+    let annotations = project.as_ref().map_or(&[][..], |p| &p.annotations[..]);
+    let groups = layers.group_names(annotations);
+
+    // Ungrouped annotations are always visible, but can be the active target
+    ui.radio_value(&mut layers.active, None, "(no group)");
+
+    for group in &groups {
+        ui.horizontal(|ui| {
+            let mut visible = layers.is_group_visible(group);
+            if ui.checkbox(&mut visible, "").on_hover_text("Show/hide group").changed() {
+                layers.set_group_visible(group, visible);
+            }
+
+            let count = annotations
+                .iter()
+                .filter(|a| a.group.as_deref() == Some(group.as_str()))
+                .count();
+            ui.radio_value(
+                &mut layers.active,
+                Some(group.clone()),
+                format!("{} ({})", group, count),
+            );
+        });
+    }
+
+    ui.horizontal(|ui| {
+        ui.text_edit_singleline(new_group_name);
+        let name = new_group_name.trim().to_string();
+        if ui.add_enabled(!name.is_empty(), egui::Button::new("Add")).clicked() {
+            layers.active = Some(name);
+            new_group_name.clear();
+        }
+    });
+}
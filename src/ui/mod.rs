@@ -4,5 +4,14 @@
 //! UI components for the ROIDS application.
 
 pub mod canvas;
+pub mod labels;
+pub mod keymap;
+pub mod layers;
+pub mod notifications;
+pub mod preferences;
 pub mod properties;
+pub mod render_cache;
+pub mod statistics;
+pub mod templates;
 pub mod toolbar;
+pub mod view;
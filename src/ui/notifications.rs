@@ -0,0 +1,96 @@
+// Copyright (c) 2025, Jason Jenkins
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Toast notifications.
+//!
+//! This module provides short-lived messages shown in the corner of the
+//! window to report the outcome of operations such as imports and exports.
+
+use std::time::{Duration, Instant};
+
+/// How long a notification stays on screen.
+const NOTIFICATION_LIFETIME: Duration = Duration::from_secs(5);
+
+/// Severity of a notification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotificationLevel {
+    Info,
+    Warning,
+    Error,
+}
+
+/// A single notification message.
+struct Notification {
+    level: NotificationLevel,
+    message: String,
+    created: Instant,
+}
+
+/// Queue of active notifications.
+#[derive(Default)]
+pub struct Notifications {
+    active: Vec<Notification>,
+}
+
+impl Notifications {
+    /// Create an empty notification queue.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a notification with the given level.
+    pub fn push(&mut self, level: NotificationLevel, message: impl Into<String>) {
+        self.active.push(Notification {
+            level,
+            message: message.into(),
+            created: Instant::now(),
+        });
+    }
+
+    /// Add an informational notification.
+    pub fn info(&mut self, message: impl Into<String>) {
+        self.push(NotificationLevel::Info, message);
+    }
+
+    /// Add a warning notification.
+    pub fn warning(&mut self, message: impl Into<String>) {
+        self.push(NotificationLevel::Warning, message);
+    }
+
+    /// Add an error notification.
+    pub fn error(&mut self, message: impl Into<String>) {
+        self.push(NotificationLevel::Error, message);
+    }
+
+    /// Display active notifications and drop expired ones.
+    pub fn show(&mut self, ctx: &egui::Context) {
+        self.active
+            .retain(|n| n.created.elapsed() < NOTIFICATION_LIFETIME);
+
+        if self.active.is_empty() {
+            return;
+        }
+
+        egui::Area::new(egui::Id::new("notifications"))
+            .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-12.0, -40.0))
+            .order(egui::Order::Foreground)
+            .interactable(false)
+            .show(ctx, |ui| {
+                for notification in &self.active {
+                    let color = match notification.level {
+                        NotificationLevel::Info => egui::Color32::from_gray(220),
+                        NotificationLevel::Warning => egui::Color32::from_rgb(255, 200, 80),
+                        NotificationLevel::Error => egui::Color32::from_rgb(255, 100, 100),
+                    };
+
+                    egui::Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.label(egui::RichText::new(&notification.message).color(color));
+                    });
+                    ui.add_space(4.0);
+                }
+            });
+
+        // Keep repainting so notifications disappear on time
+        ctx.request_repaint_after(Duration::from_millis(250));
+    }
+}
@@ -0,0 +1,271 @@
+// Copyright (c) 2025, Jason Jenkins
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Preferences window.
+//!
+//! This module provides a window for editing application settings that
+//! don't warrant a place in the toolbar.
+
+use crate::ui::keymap::{self, Keymap};
+use crate::models::settings::{AfterFinish, AnnotationFormat, AutosaveLocation, ColorRamp, FillRule, HandleShape, LineCap, OverlayCompositing, SessionRestore, Settings};
+
+/// Display the preferences window while `open` is true.
+pub fn show(ctx: &egui::Context, open: &mut bool, settings: &mut Settings, keymap: &mut Keymap) {
+    egui::Window::new("Preferences")
+        .open(open)
+        .resizable(false)
+        .collapsible(false)
+        .show(ctx, |ui| {
+            ui.heading("Rendering");
+            ui.separator();
+
+            ui.checkbox(&mut settings.antialiasing, "Anti-aliased lines");
+
+            ui.horizontal(|ui| {
+                ui.label("Line caps and joins:");
+                ui.radio_value(&mut settings.line_cap, LineCap::Round, "Round");
+                ui.radio_value(&mut settings.line_cap, LineCap::Square, "Square");
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("Vertex handles:");
+                ui.radio_value(&mut settings.handle_shape, HandleShape::Circle, "Circle");
+                ui.radio_value(&mut settings.handle_shape, HandleShape::Square, "Square");
+                ui.radio_value(&mut settings.handle_shape, HandleShape::Diamond, "Diamond");
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("Fill rule:");
+                ui.radio_value(&mut settings.fill_rule, FillRule::EvenOdd, "Even-odd");
+                ui.radio_value(&mut settings.fill_rule, FillRule::NonZero, "Nonzero");
+            })
+            .response
+            .on_hover_text("Which parts of self-overlapping polygons are shaded and counted in areas");
+
+            ui.horizontal(|ui| {
+                ui.label("Annotations:");
+                ui.radio_value(&mut settings.overlay_compositing, OverlayCompositing::AfterAdjustment, "Full color");
+                ui.radio_value(&mut settings.overlay_compositing, OverlayCompositing::WithAdjustment, "Match image opacity");
+            })
+            .response
+            .on_hover_text("Whether the image opacity setting also fades annotations");
+
+            ui.horizontal(|ui| {
+                ui.label("Dash length:");
+                ui.add(
+                    egui::DragValue::new(&mut settings.dash_length)
+                        .range(2.0..=50.0)
+                        .suffix(" pt"),
+                )
+                .on_hover_text("Length of dashes and gaps on annotations with dashed edges");
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("Ruler grid:");
+                ui.add(
+                    egui::DragValue::new(&mut settings.ruler_major_spacing)
+                        .range(1..=10_000)
+                        .prefix("every ")
+                        .suffix(" px"),
+                );
+                ui.add(
+                    egui::DragValue::new(&mut settings.ruler_subdivisions)
+                        .range(1..=20)
+                        .suffix(" subdivisions"),
+                );
+            })
+            .response
+            .on_hover_text("Labeled major lines and unlabeled minor lines of the ruler grid (View menu)");
+
+            ui.horizontal(|ui| {
+                ui.label("Heatmap:");
+                ui.add(
+                    egui::DragValue::new(&mut settings.heatmap_resolution)
+                        .range(4..=512)
+                        .suffix(" cells across"),
+                );
+                ui.radio_value(&mut settings.heatmap_ramp, ColorRamp::Heat, "Heat");
+                ui.radio_value(&mut settings.heatmap_ramp, ColorRamp::Viridis, "Viridis");
+                ui.radio_value(&mut settings.heatmap_ramp, ColorRamp::Grayscale, "Gray");
+            })
+            .response
+            .on_hover_text("Grid size and colors of the annotation density heatmap (View menu)");
+
+            ui.add_space(8.0);
+            ui.heading("Drawing");
+            ui.separator();
+
+            ui.horizontal(|ui| {
+                ui.label("Complexity warning above:");
+                ui.add(
+                    egui::DragValue::new(&mut settings.complexity_threshold)
+                        .range(3..=100_000)
+                        .suffix(" vertices"),
+                );
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("Simplified drawing above:");
+                ui.add(
+                    egui::DragValue::new(&mut settings.lod_vertex_threshold)
+                        .range(100..=1_000_000)
+                        .suffix(" vertices"),
+                )
+                .on_hover_text("Larger annotations are outlined as a single path, with vertex handles only while selected");
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("Simplify tolerance:");
+                ui.add(
+                    egui::DragValue::new(&mut settings.simplify_tolerance)
+                        .range(0.0001..=0.05)
+                        .speed(0.0001)
+                        .fixed_decimals(4),
+                );
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("Snap radius:");
+                ui.add(
+                    egui::DragValue::new(&mut settings.snap_radius)
+                        .range(2.0..=100.0)
+                        .suffix(" pt"),
+                )
+                .on_hover_text("How close the cursor must be to a vertex or edge to snap to it");
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("Box aspect ratio:");
+                ui.add(egui::DragValue::new(&mut settings.box_aspect_ratio).range(0.1..=10.0).speed(0.01).max_decimals(3))
+                    .on_hover_text("Width to height ratio kept while dragging a box with Shift held");
+                for (label, ratio) in [("1:1", 1.0), ("4:3", 4.0 / 3.0), ("16:9", 16.0 / 9.0)] {
+                    if ui.small_button(label).clicked() {
+                        settings.box_aspect_ratio = ratio;
+                    }
+                }
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("Double-click time:");
+                ui.add(
+                    egui::DragValue::new(&mut settings.double_click_time)
+                        .range(0.1..=2.0)
+                        .speed(0.01)
+                        .suffix(" s"),
+                )
+                .on_hover_text("Longest pause between two clicks that close a polygon");
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("Trace vertex spacing:");
+                ui.add(
+                    egui::DragValue::new(&mut settings.min_segment_length)
+                        .range(1.0..=200.0)
+                        .suffix(" pt"),
+                )
+                .on_hover_text("Minimum distance between vertices placed in trace mode");
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("Keyboard pan step:");
+                ui.add(
+                    egui::DragValue::new(&mut settings.pan_step)
+                        .range(5.0..=500.0)
+                        .suffix(" pt"),
+                );
+            });
+
+            ui.checkbox(&mut settings.integer_pixels, "Snap vertices to whole pixels")
+                .on_hover_text("Round placed and dragged vertices so exported pixel coordinates are integers");
+
+            ui.checkbox(&mut settings.inherit_class, "Repeat the last custom class")
+                .on_hover_text("After renaming e.g. \"car\", new annotations of that type are named \"car 1\", \"car 2\"...");
+
+            ui.horizontal(|ui| {
+                ui.label("After finishing:");
+                ui.radio_value(&mut settings.after_finish, AfterFinish::Keep, "Keep tool");
+                ui.radio_value(&mut settings.after_finish, AfterFinish::Continue, "Start next")
+                    .on_hover_text("Keep the drawing tool active and immediately start a new annotation");
+                ui.radio_value(&mut settings.after_finish, AfterFinish::Select, "Switch to Select");
+            });
+
+            ui.add_space(8.0);
+            ui.heading("Files");
+            ui.separator();
+
+            ui.checkbox(&mut settings.load_sidecar, "Load sidecar annotations when opening an image")
+                .on_hover_text("frame_0001.png loads frame_0001.json or frame_0001.yaml if present");
+
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut settings.merge_collinear_on_export, "Merge straight edges on export")
+                    .on_hover_text("Leave out vertices that lie on a straight edge; project saves keep every vertex");
+                ui.add_enabled(
+                    settings.merge_collinear_on_export,
+                    egui::DragValue::new(&mut settings.collinear_tolerance)
+                        .range(0.0..=10.0)
+                        .speed(0.05)
+                        .suffix("°"),
+                )
+                .on_hover_text("Largest bend at a vertex that still counts as straight");
+            });
+
+            ui.checkbox(&mut settings.export_vertex_indices, "Number vertices in JSON and SVG exports")
+                .on_hover_text("Adds a vertex_indices array to each annotation in JSON and index labels in SVG, for debugging");
+
+            ui.horizontal(|ui| {
+                ui.label("Reopen last session:");
+                ui.radio_value(&mut settings.restore_session, SessionRestore::Off, "Never");
+                ui.radio_value(&mut settings.restore_session, SessionRestore::Ask, "Ask");
+                ui.radio_value(&mut settings.restore_session, SessionRestore::Auto, "Always");
+            });
+
+            ui.add_space(8.0);
+            ui.heading("Autosave");
+            ui.separator();
+
+            ui.horizontal(|ui| {
+                ui.label("Interval:");
+                ui.add(
+                    egui::DragValue::new(&mut settings.autosave_interval)
+                        .range(0..=3600)
+                        .suffix(" s"),
+                )
+                .on_hover_text("0 disables autosave");
+            });
+
+            ui.add_enabled_ui(settings.autosave_interval > 0, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Format:");
+                    ui.radio_value(&mut settings.autosave_format, AnnotationFormat::Json, "JSON");
+                    ui.radio_value(&mut settings.autosave_format, AnnotationFormat::Yaml, "YAML");
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("Location:");
+                    ui.radio_value(&mut settings.autosave_location, AutosaveLocation::BesideMedia, "Beside image");
+                    ui.radio_value(&mut settings.autosave_location, AutosaveLocation::TempDir, "Temp folder");
+                    ui.radio_value(&mut settings.autosave_location, AutosaveLocation::Folder, "Folder:");
+                });
+
+                if settings.autosave_location == AutosaveLocation::Folder {
+                    ui.horizontal(|ui| {
+                        if ui.button("Choose...").clicked() {
+                            if let Some(dir) = rfd::FileDialog::new().pick_folder() {
+                                settings.autosave_folder = dir;
+                            }
+                        }
+                        if settings.autosave_folder.as_os_str().is_empty() {
+                            ui.weak("None chosen, saving to the temp folder");
+                        } else {
+                            ui.label(settings.autosave_folder.display().to_string());
+                        }
+                    });
+                }
+            });
+
+            ui.add_space(8.0);
+            egui::CollapsingHeader::new("Keyboard Shortcuts").show(ui, |ui| {
+                keymap::show_editor(ui, keymap);
+            });
+        });
+}
@@ -6,22 +6,50 @@
 //! This module provides the properties panel for viewing and editing
 //! annotation metadata such as names, types, and vertex coordinates.
 
-use crate::models::project::ProjectData;
+use super::render_cache;
+use crate::models::{
+    annotation::{Annotation, AnnotationRole, AnnotationType, LineStyle, Point},
+    project::ProjectData,
+    settings::CoordinateUnit,
+    vertex_selection::VertexSelection,
+};
+use crate::io::{media::MediaInfo, validation};
+use crate::util::{color, geometry};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
 
 /// Action from the properties panel.
 pub enum PropertiesAction {
     None,
     SelectAnnotation(usize),
     DeleteAnnotation(usize),
+    OffsetAnnotation(usize),
+    /// Remove the selected vertices of an annotation
+    DeleteVertices(usize),
+    /// Add a circle annotation enclosing an annotation's vertices
+    CreateEnclosingCircle(usize),
 }
 
 /// Display the properties panel showing annotations and their details.
+#[allow(clippy::too_many_arguments)]
 pub fn show(
     ui: &mut egui::Ui,
     project: &mut Option<ProjectData>,
     selected_annotation: Option<usize>,
+    pinned_annotation: &mut Option<usize>,
+    group_names: &[String],
+    reference: Option<&ProjectData>,
+    offset_distance: &mut f64,
+    unit: CoordinateUnit,
+    media_info: Option<&MediaInfo>,
+    recent_colors: &mut Vec<[u8; 4]>,
+    vertex_selection: &mut VertexSelection,
 ) -> PropertiesAction {
     let mut action = PropertiesAction::None;
+    if let Some(info) = media_info {
+        show_media_info(ui, info);
+        ui.separator();
+    }
     ui.heading("Annotations");
     ui.separator();
 
@@ -86,11 +114,27 @@ pub fn show(
 
     ui.separator();
 
-    // Properties section
-    if let Some(idx) = selected_annotation {
+    // Drop a pin whose annotation no longer exists
+    let annotation_count = project.as_ref().map_or(0, |p| p.annotations.len());
+    if pinned_annotation.is_some_and(|idx| idx >= annotation_count) {
+        *pinned_annotation = None;
+    }
+
+    // Properties section, locked to the pinned annotation if there is one
+    if let Some(idx) = pinned_annotation.or(selected_annotation) {
         if let Some(proj) = project {
             if let Some(annotation) = proj.annotations.get_mut(idx) {
-                ui.heading("Properties");
+                ui.horizontal(|ui| {
+                    ui.heading("Properties");
+                    if pinned_annotation.is_some() {
+                        ui.colored_label(egui::Color32::LIGHT_BLUE, "📌 Pinned");
+                        if ui.small_button("Unpin").on_hover_text("Follow the canvas selection again").clicked() {
+                            *pinned_annotation = None;
+                        }
+                    } else if ui.small_button("📌 Pin").on_hover_text("Keep showing this annotation while selecting others").clicked() {
+                        *pinned_annotation = Some(idx);
+                    }
+                });
                 ui.separator();
 
                 // Editable name
@@ -99,12 +143,332 @@ pub fn show(
                     ui.text_edit_singleline(&mut annotation.name);
                 });
 
+                // Group assignment
+                ui.horizontal(|ui| {
+                    ui.label("Group:");
+                    egui::ComboBox::from_id_source("annotation_group")
+                        .selected_text(annotation.group.as_deref().unwrap_or("(none)"))
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut annotation.group, None, "(none)");
+                            for group in group_names {
+                                ui.selectable_value(&mut annotation.group, Some(group.clone()), group);
+                            }
+                        });
+                });
+
+                // Counting role
+                ui.horizontal(|ui| {
+                    ui.label("Role:");
+                    egui::ComboBox::from_id_source("annotation_role")
+                        .selected_text(annotation.role.label())
+                        .show_ui(ui, |ui| {
+                            for role in AnnotationRole::ALL {
+                                ui.selectable_value(&mut annotation.role, role, role.label());
+                            }
+                        });
+                });
+
+                show_color(ui, annotation, recent_colors);
+
+                // Edge style, e.g. dashed for reference regions
+                if annotation.annotation_type != AnnotationType::Point {
+                    ui.horizontal(|ui| {
+                        ui.label("Edges:");
+                        ui.radio_value(&mut annotation.line_style, LineStyle::Solid, "Solid");
+                        ui.radio_value(&mut annotation.line_style, LineStyle::Dashed, "Dashed");
+                    });
+                }
+
+                ui.checkbox(&mut annotation.export, "Include in exports")
+                    .on_hover_text("Unchecked annotations are kept in project files but left out of COCO, YOLO, GeoJSON, mesh, and image exports");
+
+                // Free-text notes (empty clears them)
+                ui.label("Notes:");
+                let mut notes = annotation.notes.clone().unwrap_or_default();
+                if ui
+                    .add(egui::TextEdit::multiline(&mut notes).desired_rows(3).hint_text("e.g. partially occluded"))
+                    .changed()
+                {
+                    annotation.notes = (!notes.trim().is_empty()).then_some(notes);
+                }
+
                 ui.label(format!("Type: {:?}", annotation.annotation_type));
                 ui.label(format!("Closed: {}", annotation.is_closed()));
                 ui.label(format!("Vertices: {}", annotation.vertex_count()));
+
+                // Quality heuristic for review triage, with the reasons on hover
+                let frame_size = (proj.frame_width, proj.frame_height);
+                if let Some((width, height)) = validation::aspect_mismatch(annotation, frame_size) {
+                    ui.colored_label(egui::Color32::YELLOW, format!("⚠ Drawn on a {} × {} frame", width, height))
+                        .on_hover_text(format!(
+                            "Its aspect ratio differs from this {} × {} frame, so the shape is stretched",
+                            frame_size.0, frame_size.1
+                        ));
+                }
+                let measurements = measurements(ui, idx, annotation, frame_size);
+                let report = &measurements.report;
+                let color = match report.score {
+                    90.. => egui::Color32::GREEN,
+                    60..=89 => egui::Color32::YELLOW,
+                    _ => egui::Color32::RED,
+                };
+                let breakdown = if report.flags.is_empty() {
+                    "No problems found".to_string()
+                } else {
+                    report.flags.iter().map(|flag| format!("−{}: {}", flag.penalty(), flag.description())).collect::<Vec<_>>().join("\n")
+                };
+                ui.horizontal(|ui| {
+                    ui.label("Quality:");
+                    ui.colored_label(color, format!("{}/100", report.score)).on_hover_text(breakdown);
+                });
+
+                // Vertex coordinates in the display unit; click, Shift-click,
+                // and Ctrl-click the indices to pick vertices for deletion
+                egui::CollapsingHeader::new("Vertex coordinates").id_source("vertex_table").show(ui, |ui| {
+                    egui::ScrollArea::vertical().max_height(150.0).show(ui, |ui| {
+                        egui::Grid::new("vertex_grid").num_columns(2).striped(true).show(ui, |ui| {
+                            for (i, vertex) in annotation.vertices.0.iter().enumerate() {
+                                if ui.selectable_label(vertex_selection.contains(idx, i), format!("{}", i)).clicked() {
+                                    let modifiers = ui.input(|input| input.modifiers);
+                                    vertex_selection.click(idx, i, modifiers.shift, modifiers.command);
+                                }
+                                ui.label(unit.format_point(vertex, frame_size));
+                                ui.end_row();
+                            }
+                        });
+                    });
+
+                    let picked = vertex_selection.vertices_of(idx).len();
+                    ui.horizontal(|ui| {
+                        if ui.add_enabled(picked > 0, egui::Button::new(format!("Delete {} vertices", picked))).clicked() {
+                            action = PropertiesAction::DeleteVertices(idx);
+                        }
+                        if ui.add_enabled(picked > 0, egui::Button::new("Clear")).clicked() {
+                            vertex_selection.clear();
+                        }
+                    });
+                });
+
+                // Copy vertices as CSV in the display unit
+                if ui.button("Copy coordinates").clicked() {
+                    let csv = vertices_csv(annotation, (unit == CoordinateUnit::Pixel).then_some(frame_size));
+                    ui.ctx().copy_text(csv);
+                }
+
+                // Expand or contract the polygon, previewed on the canvas (selection only)
+                if annotation.annotation_type == AnnotationType::Polygon && selected_annotation == Some(idx) {
+                    ui.horizontal(|ui| {
+                        ui.label("Offset:");
+                        ui.add(egui::Slider::new(offset_distance, -50.0..=50.0).suffix(" px"))
+                            .on_hover_text("Positive grows the polygon, negative shrinks it");
+                        if ui.add_enabled(*offset_distance != 0.0, egui::Button::new("Apply")).clicked() {
+                            action = PropertiesAction::OffsetAnnotation(idx);
+                        }
+                    });
+                    if *offset_distance != 0.0 {
+                        let pixels = frame_size.0 as f64 * frame_size.1 as f64;
+                        let offset = geometry::offset_polygon(&annotation.vertices.0, *offset_distance, frame_size);
+                        ui.label(format!(
+                            "Area: {:.0} → {:.0} px²",
+                            geometry::polygon_area(&annotation.vertices.0) * pixels,
+                            geometry::polygon_area(&offset) * pixels
+                        ));
+                    }
+                }
+
+                // Smallest circle around the vertices, measured in pixels so it
+                // stays round on non-square frames
+                if let Some((center, radius)) = measurements.circle {
+                    if radius > 0.0 {
+                        let center = geometry::normalize_coordinates(center.x, center.y, frame_size.0, frame_size.1);
+                        ui.horizontal(|ui| {
+                            ui.label(format!("Enclosing circle: {} r = {:.1} px", unit.format_point(&center, frame_size), radius));
+                            if ui.small_button("Create").on_hover_text("Add the circle as a new polygon").clicked() {
+                                action = PropertiesAction::CreateEnclosingCircle(idx);
+                            }
+                        });
+                    }
+                }
+
+                // Best overlap with the comparison set
+                if let (Some(reference), AnnotationType::Polygon) = (reference, annotation.annotation_type) {
+                    let best = reference
+                        .annotations
+                        .iter()
+                        .filter(|other| other.annotation_type == AnnotationType::Polygon)
+                        .map(|other| (geometry::polygon_iou(&annotation.vertices.0, &other.vertices.0), &other.name))
+                        .filter(|(iou, _)| *iou > 0.0)
+                        .max_by(|(a, _), (b, _)| a.total_cmp(b));
+                    match best {
+                        Some((iou, name)) => ui.label(format!("IoU: {:.3} (vs {})", iou, name)),
+                        None => ui.label("IoU: no overlapping reference"),
+                    };
+                }
             }
         }
     }
 
     action
 }
+
+/// Per-annotation results too costly to recompute every frame for dense
+/// outlines.
+#[derive(Clone)]
+struct Measurements {
+    report: validation::QualityReport,
+    /// Pixel center and radius of the smallest circle around a polygon's
+    /// vertices, measured in pixels so it stays round on non-square frames
+    circle: Option<(Point, f64)>,
+}
+
+/// Measurements of the annotation at `idx`, recomputed only when its
+/// geometry or the frame size changes.
+fn measurements(ui: &egui::Ui, idx: usize, annotation: &Annotation, frame_size: (u32, u32)) -> Measurements {
+    let mut hasher = DefaultHasher::new();
+    render_cache::hash_geometry(annotation, &mut hasher);
+    frame_size.hash(&mut hasher);
+    let key = hasher.finish();
+
+    let id = egui::Id::new("annotation_measurements").with(idx);
+    if let Some((_, cached)) = ui.data(|data| data.get_temp::<(u64, Measurements)>(id)).filter(|(built, _)| *built == key) {
+        return cached;
+    }
+    let circle = (annotation.annotation_type == AnnotationType::Polygon && frame_size.0 > 0 && frame_size.1 > 0).then(|| {
+        let vertices: Vec<_> = annotation.rings().flatten().copied().collect();
+        geometry::min_enclosing_circle(&geometry::to_pixels(&vertices, frame_size))
+    });
+    let measurements = Measurements { report: validation::quality_report(annotation, frame_size), circle };
+    ui.data_mut(|data| data.insert_temp(id, (key, measurements.clone())));
+    measurements
+}
+
+/// Show the annotation's color picker with the recently used colors as
+/// one-click swatches.
+fn show_color(ui: &mut egui::Ui, annotation: &mut Annotation, recent_colors: &mut Vec<[u8; 4]>) {
+    // Set while the picker is being dragged, so a color is remembered
+    // once when the mouse is released rather than on every frame
+    let picking_id = ui.id().with("color_picking");
+    ui.horizontal(|ui| {
+        ui.label("Color:");
+        let [r, g, b, a] = annotation.display_color();
+        let mut picked = egui::Color32::from_rgba_unmultiplied(r, g, b, a);
+        if egui::color_picker::color_edit_button_srgba(ui, &mut picked, egui::color_picker::Alpha::OnlyBlend).changed() {
+            annotation.color = Some(picked.to_srgba_unmultiplied());
+            ui.data_mut(|data| data.insert_temp(picking_id, true));
+        }
+        if annotation.color.is_some() && ui.small_button("Reset").on_hover_text("Color by group or category again").clicked() {
+            annotation.color = None;
+        }
+    });
+
+    let picking = ui.data(|data| data.get_temp(picking_id).unwrap_or(false));
+    if let (true, Some(picked)) = (picking && !ui.input(|i| i.pointer.any_down()), annotation.color) {
+        color::remember_color(recent_colors, picked);
+        ui.data_mut(|data| data.remove::<bool>(picking_id));
+    }
+
+    if recent_colors.is_empty() {
+        return;
+    }
+    ui.horizontal_wrapped(|ui| {
+        ui.label(egui::RichText::new("Recent:").small().weak());
+        for swatch in recent_colors.clone() {
+            let [r, g, b, a] = swatch;
+            let button = egui::Button::new("")
+                .fill(egui::Color32::from_rgba_unmultiplied(r, g, b, a))
+                .min_size(egui::vec2(16.0, 16.0));
+            if ui.add(button).on_hover_text(format!("#{:02x}{:02x}{:02x}", r, g, b)).clicked() {
+                annotation.color = Some(swatch);
+                color::remember_color(recent_colors, swatch);
+            }
+        }
+    });
+}
+
+/// Show which media file is being annotated, flagging it if it was deleted.
+fn show_media_info(ui: &mut egui::Ui, info: &MediaInfo) {
+    ui.label(egui::RichText::new(info.file_name()).strong())
+        .on_hover_text(info.path.display().to_string());
+    ui.label(egui::RichText::new(format!("{} × {} px", info.width, info.height)).small());
+    if info.missing {
+        ui.colored_label(egui::Color32::RED, format!("⚠ {}", info.modified_text()))
+            .on_hover_text("The image was moved or deleted after it was loaded");
+    } else {
+        ui.label(egui::RichText::new(info.modified_text()).small().weak());
+    }
+}
+
+/// Format an annotation's vertices as CSV with a `#` header naming it.
+///
+/// Coordinates are normalized, or in pixels when a frame size is given.
+fn vertices_csv(annotation: &Annotation, frame_size: Option<(u32, u32)>) -> String {
+    let type_name = format!("{:?}", annotation.annotation_type).to_lowercase();
+    let mut csv = format!("# {} ({})\nx,y\n", annotation.name, type_name);
+    for vertex in &annotation.vertices.0 {
+        let line = match frame_size {
+            Some((width, height)) => {
+                let (x, y) = geometry::denormalize_coordinates(vertex, width, height);
+                format!("{:.2},{:.2}\n", x, y)
+            }
+            None => format!("{:.6},{:.6}\n", vertex.x, vertex.y),
+        };
+        csv.push_str(&line);
+    }
+    csv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vertices_csv() {
+        let mut annotation = Annotation::new("Region 1".to_string(), AnnotationType::Polygon);
+        annotation.add_vertex(Point::new(0.1, 0.2));
+        annotation.add_vertex(Point::new(0.5, 0.25));
+        annotation.add_vertex(Point::new(0.75, 1.0));
+
+        assert_eq!(
+            vertices_csv(&annotation, None),
+            "# Region 1 (polygon)\nx,y\n0.100000,0.200000\n0.500000,0.250000\n0.750000,1.000000\n"
+        );
+        assert_eq!(
+            vertices_csv(&annotation, Some((200, 100))),
+            "# Region 1 (polygon)\nx,y\n20.00,20.00\n100.00,25.00\n150.00,100.00\n"
+        );
+    }
+
+    #[test]
+    fn test_measurements_are_cached_until_the_geometry_changes() {
+        let mut annotation = Annotation::new("region 1".to_string(), AnnotationType::Polygon);
+        for (x, y) in [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)] {
+            annotation.add_vertex(Point::new(x, y));
+        }
+        let ctx = egui::Context::default();
+        let _ = ctx.run(egui::RawInput::default(), |ctx| {
+            egui::CentralPanel::default().show(ctx, |ui| {
+                let first = measurements(ui, 0, &annotation, (100, 100));
+                assert_eq!(first.report.score, 100);
+                let (_, radius) = first.circle.unwrap();
+                assert!((radius - 50.0 * 2f64.sqrt()).abs() < 1e-6);
+
+                // A planted result under the same key is returned as is
+                let id = egui::Id::new("annotation_measurements").with(0);
+                let (key, mut planted) = ui.data(|data| data.get_temp::<(u64, Measurements)>(id)).unwrap();
+                planted.report.score = 1;
+                ui.data_mut(|data| data.insert_temp(id, (key, planted)));
+                assert_eq!(measurements(ui, 0, &annotation, (100, 100)).report.score, 1);
+                // Renaming doesn't change the measurements
+                let mut renamed = annotation.clone();
+                renamed.name = "region 2".to_string();
+                assert_eq!(measurements(ui, 0, &renamed, (100, 100)).report.score, 1);
+
+                // Moving a vertex or resizing the frame recomputes
+                let mut moved = annotation.clone();
+                moved.update_vertex(2, Point::new(0.9, 0.9));
+                assert_eq!(measurements(ui, 0, &moved, (100, 100)).report.score, 100);
+                assert_eq!(measurements(ui, 0, &moved, (200, 100)).report.score, 100);
+            });
+        });
+    }
+}
@@ -0,0 +1,141 @@
+// Copyright (c) 2025, Jason Jenkins
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Shape cache for committed annotations.
+//!
+//! Building the shapes for hundreds of annotations every frame is wasteful
+//! when nothing changed. The canvas keys the cached shapes on a fingerprint
+//! of everything that affects them and only rebuilds when it changes.
+
+use crate::models::{annotation::Annotation, settings::Settings};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+/// Cached shapes for the committed annotations.
+#[derive(Default)]
+pub struct RenderCache {
+    /// Fingerprint of the inputs the shapes were built from
+    key: Option<u64>,
+    /// Shapes from the last build
+    shapes: Vec<egui::Shape>,
+    /// Number of times the shapes have been (re)built
+    generation: u64,
+}
+
+impl RenderCache {
+    /// Create an empty cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the shapes for `key`, calling `build` only if the key changed.
+    pub fn get_or_build(&mut self, key: u64, build: impl FnOnce() -> Vec<egui::Shape>) -> &[egui::Shape] {
+        if self.key != Some(key) {
+            self.shapes = build();
+            self.key = Some(key);
+            self.generation += 1;
+        }
+        &self.shapes
+    }
+
+    /// Number of times the shapes have been built.
+    #[cfg(test)]
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+}
+
+/// Hash an annotation's type and the vertices of all its rings.
+pub fn hash_geometry(annotation: &Annotation, hasher: &mut impl Hasher) {
+    annotation.annotation_type.hash(hasher);
+    for ring in annotation.rings() {
+        ring.len().hash(hasher);
+        for vertex in ring {
+            vertex.x.to_bits().hash(hasher);
+            vertex.y.to_bits().hash(hasher);
+        }
+    }
+}
+
+/// Fingerprint everything that affects the committed annotation shapes:
+/// the annotations themselves, selection, which are shown, the on-screen
+/// image rectangle (zoom and pan), and the relevant display settings.
+pub fn cache_key(
+    annotations: &[Annotation],
+    selected: Option<usize>,
+    lasso_selected: &[usize],
+    shown: impl Fn(usize, &Annotation) -> bool,
+    image_rect: egui::Rect,
+    image_size: (u32, u32),
+    settings: &Settings,
+) -> u64 {
+    let mut hasher = DefaultHasher::new();
+
+    for (idx, annotation) in annotations.iter().enumerate() {
+        annotation.name.hash(&mut hasher);
+        annotation.color.hash(&mut hasher);
+        annotation.group.hash(&mut hasher);
+        annotation.role.hash(&mut hasher);
+        annotation.line_style.hash(&mut hasher);
+        shown(idx, annotation).hash(&mut hasher);
+        hash_geometry(annotation, &mut hasher);
+    }
+    annotations.len().hash(&mut hasher);
+    selected.hash(&mut hasher);
+    lasso_selected.hash(&mut hasher);
+
+    for value in [image_rect.min.x, image_rect.min.y, image_rect.max.x, image_rect.max.y] {
+        value.to_bits().hash(&mut hasher);
+    }
+    image_size.hash(&mut hasher);
+    settings.line_cap.hash(&mut hasher);
+    settings.handle_shape.hash(&mut hasher);
+    settings.dash_length.to_bits().hash(&mut hasher);
+    settings.fill_rule.hash(&mut hasher);
+    settings.lod_vertex_threshold.hash(&mut hasher);
+    settings.show_vertex_indices.hash(&mut hasher);
+    settings.coordinate_unit.hash(&mut hasher);
+    super::canvas::overlay_opacity(settings).to_bits().hash(&mut hasher);
+
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::annotation::{AnnotationType, Point};
+    use crate::models::layers::Layers;
+
+    #[test]
+    fn test_cache_invalidates_on_mutation() {
+        let mut annotation = Annotation::new("region 1".to_string(), AnnotationType::Polygon);
+        annotation.add_vertex(Point::new(0.1, 0.1));
+        annotation.add_vertex(Point::new(0.5, 0.1));
+        annotation.add_vertex(Point::new(0.5, 0.5));
+        let mut annotations = vec![annotation];
+
+        let layers = Layers::new();
+        let settings = Settings::default();
+        let rect = egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(800.0, 600.0));
+        let shown = |_: usize, annotation: &Annotation| layers.is_visible(annotation);
+        let key = |annotations: &[Annotation], selected| cache_key(annotations, selected, &[], shown, rect, (100, 100), &settings);
+
+        let mut cache = RenderCache::new();
+        let build = || vec![egui::Shape::Noop];
+
+        cache.get_or_build(key(&annotations, None), build);
+        cache.get_or_build(key(&annotations, None), build);
+        assert_eq!(cache.generation(), 1, "unchanged inputs must reuse the cache");
+
+        annotations[0].update_vertex(1, Point::new(0.6, 0.1));
+        cache.get_or_build(key(&annotations, None), build);
+        assert_eq!(cache.generation(), 2, "moving a vertex must rebuild");
+
+        annotations[0].name = "region 2".to_string();
+        cache.get_or_build(key(&annotations, None), build);
+        assert_eq!(cache.generation(), 3, "renaming must rebuild");
+
+        cache.get_or_build(key(&annotations, Some(0)), build);
+        assert_eq!(cache.generation(), 4, "changing the selection must rebuild");
+    }
+}
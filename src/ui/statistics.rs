@@ -0,0 +1,183 @@
+// Copyright (c) 2025, Jason Jenkins
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Annotation statistics window.
+//!
+//! This module provides a window showing the distribution of polygon
+//! areas, which helps spot suspiciously tiny or huge regions, and the
+//! total length of line annotations.
+
+use super::render_cache;
+use crate::models::{project::ProjectData, settings::FillRule};
+use crate::util::{color::color_for_category, stats};
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+/// Height of each histogram plot.
+const PLOT_HEIGHT: f32 = 80.0;
+
+/// Display options for the statistics window.
+#[derive(Debug, Clone)]
+pub struct StatisticsView {
+    /// Number of histogram buckets
+    pub bucket_count: usize,
+    /// Draw one histogram per category
+    pub by_category: bool,
+    /// Bucket on log10(area) so outliers are easier to see
+    pub log_scale: bool,
+}
+
+impl Default for StatisticsView {
+    fn default() -> Self {
+        Self {
+            bucket_count: 20,
+            by_category: false,
+            log_scale: true,
+        }
+    }
+}
+
+/// Display the statistics window while `open` is true.
+pub fn show(ctx: &egui::Context, open: &mut bool, project: &Option<ProjectData>, view: &mut StatisticsView, fill_rule: FillRule) {
+    egui::Window::new("Statistics")
+        .open(open)
+        .default_width(360.0)
+        .show(ctx, |ui| {
+            let Some(project) = project else {
+                ui.label("No project loaded");
+                return;
+            };
+
+            egui::CollapsingHeader::new("Line Lengths").default_open(true).show(ui, |ui| {
+                line_lengths(ui, project);
+            });
+            egui::CollapsingHeader::new("Area Distribution").default_open(true).show(ui, |ui| {
+                area_distribution(ui, project, view, fill_rule);
+            });
+        });
+}
+
+/// Show the total length of all lines with a per-line breakdown.
+fn line_lengths(ui: &mut egui::Ui, project: &ProjectData) {
+    let lengths = stats::line_lengths(&project.annotations, project.frame_width, project.frame_height);
+    if lengths.is_empty() {
+        ui.label("No lines to measure");
+        return;
+    }
+
+    let total: f64 = lengths.iter().map(|(_, length)| length).sum();
+    ui.label(egui::RichText::new(format!("Total: {:.1} px over {} lines", total, lengths.len())).strong());
+    egui::ScrollArea::vertical().id_source("line_lengths").max_height(120.0).show(ui, |ui| {
+        egui::Grid::new("line_length_grid").striped(true).show(ui, |ui| {
+            for (name, length) in &lengths {
+                ui.label(*name);
+                ui.label(format!("{:.1} px", length));
+                ui.end_row();
+            }
+        });
+    });
+}
+
+/// Show histograms of polygon areas.
+fn area_distribution(ui: &mut egui::Ui, project: &ProjectData, view: &mut StatisticsView, fill_rule: FillRule) {
+    ui.horizontal(|ui| {
+        ui.checkbox(&mut view.by_category, "Group by category");
+        ui.checkbox(&mut view.log_scale, "Log scale");
+        ui.add(egui::DragValue::new(&mut view.bucket_count).range(2..=100).suffix(" buckets"));
+    });
+    ui.separator();
+
+    // Measuring dense or self-overlapping outlines is too costly to repeat
+    // every frame, so areas are cached by geometry and fill rule. Entries
+    // for shapes that no longer exist are dropped.
+    let id = egui::Id::new("polygon_area_cache");
+    let cached = ui.data(|data| data.get_temp::<HashMap<u64, f64>>(id)).unwrap_or_default();
+    let mut current = HashMap::new();
+    let areas = stats::polygon_areas(
+        &project.annotations,
+        project.frame_width,
+        project.frame_height,
+        view.by_category,
+        |annotation| {
+            let mut hasher = DefaultHasher::new();
+            render_cache::hash_geometry(annotation, &mut hasher);
+            fill_rule.hash(&mut hasher);
+            let key = hasher.finish();
+            *current.entry(key).or_insert_with(|| cached.get(&key).copied().unwrap_or_else(|| stats::filled_area(annotation, fill_rule)))
+        },
+    );
+    ui.data_mut(|data| data.insert_temp(id, current));
+    if areas.is_empty() {
+        ui.label("No polygons to analyze");
+        return;
+    }
+
+    // Share one range across categories so the plots are comparable
+    let transform = |area: f64| if view.log_scale { area.max(1.0).log10() } else { area };
+    let all: Vec<f64> = areas.values().flatten().map(|&a| transform(a)).collect();
+    let overall = stats::Histogram::new(&all, view.bucket_count);
+
+    egui::ScrollArea::vertical().show(ui, |ui| {
+        for (category, values) in &areas {
+            let values: Vec<f64> = values.iter().map(|&a| transform(a)).collect();
+            let histogram = stats::Histogram::with_range(&values, overall.min, overall.max, view.bucket_count);
+
+            let color = if category.is_empty() {
+                egui::Color32::LIGHT_BLUE
+            } else {
+                ui.label(format!("{} ({})", category, histogram.total()));
+                let [r, g, b, a] = color_for_category(category);
+                egui::Color32::from_rgba_unmultiplied(r, g, b, a)
+            };
+            draw_histogram(ui, &histogram, color, view.log_scale);
+        }
+    });
+
+    let format = |value: f64| format_area(value, view.log_scale);
+    ui.horizontal(|ui| {
+        ui.label(format!("{} px²", format(overall.min)));
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            ui.label(format!("{} px²", format(overall.max)));
+        });
+    });
+}
+
+/// Draw a histogram as hand-drawn bars with a hover tooltip per bucket.
+fn draw_histogram(ui: &mut egui::Ui, histogram: &stats::Histogram, color: egui::Color32, log_scale: bool) {
+    let width = ui.available_width();
+    let (rect, response) = ui.allocate_exact_size(egui::vec2(width, PLOT_HEIGHT), egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 2.0, ui.visuals().extreme_bg_color);
+
+    let max_count = histogram.max_count().max(1) as f32;
+    let bar_width = rect.width() / histogram.counts.len() as f32;
+    for (idx, &count) in histogram.counts.iter().enumerate() {
+        if count == 0 {
+            continue;
+        }
+        let height = rect.height() * count as f32 / max_count;
+        let left = rect.min.x + bar_width * idx as f32;
+        let bar = egui::Rect::from_min_max(
+            egui::pos2(left + 1.0, rect.max.y - height),
+            egui::pos2(left + bar_width - 1.0, rect.max.y),
+        );
+        painter.rect_filled(bar, 0.0, color);
+    }
+
+    if let Some(pos) = response.hover_pos() {
+        let idx = (((pos.x - rect.min.x) / bar_width) as usize).min(histogram.counts.len() - 1);
+        let (low, high) = histogram.bucket_range(idx);
+        response.on_hover_text_at_pointer(format!(
+            "{} – {} px²: {}",
+            format_area(low, log_scale),
+            format_area(high, log_scale),
+            histogram.counts[idx]
+        ));
+    }
+}
+
+/// Format a bucket bound as an area, undoing the log transform if needed.
+fn format_area(value: f64, log_scale: bool) -> String {
+    let area = if log_scale { 10f64.powf(value) } else { value };
+    format!("{:.0}", area)
+}
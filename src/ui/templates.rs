@@ -0,0 +1,61 @@
+// Copyright (c) 2025, Jason Jenkins
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Templates panel.
+//!
+//! This module provides the panel listing the loaded annotation templates
+//! and choosing the one the Stamp tool places.
+
+use crate::models::annotation::Annotation;
+
+/// Template library operation requested from the panel.
+pub enum TemplatesAction {
+    None,
+    Load,
+    Save,
+    AddSelected,
+    Remove(usize),
+}
+
+/// Display the templates panel.
+pub fn show(
+    ui: &mut egui::Ui,
+    templates: &[Annotation],
+    active: &mut Option<usize>,
+    has_selection: bool,
+) -> TemplatesAction {
+    let mut action = TemplatesAction::None;
+
+    ui.heading("Templates");
+    ui.separator();
+
+    if templates.is_empty() {
+        ui.label(egui::RichText::new("No templates loaded").italics().weak());
+    }
+    for (i, template) in templates.iter().enumerate() {
+        ui.horizontal(|ui| {
+            ui.radio_value(active, Some(i), format!("{} ({})", template.name, template.annotation_type.default_name_prefix()));
+            if ui.small_button("✖").on_hover_text("Remove from library").clicked() {
+                action = TemplatesAction::Remove(i);
+            }
+        });
+    }
+
+    ui.horizontal(|ui| {
+        if ui.button("Load...").clicked() {
+            action = TemplatesAction::Load;
+        }
+        if ui.add_enabled(!templates.is_empty(), egui::Button::new("Save...")).clicked() {
+            action = TemplatesAction::Save;
+        }
+        if ui
+            .add_enabled(has_selection, egui::Button::new("Add Selected"))
+            .on_hover_text("Add the selected annotation to the library")
+            .clicked()
+        {
+            action = TemplatesAction::AddSelected;
+        }
+    });
+
+    action
+}
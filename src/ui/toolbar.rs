@@ -7,9 +7,36 @@
 //! tools and performing common operations like file open/save.
 
 use crate::app::Tool;
+use crate::models::{annotation::Annotation, settings::Settings};
+use crate::ui::canvas::CanvasAction;
+
+/// Tools in toolbar order, with their icon and name.
+const TOOLS: &[(Tool, &str, &str)] = &[
+    (Tool::Select, "⬆", "Select"),
+    (Tool::Polygon, "▱", "Polygon"),
+    (Tool::Line, "⟋", "Line"),
+    (Tool::OrientedBox, "▭", "Box"),
+    (Tool::Point, "•", "Point"),
+    (Tool::Measure, "↔", "Measure"),
+    (Tool::Split, "✂", "Split"),
+    (Tool::Weld, "⊙", "Weld"),
+    (Tool::Stamp, "⎘", "Stamp"),
+];
+
+/// Screen-space offset of the tool palette from the canvas corner when it
+/// has not been moved yet.
+const PALETTE_MARGIN: f32 = 12.0;
 
 /// Display the toolbar with tool selection buttons.
-pub fn show(ui: &mut egui::Ui, current_tool: &mut Tool) {
+///
+/// Returns `CanvasAction::FinishAnnotation` when the Finish button is clicked.
+pub fn show(
+    ui: &mut egui::Ui,
+    current_tool: &mut Tool,
+    settings: &mut Settings,
+    in_progress_annotation: Option<&Annotation>,
+) -> CanvasAction {
+    let mut action = CanvasAction::None;
     ui.horizontal(|ui| {
         ui.spacing_mut().item_spacing.x = 8.0;
 
@@ -17,30 +44,116 @@ pub fn show(ui: &mut egui::Ui, current_tool: &mut Tool) {
 
         ui.separator();
 
-        // Select tool
-        if ui.selectable_label(*current_tool == Tool::Select, "⬆ Select").clicked() {
-            *current_tool = Tool::Select;
+        for &(tool, icon, name) in TOOLS {
+            if ui.selectable_label(*current_tool == tool, format!("{} {}", icon, name)).clicked() {
+                *current_tool = tool;
+            }
         }
 
-        // Polygon tool
-        if ui.selectable_label(*current_tool == Tool::Polygon, "▱ Polygon").clicked() {
-            *current_tool = Tool::Polygon;
+        ui.separator();
+
+        // Finish/close the in-progress annotation
+        let can_finish = in_progress_annotation.is_some_and(|a| a.can_finish());
+        let finish_label = match in_progress_annotation {
+            Some(a) if a.is_closed() => "✔ Close",
+            _ => "✔ Finish",
+        };
+        if ui
+            .add_enabled(can_finish, egui::Button::new(finish_label))
+            .on_disabled_hover_text("Nothing to finish")
+            .clicked()
+        {
+            action = CanvasAction::FinishAnnotation;
         }
 
-        // Line tool
-        if ui.selectable_label(*current_tool == Tool::Line, "⟋ Line").clicked() {
-            *current_tool = Tool::Line;
+        // Drawing state
+        if let Some(annotation) = in_progress_annotation {
+            let needed = annotation.annotation_type.min_vertices();
+            let status = if annotation.can_finish() {
+                format!("{} vertices (ready)", annotation.vertex_count())
+            } else {
+                format!("{} vertices (need {})", annotation.vertex_count(), needed)
+            };
+            ui.label(status);
         }
 
         ui.separator();
 
         // Tool description
         let tool_text = match current_tool {
-            Tool::Select => "Click to select annotations, drag vertices to move them",
-            Tool::Polygon => "Click to add vertices, double-click to close polygon",
+            Tool::Select => "Click to select annotations, drag vertices to move them, drag elsewhere to lasso-select",
+            Tool::Polygon => "Click to add vertices (Shift snaps to 45°), double-click or click the first vertex to close",
             Tool::Line => "Click to add points, press Escape to finish line",
+            Tool::Point => "Click to place a keypoint",
+            Tool::Measure => "Drag to measure, hold Shift to snap to 45° steps",
+            Tool::Split => "Drag a cut line across the selected polygon to split it in two",
+            Tool::OrientedBox => "Drag out from the center to size a box (Shift locks the aspect ratio), then drag its handle to rotate it",
+            Tool::Weld => "Click two adjacent vertices to merge them at their midpoint",
+            Tool::Stamp => "Click to place the template chosen in the Templates panel",
         };
 
         ui.label(egui::RichText::new(tool_text).italics().weak());
+
+        if !matches!(current_tool, Tool::Select | Tool::Measure | Tool::Split | Tool::Weld | Tool::Stamp | Tool::OrientedBox) {
+            ui.separator();
+            ui.checkbox(&mut settings.borrow_edges, "Borrow edges")
+                .on_hover_text("Snap to neighbor vertices and follow their edges between clicks");
+            ui.checkbox(&mut settings.snap_to_edges, "Snap to edges")
+                .on_hover_text("Place new vertices on the nearest edge of another annotation");
+            if *current_tool != Tool::Point {
+                ui.checkbox(&mut settings.trace_mode, "Trace")
+                    .on_hover_text("Hold the mouse button and move to place vertices continuously");
+            }
+        }
+
+        // Display controls on the right
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            let unit = settings.coordinate_unit;
+            if ui
+                .button(unit.label())
+                .on_hover_text("Coordinate units for display and copying; click to switch")
+                .clicked()
+            {
+                settings.coordinate_unit = unit.toggled();
+            }
+            ui.add(
+                egui::Slider::new(&mut settings.image_opacity, 0.0..=1.0)
+                    .fixed_decimals(2)
+                    .show_value(false),
+            )
+            .on_hover_text("Dim the image to make annotations easier to trace");
+            ui.label("Image opacity:");
+        });
     });
+
+    action
+}
+
+/// Display the floating tool palette over the canvas.
+///
+/// The palette can be dragged anywhere; its position is kept in
+/// `settings` so it reopens where it was left.
+pub fn show_palette(ctx: &egui::Context, canvas_rect: egui::Rect, current_tool: &mut Tool, settings: &mut Settings) {
+    let default_pos = settings
+        .tool_palette_pos
+        .map_or(canvas_rect.min + egui::vec2(PALETTE_MARGIN, PALETTE_MARGIN), |[x, y]| egui::pos2(x, y));
+    let response = egui::Window::new("Tool Palette")
+        .title_bar(false)
+        .resizable(false)
+        .default_pos(default_pos)
+        .constrain_to(canvas_rect)
+        .show(ctx, |ui| {
+            ui.spacing_mut().item_spacing.y = 2.0;
+            for &(tool, icon, name) in TOOLS {
+                let button = egui::SelectableLabel::new(*current_tool == tool, egui::RichText::new(icon).size(16.0));
+                if ui.add_sized([24.0, 24.0], button).on_hover_text(name).clicked() {
+                    *current_tool = tool;
+                }
+            }
+        });
+
+    if let Some(response) = response {
+        let pos = response.response.rect.min;
+        settings.tool_palette_pos = Some([pos.x, pos.y]);
+    }
 }
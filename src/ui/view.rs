@@ -0,0 +1,303 @@
+// Copyright (c) 2025, Jason Jenkins
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Canvas view transform (zoom and pan).
+//!
+//! The image is first fitted to the canvas, then scaled by `zoom` about the
+//! canvas center and shifted by `pan` (in screen points).
+
+use crate::models::annotation::Point;
+
+/// Smallest allowed zoom factor.
+pub const MIN_ZOOM: f32 = 0.1;
+
+/// Largest allowed zoom factor.
+pub const MAX_ZOOM: f32 = 32.0;
+
+/// Amount of the image (in screen points) kept on screen when panning.
+const PAN_MARGIN: f32 = 64.0;
+
+/// Zoom and pan state of the canvas.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ViewTransform {
+    /// Scale relative to fit-to-window
+    pub zoom: f32,
+    /// Offset of the image center from the canvas center (screen points)
+    pub pan: egui::Vec2,
+    /// Normalized image region to frame once the canvas size is known
+    pub pending_frame: Option<(Point, Point)>,
+    /// Normalized image region visible in the last drawn frame
+    pub visible: Option<(Point, Point)>,
+    /// Screen points per image pixel at zoom 1, from the last drawn frame
+    pub fit_scale: f32,
+}
+
+impl Default for ViewTransform {
+    fn default() -> Self {
+        Self {
+            zoom: 1.0,
+            pan: egui::Vec2::ZERO,
+            pending_frame: None,
+            visible: None,
+            fit_scale: 1.0,
+        }
+    }
+}
+
+impl ViewTransform {
+    /// Create a fit-to-window view.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reset to fit-to-window.
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Convert a screen distance to normalized image units at the current
+    /// zoom (see [`screen_to_normalized`]).
+    pub fn normalized_distance(&self, distance: f32, image_size: (u32, u32)) -> f64 {
+        screen_to_normalized(distance, image_size, self.fit_scale * self.zoom)
+    }
+
+    /// Check if the view is zoomed in beyond fit-to-window.
+    pub fn is_zoomed_in(&self) -> bool {
+        self.zoom > 1.0
+    }
+
+    /// Zoom by a factor about the canvas center.
+    pub fn zoom_by(&mut self, factor: f32) {
+        self.zoom_about(factor, egui::Vec2::ZERO);
+    }
+
+    /// Zoom by a factor keeping the point at `anchor` (relative to the
+    /// canvas center) fixed on screen.
+    pub fn zoom_about(&mut self, factor: f32, anchor: egui::Vec2) {
+        let new_zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+        let applied = new_zoom / self.zoom;
+        self.pan = anchor - (anchor - self.pan) * applied;
+        self.zoom = new_zoom;
+    }
+
+    /// Pan by a screen-space offset.
+    pub fn pan_by(&mut self, delta: egui::Vec2) {
+        self.pan += delta;
+    }
+
+    /// Clamp the pan so part of the image always stays on screen.
+    ///
+    /// `display_size` is the zoomed image size and `viewport_size` the canvas
+    /// size, both in screen points.
+    pub fn clamp_pan(&mut self, display_size: egui::Vec2, viewport_size: egui::Vec2) {
+        let limit = |display: f32, viewport: f32| {
+            let margin = PAN_MARGIN.min(display / 2.0);
+            ((display + viewport) / 2.0 - margin).max(0.0)
+        };
+        let max_x = limit(display_size.x, viewport_size.x);
+        let max_y = limit(display_size.y, viewport_size.y);
+        self.pan.x = self.pan.x.clamp(-max_x, max_x);
+        self.pan.y = self.pan.y.clamp(-max_y, max_y);
+    }
+
+    /// Request that a normalized image region be framed on the next draw.
+    pub fn frame(&mut self, min: Point, max: Point) {
+        self.pending_frame = Some((min, max));
+    }
+
+    /// Apply a pending frame request now that the fitted image size and
+    /// the viewport size are known.
+    pub fn apply_pending_frame(&mut self, fit_size: egui::Vec2, viewport_size: egui::Vec2) {
+        if let Some((min, max)) = self.pending_frame.take() {
+            let framed = Self::framing(min, max, fit_size, viewport_size);
+            self.zoom = framed.zoom;
+            self.pan = framed.pan;
+        }
+    }
+
+    /// Compute the view that fits the normalized image region from `min` to
+    /// `max` in the viewport, centered.
+    ///
+    /// `fit_size` is the image size when fitted to the viewport at zoom 1.
+    pub fn framing(min: Point, max: Point, fit_size: egui::Vec2, viewport_size: egui::Vec2) -> Self {
+        let region = egui::vec2(
+            ((max.x - min.x).abs() as f32 * fit_size.x).max(f32::EPSILON),
+            ((max.y - min.y).abs() as f32 * fit_size.y).max(f32::EPSILON),
+        );
+        let zoom = (viewport_size.x / region.x).min(viewport_size.y / region.y).clamp(MIN_ZOOM, MAX_ZOOM);
+
+        // Move the region center (relative to the image center) to the canvas center
+        let center = egui::vec2(((min.x + max.x) / 2.0 - 0.5) as f32, ((min.y + max.y) / 2.0 - 0.5) as f32);
+        Self {
+            zoom,
+            pan: -center * fit_size * zoom,
+            ..Self::default()
+        }
+    }
+
+    /// Pan the shortest distance that brings the normalized region from
+    /// `min` to `max` into the last visible region, keeping the zoom. A
+    /// region larger than the view is centered instead. Does nothing before
+    /// the first draw.
+    pub fn reveal(&mut self, min: Point, max: Point, image_size: (u32, u32)) {
+        let Some((visible_min, visible_max)) = self.visible else {
+            return;
+        };
+        let shift = |min: f64, max: f64, visible_min: f64, visible_max: f64| {
+            if max - min > visible_max - visible_min {
+                (min + max - visible_min - visible_max) / 2.0
+            } else if min < visible_min {
+                min - visible_min
+            } else if max > visible_max {
+                max - visible_max
+            } else {
+                0.0
+            }
+        };
+        let dx = shift(min.x, max.x, visible_min.x, visible_max.x);
+        let dy = shift(min.y, max.y, visible_min.y, visible_max.y);
+        let scale = self.fit_scale * self.zoom;
+        self.pan -= egui::vec2(dx as f32 * image_size.0 as f32 * scale, dy as f32 * image_size.1 as f32 * scale);
+    }
+
+    /// Compute the normalized image region visible in the viewport.
+    /// Returns None if the image is entirely off screen.
+    pub fn visible_region(&self, viewport: egui::Rect, fit_size: egui::Vec2) -> Option<(Point, Point)> {
+        let image_rect = self.image_rect(viewport, fit_size);
+        let visible = image_rect.intersect(viewport);
+        if !visible.is_positive() {
+            return None;
+        }
+        let normalize = |pos: egui::Pos2| {
+            let rel = (pos - image_rect.min) / image_rect.size();
+            Point::new(rel.x as f64, rel.y as f64)
+        };
+        Some((normalize(visible.min), normalize(visible.max)))
+    }
+
+    /// Compute the on-screen image rectangle.
+    ///
+    /// `fit_size` is the image size when fitted to the viewport at zoom 1.
+    pub fn image_rect(&self, viewport: egui::Rect, fit_size: egui::Vec2) -> egui::Rect {
+        egui::Rect::from_center_size(viewport.center() + self.pan, fit_size * self.zoom)
+    }
+}
+
+/// Convert a distance in screen points to normalized image units, given
+/// the number of screen points per image pixel.
+///
+/// Normalized units are relative to the longer image side, so the result
+/// never reaches further than `distance` on screen in either direction.
+pub fn screen_to_normalized(distance: f32, image_size: (u32, u32), screen_scale: f32) -> f64 {
+    let longest = image_size.0.max(image_size.1).max(1) as f64;
+    distance as f64 / (screen_scale.max(f32::EPSILON) as f64 * longest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_screen_to_normalized() {
+        // A 1000 px image fitted at half size and zoomed 2x shows 1 pt per pixel
+        let view = ViewTransform { zoom: 2.0, fit_scale: 0.5, ..ViewTransform::new() };
+        assert!((view.normalized_distance(10.0, (1000, 500)) - 0.01).abs() < 1e-9);
+
+        // Zooming in shrinks the radius in image terms
+        assert!((screen_to_normalized(10.0, (1000, 500), 4.0) - 0.0025).abs() < 1e-9);
+        assert!((screen_to_normalized(10.0, (500, 1000), 0.25) - 0.04).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_clamp_pan_keeps_image_on_screen() {
+        let display = egui::vec2(2000.0, 1000.0);
+        let viewport = egui::vec2(800.0, 600.0);
+
+        let mut view = ViewTransform { zoom: 2.0, ..ViewTransform::new() };
+        for _ in 0..100 {
+            view.pan_by(egui::vec2(50.0, -50.0));
+            view.clamp_pan(display, viewport);
+        }
+
+        // The image edge stops PAN_MARGIN points inside the viewport
+        assert_eq!(view.pan.x, (2000.0 + 800.0) / 2.0 - PAN_MARGIN);
+        assert_eq!(view.pan.y, -((1000.0 + 600.0) / 2.0 - PAN_MARGIN));
+
+        let viewport_rect = egui::Rect::from_min_size(egui::Pos2::ZERO, viewport);
+        let image_rect = view.image_rect(viewport_rect, display / view.zoom);
+        assert!(image_rect.intersects(viewport_rect));
+    }
+
+    #[test]
+    fn test_clamp_pan_small_image() {
+        let mut view = ViewTransform { pan: egui::vec2(-1000.0, 0.0), ..ViewTransform::new() };
+        view.clamp_pan(egui::vec2(40.0, 40.0), egui::vec2(800.0, 600.0));
+        // Half of a tiny image must remain visible
+        assert_eq!(view.pan.x, -400.0);
+    }
+
+    #[test]
+    fn test_zoom_about_keeps_anchor_fixed() {
+        let mut view = ViewTransform::new();
+        let anchor = egui::vec2(100.0, 50.0);
+        view.zoom_about(2.0, anchor);
+
+        assert_eq!(view.zoom, 2.0);
+        assert_eq!(view.pan, egui::vec2(-100.0, -50.0));
+
+        view.zoom_by(1000.0);
+        assert_eq!(view.zoom, MAX_ZOOM);
+    }
+
+    #[test]
+    fn test_framing_region() {
+        let fit_size = egui::vec2(800.0, 600.0);
+        let viewport = egui::Rect::from_min_size(egui::Pos2::ZERO, fit_size);
+
+        // The right-bottom quarter fills the viewport at zoom 2
+        let view = ViewTransform::framing(Point::new(0.5, 0.5), Point::new(1.0, 1.0), fit_size, viewport.size());
+        assert_eq!(view.zoom, 2.0);
+        assert_eq!(view.pan, egui::vec2(-400.0, -300.0));
+
+        let (min, max) = view.visible_region(viewport, fit_size).unwrap();
+        assert!(min.distance(&Point::new(0.5, 0.5)) < 1e-6);
+        assert!(max.distance(&Point::new(1.0, 1.0)) < 1e-6);
+
+        // A wide region is limited by its width and centered vertically
+        let view = ViewTransform::framing(Point::new(0.0, 0.4), Point::new(0.5, 0.5), fit_size, viewport.size());
+        assert_eq!(view.zoom, 2.0);
+        assert_eq!(view.pan, egui::vec2(400.0, 60.0));
+    }
+
+    #[test]
+    fn test_apply_pending_frame() {
+        let mut view = ViewTransform::new();
+        view.frame(Point::new(0.25, 0.25), Point::new(0.75, 0.75));
+        view.apply_pending_frame(egui::vec2(800.0, 600.0), egui::vec2(800.0, 600.0));
+
+        assert_eq!(view.zoom, 2.0);
+        assert_eq!(view.pan, egui::Vec2::ZERO);
+        assert_eq!(view.pending_frame, None);
+    }
+
+    #[test]
+    fn test_reveal() {
+        // Zoomed in 2x on a 400 × 300 image fitted at 2 points per pixel,
+        // showing its top-left quarter
+        let mut view = ViewTransform { zoom: 2.0, fit_scale: 2.0, ..ViewTransform::default() };
+        view.visible = Some((Point::new(0.0, 0.0), Point::new(0.5, 0.5)));
+
+        // Already visible: no pan
+        view.reveal(Point::new(0.1, 0.1), Point::new(0.2, 0.2), (400, 300));
+        assert_eq!(view.pan, egui::Vec2::ZERO);
+
+        // Off to the right: shift left just enough
+        view.reveal(Point::new(0.55, 0.1), Point::new(0.6, 0.2), (400, 300));
+        assert_eq!(view.pan, egui::vec2(-0.1 * 400.0 * 4.0, 0.0));
+
+        // Taller than the view: centered vertically
+        view.pan = egui::Vec2::ZERO;
+        view.reveal(Point::new(0.1, 0.0), Point::new(0.2, 0.75), (400, 300));
+        assert_eq!(view.pan, egui::vec2(0.0, -0.125 * 300.0 * 4.0));
+    }
+}
@@ -0,0 +1,208 @@
+// Copyright (c) 2025, Jason Jenkins
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Color utility functions.
+//!
+//! This module provides deterministic color assignment so that annotations
+//! of the same category share a color without manual selection.
+
+use crate::models::settings::ColorRamp;
+
+/// Number of evenly spaced hues used for category colors.
+const HUE_SLOTS: u64 = 12;
+
+/// Saturation used for category colors.
+const CATEGORY_SATURATION: f64 = 0.75;
+
+/// Value (brightness) used for category colors.
+const CATEGORY_VALUE: f64 = 0.95;
+
+/// Number of swatches kept in the recently-used colors palette.
+pub const MAX_RECENT_COLORS: usize = 8;
+
+/// Move a color to the front of the recently-used list, dropping any
+/// earlier copy and the oldest colors past [`MAX_RECENT_COLORS`].
+pub fn remember_color(recent: &mut Vec<[u8; 4]>, color: [u8; 4]) {
+    recent.retain(|&c| c != color);
+    recent.insert(0, color);
+    recent.truncate(MAX_RECENT_COLORS);
+}
+
+/// Get a stable RGBA color for a category name.
+///
+/// The name is hashed with FNV-1a (which, unlike the std hasher, is stable
+/// across Rust versions and sessions) and mapped onto one of a fixed set of
+/// evenly spaced hues. With only [`HUE_SLOTS`] hues, unrelated categories
+/// can share a color; groups and explicit colors tell them apart.
+pub fn color_for_category(name: &str) -> [u8; 4] {
+    let hue = (mix(fnv1a(name)) % HUE_SLOTS) as f64 * (360.0 / HUE_SLOTS as f64);
+    let (r, g, b) = hsv_to_rgb(hue, CATEGORY_SATURATION, CATEGORY_VALUE);
+    [r, g, b, 255]
+}
+
+/// Convert an HSV color (hue in degrees, saturation and value in 0..=1) to RGB.
+pub fn hsv_to_rgb(hue: f64, saturation: f64, value: f64) -> (u8, u8, u8) {
+    let hue = hue.rem_euclid(360.0);
+    let chroma = value * saturation;
+    let x = chroma * (1.0 - ((hue / 60.0) % 2.0 - 1.0).abs());
+    let m = value - chroma;
+
+    let (r, g, b) = match (hue / 60.0) as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+
+    let to_byte = |c: f64| ((c + m) * 255.0).round().clamp(0.0, 255.0) as u8;
+    (to_byte(r), to_byte(g), to_byte(b))
+}
+
+/// Color at `t` (0..=1) along a color ramp.
+pub fn ramp_color(ramp: ColorRamp, t: f64) -> [u8; 3] {
+    let t = t.clamp(0.0, 1.0);
+    match ramp {
+        ColorRamp::Heat => {
+            let (r, g, b) = hsv_to_rgb(240.0 * (1.0 - t), 1.0, 1.0);
+            [r, g, b]
+        }
+        ColorRamp::Viridis => {
+            // Piecewise-linear through samples of matplotlib's viridis
+            const STOPS: [[f64; 3]; 5] = [[68.0, 1.0, 84.0], [59.0, 82.0, 139.0], [33.0, 145.0, 140.0], [94.0, 201.0, 98.0], [253.0, 231.0, 37.0]];
+            let position = t * (STOPS.len() - 1) as f64;
+            let index = (position.floor() as usize).min(STOPS.len() - 2);
+            let fraction = position - index as f64;
+            let (from, to) = (STOPS[index], STOPS[index + 1]);
+            std::array::from_fn(|c| (from[c] + (to[c] - from[c]) * fraction).round() as u8)
+        }
+        ColorRamp::Grayscale => {
+            let level = (40.0 + 215.0 * t).round() as u8;
+            [level; 3]
+        }
+    }
+}
+
+/// 64-bit FNV-1a hash of a string.
+fn fnv1a(text: &str) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in text.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash
+}
+
+/// SplitMix64 finalizer to spread similar hashes across the hue slots.
+fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Get the hue (in degrees) of an RGB color.
+    fn hue_of(color: [u8; 4]) -> f64 {
+        let r = color[0] as f64 / 255.0;
+        let g = color[1] as f64 / 255.0;
+        let b = color[2] as f64 / 255.0;
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let delta = max - min;
+
+        let hue = if delta == 0.0 {
+            0.0
+        } else if max == r {
+            60.0 * (((g - b) / delta) % 6.0)
+        } else if max == g {
+            60.0 * ((b - r) / delta + 2.0)
+        } else {
+            60.0 * ((r - g) / delta + 4.0)
+        };
+        hue.rem_euclid(360.0)
+    }
+
+    #[test]
+    fn test_color_for_category_is_deterministic() {
+        assert_eq!(color_for_category("region"), color_for_category("region"));
+        assert_eq!(color_for_category("region")[3], 255);
+    }
+
+    #[test]
+    fn test_color_for_category_distinct_hues() {
+        let a = hue_of(color_for_category("region"));
+        let b = hue_of(color_for_category("line"));
+
+        let diff = (a - b).abs();
+        let circular_diff = diff.min(360.0 - diff);
+        assert!(circular_diff >= 30.0, "hues too close: {} vs {}", a, b);
+    }
+
+    #[test]
+    fn test_color_for_category_collisions() {
+        // Categories either share a hue slot or sit at least a slot apart,
+        // never at nearly indistinguishable hues
+        let slot = 360.0 / HUE_SLOTS as f64;
+        let hues: Vec<f64> = ["bus", "car", "lane", "person", "road", "tree", "building", "sign"]
+            .iter()
+            .map(|name| hue_of(color_for_category(name)))
+            .collect();
+        for (i, a) in hues.iter().enumerate() {
+            for b in &hues[i + 1..] {
+                let diff = (a - b).abs();
+                let circular_diff = diff.min(360.0 - diff);
+                assert!(circular_diff < 0.5 || circular_diff >= slot - 0.5, "hues too close: {} vs {}", a, b);
+            }
+        }
+
+        // One more category than there are slots always collides
+        let colors: std::collections::HashSet<[u8; 4]> =
+            (0..=HUE_SLOTS).map(|i| color_for_category(&format!("class {}", i))).collect();
+        assert!(colors.len() <= HUE_SLOTS as usize);
+    }
+
+    #[test]
+    fn test_hsv_to_rgb_primaries() {
+        assert_eq!(hsv_to_rgb(0.0, 1.0, 1.0), (255, 0, 0));
+        assert_eq!(hsv_to_rgb(120.0, 1.0, 1.0), (0, 255, 0));
+        assert_eq!(hsv_to_rgb(240.0, 1.0, 1.0), (0, 0, 255));
+    }
+
+    #[test]
+    fn test_remember_color() {
+        let (red, green) = ([255, 0, 0, 255], [0, 255, 0, 255]);
+        let mut recent = Vec::new();
+        remember_color(&mut recent, red);
+        remember_color(&mut recent, green);
+        assert_eq!(recent, [green, red]);
+
+        // Picking a color again moves it to the front without duplicating it
+        remember_color(&mut recent, red);
+        assert_eq!(recent, [red, green]);
+
+        // The oldest colors fall off the end
+        for i in 0..MAX_RECENT_COLORS as u8 {
+            remember_color(&mut recent, [i, i, i, 255]);
+        }
+        assert_eq!(recent.len(), MAX_RECENT_COLORS);
+        let newest = MAX_RECENT_COLORS as u8 - 1;
+        assert_eq!(recent[0], [newest, newest, newest, 255]);
+        assert!(!recent.contains(&red) && !recent.contains(&green));
+    }
+
+    #[test]
+    fn test_ramp_color() {
+        assert_eq!(ramp_color(ColorRamp::Heat, 0.0), [0, 0, 255]);
+        assert_eq!(ramp_color(ColorRamp::Heat, 1.0), [255, 0, 0]);
+        assert_eq!(ramp_color(ColorRamp::Viridis, 0.0), [68, 1, 84]);
+        assert_eq!(ramp_color(ColorRamp::Viridis, 1.0), [253, 231, 37]);
+        assert_eq!(ramp_color(ColorRamp::Viridis, 0.5), [33, 145, 140]);
+        // Out-of-range values are clamped
+        assert_eq!(ramp_color(ColorRamp::Grayscale, 2.0), [255, 255, 255]);
+        assert_eq!(ramp_color(ColorRamp::Grayscale, -1.0), [40, 40, 40]);
+    }
+}
@@ -6,7 +6,7 @@
 //! This module provides utilities for coordinate transformations between
 //! pixel coordinates and normalized coordinates.
 
-use crate::models::annotation::Point;
+use crate::models::{annotation::Point, settings::FillRule};
 
 /// Convert pixel coordinates to normalized coordinates (0.0 to 1.0).
 pub fn normalize_coordinates(pixel_x: f64, pixel_y: f64, width: u32, height: u32) -> Point {
@@ -21,10 +21,1218 @@ pub fn denormalize_coordinates(point: &Point, width: u32, height: u32) -> (f64,
     (point.x * width as f64, point.y * height as f64)
 }
 
+/// Move a normalized point to the nearest whole pixel, kept in the image.
+pub fn round_to_pixel(point: &Point, image_size: (u32, u32)) -> Point {
+    let (width, height) = (image_size.0.max(1) as f64, image_size.1.max(1) as f64);
+    Point::new(
+        (point.x * width).round().clamp(0.0, width) / width,
+        (point.y * height).round().clamp(0.0, height) / height,
+    )
+}
+
+/// Project a point onto the segment from `a` to `b`, returning the closest
+/// point on the segment.
+pub fn project_onto_segment(p: &Point, a: &Point, b: &Point) -> Point {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let length_sq = dx * dx + dy * dy;
+    if length_sq == 0.0 {
+        return *a;
+    }
+
+    let t = (((p.x - a.x) * dx + (p.y - a.y) * dy) / length_sq).clamp(0.0, 1.0);
+    Point::new(a.x + t * dx, a.y + t * dy)
+}
+
+/// Distance from a point to the segment from `a` to `b`.
+pub fn point_segment_distance(p: &Point, a: &Point, b: &Point) -> f64 {
+    p.distance_squared(&project_onto_segment(p, a, b)).sqrt()
+}
+
+/// Remove vertices where the outline turns by no more than `angle_eps`
+/// radians, plus repeated vertices.
+///
+/// Unlike [`simplify_polygon`] this never moves the outline by more than
+/// the tolerated bend: only midpoints of (nearly) straight runs go. When
+/// `closed` is true the chain is treated as a ring; otherwise both
+/// endpoints are kept.
+pub fn remove_collinear(vertices: &[Point], angle_eps: f64, closed: bool) -> Vec<Point> {
+    let min_vertices = if closed { 3 } else { 2 };
+    if vertices.len() <= min_vertices {
+        return vertices.to_vec();
+    }
+
+    let redundant = |prev: &Point, p: &Point, next: &Point| {
+        let (ax, ay) = (p.x - prev.x, p.y - prev.y);
+        let (bx, by) = (next.x - p.x, next.y - p.y);
+        if (ax == 0.0 && ay == 0.0) || (bx == 0.0 && by == 0.0) {
+            return true;
+        }
+        let turn = (ax * by - ay * bx).atan2(ax * bx + ay * by);
+        turn.abs() <= angle_eps
+    };
+
+    let n = vertices.len();
+    let mut kept = vec![vertices[0]];
+    let last = if closed { n } else { n - 1 };
+    for i in 1..last {
+        let prev = kept[kept.len() - 1];
+        if !redundant(&prev, &vertices[i], &vertices[(i + 1) % n]) {
+            kept.push(vertices[i]);
+        }
+    }
+    if closed {
+        // The first vertex may itself sit midway along the closing edge
+        if kept.len() > 3 && redundant(&kept[kept.len() - 1], &kept[0], &kept[1]) {
+            kept.remove(0);
+        }
+        if kept.len() < 3 {
+            return vertices.to_vec();
+        }
+    } else {
+        kept.push(vertices[n - 1]);
+    }
+    kept
+}
+
+/// Simplify a vertex chain with the Douglas-Peucker algorithm.
+///
+/// Vertices closer than `tolerance` to the simplified outline are removed.
+/// When `closed` is true the chain is treated as a ring.
+pub fn simplify_polygon(vertices: &[Point], tolerance: f64, closed: bool) -> Vec<Point> {
+    if vertices.len() < 3 {
+        return vertices.to_vec();
+    }
+
+    if !closed {
+        let mut keep = vec![false; vertices.len()];
+        keep[0] = true;
+        keep[vertices.len() - 1] = true;
+        douglas_peucker(vertices, 0, vertices.len() - 1, tolerance, &mut keep);
+        return vertices
+            .iter()
+            .zip(keep)
+            .filter_map(|(p, k)| k.then_some(*p))
+            .collect();
+    }
+
+    // Split the ring at the vertex farthest from the first one and
+    // simplify both halves as open chains
+    let far = (1..vertices.len())
+        .max_by(|&a, &b| {
+            vertices[0]
+                .distance_squared(&vertices[a])
+                .partial_cmp(&vertices[0].distance_squared(&vertices[b]))
+                .unwrap_or(std::cmp::Ordering::Equal)
+        })
+        .unwrap_or(0);
+
+    let mut first_half = simplify_polygon(&vertices[..=far], tolerance, false);
+    let mut second: Vec<Point> = vertices[far..].to_vec();
+    second.push(vertices[0]);
+    let second_half = simplify_polygon(&second, tolerance, false);
+
+    // Drop the shared endpoints of the second half
+    first_half.extend_from_slice(&second_half[1..second_half.len() - 1]);
+    first_half
+}
+
+/// Mark the vertices between `start` and `end` that must be kept.
+fn douglas_peucker(vertices: &[Point], start: usize, end: usize, tolerance: f64, keep: &mut [bool]) {
+    if end <= start + 1 {
+        return;
+    }
+
+    let (mut max_distance, mut max_index) = (0.0, start);
+    for i in start + 1..end {
+        let distance = point_segment_distance(&vertices[i], &vertices[start], &vertices[end]);
+        if distance > max_distance {
+            max_distance = distance;
+            max_index = i;
+        }
+    }
+
+    if max_distance > tolerance {
+        keep[max_index] = true;
+        douglas_peucker(vertices, start, max_index, tolerance, keep);
+        douglas_peucker(vertices, max_index, end, tolerance, keep);
+    }
+}
+
+/// Get the vertices of a ring between two vertex indices (inclusive).
+///
+/// When `forward` is true the path follows increasing indices, otherwise
+/// decreasing indices, wrapping around the end of the ring as needed.
+pub fn ring_sub_path(ring: &[Point], start: usize, end: usize, forward: bool) -> Vec<Point> {
+    let len = ring.len();
+    if start >= len || end >= len {
+        return Vec::new();
+    }
+
+    let mut path = vec![ring[start]];
+    let mut i = start;
+    while i != end {
+        i = if forward { (i + 1) % len } else { (i + len - 1) % len };
+        path.push(ring[i]);
+    }
+    path
+}
+
+/// Get the shorter of the two sub-paths between two vertices of a ring.
+pub fn shortest_ring_sub_path(ring: &[Point], start: usize, end: usize) -> Vec<Point> {
+    let forward = ring_sub_path(ring, start, end, true);
+    let backward = ring_sub_path(ring, start, end, false);
+    if backward.len() < forward.len() {
+        backward
+    } else {
+        forward
+    }
+}
+
+/// Area enclosed by a polygon ring (shoelace formula).
+///
+/// The result is always non-negative, regardless of winding order.
+/// Non-finite vertices are skipped.
+pub fn polygon_area(vertices: &[Point]) -> f64 {
+    let finite = || vertices.iter().filter(|p| p.is_finite());
+    if finite().nth(2).is_none() {
+        return 0.0;
+    }
+
+    let twice_area: f64 = finite()
+        .zip(finite().cycle().skip(1))
+        .map(|(a, b)| a.x * b.y - b.x * a.y)
+        .sum();
+    twice_area.abs() / 2.0
+}
+
+/// Horizontal spans `(x_start, x_end)` of a polygon that are inside it at
+/// height `y` under the given fill rule, in increasing x order.
+pub fn fill_spans(vertices: &[Point], y: f64, rule: FillRule) -> Vec<(f64, f64)> {
+    edge_fill_spans(ring_edges(vertices), y, rule)
+}
+
+/// Closed edges of a ring, including the one back to the first vertex.
+fn ring_edges(vertices: &[Point]) -> impl Iterator<Item = (&Point, &Point)> {
+    vertices.iter().zip(vertices.iter().cycle().skip(1))
+}
+
+/// Filled spans at height `y` of the region bounded by `edges`.
+fn edge_fill_spans<'a>(edges: impl Iterator<Item = (&'a Point, &'a Point)>, y: f64, rule: FillRule) -> Vec<(f64, f64)> {
+    // Edge crossings with their winding direction (half-open in y)
+    let mut crossings: Vec<(f64, i32)> = Vec::new();
+    for (a, b) in edges {
+        if (a.y <= y) != (b.y <= y) {
+            let x = a.x + (y - a.y) * (b.x - a.x) / (b.y - a.y);
+            crossings.push((x, if b.y > a.y { 1 } else { -1 }));
+        }
+    }
+    crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut spans: Vec<(f64, f64)> = Vec::new();
+    let mut winding = 0;
+    for pair in crossings.windows(2) {
+        winding += pair[0].1;
+        if rule.contains(winding) && pair[1].0 > pair[0].0 {
+            match spans.last_mut() {
+                Some(last) if last.1 == pair[0].0 => last.1 = pair[1].0,
+                _ => spans.push((pair[0].0, pair[1].0)),
+            }
+        }
+    }
+    spans
+}
+
+/// Area of the region bounded by one or more polygon rings under the
+/// given fill rule, so e.g. a ring inside another is a hole under
+/// [`FillRule::EvenOdd`].
+///
+/// A single ring whose edges don't cross is measured with [`polygon_area`].
+/// Otherwise the region is integrated exactly between the heights of the
+/// vertices and edge crossings, where the covered width varies linearly.
+pub fn filled_area(rings: &[&[Point]], rule: FillRule) -> f64 {
+    let rings: Vec<&[Point]> = rings.iter().copied().filter(|ring| ring.len() >= 3).collect();
+    let crossings = edge_crossing_heights(&rings);
+    match rings[..] {
+        [] => return 0.0,
+        [ring] if crossings.is_empty() => return polygon_area(ring),
+        _ => {}
+    }
+
+    let mut heights: Vec<f64> = rings.iter().flat_map(|ring| ring.iter().map(|p| p.y)).chain(crossings).collect();
+    heights.sort_by(f64::total_cmp);
+    heights.dedup();
+    heights
+        .windows(2)
+        .map(|band| {
+            let middle = (band[0] + band[1]) / 2.0;
+            let edges = rings.iter().flat_map(|ring| ring_edges(ring));
+            let width: f64 = edge_fill_spans(edges, middle, rule).iter().map(|(start, end)| end - start).sum();
+            width * (band[1] - band[0])
+        })
+        .sum()
+}
+
+/// Heights at which edges of the rings cross, ignoring the shared vertex
+/// of neighbouring edges in the same ring.
+///
+/// Edges are swept in order of their lowest point, so only edges whose
+/// height ranges overlap are tested against each other.
+fn edge_crossing_heights(rings: &[&[Point]]) -> Vec<f64> {
+    // (ring, index in ring, ring length, start, end)
+    let mut edges: Vec<(usize, usize, usize, &Point, &Point)> = rings
+        .iter()
+        .enumerate()
+        .flat_map(|(r, ring)| ring_edges(ring).enumerate().map(move |(i, (a, b))| (r, i, ring.len(), a, b)))
+        .collect();
+    edges.sort_by(|a, b| a.3.y.min(a.4.y).total_cmp(&b.3.y.min(b.4.y)));
+
+    let mut heights = Vec::new();
+    let mut active: Vec<usize> = Vec::new();
+    for (k, &(ring, i, n, a0, a1)) in edges.iter().enumerate() {
+        let bottom = a0.y.min(a1.y);
+        active.retain(|&j| edges[j].3.y.max(edges[j].4.y) >= bottom);
+        for &j in &active {
+            let (other_ring, other, _, b0, b1) = edges[j];
+            if ring == other_ring && ((i + 1) % n == other || (other + 1) % n == i) {
+                continue;
+            }
+            if let Some((_, point)) = segment_intersection(a0, a1, b0, b1) {
+                heights.push(point.y);
+            }
+        }
+        active.push(k);
+    }
+    heights
+}
+
+/// Centroid of a polygon ring.
+///
+/// Falls back to the vertex average for degenerate (zero-area) rings.
+/// Returns None if there are no vertices.
+pub fn polygon_centroid(vertices: &[Point]) -> Option<Point> {
+    if vertices.is_empty() {
+        return None;
+    }
+
+    let (mut cx, mut cy, mut twice_area) = (0.0, 0.0, 0.0);
+    for (a, b) in vertices.iter().zip(vertices.iter().cycle().skip(1)) {
+        let cross = a.x * b.y - b.x * a.y;
+        cx += (a.x + b.x) * cross;
+        cy += (a.y + b.y) * cross;
+        twice_area += cross;
+    }
+
+    if twice_area.abs() < f64::EPSILON {
+        let n = vertices.len() as f64;
+        let (sx, sy) = vertices.iter().fold((0.0, 0.0), |(sx, sy), p| (sx + p.x, sy + p.y));
+        return Some(Point::new(sx / n, sy / n));
+    }
+    Some(Point::new(cx / (3.0 * twice_area), cy / (3.0 * twice_area)))
+}
+
+/// Constrain the segment from `from` to `to` to the nearest multiple of
+/// 45 degrees, measured in pixel space so the result is aspect-correct.
+///
+/// Returns the constrained end point: the projection of `to` onto the
+/// snapped direction.
+pub fn constrain_segment(from: &Point, to: &Point, image_size: (u32, u32)) -> Point {
+    let (width, height) = (image_size.0 as f64, image_size.1 as f64);
+    let (dx, dy) = ((to.x - from.x) * width, (to.y - from.y) * height);
+    if dx == 0.0 && dy == 0.0 {
+        return *from;
+    }
+
+    // Exact unit directions so axis-aligned results have no rounding error
+    let diagonal = std::f64::consts::FRAC_1_SQRT_2;
+    let directions = [
+        (1.0, 0.0),
+        (diagonal, diagonal),
+        (0.0, 1.0),
+        (-diagonal, diagonal),
+        (-1.0, 0.0),
+        (-diagonal, -diagonal),
+        (0.0, -1.0),
+        (diagonal, -diagonal),
+    ];
+    let octant = (dy.atan2(dx) / std::f64::consts::FRAC_PI_4).round().rem_euclid(8.0) as usize;
+    let (ux, uy) = directions[octant];
+
+    let length = dx * ux + dy * uy;
+    Point::new(from.x + length * ux / width, from.y + length * uy / height)
+}
+
+/// Move the dragged corner of a rectangle so its width-to-height ratio is
+/// `ratio`, in pixel space.
+///
+/// The corner stays on the cursor's side of `start_px` on both axes and
+/// grows the rectangle until it reaches the cursor along the dominant axis.
+pub fn constrain_rect_aspect(start_px: (f64, f64), current_px: (f64, f64), ratio: f64) -> (f64, f64) {
+    if !(ratio.is_finite() && ratio > 0.0) {
+        return current_px;
+    }
+    let (dx, dy) = (current_px.0 - start_px.0, current_px.1 - start_px.1);
+    let width = dx.abs().max(dy.abs() * ratio);
+    let height = width / ratio;
+    (start_px.0 + width.copysign(dx), start_px.1 + height.copysign(dy))
+}
+
+/// Measure the segment from `from` to `to` in pixels.
+///
+/// Returns the length and the angle in degrees counter-clockwise from the
+/// positive x axis (image y points down, so "up" on screen is 90 degrees).
+pub fn measure_segment(from: &Point, to: &Point, image_size: (u32, u32)) -> (f64, f64) {
+    let dx = (to.x - from.x) * image_size.0 as f64;
+    let dy = (to.y - from.y) * image_size.1 as f64;
+    ((dx * dx + dy * dy).sqrt(), (-dy).atan2(dx).to_degrees())
+}
+
+/// Intersection of segments `a0`-`a1` and `b0`-`b1`.
+///
+/// Returns the parameter along the first segment and the crossing point,
+/// or `None` if the segments are parallel or do not meet.
+fn segment_intersection(a0: &Point, a1: &Point, b0: &Point, b1: &Point) -> Option<(f64, Point)> {
+    let (rx, ry) = (a1.x - a0.x, a1.y - a0.y);
+    let (sx, sy) = (b1.x - b0.x, b1.y - b0.y);
+    let denominator = rx * sy - ry * sx;
+    if denominator.abs() < f64::EPSILON {
+        return None;
+    }
+    let (qx, qy) = (b0.x - a0.x, b0.y - a0.y);
+    let t = (qx * sy - qy * sx) / denominator;
+    let u = (qx * ry - qy * rx) / denominator;
+    // Allow a little slack so cuts through a vertex are not lost to rounding
+    let range = -1e-9..=1.0 + 1e-9;
+    if range.contains(&t) && range.contains(&u) {
+        Some((t, Point::new(a0.x + t * rx, a0.y + t * ry)))
+    } else {
+        None
+    }
+}
+
+/// Split a polygon in two along a cut segment.
+///
+/// The cut must cross the polygon boundary exactly twice. Returns `None`
+/// otherwise, or if either half would have fewer than three vertices.
+pub fn split_polygon(vertices: &[Point], cut: (Point, Point)) -> Option<(Vec<Point>, Vec<Point>)> {
+    const SAME_POINT: f64 = 1e-12;
+    let n = vertices.len();
+    if n < 3 {
+        return None;
+    }
+
+    // Find where the cut crosses each edge, in edge order
+    let mut crossings: Vec<(usize, Point)> = Vec::new();
+    for i in 0..n {
+        let (a, b) = (&vertices[i], &vertices[(i + 1) % n]);
+        if let Some((_, point)) = segment_intersection(&cut.0, &cut.1, a, b) {
+            // A cut through a vertex meets both adjacent edges; count it once
+            if crossings.iter().all(|(_, p)| p.distance(&point) > SAME_POINT) {
+                crossings.push((i, point));
+            }
+        }
+    }
+    let [(i, p), (j, q)] = crossings[..] else {
+        return None;
+    };
+
+    // Walk the boundary from one crossing to the other, then back around
+    let mut first = vec![p];
+    first.extend_from_slice(&vertices[i + 1..=j]);
+    first.push(q);
+    let mut second = vec![q];
+    second.extend_from_slice(&vertices[j + 1..]);
+    second.extend_from_slice(&vertices[..=i]);
+    second.push(p);
+
+    // Drop repeated points where the cut passes through a vertex
+    let dedup = |mut ring: Vec<Point>| {
+        ring.dedup_by(|a, b| a.distance(b) <= SAME_POINT);
+        if ring.len() > 1 && ring[0].distance(&ring[ring.len() - 1]) <= SAME_POINT {
+            ring.pop();
+        }
+        ring
+    };
+    let (first, second) = (dedup(first), dedup(second));
+    (first.len() >= 3 && second.len() >= 3).then_some((first, second))
+}
+
+/// Length of a polyline in pixels.
+pub fn polyline_length(vertices: &[Point], image_size: (u32, u32)) -> f64 {
+    vertices
+        .windows(2)
+        .map(|pair| measure_segment(&pair[0], &pair[1], image_size).0)
+        .sum()
+}
+
+/// Smallest circle enclosing every point, as (center, radius), found with
+/// Welzl's algorithm.
+///
+/// Non-finite points are ignored. With no points the result is a zero
+/// circle at the origin, and with one point a zero circle on it.
+pub fn min_enclosing_circle(points: &[Point]) -> (Point, f64) {
+    let mut points: Vec<Point> = points.iter().copied().filter(Point::is_finite).collect();
+
+    // Welzl's expected linear time needs the points in random order; a fixed
+    // xorshift sequence keeps the result reproducible
+    let mut state: u64 = 0x9e37_79b9_7f4a_7c15;
+    for i in (1..points.len()).rev() {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        points.swap(i, (state % (i as u64 + 1)) as usize);
+    }
+
+    let Some(&first) = points.first() else {
+        return (Point::new(0.0, 0.0), 0.0);
+    };
+    let contains = |(center, radius): (Point, f64), p: &Point| center.distance(p) <= radius * (1.0 + 1e-12) + 1e-12;
+
+    // Iterative form: each point outside the circle so far must lie on the
+    // boundary of the circle for the points up to it
+    let mut circle = (first, 0.0);
+    for i in 1..points.len() {
+        if contains(circle, &points[i]) {
+            continue;
+        }
+        circle = (points[i], 0.0);
+        for j in 0..i {
+            if contains(circle, &points[j]) {
+                continue;
+            }
+            circle = circle_from_diameter(&points[i], &points[j]);
+            for k in 0..j {
+                if !contains(circle, &points[k]) {
+                    circle = circumcircle(&points[i], &points[j], &points[k]);
+                }
+            }
+        }
+    }
+    circle
+}
+
+/// Circle with the segment from `a` to `b` as its diameter.
+fn circle_from_diameter(a: &Point, b: &Point) -> (Point, f64) {
+    let center = Point::new((a.x + b.x) / 2.0, (a.y + b.y) / 2.0);
+    (center, center.distance(a))
+}
+
+/// Circle through three points; for (near-)collinear points, the circle
+/// on the two farthest apart.
+fn circumcircle(a: &Point, b: &Point, c: &Point) -> (Point, f64) {
+    let (bx, by) = (b.x - a.x, b.y - a.y);
+    let (cx, cy) = (c.x - a.x, c.y - a.y);
+    let d = 2.0 * (bx * cy - by * cx);
+    if d.abs() < 1e-18 {
+        return [(a, b), (a, c), (b, c)]
+            .into_iter()
+            .map(|(p, q)| circle_from_diameter(p, q))
+            .fold((*a, 0.0), |best, circle| if circle.1 > best.1 { circle } else { best });
+    }
+    let (b2, c2) = (bx * bx + by * by, cx * cx + cy * cy);
+    let center = Point::new(a.x + (cy * b2 - by * c2) / d, a.y + (bx * c2 - cx * b2) / d);
+    (center, center.distance(a))
+}
+
+/// Outline of a circle as a polygon with `segments` vertices.
+pub fn circle_polygon(center: &Point, radius: f64, segments: usize) -> Vec<Point> {
+    (0..segments)
+        .map(|i| {
+            let angle = i as f64 / segments as f64 * std::f64::consts::TAU;
+            Point::new(center.x + radius * angle.cos(), center.y + radius * angle.sin())
+        })
+        .collect()
+}
+
+/// Rotate a normalized point by 90 degrees with the image, clockwise or
+/// counter-clockwise as seen on screen (y pointing down).
+pub fn rotate_point_90(p: &Point, cw: bool) -> Point {
+    if cw {
+        Point::new(1.0 - p.y, p.x)
+    } else {
+        Point::new(p.y, 1.0 - p.x)
+    }
+}
+
+/// Mirror a normalized point with the image, left-right when `horizontal`
+/// and top-bottom otherwise.
+pub fn flip_point(p: &Point, horizontal: bool) -> Point {
+    if horizontal {
+        Point::new(1.0 - p.x, p.y)
+    } else {
+        Point::new(p.x, 1.0 - p.y)
+    }
+}
+
+/// Re-normalize a point relative to a crop rectangle (min, max) so the
+/// crop spans 0..1, or None if the point lies outside it. Points on the
+/// crop's edge are kept.
+pub fn reproject_to_crop(point: &Point, crop: (Point, Point)) -> Option<Point> {
+    let (min, max) = crop;
+    let (width, height) = (max.x - min.x, max.y - min.y);
+    let inside = (min.x..=max.x).contains(&point.x) && (min.y..=max.y).contains(&point.y);
+    (inside && width > 0.0 && height > 0.0).then(|| Point::new((point.x - min.x) / width, (point.y - min.y) / height))
+}
+
+/// Clip a polygon to a rectangle (min, max) with Sutherland-Hodgman.
+///
+/// The result is empty when the polygon lies outside the rectangle.
+pub fn clip_polygon_to_rect(vertices: &[Point], rect: (Point, Point)) -> Vec<Point> {
+    let (min, max) = rect;
+    // Each rectangle side as (is inside, crossing point on it)
+    type Side = (fn(&Point, &Point, &Point) -> bool, fn(&Point, &Point, &Point, &Point) -> Point);
+    let sides: [Side; 4] = [
+        (|p, min, _| p.x >= min.x, |a, b, min, _| {
+            let t = (min.x - a.x) / (b.x - a.x);
+            Point::new(min.x, a.y + (b.y - a.y) * t)
+        }),
+        (|p, _, max| p.x <= max.x, |a, b, _, max| {
+            let t = (max.x - a.x) / (b.x - a.x);
+            Point::new(max.x, a.y + (b.y - a.y) * t)
+        }),
+        (|p, min, _| p.y >= min.y, |a, b, min, _| {
+            let t = (min.y - a.y) / (b.y - a.y);
+            Point::new(a.x + (b.x - a.x) * t, min.y)
+        }),
+        (|p, _, max| p.y <= max.y, |a, b, _, max| {
+            let t = (max.y - a.y) / (b.y - a.y);
+            Point::new(a.x + (b.x - a.x) * t, max.y)
+        }),
+    ];
+
+    let mut output = vertices.to_vec();
+    for (inside, crossing) in sides {
+        let input = std::mem::take(&mut output);
+        for (i, current) in input.iter().enumerate() {
+            let previous = &input[(i + input.len() - 1) % input.len()];
+            match (inside(previous, &min, &max), inside(current, &min, &max)) {
+                (true, true) => output.push(*current),
+                (true, false) => output.push(crossing(previous, current, &min, &max)),
+                (false, true) => {
+                    output.push(crossing(previous, current, &min, &max));
+                    output.push(*current);
+                }
+                (false, false) => {}
+            }
+        }
+    }
+    // Rounding can put interpolated crossings just outside the rectangle
+    for point in &mut output {
+        *point = Point::new(point.x.clamp(min.x, max.x), point.y.clamp(min.y, max.y));
+    }
+    output.dedup();
+    if output.len() > 1 && output[0] == output[output.len() - 1] {
+        output.pop();
+    }
+    output
+}
+
+/// Clip a polyline to a rectangle (min, max), returning the pieces that
+/// lie inside it in order.
+pub fn clip_polyline_to_rect(vertices: &[Point], rect: (Point, Point)) -> Vec<Vec<Point>> {
+    let (min, max) = rect;
+    let mut pieces: Vec<Vec<Point>> = Vec::new();
+    let mut current: Vec<Point> = Vec::new();
+    for pair in vertices.windows(2) {
+        let (a, b) = (pair[0], pair[1]);
+        // Liang-Barsky: narrow the segment's parameter range side by side
+        let (dx, dy) = (b.x - a.x, b.y - a.y);
+        let (mut t0, mut t1) = (0.0f64, 1.0f64);
+        let mut visible = true;
+        for (p, q) in [(-dx, a.x - min.x), (dx, max.x - a.x), (-dy, a.y - min.y), (dy, max.y - a.y)] {
+            if p == 0.0 {
+                visible &= q >= 0.0;
+            } else if p < 0.0 {
+                t0 = t0.max(q / p);
+            } else {
+                t1 = t1.min(q / p);
+            }
+        }
+        if !visible || t0 > t1 {
+            if current.len() > 1 {
+                pieces.push(std::mem::take(&mut current));
+            }
+            current.clear();
+            continue;
+        }
+
+        // Keep original vertices exact; only crossings are interpolated
+        let at = |t: f64| match t {
+            0.0 => a,
+            1.0 => b,
+            _ => Point::new((a.x + dx * t).clamp(min.x, max.x), (a.y + dy * t).clamp(min.y, max.y)),
+        };
+        let (start, end) = (at(t0), at(t1));
+        if current.last() != Some(&start) {
+            if current.len() > 1 {
+                pieces.push(std::mem::take(&mut current));
+            }
+            current = vec![start];
+        }
+        current.push(end);
+        // Leaving the rectangle ends this piece
+        if t1 < 1.0 {
+            pieces.push(std::mem::take(&mut current));
+        }
+    }
+    if current.len() > 1 {
+        pieces.push(current);
+    }
+    pieces.retain(|piece| piece.len() > 1);
+    pieces
+}
+
+/// Remove consecutive vertices closer than `min_distance` to the previous
+/// kept vertex.
+pub fn dedup_consecutive(vertices: &mut Vec<Point>, min_distance: f64) {
+    vertices.dedup_by(|next, kept| next.distance(kept) < min_distance);
+}
+
+/// Drop a closing vertex that repeats the first one within `eps`.
+///
+/// Rings are closed implicitly, but some formats repeat the first point
+/// at the end.
+pub fn normalize_ring(vertices: &mut Vec<Point>, eps: f64) {
+    if let [first, .., last] = vertices.as_slice() {
+        if first.distance(last) <= eps {
+            vertices.pop();
+        }
+    }
+}
+
+/// Whether any two non-adjacent edges of a polyline or polygon cross or touch.
+///
+/// For closed rings the edge from the last vertex back to the first is
+/// included, and counts as adjacent to the first edge.
+pub fn has_self_intersection(vertices: &[Point], closed: bool) -> bool {
+    let n = vertices.len();
+    let edge_count = if closed { n } else { n.saturating_sub(1) };
+    if edge_count < 3 {
+        return false;
+    }
+    let edge = |i: usize| (&vertices[i], &vertices[(i + 1) % n]);
+
+    for i in 0..edge_count {
+        for j in i + 2..edge_count {
+            // The closing edge shares the first vertex with edge 0
+            if closed && i == 0 && j == edge_count - 1 {
+                continue;
+            }
+            let ((a0, a1), (b0, b1)) = (edge(i), edge(j));
+            if segment_intersection(a0, a1, b0, b1).is_some() {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+/// Whether a point lies inside a polygon ring (even-odd rule).
+pub fn point_in_polygon(point: &Point, vertices: &[Point]) -> bool {
+    let mut inside = false;
+    for (i, a) in vertices.iter().enumerate() {
+        let b = &vertices[(i + 1) % vertices.len()];
+        if (a.y > point.y) != (b.y > point.y) && point.x < a.x + (point.y - a.y) * (b.x - a.x) / (b.y - a.y) {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+/// Twice the signed area of a ring; positive for counter-clockwise winding
+/// in a y-up frame.
+fn twice_signed_area(vertices: &[Point]) -> f64 {
+    vertices
+        .iter()
+        .zip(vertices.iter().cycle().skip(1))
+        .map(|(a, b)| a.x * b.y - b.x * a.y)
+        .sum()
+}
+
+/// Area of the intersection of two simple polygons.
+///
+/// Uses Green's theorem over the boundary of the intersection, which is
+/// made of the parts of each ring that lie inside the other. Works for
+/// concave polygons; boundary shared by both rings is counted once.
+pub fn polygon_intersection_area(a: &[Point], b: &[Point]) -> f64 {
+    const ON_EDGE: f64 = 1e-12;
+    if a.len() < 3 || b.len() < 3 {
+        return 0.0;
+    }
+
+    // Wind both rings the same way so their boundary pieces add up
+    let orient = |ring: &[Point]| {
+        let mut ring = ring.to_vec();
+        if twice_signed_area(&ring) < 0.0 {
+            ring.reverse();
+        }
+        ring
+    };
+    let (a, b) = (orient(a), orient(b));
+    let edges = |ring: &[Point]| -> Vec<(Point, Point)> {
+        ring.iter().zip(ring.iter().cycle().skip(1)).map(|(p, q)| (*p, *q)).collect()
+    };
+    let (a_edges, b_edges) = (edges(&a), edges(&b));
+
+    // Sum x dy - y dx over the pieces of `ring` selected by `keep`
+    let boundary_integral = |ring_edges: &[(Point, Point)], other_edges: &[(Point, Point)], keep: &dyn Fn(&Point, (f64, f64)) -> bool| {
+        let mut sum = 0.0;
+        for (p, q) in ring_edges {
+            // Split the edge wherever it crosses the other ring
+            let mut cuts = vec![0.0, 1.0];
+            cuts.extend(other_edges.iter().filter_map(|(r, s)| segment_intersection(p, q, r, s).map(|(t, _)| t)));
+            cuts.sort_by(f64::total_cmp);
+            cuts.dedup_by(|x, y| (*x - *y).abs() < ON_EDGE);
+
+            let direction = (q.x - p.x, q.y - p.y);
+            let at = |t: f64| Point::new(p.x + t * direction.0, p.y + t * direction.1);
+            for pair in cuts.windows(2) {
+                let (from, to) = (at(pair[0]), at(pair[1]));
+                if keep(&at((pair[0] + pair[1]) / 2.0), direction) {
+                    sum += from.x * to.y - to.x * from.y;
+                }
+            }
+        }
+        sum
+    };
+    let on_edge = |point: &Point, ring_edges: &[(Point, Point)]| {
+        ring_edges.iter().find(|(r, s)| point_segment_distance(point, r, s) < ON_EDGE).map(|(r, s)| (s.x - r.x, s.y - r.y))
+    };
+
+    // Pieces of A inside B, plus shared boundary running the same way
+    let from_a = boundary_integral(&a_edges, &b_edges, &|mid, direction| match on_edge(mid, &b_edges) {
+        Some(other) => direction.0 * other.0 + direction.1 * other.1 > 0.0,
+        None => point_in_polygon(mid, &b),
+    });
+    // Pieces of B strictly inside A
+    let from_b = boundary_integral(&b_edges, &a_edges, &|mid, _| on_edge(mid, &a_edges).is_none() && point_in_polygon(mid, &a));
+
+    ((from_a + from_b) / 2.0).max(0.0)
+}
+
+/// Intersection over union of two polygons, in 0..=1.
+///
+/// Non-overlapping polygons (including ones that only share an edge) give
+/// 0.0 and identical polygons give 1.0, whatever their winding.
+pub fn polygon_iou(a: &[Point], b: &[Point]) -> f64 {
+    // Skip the clipping when the bounding boxes are disjoint
+    let bounds = |ring: &[Point]| {
+        ring.iter().fold((f64::INFINITY, f64::INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY), |(x0, y0, x1, y1), p| {
+            (x0.min(p.x), y0.min(p.y), x1.max(p.x), y1.max(p.y))
+        })
+    };
+    let ((ax0, ay0, ax1, ay1), (bx0, by0, bx1, by1)) = (bounds(a), bounds(b));
+    if ax1 < bx0 || bx1 < ax0 || ay1 < by0 || by1 < ay0 {
+        return 0.0;
+    }
+
+    let intersection = polygon_intersection_area(a, b);
+    let union = polygon_area(a) + polygon_area(b) - intersection;
+    if union <= 0.0 {
+        0.0
+    } else {
+        (intersection / union).clamp(0.0, 1.0)
+    }
+}
+
+/// Inflate (positive distance) or deflate (negative) a polygon by a
+/// distance in pixels, using miter joins.
+///
+/// Corners sharper than the miter limit are beveled instead, with one
+/// vertex offset along each adjacent edge. Results are clamped to the
+/// image. Deflating by more than the polygon's inradius inverts it, so
+/// callers should check the result before using it.
+pub fn offset_polygon(vertices: &[Point], distance_px: f64, image_size: (u32, u32)) -> Vec<Point> {
+    // Miters longer than this multiple of the distance are beveled
+    const MITER_LIMIT: f64 = 4.0;
+    if vertices.len() < 3 || distance_px == 0.0 {
+        return vertices.to_vec();
+    }
+
+    // Work in pixels so the offset is the same in x and y
+    let (width, height) = (image_size.0.max(1) as f64, image_size.1.max(1) as f64);
+    let mut ring: Vec<(f64, f64)> = vertices.iter().map(|p| (p.x * width, p.y * height)).collect();
+    ring.dedup_by(|a, b| a == b);
+    if ring.len() > 1 && ring[0] == ring[ring.len() - 1] {
+        ring.pop();
+    }
+    if ring.len() < 3 {
+        return vertices.to_vec();
+    }
+
+    // Outward unit normal of the edge from a to b for either winding
+    let outward = if twice_signed_area(vertices) > 0.0 { 1.0 } else { -1.0 };
+    let normal = |a: (f64, f64), b: (f64, f64)| {
+        let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+        let length = dx.hypot(dy);
+        (outward * dy / length, -outward * dx / length)
+    };
+
+    let n = ring.len();
+    let mut result = Vec::with_capacity(n);
+    let mut push = |x: f64, y: f64| result.push(Point::new((x / width).clamp(0.0, 1.0), (y / height).clamp(0.0, 1.0)));
+    for i in 0..n {
+        let (prev, current, next) = (ring[(i + n - 1) % n], ring[i], ring[(i + 1) % n]);
+        let (n1, n2) = (normal(prev, current), normal(current, next));
+
+        // The miter runs along the bisector of the two normals, lengthened
+        // so each edge moves by exactly the distance
+        let (bx, by) = (n1.0 + n2.0, n1.1 + n2.1);
+        let bisector_length = bx.hypot(by);
+        let cos_half = bisector_length / 2.0;
+        if bisector_length > 1e-12 && 1.0 / cos_half <= MITER_LIMIT {
+            let scale = distance_px / (cos_half * bisector_length);
+            push(current.0 + bx * scale, current.1 + by * scale);
+        } else {
+            push(current.0 + n1.0 * distance_px, current.1 + n1.1 * distance_px);
+            push(current.0 + n2.0 * distance_px, current.1 + n2.1 * distance_px);
+        }
+    }
+    result
+}
+
+/// Triangulate a simple polygon by ear clipping.
+///
+/// Returns triangles as indices into `vertices`, wound counter-clockwise in
+/// a y-up frame (clockwise on screen). Concave polygons are supported;
+/// degenerate input (fewer than 3 vertices, zero area, or a
+/// self-intersecting ring with no ears) gives an empty list.
+pub fn triangulate(vertices: &[Point]) -> Vec<[usize; 3]> {
+    const COLLINEAR: f64 = 1e-15;
+    let area = twice_signed_area(vertices);
+    if vertices.len() < 3 || area.abs() < COLLINEAR {
+        return Vec::new();
+    }
+
+    // Walk the ring counter-clockwise so convex corners have positive cross products
+    let mut remaining: Vec<usize> = if area > 0.0 { (0..vertices.len()).collect() } else { (0..vertices.len()).rev().collect() };
+    let cross = |o: usize, a: usize, b: usize| {
+        let (o, a, b) = (&vertices[o], &vertices[a], &vertices[b]);
+        (a.x - o.x) * (b.y - o.y) - (a.y - o.y) * (b.x - o.x)
+    };
+    let corner = |remaining: &[usize], i: usize| {
+        let m = remaining.len();
+        (remaining[(i + m - 1) % m], remaining[i], remaining[(i + 1) % m])
+    };
+
+    let mut triangles = Vec::with_capacity(vertices.len() - 2);
+    while remaining.len() > 3 {
+        // An ear is a convex corner with no other vertex inside or on it
+        let ear = (0..remaining.len()).find(|&i| {
+            let (a, b, c) = corner(&remaining, i);
+            cross(a, b, c) > COLLINEAR
+                && !remaining.iter().any(|&j| {
+                    j != a && j != b && j != c && vertices[j] != vertices[a] && vertices[j] != vertices[b] && vertices[j] != vertices[c]
+                        && cross(a, b, j) >= 0.0 && cross(b, c, j) >= 0.0 && cross(c, a, j) >= 0.0
+                })
+        });
+        match ear {
+            Some(i) => {
+                let (a, b, c) = corner(&remaining, i);
+                triangles.push([a, b, c]);
+                remaining.remove(i);
+            }
+            None => {
+                // Drop a collinear vertex, which adds no area, or give up
+                let Some(i) = (0..remaining.len()).find(|&i| {
+                    let (a, b, c) = corner(&remaining, i);
+                    cross(a, b, c).abs() <= COLLINEAR
+                }) else {
+                    return Vec::new();
+                };
+                remaining.remove(i);
+            }
+        }
+    }
+    if cross(remaining[0], remaining[1], remaining[2]) > COLLINEAR {
+        triangles.push([remaining[0], remaining[1], remaining[2]]);
+    }
+    triangles
+}
+
+/// Scale normalized points to pixel coordinates.
+pub fn to_pixels(vertices: &[Point], image_size: (u32, u32)) -> Vec<Point> {
+    let (width, height) = (image_size.0 as f64, image_size.1 as f64);
+    vertices.iter().map(|p| Point::new(p.x * width, p.y * height)).collect()
+}
+
+/// Scale pixel points back to normalized coordinates.
+pub fn from_pixels(vertices: &[Point], image_size: (u32, u32)) -> Vec<Point> {
+    let (width, height) = (image_size.0 as f64, image_size.1 as f64);
+    vertices.iter().map(|p| Point::new(p.x / width, p.y / height)).collect()
+}
+
+/// A rotated rectangle.
+///
+/// `angle` is in degrees, clockwise on screen (y down), from the x axis to
+/// the box's first edge, which has length `width`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OrientedBox {
+    pub cx: f64,
+    pub cy: f64,
+    pub width: f64,
+    pub height: f64,
+    pub angle: f64,
+}
+
+impl OrientedBox {
+    /// Corners in drawing order, starting at the top-left of the unrotated
+    /// box and going clockwise on screen.
+    pub fn corners(&self) -> [Point; 4] {
+        let (sin, cos) = self.angle.to_radians().sin_cos();
+        let (hw, hh) = (self.width / 2.0, self.height / 2.0);
+        [(-hw, -hh), (hw, -hh), (hw, hh), (-hw, hh)].map(|(x, y)| Point::new(self.cx + x * cos - y * sin, self.cy + x * sin + y * cos))
+    }
+}
+
+/// Recognize a 4-vertex polygon as a rotated rectangle.
+///
+/// The vertices must be in a space with equal x and y scales (pixels, not
+/// normalized coordinates, for a non-square image). Returns None unless
+/// the sides are perpendicular and opposite sides equal, to a small
+/// tolerance relative to the box size.
+pub fn obb_from_polygon(vertices: &[Point]) -> Option<OrientedBox> {
+    const TOLERANCE: f64 = 1e-3;
+    let [a, b, c, d] = vertices else {
+        return None;
+    };
+    let edge = |from: &Point, to: &Point| (to.x - from.x, to.y - from.y);
+    let (e0, e1, e2, e3) = (edge(a, b), edge(b, c), edge(c, d), edge(d, a));
+    let (width, height) = (e0.0.hypot(e0.1), e1.0.hypot(e1.1));
+    let size = width.max(height);
+    if width.min(height) <= TOLERANCE * size {
+        return None;
+    }
+
+    let parallelogram = (e0.0 + e2.0).hypot(e0.1 + e2.1) <= TOLERANCE * size && (e1.0 + e3.0).hypot(e1.1 + e3.1) <= TOLERANCE * size;
+    let perpendicular = (e0.0 * e1.0 + e0.1 * e1.1).abs() <= TOLERANCE * width * height;
+    (parallelogram && perpendicular).then(|| OrientedBox {
+        cx: (a.x + b.x + c.x + d.x) / 4.0,
+        cy: (a.y + b.y + c.y + d.y) / 4.0,
+        width,
+        height,
+        angle: e0.1.atan2(e0.0).to_degrees(),
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn rect(x0: f64, y0: f64, x1: f64, y1: f64) -> Vec<Point> {
+        vec![Point::new(x0, y0), Point::new(x1, y0), Point::new(x1, y1), Point::new(x0, y1)]
+    }
+
+    #[test]
+    fn test_polygon_iou_identical() {
+        let square = rect(0.0, 0.0, 1.0, 1.0);
+        assert!((polygon_iou(&square, &square) - 1.0).abs() < 1e-9);
+
+        // Winding doesn't matter
+        let reversed: Vec<Point> = square.iter().rev().copied().collect();
+        assert!((polygon_iou(&square, &reversed) - 1.0).abs() < 1e-9);
+
+        // Nor does the starting vertex
+        let rotated = [square[2], square[3], square[0], square[1]];
+        assert!((polygon_iou(&square, &rotated) - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_polygon_iou_half_overlap() {
+        // Intersection 0.5, union 1.5
+        let square = rect(0.0, 0.0, 1.0, 1.0);
+        let shifted = rect(0.5, 0.0, 1.5, 1.0);
+        assert!((polygon_iou(&square, &shifted) - 1.0 / 3.0).abs() < 1e-9);
+        assert!((polygon_iou(&shifted, &square) - 1.0 / 3.0).abs() < 1e-9);
+
+        // Offset in both directions: intersection 0.25, union 1.75
+        let diagonal = rect(0.5, 0.5, 1.5, 1.5);
+        assert!((polygon_iou(&square, &diagonal) - 0.25 / 1.75).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_polygon_iou_no_overlap() {
+        let square = rect(0.0, 0.0, 1.0, 1.0);
+        assert_eq!(polygon_iou(&square, &rect(2.0, 2.0, 3.0, 3.0)), 0.0);
+        // Sharing an edge is not overlapping
+        assert!(polygon_iou(&square, &rect(1.0, 0.0, 2.0, 1.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_polygon_intersection_area() {
+        let square = rect(0.0, 0.0, 1.0, 1.0);
+
+        // Contained: a quarter-size square inside
+        let inner = rect(0.25, 0.25, 0.75, 0.75);
+        assert!((polygon_iou(&square, &inner) - 0.25).abs() < 1e-9);
+
+        // Concave L shape against the square covering its corner notch
+        let l_shape = vec![
+            Point::new(0.0, 0.0),
+            Point::new(2.0, 0.0),
+            Point::new(2.0, 1.0),
+            Point::new(1.0, 1.0),
+            Point::new(1.0, 2.0),
+            Point::new(0.0, 2.0),
+        ];
+        let notch = rect(0.5, 0.5, 1.5, 1.5);
+        // Overlap is the square minus its top-right quarter: 0.75
+        assert!((polygon_intersection_area(&l_shape, &notch) - 0.75).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_reproject_to_crop() {
+        let crop = (Point::new(0.25, 0.5), Point::new(0.75, 1.0));
+        // Inside
+        assert_eq!(reproject_to_crop(&Point::new(0.5, 0.75), crop), Some(Point::new(0.5, 0.5)));
+        // On the boundary, including corners
+        assert_eq!(reproject_to_crop(&Point::new(0.25, 0.625), crop), Some(Point::new(0.0, 0.25)));
+        assert_eq!(reproject_to_crop(&Point::new(0.75, 1.0), crop), Some(Point::new(1.0, 1.0)));
+        // Outside
+        assert_eq!(reproject_to_crop(&Point::new(0.2, 0.75), crop), None);
+        assert_eq!(reproject_to_crop(&Point::new(0.5, 0.4), crop), None);
+        // A crop with no area holds nothing
+        let flat = (Point::new(0.5, 0.5), Point::new(0.5, 1.0));
+        assert_eq!(reproject_to_crop(&Point::new(0.5, 0.75), flat), None);
+    }
+
+    #[test]
+    fn test_clip_to_rect() {
+        let rect_bounds = (Point::new(0.0, 0.0), Point::new(0.5, 0.5));
+        // A square straddling the corner keeps the overlapping quarter
+        let clipped = clip_polygon_to_rect(&rect(0.25, 0.25, 0.75, 0.75), rect_bounds);
+        assert!((polygon_area(&clipped) - 0.0625).abs() < 1e-12);
+        assert!(clipped.iter().all(|p| p.x <= 0.5 && p.y <= 0.5));
+        assert!(clip_polygon_to_rect(&rect(0.6, 0.6, 0.9, 0.9), rect_bounds).is_empty());
+
+        // A crossing that rounds to just outside the rectangle is clamped
+        // back onto its edge
+        let crop = (Point::new(0.1, 0.1), Point::new(0.7, 0.3));
+        let triangle = [Point::new(0.87, 0.24), Point::new(0.75, 0.58), Point::new(0.1, 0.3)];
+        let clipped = clip_polygon_to_rect(&triangle, crop);
+        assert!(clipped.iter().all(|p| reproject_to_crop(p, crop).is_some()), "{:?}", clipped);
+
+        // A line leaving and re-entering splits in two
+        let line = [Point::new(0.1, 0.1), Point::new(0.1, 0.9), Point::new(0.4, 0.9), Point::new(0.4, 0.1)];
+        let pieces = clip_polyline_to_rect(&line, rect_bounds);
+        assert_eq!(pieces, [
+            vec![Point::new(0.1, 0.1), Point::new(0.1, 0.5)],
+            vec![Point::new(0.4, 0.5), Point::new(0.4, 0.1)],
+        ]);
+        assert!(clip_polyline_to_rect(&[Point::new(0.6, 0.6), Point::new(0.9, 0.6)], rect_bounds).is_empty());
+    }
+
+    #[test]
+    fn test_flip_point() {
+        assert_eq!(flip_point(&Point::new(0.25, 0.1), true), Point::new(0.75, 0.1));
+        assert_eq!(flip_point(&Point::new(0.25, 0.1), false), Point::new(0.25, 0.9));
+
+        // Flipping twice returns the original point
+        for horizontal in [true, false] {
+            let original = Point::new(0.3, 0.7);
+            let there_and_back = flip_point(&flip_point(&original, horizontal), horizontal);
+            assert!(there_and_back.distance(&original) < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_rotate_point_90() {
+        // The top-left corner goes to the top-right when rotating clockwise
+        assert_eq!(rotate_point_90(&Point::new(0.0, 0.0), true), Point::new(1.0, 0.0));
+        assert_eq!(rotate_point_90(&Point::new(0.0, 0.0), false), Point::new(0.0, 1.0));
+
+        for cw in [true, false] {
+            let original = Point::new(0.2, 0.7);
+            let mut point = original;
+            for _ in 0..4 {
+                point = rotate_point_90(&point, cw);
+            }
+            assert!(point.distance(&original) < 1e-12);
+            // Rotating back undoes a rotation
+            let there_and_back = rotate_point_90(&rotate_point_90(&original, cw), !cw);
+            assert!(there_and_back.distance(&original) < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_constrain_rect_aspect() {
+        let ratio = 16.0 / 9.0;
+        // Wide drag: the width sets the size
+        assert_eq!(constrain_rect_aspect((100.0, 100.0), (260.0, 150.0), ratio), (260.0, 190.0));
+        // Tall drag: the height sets the size
+        let (x, y) = constrain_rect_aspect((100.0, 100.0), (140.0, 280.0), ratio);
+        assert!((x - 420.0).abs() < 1e-9 && y == 280.0);
+        // Up and to the left keeps the direction
+        assert_eq!(constrain_rect_aspect((200.0, 200.0), (40.0, 190.0), ratio), (40.0, 110.0));
+        // An invalid ratio leaves the corner alone
+        assert_eq!(constrain_rect_aspect((0.0, 0.0), (3.0, 4.0), 0.0), (3.0, 4.0));
+    }
+
+    #[test]
+    fn test_normalize_ring() {
+        let mut closed = rect(0.0, 0.0, 1.0, 1.0);
+        closed.push(Point::new(0.0, 0.0));
+        normalize_ring(&mut closed, 1e-9);
+        assert_eq!(closed, rect(0.0, 0.0, 1.0, 1.0));
+
+        // Only one closing vertex is dropped; open rings are untouched
+        normalize_ring(&mut closed, 1e-9);
+        assert_eq!(closed.len(), 4);
+        let mut single = vec![Point::new(0.5, 0.5)];
+        normalize_ring(&mut single, 1e-9);
+        assert_eq!(single.len(), 1);
+    }
+
+    #[test]
+    fn test_dedup_consecutive() {
+        let mut vertices = vec![
+            Point::new(0.0, 0.0),
+            Point::new(0.0, 0.0),
+            Point::new(0.5, 0.0),
+            Point::new(0.5000001, 0.0),
+            Point::new(0.0, 0.0),
+        ];
+        dedup_consecutive(&mut vertices, 1e-3);
+        // Only neighbors are merged, so returning to the start is kept
+        assert_eq!(vertices, [Point::new(0.0, 0.0), Point::new(0.5, 0.0), Point::new(0.0, 0.0)]);
+    }
+
+    #[test]
+    fn test_has_self_intersection() {
+        let square = [Point::new(0.0, 0.0), Point::new(1.0, 0.0), Point::new(1.0, 1.0), Point::new(0.0, 1.0)];
+        assert!(!has_self_intersection(&square, true));
+
+        // Swapping two vertices makes a bow tie
+        let bow_tie = [Point::new(0.0, 0.0), Point::new(1.0, 1.0), Point::new(1.0, 0.0), Point::new(0.0, 1.0)];
+        assert!(has_self_intersection(&bow_tie, true));
+
+        // The same path left open only crosses once the closing edge is added
+        let zigzag = [Point::new(0.0, 0.0), Point::new(1.0, 0.0), Point::new(1.0, 1.0), Point::new(2.0, 0.5)];
+        assert!(!has_self_intersection(&zigzag, false));
+        assert!(has_self_intersection(&zigzag, true));
+    }
+
+    #[test]
+    fn test_split_polygon() {
+        let square = [Point::new(0.0, 0.0), Point::new(1.0, 0.0), Point::new(1.0, 1.0), Point::new(0.0, 1.0)];
+
+        // Vertical cut through the middle gives two equal rectangles
+        let (left, right) = split_polygon(&square, (Point::new(0.5, -0.1), Point::new(0.5, 1.1))).unwrap();
+        assert_eq!(left.len(), 4);
+        assert_eq!(right.len(), 4);
+        assert!((polygon_area(&left) - 0.5).abs() < 1e-9);
+        assert!((polygon_area(&right) - 0.5).abs() < 1e-9);
+
+        // Diagonal cut through opposite corners gives two triangles
+        let (a, b) = split_polygon(&square, (Point::new(-0.1, -0.1), Point::new(1.1, 1.1))).unwrap();
+        assert_eq!((a.len(), b.len()), (3, 3));
+        assert!((polygon_area(&a) - 0.5).abs() < 1e-9);
+
+        // A cut that stops inside the polygon does not split it
+        assert!(split_polygon(&square, (Point::new(0.5, -0.1), Point::new(0.5, 0.5))).is_none());
+        // Nor does one that misses it
+        assert!(split_polygon(&square, (Point::new(2.0, 0.0), Point::new(2.0, 1.0))).is_none());
+    }
+
     #[test]
     fn test_normalize_denormalize_roundtrip() {
         let width = 1920;
@@ -54,4 +1262,355 @@ mod tests {
         assert_eq!(br.x, 1.0);
         assert_eq!(br.y, 1.0);
     }
+
+    fn square_ring() -> Vec<Point> {
+        vec![
+            Point::new(0.0, 0.0),
+            Point::new(1.0, 0.0),
+            Point::new(1.0, 1.0),
+            Point::new(0.0, 1.0),
+        ]
+    }
+
+    #[test]
+    fn test_ring_sub_path_forward() {
+        let ring = square_ring();
+        let path = ring_sub_path(&ring, 3, 1, true);
+        assert_eq!(path, vec![ring[3], ring[0], ring[1]]);
+    }
+
+    #[test]
+    fn test_ring_sub_path_backward() {
+        let ring = square_ring();
+        let path = ring_sub_path(&ring, 1, 3, false);
+        assert_eq!(path, vec![ring[1], ring[0], ring[3]]);
+
+        let path = ring_sub_path(&ring, 2, 0, false);
+        assert_eq!(path, vec![ring[2], ring[1], ring[0]]);
+    }
+
+    #[test]
+    fn test_ring_sub_path_out_of_bounds() {
+        let ring = square_ring();
+        assert!(ring_sub_path(&ring, 0, 10, true).is_empty());
+    }
+
+    #[test]
+    fn test_shortest_ring_sub_path() {
+        let ring = square_ring();
+        // 0 -> 3 is one step backward but three steps forward
+        let path = shortest_ring_sub_path(&ring, 0, 3);
+        assert_eq!(path, vec![ring[0], ring[3]]);
+    }
+
+    #[test]
+    fn test_project_onto_segment() {
+        let a = Point::new(0.0, 0.0);
+        let b = Point::new(1.0, 0.0);
+
+        assert_eq!(project_onto_segment(&Point::new(0.5, 0.3), &a, &b), Point::new(0.5, 0.0));
+        // Beyond the ends the projection clamps to the endpoints
+        assert_eq!(project_onto_segment(&Point::new(-1.0, 0.5), &a, &b), a);
+        assert_eq!(project_onto_segment(&Point::new(2.0, -0.5), &a, &b), b);
+        assert!((point_segment_distance(&Point::new(0.5, 0.3), &a, &b) - 0.3).abs() < 1e-12);
+
+        // A degenerate segment projects onto its single point
+        assert_eq!(project_onto_segment(&Point::new(0.5, 0.3), &a, &a), a);
+    }
+
+    #[test]
+    fn test_simplify_polygon_removes_near_collinear_points() {
+        let ring = vec![
+            Point::new(0.0, 0.0),
+            Point::new(0.5, 0.001),
+            Point::new(1.0, 0.0),
+            Point::new(1.0, 1.0),
+            Point::new(0.5, 0.999),
+            Point::new(0.0, 1.0),
+        ];
+
+        let simplified = simplify_polygon(&ring, 0.01, true);
+        assert_eq!(simplified.len(), 4);
+        assert!(simplified.contains(&Point::new(1.0, 1.0)));
+
+        // A tight tolerance keeps everything
+        assert_eq!(simplify_polygon(&ring, 0.0001, true).len(), 6);
+    }
+
+    #[test]
+    fn test_remove_collinear() {
+        // A square with an extra midpoint on its bottom edge
+        let ring = vec![
+            Point::new(0.0, 0.0),
+            Point::new(1.0, 0.0),
+            Point::new(1.0, 1.0),
+            Point::new(0.5, 1.0),
+            Point::new(0.0, 1.0),
+        ];
+        let merged = remove_collinear(&ring, 1e-6, true);
+        assert_eq!(merged, rect(0.0, 0.0, 1.0, 1.0));
+
+        // The midpoint may also be the ring's first vertex
+        let rotated = [&ring[3..], &ring[..3]].concat();
+        let merged = remove_collinear(&rotated, 1e-6, true);
+        assert_eq!(merged.len(), 4);
+        assert!(!merged.contains(&Point::new(0.5, 1.0)));
+
+        // A slight bend survives a tight tolerance but not a loose one
+        let bent = [Point::new(0.0, 0.0), Point::new(0.5, 0.001), Point::new(1.0, 0.0)];
+        assert_eq!(remove_collinear(&bent, 1e-6, false).len(), 3);
+        assert_eq!(remove_collinear(&bent, 0.01, false), [bent[0], bent[2]]);
+
+        // Repeated vertices go, endpoints of open chains stay
+        let line = [Point::new(0.0, 0.0), Point::new(0.2, 0.2), Point::new(0.2, 0.2), Point::new(0.4, 0.5)];
+        assert_eq!(remove_collinear(&line, 1e-6, false), [line[0], line[1], line[3]]);
+    }
+
+    #[test]
+    fn test_offset_polygon_square() {
+        let square = rect(0.25, 0.25, 0.75, 0.75);
+        let area = polygon_area(&square);
+
+        // 50 x 50 px square grows to 70 x 70 px
+        let grown = offset_polygon(&square, 10.0, (100, 100));
+        assert_eq!(grown.len(), 4);
+        assert!(polygon_area(&grown) > area);
+        assert!((polygon_area(&grown) - 0.49).abs() < 1e-9);
+
+        // Winding doesn't change the direction of the offset
+        let reversed: Vec<Point> = square.iter().rev().copied().collect();
+        assert!((polygon_area(&offset_polygon(&reversed, 10.0, (100, 100))) - 0.49).abs() < 1e-9);
+
+        let shrunk = offset_polygon(&square, -10.0, (100, 100));
+        assert!(polygon_area(&shrunk) < area);
+        assert!((polygon_area(&shrunk) - 0.09).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_offset_polygon_bevels_sharp_corners() {
+        // The tip of a thin spike would miter far past the limit
+        let spike = vec![Point::new(0.4, 0.5), Point::new(0.9, 0.48), Point::new(0.4, 0.46)];
+        let grown = offset_polygon(&spike, 2.0, (100, 100));
+        assert_eq!(grown.len(), 4);
+        assert!(grown.iter().all(|p| p.x < 0.95));
+        assert!(polygon_area(&grown) > polygon_area(&spike));
+    }
+
+    #[test]
+    fn test_obb_round_trip() {
+        let obb = OrientedBox { cx: 320.0, cy: 240.0, width: 120.0, height: 40.0, angle: 30.0 };
+        let corners = obb.corners();
+        let recovered = obb_from_polygon(&corners).unwrap();
+        assert!((recovered.cx - obb.cx).abs() < 1e-9 && (recovered.cy - obb.cy).abs() < 1e-9);
+        assert!((recovered.width - obb.width).abs() < 1e-9 && (recovered.height - obb.height).abs() < 1e-9);
+        assert!((recovered.angle - obb.angle).abs() < 1e-9);
+        for (a, b) in recovered.corners().iter().zip(&corners) {
+            assert!(a.distance(b) < 1e-9);
+        }
+
+        // Through normalized coordinates of a non-square image
+        let normalized = from_pixels(&corners, (640, 480));
+        let recovered = obb_from_polygon(&to_pixels(&normalized, (640, 480))).unwrap();
+        assert!((recovered.angle - 30.0).abs() < 1e-9);
+
+        // An axis-aligned rectangle has no rotation
+        assert_eq!(obb_from_polygon(&rect(10.0, 20.0, 50.0, 40.0)).unwrap().angle, 0.0);
+
+        // Not rectangles
+        let skewed = [Point::new(0.0, 0.0), Point::new(10.0, 0.0), Point::new(12.0, 5.0), Point::new(2.0, 5.0)];
+        assert!(obb_from_polygon(&skewed).is_none());
+        assert!(obb_from_polygon(&rect(0.0, 0.0, 1.0, 1.0)[..3]).is_none());
+    }
+
+    #[test]
+    fn test_triangulate() {
+        let square = rect(0.0, 0.0, 1.0, 1.0);
+        let triangles = triangulate(&square);
+        assert_eq!(triangles.len(), 2);
+        let area: f64 = triangles.iter().map(|t| polygon_area(&t.map(|i| square[i]))).sum();
+        assert!((area - 1.0).abs() < 1e-12);
+
+        // Concave pentagon (a notch in the top edge), either winding
+        let pentagon = vec![
+            Point::new(0.0, 0.0),
+            Point::new(0.5, 0.4),
+            Point::new(1.0, 0.0),
+            Point::new(1.0, 1.0),
+            Point::new(0.0, 1.0),
+        ];
+        for ring in [pentagon.clone(), pentagon.iter().rev().copied().collect()] {
+            let triangles = triangulate(&ring);
+            assert_eq!(triangles.len(), 3);
+            let area: f64 = triangles.iter().map(|t| polygon_area(&t.map(|i| ring[i]))).sum();
+            assert!((area - polygon_area(&ring)).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_triangulate_degenerate() {
+        assert!(triangulate(&[Point::new(0.0, 0.0), Point::new(1.0, 0.0)]).is_empty());
+        assert!(triangulate(&[Point::new(0.0, 0.0), Point::new(0.5, 0.0), Point::new(1.0, 0.0)]).is_empty());
+
+        // A vertex on an edge adds no triangle
+        let mut square = rect(0.0, 0.0, 1.0, 1.0);
+        square.insert(1, Point::new(0.5, 0.0));
+        assert_eq!(triangulate(&square).len(), 3);
+    }
+
+    #[test]
+    fn test_polygon_area() {
+        assert_eq!(polygon_area(&square_ring()), 1.0);
+
+        // Winding order doesn't matter
+        let mut reversed = square_ring();
+        reversed.reverse();
+        assert_eq!(polygon_area(&reversed), 1.0);
+
+        let triangle = vec![Point::new(0.0, 0.0), Point::new(0.5, 0.0), Point::new(0.0, 0.5)];
+        assert_eq!(polygon_area(&triangle), 0.125);
+
+        assert_eq!(polygon_area(&triangle[..2]), 0.0);
+    }
+
+    #[test]
+    fn test_polygon_area_skips_non_finite() {
+        let mut ring = square_ring();
+        ring.insert(2, Point::new(f64::NAN, 0.5));
+        assert_eq!(polygon_area(&ring), 1.0);
+
+        let broken = [Point::new(0.0, 0.0), Point::new(f64::INFINITY, 0.0), Point::new(0.0, f64::NAN)];
+        assert_eq!(polygon_area(&broken), 0.0);
+    }
+
+    /// Pixels of a 100×100 grid whose centers fall inside a ring.
+    fn filled_pixels(ring: &[Point], rule: FillRule) -> usize {
+        (0..100)
+            .map(|row| {
+                let y = (row as f64 + 0.5) / 100.0;
+                let spans = fill_spans(ring, y, rule);
+                (0..100).filter(|&col| {
+                    let x = (col as f64 + 0.5) / 100.0;
+                    spans.iter().any(|&(start, end)| (start..end).contains(&x))
+                }).count()
+            })
+            .sum()
+    }
+
+    #[test]
+    fn test_fill_rules() {
+        // A figure-eight's lobes wind in opposite directions, so both rules fill them
+        let figure_eight = vec![Point::new(0.1, 0.1), Point::new(0.9, 0.9), Point::new(0.9, 0.1), Point::new(0.1, 0.9)];
+        assert_eq!(filled_pixels(&figure_eight, FillRule::EvenOdd), filled_pixels(&figure_eight, FillRule::NonZero));
+        assert!((filled_area(&[&figure_eight], FillRule::EvenOdd) - 0.32).abs() < 1e-12);
+        assert!((filled_area(&[&figure_eight], FillRule::NonZero) - 0.32).abs() < 1e-12);
+        // The plain shoelace area cancels the lobes out
+        assert!(polygon_area(&figure_eight) < 1e-12);
+
+        // A ring that loops around a second time winds twice over the inner
+        // square, which even-odd leaves as a hole; the path also cuts a
+        // 0.1 × 0.1 notch from the outer square's corner
+        let double_loop = vec![
+            Point::new(0.1, 0.1), Point::new(0.5, 0.1), Point::new(0.5, 0.5), Point::new(0.1, 0.5),
+            Point::new(0.1, 0.2), Point::new(0.4, 0.2), Point::new(0.4, 0.4), Point::new(0.2, 0.4),
+            Point::new(0.2, 0.1),
+        ];
+        assert_eq!(filled_pixels(&double_loop, FillRule::NonZero), 1500);
+        assert_eq!(filled_pixels(&double_loop, FillRule::EvenOdd), 1100);
+        assert!((filled_area(&[&double_loop], FillRule::NonZero) - 0.15).abs() < 1e-12);
+        assert!((filled_area(&[&double_loop], FillRule::EvenOdd) - 0.11).abs() < 1e-12);
+
+        // Simple rings agree with the shoelace area under either rule
+        assert_eq!(filled_area(&[&square_ring()], FillRule::NonZero), polygon_area(&square_ring()));
+
+        // A second ring inside the first is a hole under even-odd, and under
+        // non-zero when wound the other way; a separate part adds its area
+        let hole = [Point::new(0.2, 0.2), Point::new(0.2, 0.4), Point::new(0.4, 0.4), Point::new(0.4, 0.2)];
+        let reversed: Vec<Point> = hole.iter().rev().copied().collect();
+        let part = [Point::new(0.6, 0.6), Point::new(0.8, 0.6), Point::new(0.8, 0.8), Point::new(0.6, 0.8)];
+        assert!((filled_area(&[&double_loop[..4], &hole], FillRule::EvenOdd) - 0.12).abs() < 1e-12);
+        assert!((filled_area(&[&double_loop[..4], &hole], FillRule::NonZero) - 0.12).abs() < 1e-12);
+        assert!((filled_area(&[&double_loop[..4], &reversed], FillRule::NonZero) - 0.16).abs() < 1e-12);
+        assert!((filled_area(&[&double_loop[..4], &part], FillRule::EvenOdd) - 0.2).abs() < 1e-12);
+        assert_eq!(filled_area(&[], FillRule::EvenOdd), 0.0);
+    }
+
+    #[test]
+    fn test_polygon_centroid() {
+        let centroid = polygon_centroid(&square_ring()).unwrap();
+        assert!((centroid.x - 0.5).abs() < 1e-12 && (centroid.y - 0.5).abs() < 1e-12);
+
+        // An L-shape's centroid is pulled toward its larger arm
+        let l_shape = vec![
+            Point::new(0.0, 0.0),
+            Point::new(2.0, 0.0),
+            Point::new(2.0, 1.0),
+            Point::new(1.0, 1.0),
+            Point::new(1.0, 2.0),
+            Point::new(0.0, 2.0),
+        ];
+        let centroid = polygon_centroid(&l_shape).unwrap();
+        assert!((centroid.x - 5.0 / 6.0).abs() < 1e-12);
+        assert!((centroid.y - 5.0 / 6.0).abs() < 1e-12);
+
+        // Degenerate rings use the vertex average
+        let collinear = vec![Point::new(0.0, 0.0), Point::new(1.0, 0.0), Point::new(2.0, 0.0)];
+        assert_eq!(polygon_centroid(&collinear), Some(Point::new(1.0, 0.0)));
+        assert_eq!(polygon_centroid(&[]), None);
+    }
+
+    #[test]
+    fn test_constrain_segment_snaps_near_horizontal() {
+        let from = Point::new(0.2, 0.5);
+        let to = constrain_segment(&from, &Point::new(0.6, 0.52), (1920, 1080));
+
+        assert_eq!(to.y, from.y);
+        let (length, angle) = measure_segment(&from, &to, (1920, 1080));
+        assert_eq!(angle, 0.0);
+        assert!((length - 0.4 * 1920.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_constrain_segment_aspect_correct() {
+        // 45 degrees in pixels on a 2:1 image is half as far in normalized x
+        let from = Point::new(0.5, 0.5);
+        let to = constrain_segment(&from, &Point::new(0.6, 0.29), (200, 100));
+        let (_, angle) = measure_segment(&from, &to, (200, 100));
+        assert!((angle - 45.0).abs() < 1e-9);
+
+        // Near-vertical snaps to exactly 90 degrees (straight up)
+        let to = constrain_segment(&from, &Point::new(0.501, 0.1), (200, 100));
+        assert_eq!(to.x, from.x);
+        assert_eq!(measure_segment(&from, &to, (200, 100)).1, 90.0);
+
+        assert_eq!(constrain_segment(&from, &from, (200, 100)), from);
+    }
+
+    #[test]
+    fn test_min_enclosing_circle() {
+        // An obtuse triangle: the circle passes through the two farthest
+        // points, with the third strictly inside
+        let triangle = [Point::new(0.0, 0.0), Point::new(4.0, 0.0), Point::new(2.0, 1.0)];
+        let (center, radius) = min_enclosing_circle(&triangle);
+        assert!(center.distance(&Point::new(2.0, 0.0)) < 1e-12);
+        assert!((radius - 2.0).abs() < 1e-12);
+        assert!(center.distance(&triangle[2]) < radius);
+
+        // An acute triangle needs its circumcircle
+        let (center, radius) = min_enclosing_circle(&[Point::new(0.0, 0.0), Point::new(2.0, 0.0), Point::new(1.0, 1.5)]);
+        assert!(center.distance(&Point::new(1.0, 5.0 / 12.0)) < 1e-12);
+        assert!((radius - 13.0 / 12.0).abs() < 1e-12);
+
+        // Every vertex of a dense polygon ends up inside
+        let ring = circle_polygon(&Point::new(3.0, -2.0), 5.0, 200);
+        let (center, radius) = min_enclosing_circle(&ring);
+        assert!(center.distance(&Point::new(3.0, -2.0)) < 1e-9);
+        assert!((radius - 5.0).abs() < 1e-9);
+        assert!(ring.iter().all(|p| center.distance(p) <= radius + 1e-9));
+
+        // Degenerate input
+        assert_eq!(min_enclosing_circle(&[]), (Point::new(0.0, 0.0), 0.0));
+        assert_eq!(min_enclosing_circle(&[Point::new(1.0, 2.0), Point::new(f64::NAN, 0.0)]), (Point::new(1.0, 2.0), 0.0));
+        let (center, radius) = min_enclosing_circle(&[Point::new(0.0, 0.0), Point::new(1.0, 0.0), Point::new(3.0, 0.0)]);
+        assert_eq!((center, radius), (Point::new(1.5, 0.0), 1.5));
+    }
 }
@@ -3,4 +3,12 @@
 
 //! Utility functions and helpers.
 
+pub mod color;
 pub mod geometry;
+pub mod progress;
+pub mod raster;
+pub mod rename;
+#[cfg(test)]
+pub mod scratch;
+pub mod stats;
+pub mod time;
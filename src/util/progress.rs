@@ -0,0 +1,251 @@
+// Copyright (c) 2025, Jason Jenkins
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Progress reporting for batch operations.
+//!
+//! Long folder operations run on a worker thread via [`BatchJob`], which
+//! reports each completed file back to the UI over a channel and can be
+//! cancelled between files.
+
+use anyhow::{anyhow, Result};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
+use std::sync::Arc;
+
+/// Progress of a batch operation, shown as a progress bar.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Progress {
+    /// What the operation is doing, e.g. "Importing annotations"
+    pub label: String,
+    /// Number of items completed
+    pub current: usize,
+    /// Number of items in the batch, once known
+    pub total: Option<usize>,
+    /// Name of the last completed item
+    pub item: Option<String>,
+}
+
+/// Update sent from a worker to its [`Progress`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProgressUpdate {
+    /// The batch holds this many items
+    Total(usize),
+    /// One more item, with this name, is done
+    Step(String),
+}
+
+impl Progress {
+    /// Start tracking a batch of unknown size.
+    pub fn new(label: impl Into<String>) -> Self {
+        Self {
+            label: label.into(),
+            current: 0,
+            total: None,
+            item: None,
+        }
+    }
+
+    /// Apply an update from the worker.
+    pub fn apply(&mut self, update: ProgressUpdate) {
+        match update {
+            ProgressUpdate::Total(total) => self.total = Some(total),
+            ProgressUpdate::Step(item) => {
+                self.current += 1;
+                // Never report more than the total, whatever the worker sends
+                if let Some(total) = self.total {
+                    self.current = self.current.min(total);
+                }
+                self.item = Some(item);
+            }
+        }
+    }
+
+    /// Completed fraction (0..1), or None while the total is unknown.
+    pub fn fraction(&self) -> Option<f32> {
+        self.total.map(|total| if total == 0 { 1.0 } else { self.current as f32 / total as f32 })
+    }
+
+    /// Short status text, e.g. "3 / 10: frame_0003.json".
+    pub fn text(&self) -> String {
+        let count = match self.total {
+            Some(total) => format!("{} / {}", self.current, total),
+            None => self.current.to_string(),
+        };
+        match self.item {
+            Some(ref item) => format!("{}: {}", count, item),
+            None => count,
+        }
+    }
+}
+
+/// Handle a worker uses to report progress and check for cancellation.
+#[derive(Debug, Clone)]
+pub struct ProgressReporter {
+    sender: Option<Sender<ProgressUpdate>>,
+    cancel: Arc<AtomicBool>,
+}
+
+impl ProgressReporter {
+    /// A reporter that reports nowhere and is never cancelled, for running
+    /// batch functions synchronously in tests.
+    #[cfg(test)]
+    pub fn detached() -> Self {
+        Self {
+            sender: None,
+            cancel: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Report the number of items in the batch.
+    pub fn set_total(&self, total: usize) {
+        self.send(ProgressUpdate::Total(total));
+    }
+
+    /// Report that an item is done.
+    pub fn step(&self, item: impl Into<String>) {
+        self.send(ProgressUpdate::Step(item.into()));
+    }
+
+    /// Whether the user asked to stop; workers check this between items.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.load(Ordering::Relaxed)
+    }
+
+    fn send(&self, update: ProgressUpdate) {
+        if let Some(ref sender) = self.sender {
+            // The UI may have dropped the job; the worker just finishes quietly
+            let _ = sender.send(update);
+        }
+    }
+}
+
+/// A batch operation running on a worker thread.
+pub struct BatchJob<T> {
+    /// Progress so far, updated by [`BatchJob::poll`]
+    pub progress: Progress,
+    updates: Receiver<ProgressUpdate>,
+    result: Receiver<T>,
+    cancel: Arc<AtomicBool>,
+}
+
+impl<T: Send + 'static> BatchJob<T> {
+    /// Run `work` on a worker thread, passing it a reporter for progress.
+    pub fn spawn(label: impl Into<String>, work: impl FnOnce(ProgressReporter) -> T + Send + 'static) -> Self {
+        let (update_sender, updates) = channel();
+        let (result_sender, result) = channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let reporter = ProgressReporter {
+            sender: Some(update_sender),
+            cancel: Arc::clone(&cancel),
+        };
+
+        std::thread::spawn(move || {
+            let _ = result_sender.send(work(reporter));
+        });
+
+        Self {
+            progress: Progress::new(label),
+            updates,
+            result,
+            cancel,
+        }
+    }
+
+    /// Ask the worker to stop after its current item.
+    pub fn cancel(&self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether cancellation has been requested.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.load(Ordering::Relaxed)
+    }
+
+    /// Apply pending progress updates and return the result once the
+    /// worker has finished, or an error if it died without one.
+    pub fn poll(&mut self) -> Option<Result<T>> {
+        let result = match self.result.try_recv() {
+            Ok(result) => Some(Ok(result)),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(Err(anyhow!("{} stopped unexpectedly", self.progress.label))),
+        };
+        // Drained after checking for the result, so every update sent before it is applied
+        while let Ok(update) = self.updates.try_recv() {
+            self.progress.apply(update);
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_progress_updates() {
+        let mut progress = Progress::new("Importing");
+        assert_eq!(progress.fraction(), None);
+        assert_eq!(progress.text(), "0");
+
+        progress.apply(ProgressUpdate::Total(4));
+        assert_eq!(progress.fraction(), Some(0.0));
+        progress.apply(ProgressUpdate::Step("a.json".to_string()));
+        progress.apply(ProgressUpdate::Step("b.json".to_string()));
+        assert_eq!(progress.fraction(), Some(0.5));
+        assert_eq!(progress.text(), "2 / 4: b.json");
+
+        // Extra steps are capped at the total
+        for _ in 0..5 {
+            progress.apply(ProgressUpdate::Step("c.json".to_string()));
+        }
+        assert_eq!(progress.current, 4);
+        assert_eq!(progress.fraction(), Some(1.0));
+
+        let mut empty = Progress::new("Exporting");
+        empty.apply(ProgressUpdate::Total(0));
+        assert_eq!(empty.fraction(), Some(1.0));
+    }
+
+    /// Poll a job until it finishes.
+    fn wait<T: Send + 'static>(job: &mut BatchJob<T>) -> T {
+        let start = Instant::now();
+        loop {
+            if let Some(result) = job.poll() {
+                return result.unwrap();
+            }
+            assert!(start.elapsed() < Duration::from_secs(5), "Batch job did not finish");
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn test_batch_job_reports_and_cancels() {
+        let mut job = BatchJob::spawn("Counting", |reporter: ProgressReporter| {
+            reporter.set_total(3);
+            for i in 0..3 {
+                reporter.step(format!("item {}", i));
+            }
+            3
+        });
+        assert_eq!(wait(&mut job), 3);
+        // Updates sent before the result are applied by the final poll
+        assert_eq!(job.progress.text(), "3 / 3: item 2");
+
+        // A cancelled worker stops at its next check
+        let (started_sender, started) = channel();
+        let mut job = BatchJob::spawn("Waiting", move |reporter: ProgressReporter| {
+            let _ = started_sender.send(());
+            let mut steps = 0;
+            while !reporter.is_cancelled() {
+                steps += 1;
+                std::thread::sleep(Duration::from_millis(1));
+            }
+            steps
+        });
+        started.recv().unwrap();
+        job.cancel();
+        assert!(job.is_cancelled());
+        wait(&mut job);
+    }
+}
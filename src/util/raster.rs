@@ -0,0 +1,200 @@
+// Copyright (c) 2025, Jason Jenkins
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Software rasterization of annotations.
+//!
+//! This module draws annotation outlines directly into RGBA pixel buffers,
+//! which is used to export overlays burned into the source image.
+
+use crate::io::media::LoadedImage;
+use crate::models::{
+    annotation::Annotation,
+    settings::LineCap,
+};
+
+/// Stroke parameters for rasterized lines.
+#[derive(Debug, Clone, Copy)]
+pub struct RasterStroke {
+    /// Line width in pixels
+    pub width: f64,
+    /// RGBA color
+    pub color: [u8; 4],
+    /// Blend partially covered pixels for smooth edges
+    pub antialias: bool,
+    /// Shape of line ends and joins
+    pub cap: LineCap,
+}
+
+/// Draw a line segment (pixel coordinates) into an image.
+pub fn draw_line(image: &mut LoadedImage, from: (f64, f64), to: (f64, f64), stroke: &RasterStroke) {
+    let half_width = stroke.width.max(1.0) / 2.0;
+    // Anti-aliased edges fade out over one extra pixel
+    let margin = half_width + 1.0;
+
+    let min_x = (from.0.min(to.0) - margin).floor().max(0.0) as u32;
+    let min_y = (from.1.min(to.1) - margin).floor().max(0.0) as u32;
+    let max_x = (from.0.max(to.0) + margin).ceil().min(image.width as f64 - 1.0);
+    let max_y = (from.1.max(to.1) + margin).ceil().min(image.height as f64 - 1.0);
+    if max_x < 0.0 || max_y < 0.0 {
+        return;
+    }
+
+    for y in min_y..=max_y as u32 {
+        for x in min_x..=max_x as u32 {
+            // Sample at the pixel center
+            let center = (x as f64 + 0.5, y as f64 + 0.5);
+            let distance = stroke_distance(center, from, to, stroke.cap);
+
+            let coverage = if stroke.antialias {
+                (half_width + 0.5 - distance).clamp(0.0, 1.0)
+            } else if distance <= half_width {
+                1.0
+            } else {
+                0.0
+            };
+
+            if coverage > 0.0 {
+                blend_pixel(image, x, y, stroke.color, coverage);
+            }
+        }
+    }
+}
+
+/// Draw the outline of an annotation into an image.
+pub fn draw_annotation(image: &mut LoadedImage, annotation: &Annotation, stroke: &RasterStroke) {
+    let (width, height) = (image.width as f64, image.height as f64);
+    let points: Vec<(f64, f64)> = annotation
+        .vertices
+        .0
+        .iter()
+        .map(|p| (p.x * width, p.y * height))
+        .collect();
+
+    // A single point is drawn as a dot
+    if let [point] = points[..] {
+        draw_line(image, point, point, stroke);
+    }
+
+    for pair in points.windows(2) {
+        draw_line(image, pair[0], pair[1], stroke);
+    }
+
+    if annotation.is_closed() && points.len() > 2 {
+        draw_line(image, points[points.len() - 1], points[0], stroke);
+    }
+}
+
+/// Distance from a point to a stroked segment, shaped by the line cap.
+///
+/// Round caps measure Euclidean distance to the segment (a capsule), while
+/// square caps measure the larger of the along-segment overshoot and the
+/// perpendicular distance (a rectangle extended by half the width).
+fn stroke_distance(p: (f64, f64), a: (f64, f64), b: (f64, f64), cap: LineCap) -> f64 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let length = (dx * dx + dy * dy).sqrt();
+    if length == 0.0 {
+        let (px, py) = (p.0 - a.0, p.1 - a.1);
+        return match cap {
+            LineCap::Round => (px * px + py * py).sqrt(),
+            LineCap::Square => px.abs().max(py.abs()),
+        };
+    }
+
+    // Project onto the segment's local axes
+    let (ux, uy) = (dx / length, dy / length);
+    let (px, py) = (p.0 - a.0, p.1 - a.1);
+    let along = px * ux + py * uy;
+    let across = (px * uy - py * ux).abs();
+
+    let overshoot = if along < 0.0 {
+        -along
+    } else if along > length {
+        along - length
+    } else {
+        0.0
+    };
+
+    match cap {
+        LineCap::Round => (overshoot * overshoot + across * across).sqrt(),
+        LineCap::Square => overshoot.max(across),
+    }
+}
+
+/// Alpha-blend a color into a pixel with the given coverage.
+fn blend_pixel(image: &mut LoadedImage, x: u32, y: u32, color: [u8; 4], coverage: f64) {
+    let index = ((y * image.width + x) * 4) as usize;
+    let alpha = coverage * color[3] as f64 / 255.0;
+
+    for (dst, &src) in image.pixels[index..index + 3].iter_mut().zip(&color[..3]) {
+        *dst = (src as f64 * alpha + *dst as f64 * (1.0 - alpha)).round() as u8;
+    }
+    let dst_alpha = image.pixels[index + 3] as f64;
+    image.pixels[index + 3] = (255.0 * alpha + dst_alpha * (1.0 - alpha)).round() as u8;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blank_image(width: u32, height: u32) -> LoadedImage {
+        LoadedImage {
+            width,
+            height,
+            pixels: vec![0; (width * height * 4) as usize],
+            color_type: image::ExtendedColorType::Rgba8,
+        }
+    }
+
+    fn painted_pixels(image: &LoadedImage) -> usize {
+        image.pixels.chunks(4).filter(|p| p[3] > 0).count()
+    }
+
+    fn stroke(antialias: bool, cap: LineCap) -> RasterStroke {
+        RasterStroke {
+            width: 1.0,
+            color: [255, 0, 0, 255],
+            antialias,
+            cap,
+        }
+    }
+
+    #[test]
+    fn test_antialias_covers_more_pixels() {
+        let mut aliased = blank_image(20, 20);
+        let mut smooth = blank_image(20, 20);
+
+        draw_line(&mut aliased, (2.0, 3.0), (17.0, 14.0), &stroke(false, LineCap::Round));
+        draw_line(&mut smooth, (2.0, 3.0), (17.0, 14.0), &stroke(true, LineCap::Round));
+
+        let aliased_count = painted_pixels(&aliased);
+        let smooth_count = painted_pixels(&smooth);
+        assert!(aliased_count > 0);
+        assert!(smooth_count > aliased_count);
+
+        // Without anti-aliasing every painted pixel is fully opaque
+        assert!(aliased.pixels.chunks(4).all(|p| p[3] == 0 || p[3] == 255));
+        assert!(smooth.pixels.chunks(4).any(|p| p[3] > 0 && p[3] < 255));
+    }
+
+    #[test]
+    fn test_square_cap_extends_past_endpoint() {
+        let mut round = blank_image(20, 20);
+        let mut square = blank_image(20, 20);
+        let wide = |cap| RasterStroke {
+            width: 6.0,
+            ..stroke(false, cap)
+        };
+
+        draw_line(&mut round, (5.0, 10.0), (15.0, 10.0), &wide(LineCap::Round));
+        draw_line(&mut square, (5.0, 10.0), (15.0, 10.0), &wide(LineCap::Square));
+
+        assert!(painted_pixels(&square) > painted_pixels(&round));
+    }
+
+    #[test]
+    fn test_draw_line_clips_to_image() {
+        let mut image = blank_image(10, 10);
+        draw_line(&mut image, (-50.0, 5.0), (50.0, 5.0), &stroke(true, LineCap::Round));
+        assert!(painted_pixels(&image) > 0);
+    }
+}
@@ -0,0 +1,68 @@
+// Copyright (c) 2025, Jason Jenkins
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Batch find-and-replace for annotation names.
+
+use crate::models::annotation::Annotation;
+use anyhow::{Context, Result};
+use regex::Regex;
+
+/// New names for the annotations a find-and-replace would change, as
+/// (index, new name) pairs in annotation order.
+///
+/// `find` is a plain substring unless `use_regex` is set, in which case
+/// `replace` may refer to capture groups (`$1`, `${name}`). Every match in
+/// a name is replaced. An empty `find` changes nothing.
+pub fn rename_annotations(annotations: &[Annotation], find: &str, replace: &str, use_regex: bool) -> Result<Vec<(usize, String)>> {
+    if find.is_empty() {
+        return Ok(Vec::new());
+    }
+    let pattern = if use_regex {
+        Some(Regex::new(find).context("Invalid regular expression")?)
+    } else {
+        None
+    };
+
+    Ok(annotations
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, annotation)| {
+            let renamed = match pattern {
+                Some(ref pattern) => pattern.replace_all(&annotation.name, replace).into_owned(),
+                None => annotation.name.replace(find, replace),
+            };
+            (renamed != annotation.name).then_some((idx, renamed))
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::annotation::AnnotationType;
+
+    fn named(names: &[&str]) -> Vec<Annotation> {
+        names.iter().map(|name| Annotation::new(name.to_string(), AnnotationType::Polygon)).collect()
+    }
+
+    #[test]
+    fn test_substring_rename() {
+        let annotations = named(&["region 1", "line 1", "region 12"]);
+        let renames = rename_annotations(&annotations, "region", "lane", false).unwrap();
+        assert_eq!(renames, [(0, "lane 1".to_string()), (2, "lane 12".to_string())]);
+
+        // Regex syntax is literal in substring mode
+        assert!(rename_annotations(&annotations, "region \\d", "x", false).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_regex_rename() {
+        let annotations = named(&["region 1", "line 2", "region 12"]);
+        let renames = rename_annotations(&annotations, r"^region (\d+)$", "lane_$1", true).unwrap();
+        assert_eq!(renames, [(0, "lane_1".to_string()), (2, "lane_12".to_string())]);
+
+        assert!(rename_annotations(&annotations, "^zone", "lane", true).unwrap().is_empty());
+        assert!(rename_annotations(&annotations, "(unclosed", "lane", true).is_err());
+        assert!(rename_annotations(&annotations, "", "lane", false).unwrap().is_empty());
+    }
+}
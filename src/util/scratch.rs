@@ -0,0 +1,17 @@
+// Copyright (c) 2025, Jason Jenkins
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Scratch directories for tests that touch the file system.
+
+use std::path::PathBuf;
+
+/// Create an empty scratch directory unique to the calling test.
+///
+/// `name` must differ between tests, which run in parallel; the process id
+/// keeps concurrent test runs apart.
+pub fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("roids_{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
@@ -0,0 +1,319 @@
+// Copyright (c) 2025, Jason Jenkins
+// SPDX-License-Identifier: BSD-3-Clause
+
+//! Summary statistics for dataset analysis.
+//!
+//! This module provides pure helpers for bucketing annotation measurements
+//! so they can be tested independently of the UI that displays them.
+
+use crate::models::{annotation::{Annotation, AnnotationType, Point}, settings::FillRule};
+use crate::util::geometry;
+use std::collections::BTreeMap;
+
+/// Value counts over equal-width buckets.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+    /// Lower bound of the first bucket
+    pub min: f64,
+    /// Upper bound of the last bucket
+    pub max: f64,
+    /// Number of values in each bucket
+    pub counts: Vec<usize>,
+}
+
+impl Histogram {
+    /// Bucket values over their own range.
+    pub fn new(values: &[f64], bucket_count: usize) -> Self {
+        let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+        let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        if values.is_empty() {
+            return Self::with_range(values, 0.0, 0.0, bucket_count);
+        }
+        Self::with_range(values, min, max, bucket_count)
+    }
+
+    /// Bucket values over a fixed range.
+    ///
+    /// Values outside the range are counted in the first or last bucket.
+    /// The maximum value falls in the last bucket.
+    pub fn with_range(values: &[f64], min: f64, max: f64, bucket_count: usize) -> Self {
+        let bucket_count = bucket_count.max(1);
+        let mut counts = vec![0; bucket_count];
+        let width = (max - min) / bucket_count as f64;
+
+        for &value in values {
+            let idx = if width > 0.0 {
+                (((value - min) / width).floor().max(0.0) as usize).min(bucket_count - 1)
+            } else {
+                0
+            };
+            counts[idx] += 1;
+        }
+
+        Self { min, max, counts }
+    }
+
+    /// Get the (lower, upper) bounds of a bucket.
+    pub fn bucket_range(&self, idx: usize) -> (f64, f64) {
+        let width = (self.max - self.min) / self.counts.len() as f64;
+        (self.min + width * idx as f64, self.min + width * (idx + 1) as f64)
+    }
+
+    /// Largest count in any bucket.
+    pub fn max_count(&self) -> usize {
+        self.counts.iter().copied().max().unwrap_or(0)
+    }
+
+    /// Total number of values.
+    pub fn total(&self) -> usize {
+        self.counts.iter().sum()
+    }
+}
+
+/// Normalized area enclosed by every ring of a polygon under `rule`, so
+/// holes and self-overlaps are measured the way they are drawn.
+pub fn filled_area(annotation: &Annotation, rule: FillRule) -> f64 {
+    let rings: Vec<&[Point]> = annotation.rings().map(Vec::as_slice).collect();
+    geometry::filled_area(&rings, rule)
+}
+
+/// Collect polygon areas in square pixels, keyed by category.
+///
+/// Lines are skipped since they enclose no area. Each polygon's normalized
+/// area comes from `area`, usually [`filled_area`] or a cached lookup of
+/// it. When `by_category` is false every area is collected under an empty
+/// key.
+pub fn polygon_areas(
+    annotations: &[Annotation],
+    frame_width: u32,
+    frame_height: u32,
+    by_category: bool,
+    mut area: impl FnMut(&Annotation) -> f64,
+) -> BTreeMap<String, Vec<f64>> {
+    let scale = frame_width as f64 * frame_height as f64;
+    let mut areas: BTreeMap<String, Vec<f64>> = BTreeMap::new();
+
+    for annotation in annotations {
+        if annotation.annotation_type != AnnotationType::Polygon {
+            continue;
+        }
+        let key = if by_category { annotation.category().to_string() } else { String::new() };
+        areas
+            .entry(key)
+            .or_default()
+            .push(area(annotation) * scale);
+    }
+
+    areas
+}
+
+/// Length in pixels of every line annotation, in project order.
+pub fn line_lengths(annotations: &[Annotation], frame_width: u32, frame_height: u32) -> Vec<(&str, f64)> {
+    annotations
+        .iter()
+        .filter(|annotation| annotation.annotation_type == AnnotationType::Line)
+        .map(|annotation| {
+            (annotation.name.as_str(), geometry::polyline_length(&annotation.vertices.0, (frame_width, frame_height)))
+        })
+        .collect()
+}
+
+/// Count how many annotations cover each cell of a `columns` × `rows` grid
+/// laid over the image, indexed `[row][column]`.
+///
+/// A polygon covers the cells whose centers it fills under `rule`, a line
+/// the cells it passes through, and a point the cell it lies in. Each
+/// annotation counts at most once per cell.
+pub fn density_grid(annotations: &[Annotation], columns: usize, rows: usize, rule: FillRule) -> Vec<Vec<u32>> {
+    let mut grid = vec![vec![0u32; columns]; rows];
+    if columns == 0 || rows == 0 {
+        return grid;
+    }
+    let cell_of = |x: f64, y: f64| {
+        let column = (x * columns as f64).floor();
+        let row = (y * rows as f64).floor();
+        (column >= 0.0 && row >= 0.0 && column < columns as f64 && row < rows as f64)
+            .then_some((row as usize, column as usize))
+    };
+
+    let mut covered = vec![vec![false; columns]; rows];
+    let mut touched: Vec<(usize, usize)> = Vec::new();
+    let unit_square = (Point::new(0.0, 0.0), Point::new(1.0, 1.0));
+    // Non-finite vertices would make the line sampling below run forever
+    for annotation in annotations.iter().filter(|a| a.is_finite()) {
+        let vertices = &annotation.vertices.0;
+        match annotation.annotation_type {
+            AnnotationType::Polygon => {
+                // The parts of a multi-part polygon don't overlap, so each is filled on its own
+                for (row, cells) in grid.iter_mut().enumerate() {
+                    let y = (row as f64 + 0.5) / rows as f64;
+                    for (from, to) in annotation.rings().flat_map(|ring| geometry::fill_spans(ring, y, rule)) {
+                        // Columns whose centers fall inside the span
+                        let first = (from * columns as f64 - 0.5).ceil().max(0.0) as usize;
+                        let last = (to * columns as f64 - 0.5).floor().min(columns as f64 - 1.0);
+                        if last >= 0.0 {
+                            for count in cells.iter_mut().take(last as usize + 1).skip(first) {
+                                *count += 1;
+                            }
+                        }
+                    }
+                }
+            }
+            AnnotationType::Line | AnnotationType::Point => {
+                let mut mark = |x: f64, y: f64| {
+                    if let Some((row, column)) = cell_of(x, y) {
+                        if !covered[row][column] {
+                            covered[row][column] = true;
+                            touched.push((row, column));
+                        }
+                    }
+                };
+                if let [only] = vertices.as_slice() {
+                    mark(only.x, only.y);
+                }
+                // Sample each segment at least twice per cell it crosses,
+                // clipped to the image so far-off vertices can't blow up
+                // the number of samples
+                for piece in geometry::clip_polyline_to_rect(vertices, unit_square) {
+                    for pair in piece.windows(2) {
+                        let (dx, dy) = (pair[1].x - pair[0].x, pair[1].y - pair[0].y);
+                        let steps = ((dx.abs() * columns as f64).max(dy.abs() * rows as f64) * 2.0).ceil().max(1.0) as usize;
+                        for step in 0..=steps {
+                            let t = step as f64 / steps as f64;
+                            mark(pair[0].x + dx * t, pair[0].y + dy * t);
+                        }
+                    }
+                }
+                for (row, column) in touched.drain(..) {
+                    grid[row][column] += 1;
+                    covered[row][column] = false;
+                }
+            }
+        }
+    }
+    grid
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_buckets() {
+        let histogram = Histogram::new(&[0.0, 1.0, 2.5, 9.9, 10.0], 4);
+        assert_eq!(histogram.counts, vec![2, 1, 0, 2]);
+        assert_eq!(histogram.bucket_range(1), (2.5, 5.0));
+        assert_eq!(histogram.max_count(), 2);
+        assert_eq!(histogram.total(), 5);
+    }
+
+    #[test]
+    fn test_histogram_degenerate() {
+        assert_eq!(Histogram::new(&[], 3).counts, vec![0, 0, 0]);
+        assert_eq!(Histogram::new(&[4.0, 4.0], 3).counts, vec![2, 0, 0]);
+
+        // Out-of-range values land in the end buckets
+        let histogram = Histogram::with_range(&[-5.0, 50.0], 0.0, 10.0, 2);
+        assert_eq!(histogram.counts, vec![1, 1]);
+    }
+
+    #[test]
+    fn test_line_lengths() {
+        let line = |name: &str, points: &[(f64, f64)]| {
+            let mut annotation = Annotation::new(name.to_string(), AnnotationType::Line);
+            for &(x, y) in points {
+                annotation.add_vertex(Point::new(x, y));
+            }
+            annotation
+        };
+        let mut polygon = line("region 1", &[(0.0, 0.0), (1.0, 0.0), (1.0, 1.0)]);
+        polygon.annotation_type = AnnotationType::Polygon;
+        let annotations = vec![
+            // 3-4-5 triangle legs in a 100x100 frame: 30 + 40 px
+            line("line 1", &[(0.0, 0.0), (0.3, 0.0), (0.3, 0.4)]),
+            polygon,
+            // Diagonal: 50 px
+            line("line 2", &[(0.0, 0.0), (0.3, 0.4)]),
+        ];
+
+        let lengths = line_lengths(&annotations, 100, 100);
+        assert_eq!(lengths.iter().map(|(name, _)| *name).collect::<Vec<_>>(), ["line 1", "line 2"]);
+        assert!((lengths[0].1 - 70.0).abs() < 1e-9);
+        assert!((lengths[1].1 - 50.0).abs() < 1e-9);
+        let total: f64 = lengths.iter().map(|(_, length)| length).sum();
+        assert!((total - 120.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_polygon_areas_by_category() {
+        let square = |name: &str, size: f64| {
+            let mut annotation = Annotation::new(name.to_string(), AnnotationType::Polygon);
+            annotation.add_vertex(Point::new(0.0, 0.0));
+            annotation.add_vertex(Point::new(size, 0.0));
+            annotation.add_vertex(Point::new(size, size));
+            annotation.add_vertex(Point::new(0.0, size));
+            annotation
+        };
+        let annotations = vec![
+            square("car 1", 0.5),
+            square("car 2", 0.1),
+            square("person 1", 0.2),
+            Annotation::new("line 1".to_string(), AnnotationType::Line),
+        ];
+
+        let grouped = polygon_areas(&annotations, 100, 100, true, |a| filled_area(a, FillRule::EvenOdd));
+        assert_eq!(grouped.keys().collect::<Vec<_>>(), vec!["car", "person"]);
+        assert_eq!(grouped["car"].len(), 2);
+        assert!((grouped["car"][0] - 2500.0).abs() < 1e-6);
+
+        let all = polygon_areas(&annotations, 100, 100, false, |a| filled_area(a, FillRule::NonZero));
+        assert_eq!(all[""].len(), 3);
+
+        // Holes are subtracted
+        let mut framed = square("car 3", 0.5);
+        framed.extra_rings.push(square("", 0.1).vertices);
+        let holed = polygon_areas(&[framed], 100, 100, true, |a| filled_area(a, FillRule::EvenOdd));
+        assert!((holed["car"][0] - 2400.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_density_grid() {
+        let square = |name: &str, x0: f64, y0: f64, x1: f64, y1: f64| {
+            let mut annotation = Annotation::new(name.to_string(), AnnotationType::Polygon);
+            for (x, y) in [(x0, y0), (x1, y0), (x1, y1), (x0, y1)] {
+                annotation.add_vertex(Point::new(x, y));
+            }
+            annotation
+        };
+        // Two overlapping squares on a 4 x 2 grid
+        let mut annotations = vec![square("a", 0.0, 0.0, 0.5, 1.0), square("b", 0.25, 0.0, 0.75, 0.5)];
+        let grid = density_grid(&annotations, 4, 2, FillRule::EvenOdd);
+        assert_eq!(grid, vec![vec![1, 2, 1, 0], vec![1, 1, 0, 0]]);
+
+        // A line counts once in each cell it passes through, a point in its cell
+        let mut line = Annotation::new("line".to_string(), AnnotationType::Line);
+        line.add_vertex(Point::new(0.1, 0.75));
+        line.add_vertex(Point::new(0.9, 0.75));
+        line.add_vertex(Point::new(0.8, 0.75));
+        let mut point = Annotation::new("point".to_string(), AnnotationType::Point);
+        point.add_vertex(Point::new(0.9, 0.1));
+        annotations = vec![line, point];
+        let grid = density_grid(&annotations, 4, 2, FillRule::EvenOdd);
+        assert_eq!(grid, vec![vec![0, 0, 0, 1], vec![1, 1, 1, 1]]);
+
+        assert!(density_grid(&annotations, 0, 0, FillRule::EvenOdd).is_empty());
+
+        // Far-off vertices only count where the line crosses the image
+        let mut long = Annotation::new("long".to_string(), AnnotationType::Line);
+        long.add_vertex(Point::new(-1e12, 0.25));
+        long.add_vertex(Point::new(1e12, 0.25));
+        let grid = density_grid(&[long], 4, 2, FillRule::EvenOdd);
+        assert_eq!(grid, vec![vec![1, 1, 1, 1], vec![0, 0, 0, 0]]);
+
+        // Every part of a multi-part polygon is counted
+        let mut parts = square("parts", 0.0, 0.0, 0.25, 0.5);
+        parts.extra_rings = square("", 0.75, 0.5, 1.0, 1.0).rings().map(|ring| crate::models::annotation::Vertices(ring.clone())).collect();
+        let grid = density_grid(&[parts], 4, 2, FillRule::EvenOdd);
+        assert_eq!(grid, vec![vec![1, 0, 0, 0], vec![0, 0, 0, 1]]);
+    }
+}